@@ -0,0 +1,151 @@
+//! Times parsing the baselines of the bundled corpus, and a large synthesized errors baseline.
+//!
+//! Run with `cargo bench -p test_runner --bench baseline_parse`.
+
+use std::{
+    fmt::Write,
+    fs,
+    hint::black_box,
+    path::{Path, PathBuf},
+    time::{Duration, Instant},
+};
+
+use test_runner::{
+    Baseline, BaselineKind, ErrorsBaseline, HumanSize, SELF_TEST_CORPUS, SymbolsBaseline,
+    TypesBaseline,
+};
+
+const FILES: usize = 4000;
+const ERRORS_PER_FILE: usize = 4;
+const ITERATIONS: u32 = 20;
+
+/// File name endings of the baselines, indexed like [`BaselineKind`].
+const EXTENSIONS: [&str; 3] = [".types", ".errors.txt", ".symbols"];
+
+/// Best time of [`ITERATIONS`] runs of `f`, after a warm-up run.
+fn best_of(mut f: impl FnMut()) -> Duration {
+    f();
+    let mut best = Duration::MAX;
+    for _ in 0..ITERATIONS {
+        let start = Instant::now();
+        f();
+        best = best.min(start.elapsed());
+    }
+    best
+}
+
+/// Every baseline of the bundled corpus with its kind, sorted by path.
+fn corpus_baselines() -> Vec<(PathBuf, BaselineKind, Vec<u8>)> {
+    let dir = Path::new(SELF_TEST_CORPUS).join("tests/baselines/reference");
+    let mut result: Vec<_> = fs::read_dir(dir)
+        .expect("bundled corpus to exist")
+        .filter_map(|entry| {
+            let path = entry.ok()?.path();
+            let name = path.file_name()?.to_str()?;
+            let kind = [BaselineKind::Types, BaselineKind::Errors, BaselineKind::Symbols]
+                .into_iter()
+                .find(|x| name.ends_with(EXTENSIONS[*x as usize]))?;
+            let data = fs::read(&path).ok()?;
+            Some((path, kind, data))
+        })
+        .collect();
+    result.sort_by(|a, b| a.0.cmp(&b.0));
+    result
+}
+
+/// Times every corpus baseline with the parser of its kind. The few the parsers reject on
+/// purpose (git-lfs pointers, corrupted files) are timed all the same.
+fn corpus() {
+    let baselines = corpus_baselines();
+    let mut timings: Vec<_> = baselines
+        .iter()
+        .map(|(path, kind, data)| {
+            let elapsed = best_of(|| match kind {
+                BaselineKind::Types => {
+                    black_box(TypesBaseline::parse(path, data).is_ok());
+                }
+                BaselineKind::Errors => {
+                    black_box(ErrorsBaseline::parse(path, data).is_ok());
+                }
+                BaselineKind::Symbols => {
+                    black_box(SymbolsBaseline::parse(path, data).is_ok());
+                }
+            });
+            (elapsed, path, data.len())
+        })
+        .collect();
+    timings.sort_by(|a, b| b.0.cmp(&a.0));
+
+    let total: Duration = timings.iter().map(|x| x.0).sum();
+    let size: usize = baselines.iter().map(|x| x.2.len()).sum();
+    println!(
+        "{} corpus baselines ({}): best {total:.2?} in total over {ITERATIONS} runs, slowest:",
+        baselines.len(),
+        HumanSize(size),
+    );
+    for (elapsed, path, size) in timings.iter().take(5) {
+        let name = path.file_name().unwrap_or_default().to_string_lossy();
+        println!("  {:>10}  {:>10}  {name}", format!("{elapsed:.2?}"), HumanSize(*size));
+    }
+}
+
+/// An errors baseline of a program with [`FILES`] files, each with [`ERRORS_PER_FILE`] errors.
+fn errors_baseline() -> String {
+    let file = |idx| format!("src/module{idx:05}.ts");
+    let mut out = String::new();
+    for idx in 0..FILES {
+        for line in 0..ERRORS_PER_FILE {
+            let _ = writeln!(
+                out,
+                "{}({},7): error TS2322: Type 'string' is not assignable to type 'number'.",
+                file(idx),
+                line + 1
+            );
+        }
+    }
+    out.push_str("\n\n");
+    for idx in 0..FILES {
+        let _ = writeln!(out, "==== {} ({ERRORS_PER_FILE} errors) ====", file(idx));
+        for _ in 0..ERRORS_PER_FILE {
+            out.push_str("    const a: number = \"a\";\n");
+            out.push_str("          ~\n");
+            out.push_str("!!! error TS2322: Type 'string' is not assignable to type 'number'.\n");
+        }
+        out.push_str("    export {};\n");
+    }
+    out
+}
+
+fn main() {
+    corpus();
+
+    let errors = errors_baseline();
+    let types = "//// [a.ts] ////\n\n=== a.ts ===\nconst a = 1;\n>a : 1\n>  : ^\n";
+    let parse = || {
+        Baseline::parse(
+            Path::new("a.types"),
+            types.as_bytes(),
+            Path::new("a.errors.txt"),
+            Some(errors.as_bytes()),
+            None,
+        )
+        .unwrap()
+    };
+
+    black_box(parse());
+    let mut best = Duration::MAX;
+    let mut total = Duration::ZERO;
+    for _ in 0..ITERATIONS {
+        let start = Instant::now();
+        black_box(parse());
+        let elapsed = start.elapsed();
+        best = best.min(elapsed);
+        total += elapsed;
+    }
+
+    println!(
+        "errors baseline of {FILES} files ({} bytes): best {best:.2?}, mean {:.2?} over {ITERATIONS} runs",
+        errors.len(),
+        total / ITERATIONS
+    );
+}
@@ -0,0 +1,104 @@
+//! Counts the allocations of comparing the bundled corpus, with the scratch buffers a worker
+//! reuses and with fresh ones for every comparison.
+//!
+//! Run with `cargo bench -p test_runner --bench compare_alloc`.
+
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    fs,
+    hint::black_box,
+    path::Path,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+use test_runner::{
+    ErrorsBaseline, RunOptions, SELF_TEST_CORPUS,
+    compare::{MatchOptions, Scratch, compare_errors},
+    discover, run_test,
+};
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+struct CountingAllocator;
+
+#[expect(unsafe_code)]
+// SAFETY: forwards to the system allocator
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        // SAFETY: same contract as `GlobalAlloc::alloc`
+        unsafe { System.alloc(layout) }
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        // SAFETY: same contract as `GlobalAlloc::dealloc`
+        unsafe { System.dealloc(ptr, layout) }
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAllocator = CountingAllocator;
+
+/// Number of allocations made by `f`, on any thread.
+fn allocations<T>(f: impl FnOnce() -> T) -> usize {
+    let before = ALLOCATIONS.load(Ordering::Relaxed);
+    black_box(f());
+    ALLOCATIONS.load(Ordering::Relaxed) - before
+}
+
+/// The errors baselines of the bundled corpus the parser accepts.
+fn corpus_errors_baselines() -> Vec<(String, Vec<u8>)> {
+    let dir = Path::new(SELF_TEST_CORPUS).join("tests/baselines/reference");
+    let mut result: Vec<_> = fs::read_dir(dir)
+        .expect("bundled corpus to exist")
+        .filter_map(|entry| {
+            let path = entry.ok()?.path();
+            let name = path.file_name()?.to_str()?.strip_suffix(".errors.txt")?.to_string();
+            let data = fs::read(&path).ok()?;
+            ErrorsBaseline::parse(&path, &data).is_ok().then_some((name, data))
+        })
+        .collect();
+    result.sort();
+    result
+}
+
+fn main() {
+    // Every baseline compared against the diagnostics it expects, so all of them match
+    let data = corpus_errors_baselines();
+    let baselines: Vec<_> = data
+        .iter()
+        .map(|(name, data)| ErrorsBaseline::parse(Path::new(name), data).unwrap())
+        .collect();
+    let expected: Vec<_> = baselines.iter().map(ErrorsBaseline::to_diagnostics).collect();
+    // Compares everything with `reused`, or with a fresh scratch for every baseline
+    let compare_all = |mut reused: Option<&mut Scratch>| {
+        for (baseline, actual) in baselines.iter().zip(&expected) {
+            let mut fresh = Scratch::default();
+            let scratch = reused.as_deref_mut().unwrap_or(&mut fresh);
+            assert!(compare_errors(baseline, actual, MatchOptions::default(), scratch).is_match());
+        }
+    };
+
+    // Warm up the scratch, as a worker does after its first variant
+    let mut scratch = Scratch::default();
+    compare_all(Some(&mut scratch));
+    let reused_allocations = allocations(|| compare_all(Some(&mut scratch)));
+    let fresh_allocations = allocations(|| compare_all(None));
+    println!(
+        "compare_errors on {} corpus baselines: {reused_allocations} allocations with a reused \
+         scratch, {fresh_allocations} with fresh ones",
+        baselines.len()
+    );
+
+    let options = RunOptions::parse(["--self-test".to_string()]).expect("valid options");
+    let reused = allocations(|| discover(&options, run_test));
+    let fresh = allocations(|| {
+        discover(&options, |unit, variant, baseline, options, cache, libs, _scratch| {
+            run_test(unit, variant, baseline, options, cache, libs, &mut Scratch::default())
+        })
+    });
+    println!(
+        "bundled corpus run: {reused} allocations with per-worker scratches, {fresh} with a \
+         scratch per variant"
+    );
+}
@@ -0,0 +1,169 @@
+use std::path::{Path, PathBuf};
+
+use rustc_hash::FxHashSet;
+
+use crate::{
+    RunOptions, TestUnit,
+    discover::{baseline_candidates, discover_files, is_emit_only, read_test_file},
+    human::HumanCount,
+    runner::relative_path,
+};
+
+/// Baseline kinds the runner looks up, by file extension.
+const AUDITED_KINDS: &[&str] = &["types", "errors.txt", "symbols"];
+
+/// Baseline files of the reference directory checked against the ones discovered tests map to.
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct BaselineAudit {
+    /// `.types` baselines a variant needs that do not exist.
+    pub missing: Vec<PathBuf>,
+    /// Baselines no variant of any discovered test looks up.
+    pub unreferenced: Vec<PathBuf>,
+}
+
+impl BaselineAudit {
+    /// Compares the baselines variants need (`expected`) and may read (`looked_up`) with the ones
+    /// `present` in the reference directory.
+    fn new(expected: &[PathBuf], looked_up: &FxHashSet<PathBuf>, present: &[PathBuf]) -> Self {
+        let present_set: FxHashSet<_> = present.iter().collect();
+        let mut result = Self {
+            missing: expected.iter().filter(|x| !present_set.contains(x)).cloned().collect(),
+            unreferenced: present.iter().filter(|x| !looked_up.contains(*x)).cloned().collect(),
+        };
+        result.missing.sort();
+        result.missing.dedup();
+        result.unreferenced.sort();
+        result
+    }
+
+    /// Number of missing and unreferenced baselines per kind: `types`, `errors.txt` and
+    /// `symbols`, in that order.
+    pub fn counts_by_kind(&self) -> Vec<(&'static str, usize, usize)> {
+        let count = |paths: &[PathBuf], kind| {
+            paths.iter().filter(|x| baseline_kind(x) == Some(kind)).count()
+        };
+        AUDITED_KINDS
+            .iter()
+            .map(|&kind| (kind, count(&self.missing, kind), count(&self.unreferenced, kind)))
+            .collect()
+    }
+}
+
+impl std::fmt::Display for BaselineAudit {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "{} expected but missing:", HumanCount(self.missing.len()))?;
+        for path in &self.missing {
+            writeln!(f, "  {}", path.display())?;
+        }
+        writeln!(f, "{} present but never looked up:", HumanCount(self.unreferenced.len()))?;
+        for path in &self.unreferenced {
+            writeln!(f, "  {}", path.display())?;
+        }
+        for (kind, missing, unreferenced) in self.counts_by_kind() {
+            writeln!(f, "  .{kind}: {missing} missing, {unreferenced} never looked up")?;
+        }
+        Ok(())
+    }
+}
+
+fn baseline_kind(path: &Path) -> Option<&'static str> {
+    let name = path.file_name()?.to_str()?;
+    AUDITED_KINDS
+        .iter()
+        .copied()
+        .find(|kind| name.strip_suffix(kind).is_some_and(|x| x.ends_with('.') && x.len() > 1))
+}
+
+/// Maps every variant of the discovered tests to the baselines it looks up and compares them
+/// with the baselines in the reference directory. Paths are relative to the repo.
+///
+/// Variant filters are ignored: a baseline only the filtered out variants look up is still in
+/// use. Subdirectories of the reference directory belong to other test suites and are skipped.
+///
+/// # Panics
+pub fn audit_baselines(options: &RunOptions) -> BaselineAudit {
+    let repo = options.repo.as_path();
+    let baseline_root = options.baseline_root();
+    let mut expected = Vec::new();
+    let mut looked_up = FxHashSet::default();
+    for test_file in discover_files(options) {
+        let Ok(data) = read_test_file(&test_file) else {
+            panic!("Failed to read test file: {}", relative_path(&test_file, repo).display());
+        };
+        let unit = TestUnit::parse_with_limit(&test_file, &data, options.max_variation_values());
+        let name = test_file.file_stem().expect("path to be a file");
+        for variant in unit.variations.iter() {
+            let candidates = baseline_candidates(&baseline_root, name, &variant.name);
+            let path = |x: &Path| relative_path(x, repo).to_path_buf();
+            // TS still writes the errors baseline of units without types and symbols
+            looked_up.insert(path(&candidates.errors));
+            // Multi-pass units are skipped, but their baselines are not stray
+            if unit.settings.multi_pass {
+                looked_up.extend(candidates.all().map(path));
+                continue;
+            }
+            if unit.settings.no_types_and_symbols
+                || is_emit_only(&baseline_root, name, &variant.name)
+            {
+                continue;
+            }
+
+            expected.push(path(&candidates.types));
+            looked_up.extend(candidates.all().map(path));
+        }
+    }
+
+    let Ok(entries) = baseline_root.read_dir() else {
+        panic!(
+            "Failed to read baseline directory: {}",
+            relative_path(&baseline_root, repo).display()
+        );
+    };
+    let present: Vec<_> = entries
+        .filter_map(Result::ok)
+        .map(|x| x.path())
+        .filter(|x| x.is_file() && baseline_kind(x).is_some())
+        .map(|x| relative_path(&x, repo).to_path_buf())
+        .collect();
+
+    BaselineAudit::new(&expected, &looked_up, &present)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn missing_and_unreferenced() {
+        let paths = |names: &[&str]| -> Vec<PathBuf> {
+            names.iter().map(|x| Path::new("reference").join(x)).collect()
+        };
+        let expected = paths(&["a.types", "b(strict=true).types", "b(strict=false).types"]);
+        let looked_up: FxHashSet<_> = expected
+            .iter()
+            .cloned()
+            .chain(paths(&["a.errors.txt", "a.symbols", "b(strict=true).errors.txt"]))
+            .collect();
+        let present = paths(&[
+            "a.types",
+            "a.errors.txt",
+            "b(strict=true).types",
+            "b(strict=false).errors.txt",
+            "b(Strict=false).types",
+            "c.symbols",
+        ]);
+
+        let audit = BaselineAudit::new(&expected, &looked_up, &present);
+        assert_eq!(audit.missing, paths(&["b(strict=false).types"]));
+        assert_eq!(
+            audit.unreferenced,
+            paths(&["b(Strict=false).types", "b(strict=false).errors.txt", "c.symbols"])
+        );
+        assert_eq!(
+            audit.counts_by_kind(),
+            vec![("types", 1, 1), ("errors.txt", 0, 1), ("symbols", 0, 1)]
+        );
+        assert_eq!(baseline_kind(Path::new("reference/.types")), None);
+        assert_eq!(baseline_kind(Path::new("reference/a.js")), None);
+    }
+}
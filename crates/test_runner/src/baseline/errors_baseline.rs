@@ -1,26 +1,307 @@
-use std::{collections::VecDeque, path::Path};
+use std::{borrow::Cow, collections::VecDeque, ops::Range, path::Path};
 
 use memchr::{memchr, memchr_iter, memchr2, memrchr};
+use rustc_hash::{FxHashMap, FxHashSet};
+use type_info::{Diagnostic, Severity};
+
+use super::{BaselineKind, BaselineParseError, UnderlineMismatch, file_order, line_iter::LineIter};
+use crate::parse_warning::{ParseWarning, ParseWarningKind};
+
+/// A line of the baseline being parsed, which errors are reported at.
+#[derive(Debug, Clone, Copy)]
+struct At<'p> {
+    path: &'p Path,
+    /// 0-based index of the line.
+    line_idx: usize,
+    /// Byte offset of the line in the baseline.
+    line_start: usize,
+}
 
-use crate::byte_utils::trim_space_start;
-
-use super::line_iter::LineIter;
-
-#[expect(clippy::if_same_then_else)]
-fn cmp_file(a: &str, b: &str) -> std::cmp::Ordering {
-    let result = a.cmp(b);
-    if result.is_eq() {
-        std::cmp::Ordering::Equal
-    } else if a == "tsconfig.json" {
-        std::cmp::Ordering::Less
-    } else if b == "tsconfig.json" {
-        std::cmp::Ordering::Greater
-    } else if a.starts_with("lib.") && a.ends_with(".d.ts") {
-        std::cmp::Ordering::Greater
-    } else if b.starts_with("lib.") && b.ends_with(".d.ts") {
-        std::cmp::Ordering::Less
-    } else {
-        result
+impl<'p> At<'p> {
+    fn new(path: &'p Path, line_idx: usize, line_start: usize) -> Self {
+        Self { path, line_idx, line_start }
+    }
+
+    /// Error at byte `column` of the line.
+    fn error(self, column: usize, error: &str) -> BaselineParseError {
+        BaselineParseError {
+            which: BaselineKind::Errors,
+            path: self.path.to_path_buf(),
+            offset: Some(self.line_start + column),
+            line: Some(self.line_idx + 1),
+            error: error.to_string(),
+            underline: None,
+            snippet: None,
+        }
+    }
+
+    /// Position of `needle` in `line` after `start`, relative to `start`.
+    fn find(
+        self,
+        line: &[u8],
+        start: usize,
+        needle: u8,
+        error: &str,
+    ) -> Result<usize, BaselineParseError> {
+        line.get(start..).and_then(|x| memchr(needle, x)).ok_or_else(|| self.error(start, error))
+    }
+
+    /// Text of `line` in `range`.
+    fn str<'a>(
+        self,
+        line: &'a [u8],
+        range: Range<usize>,
+        error: &str,
+    ) -> Result<&'a str, BaselineParseError> {
+        let start = range.start;
+        line.get(range)
+            .and_then(|x| std::str::from_utf8(x).ok())
+            .ok_or_else(|| self.error(start, error))
+    }
+
+    /// Line and column of `line` in the ranges. A line that is not a number, like the `--` of
+    /// errors without a position, leaves the error without a location.
+    fn loc(
+        self,
+        line: &[u8],
+        line_range: Range<usize>,
+        column_range: Range<usize>,
+    ) -> Result<Option<(u32, u32)>, BaselineParseError> {
+        let Ok(line_num) = self.str(line, line_range, "Invalid line number")?.parse() else {
+            return Ok(None);
+        };
+        let column_start = column_range.start;
+        let column = self
+            .str(line, column_range, "Invalid column number")?
+            .parse()
+            .map_err(|_| self.error(column_start, "Expected column number to be an integer"))?;
+        Ok(Some((line_num, column)))
+    }
+}
+
+/// A line of a pretty baseline with its ANSI escape sequences removed, so it can be split on the
+/// plain text. Text is still sliced from the raw line and errors point into it.
+struct Stripped<'a, 'p> {
+    at: At<'p>,
+    line: &'a [u8],
+    text: Vec<u8>,
+    /// Position in `line` of every byte of `text`, followed by the length of `line`.
+    raw: Vec<usize>,
+    /// Positions in `text` escape sequences were removed at, each listed once.
+    escapes: Vec<usize>,
+}
+
+impl<'a, 'p> Stripped<'a, 'p> {
+    /// Removes the CSI sequences TS colors its output with: `ESC [`, parameter bytes and a final
+    /// byte, like `ESC[96m`, `ESC[1m` or `ESC[38;5;14m`.
+    fn new(at: At<'p>, line: &'a [u8]) -> Self {
+        let mut text = Vec::with_capacity(line.len());
+        let mut raw = Vec::with_capacity(line.len() + 1);
+        let mut escapes = vec![];
+        let mut idx = 0;
+        while idx < line.len() {
+            if line[idx] == 0x1B && line.get(idx + 1) == Some(&b'[') {
+                let params = line[idx + 2..].iter().take_while(|x| (0x20..=0x3F).contains(*x));
+                idx += 3 + params.count();
+                if escapes.last() != Some(&text.len()) {
+                    escapes.push(text.len());
+                }
+                continue;
+            }
+
+            text.push(line[idx]);
+            raw.push(idx);
+            idx += 1;
+        }
+        raw.push(line.len());
+        Self { at, line, text, raw, escapes }
+    }
+
+    fn error(&self, column: usize, error: &str) -> BaselineParseError {
+        self.at.error(self.raw[column.min(self.text.len())], error)
+    }
+
+    /// Position of `needle` in the text after `start`, relative to `start`.
+    fn find(&self, start: usize, needle: u8, error: &str) -> Result<usize, BaselineParseError> {
+        self.text
+            .get(start..)
+            .and_then(|x| memchr(needle, x))
+            .ok_or_else(|| self.error(start, error))
+    }
+
+    /// Range of the raw line a range of the text was taken from.
+    fn raw_range(&self, range: Range<usize>) -> Range<usize> {
+        let start = self.raw[range.start.min(self.text.len())];
+        if range.is_empty() {
+            return start..start;
+        }
+        let end = self.raw[(range.end - 1).min(self.text.len())] + 1;
+        start..end
+    }
+
+    fn str(&self, range: Range<usize>, error: &str) -> Result<&'a str, BaselineParseError> {
+        self.at.str(self.line, self.raw_range(range), error)
+    }
+
+    /// Line and column of `file:line:column` from the end of the file name.
+    fn loc(&self, name_end: usize) -> Result<Option<(u32, u32)>, BaselineParseError> {
+        let line_start = name_end + 1;
+        let line_end =
+            line_start + self.find(line_start, b':', "Failed to find end of line number")?;
+        let column_end =
+            memchr(b' ', &self.text[line_end..]).map_or(self.text.len(), |x| line_end + x);
+        self.at.loc(
+            self.line,
+            self.raw_range(line_start..line_end),
+            self.raw_range(line_end + 1..column_end),
+        )
+    }
+
+    /// Length of the error underlined in this underline line, see [`underline_length`]. The code
+    /// starts after the line number gutter, which is set apart by escape sequences.
+    fn underline_length(&self, column: u32) -> Result<Option<u32>, BaselineParseError> {
+        let Some(end) = memrchr(b'~', &self.text) else {
+            return Ok(None);
+        };
+        let Some(&gutter_end) = self.escapes.get(1) else {
+            return Err(self.error(0, "Expected delimiter after line number"));
+        };
+        // Shifted by the escape sequences before the `~`, so errors point into the raw line
+        let raw_end = self.raw[end];
+        let code_start = raw_end - end + gutter_end - 1;
+        underline_length(self.at, self.line, raw_end, code_start, column).map(Some)
+    }
+}
+
+/// Severities as TS prints them before the error code, like `warning TS6133`.
+const SEVERITIES: [(&[u8], Severity); 4] = [
+    (b"error", Severity::Error),
+    (b"warning", Severity::Warning),
+    (b"suggestion", Severity::Suggestion),
+    (b"message", Severity::Message),
+];
+
+/// Splits the severity off `text` starting like `error TS2322: text`, returning it with the
+/// text after `TS`.
+fn split_severity(text: &[u8]) -> Option<(Severity, &[u8])> {
+    SEVERITIES.iter().find_map(|&(keyword, severity)| {
+        Some((severity, text.strip_prefix(keyword)?.strip_prefix(b" TS")?))
+    })
+}
+
+/// Severity and the text after `TS` of a `!!! error TS2322: text` line, `None` for other lines.
+fn split_marker(line: &[u8]) -> Option<(Severity, &[u8])> {
+    line.strip_prefix(b"!!! ").and_then(split_severity)
+}
+
+/// Text of a `!!! error TS2322: text` line.
+fn marker_text(line: &[u8]) -> &[u8] {
+    split_marker(line).and_then(|(_, x)| x.get(memchr(b':', x)? + 2..)).unwrap_or_default()
+}
+
+/// Indices of the errors of every file, built once before walking the file blocks: the summary
+/// lists the errors of a file next to each other. Each block takes the range of its file, so the
+/// ranges left over are the ones of files without a block.
+fn file_ranges<'a>(file_errors: &[FileError<'a>]) -> FxHashMap<&'a str, Range<usize>> {
+    let mut result = FxHashMap::default();
+    let mut start = 0;
+    for group in file_errors.chunk_by(|a, b| a.file == b.file) {
+        result.insert(group[0].file, start..start + group.len());
+        start += group.len();
+    }
+    result
+}
+
+/// Fails when an error of a file with a section did not get its `!!!` line there. Files in
+/// `not_printed` have no section: their errors are only listed in the summary, see
+/// [`ErrorsBaseline::unmatched_summary_errors`].
+fn check_printed(
+    at: At<'_>,
+    file_errors: &[FileError<'_>],
+    not_printed: &FxHashSet<&str>,
+) -> Result<(), BaselineParseError> {
+    match file_errors.iter().find(|x| !x.has_inline_marker && !not_printed.contains(x.file)) {
+        Some(err) => Err(at.error(
+            0,
+            &format!(
+                "Expected error TS{} of `{}` to be underlined in its section",
+                err.code, err.file
+            ),
+        )),
+        None => Ok(()),
+    }
+}
+
+/// Position right after the last line of `data`.
+fn end_of<'p>(path: &'p Path, data: &[u8]) -> At<'p> {
+    At::new(path, memchr_iter(b'\n', data).count(), data.len())
+}
+
+/// File name of a `==== file.ts (0 errors) ====` header, without a leading `./`, and the
+/// number of errors it declares.
+fn file_header<'a>(
+    at: At<'_>,
+    line: &'a [u8],
+) -> Result<(&'a str, Option<usize>), BaselineParseError> {
+    let name_end = 5 + at.find(line, 5, b' ', "Failed to find end of file name")?;
+    let file = at.str(line, 5..name_end, "Invalid file name")?;
+    let count = line.get(name_end..).and_then(|x| x.strip_prefix(b" (")).and_then(leading_number);
+    Ok((file.strip_prefix("./").unwrap_or(file), count))
+}
+
+/// Number of errors of a `Found 2 errors in 2 files.` line ending pretty baselines.
+fn found_count(line: &[u8]) -> Option<usize> {
+    line.strip_prefix(b"Found ").and_then(leading_number)
+}
+
+fn leading_number(text: &[u8]) -> Option<usize> {
+    let digits = text.iter().take_while(|x| x.is_ascii_digit()).count();
+    std::str::from_utf8(&text[..digits]).ok()?.parse().ok()
+}
+
+/// Number of errors an errors baseline declares, see [`ErrorsBaseline::validate`].
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct DeclaredCount<'a> {
+    /// File of a `==== file.ts (2 errors) ====` header, `None` for the `Found 2 errors` total
+    /// of pretty baselines.
+    pub file: Option<&'a str>,
+    pub count: usize,
+    /// 1-based line declaring it.
+    pub line: usize,
+}
+
+impl<'a> DeclaredCount<'a> {
+    fn new(file: Option<&'a str>, count: usize, line_idx: usize) -> Self {
+        Self { file, count, line: line_idx + 1 }
+    }
+}
+
+/// A declared number of errors that differs from the number of errors parsed.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct CountMismatch<'a> {
+    pub declared: DeclaredCount<'a>,
+    pub parsed: usize,
+}
+
+impl CountMismatch<'_> {
+    /// The mismatch as a warning about the errors baseline at `path`.
+    pub fn to_warning(&self, path: &Path) -> ParseWarning {
+        ParseWarning::new(
+            ParseWarningKind::ErrorCountMismatch,
+            path,
+            self.declared.line,
+            self.to_string().as_bytes(),
+        )
+    }
+}
+
+impl std::fmt::Display for CountMismatch<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.declared.file {
+            Some(file) => write!(f, "{file} declares {} errors", self.declared.count)?,
+            None => write!(f, "{} errors found in total", self.declared.count)?,
+        }
+        write!(f, ", {} parsed", self.parsed)
     }
 }
 
@@ -28,33 +309,139 @@ fn cmp_file(a: &str, b: &str) -> std::cmp::Ordering {
 pub struct ErrorsBaseline<'a> {
     config_errors: Vec<ConfigError<'a>>,
     file_errors: Vec<FileError<'a>>,
+    declared_counts: Vec<DeclaredCount<'a>>,
+    warnings: Vec<ParseWarning>,
 }
 
 #[derive(Debug, PartialEq, Eq)]
 pub struct ConfigError<'a> {
+    severity: Severity,
     code: &'a str,
     message: &'a str,
-    hint: Vec<(u8, &'a str)>,
+    hint: Vec<Hint<'a>>,
+    /// Position in the `tsconfig.json` section, when the error is underlined there.
+    loc: Option<(u32, u32)>,
+    length: Option<u32>,
 }
 
 impl<'a> ConfigError<'a> {
-    fn parse(path: &'_ Path, line: &'a [u8]) -> Self {
-        let code_start = 8;
-        let Some(code_end) = memchr(b':', &line[code_start..]) else {
-            panic!(
-                "Failed to find end of error code\n  path: {}\n  line: {}\n      : {}>",
-                path.display(),
-                std::str::from_utf8(line).unwrap().escape_debug(),
-                " ".repeat(code_start)
-            );
+    /// Parses an `error TS<code>: <message>` line, or one with another severity. A line cut
+    /// short keeps what it has: without `:` the rest of the line is the code and the message is
+    /// empty. The flag is set when the line was malformed.
+    fn parse(at: At<'_>, line: &'a [u8]) -> Result<(Self, bool), BaselineParseError> {
+        let Some((severity, rest)) = split_severity(line) else {
+            return Err(at.error(0, "Failed to find error severity"));
+        };
+        let code_start = line.len() - rest.len();
+        let (code, message) = match memchr(b':', rest) {
+            Some(code_end) => (&rest[..code_end], rest.get(code_end + 2..)),
+            None => (rest, None),
         };
 
-        let message_start = code_start + code_end + 2;
-        ConfigError {
-            code: std::str::from_utf8(&line[code_start..code_start + code_end])
-                .expect("error code to be UTF8"),
-            message: std::str::from_utf8(&line[message_start..]).expect("message to be UTF8"),
+        let error = ConfigError {
+            severity,
+            code: std::str::from_utf8(code)
+                .map_err(|_| at.error(code_start, "Invalid error code"))?,
+            message: std::str::from_utf8(message.unwrap_or_default())
+                .map_err(|_| at.error(code_start, "Invalid message"))?,
             hint: vec![],
+            loc: None,
+            length: None,
+        };
+        Ok((error, message.is_none()))
+    }
+
+    /// Matches the error to the next line of `iter` when it is an underline followed by the
+    /// `!!!` lines of the error, in the section of the config file at `code_line`. Returns
+    /// whether it matched. The `!!!` lines fill in the hints like for file errors.
+    fn parse_underlined(
+        &mut self,
+        path: &Path,
+        iter: &mut LineIter<'a>,
+        code_line: u32,
+    ) -> Result<bool, BaselineParseError> {
+        let mut peek = iter.clone();
+        let Some((underline_idx, underline_start, underline)) =
+            peek.next().filter(|x| is_underline(x.2))
+        else {
+            return Ok(false);
+        };
+        // Config errors of the same code differ in their message, like the removed options
+        let marker = peek.next().map(|x| x.2).unwrap_or_default();
+        let matches = split_marker(marker)
+            .filter(|x| x.0 == self.severity)
+            .and_then(|x| x.1.strip_prefix(self.code.as_bytes()))
+            .is_some_and(|x| x.first() == Some(&b':'))
+            && marker_text(marker) == self.message.as_bytes();
+        if !matches {
+            return Ok(false);
+        }
+        iter.next();
+
+        // Underlines start with 4 spaces, columns are 1-based
+        let start = underline.iter().position(|&x| x == b'~').unwrap_or_default();
+        let column = u32::try_from(start.saturating_sub(3)).unwrap_or(u32::MAX);
+        let at = At::new(path, underline_idx, underline_start);
+        self.loc = Some((code_line, column));
+        self.length = match memrchr(b'~', underline) {
+            Some(end) => Some(underline_length(at, underline, end, 2, column)?),
+            None => None,
+        };
+
+        let fill_hints = self.hint.is_empty();
+        let mut message_seen = false;
+        while let Some((line_idx, line_start, line)) = iter.next_starting_with(b'!') {
+            if !fill_hints
+                || split_marker(line).is_none()
+                || !std::mem::replace(&mut message_seen, true)
+            {
+                continue;
+            }
+            let (column, hint, _) = split_hint(marker_text(line));
+            let hint = std::str::from_utf8(hint)
+                .map_err(|_| At::new(path, line_idx, line_start).error(0, "Invalid hint"))?;
+            push_hint_line(&mut self.hint, column, hint);
+        }
+        Ok(true)
+    }
+
+    /// Severity the error is listed with, nearly always [`Severity::Error`].
+    pub fn severity(&self) -> Severity {
+        self.severity
+    }
+
+    /// Error code without the `TS` prefix, like `5102`.
+    pub fn code(&self) -> &'a str {
+        self.code
+    }
+
+    /// First line of the message, without its elaboration chain.
+    pub fn message(&self) -> &'a str {
+        self.message
+    }
+
+    /// Elaboration chain of the message as `(depth, text)` pairs.
+    pub fn hints(&self) -> impl Iterator<Item = (u8, &str)> {
+        self.hint.iter().map(Hint::as_pair)
+    }
+
+    /// 1-based line and column in the `tsconfig.json` section, `None` unless the error is
+    /// underlined there.
+    pub fn loc(&self) -> Option<(u32, u32)> {
+        self.loc
+    }
+
+    /// Length of the underlined span in the `tsconfig.json` section.
+    pub fn length(&self) -> Option<u32> {
+        self.length
+    }
+
+    fn to_diagnostic(&self) -> Diagnostic {
+        Diagnostic {
+            severity: self.severity,
+            code: self.code.parse().unwrap_or_default(),
+            message: join_hint(self.message, &self.hint),
+            ..Diagnostic::default()
         }
     }
 }
@@ -66,648 +453,909 @@ pub struct FileError<'a> {
     // column: u32,
     loc: Option<(u32, u32)>,
     length: Option<u32>,
+    /// The error has its `!!!` line in the section of its file. Errors of files without a
+    /// section, like lib files, only appear in the summary.
+    has_inline_marker: bool,
+    /// [`Severity::Message`] for related information.
+    severity: Severity,
     code: &'a str,
     message: &'a str,
-    hint: Vec<(u8, &'a str)>,
+    hint: Vec<Hint<'a>>,
     related: Vec<Self>,
 }
 
 impl<'a> FileError<'a> {
-    fn parse(path: &'_ Path, line: &'a [u8]) -> Self {
-        let Some(name_end) = memchr(b'(', line) else {
-            panic!(
-                "Failed to find end of file name\n  path: {}\n  line: {}\n      : >",
-                path.display(),
-                std::str::from_utf8(line).unwrap().escape_debug(),
-            );
-        };
+    fn parse(at: At<'_>, line: &'a [u8]) -> Result<Self, BaselineParseError> {
+        let name_end = at.find(line, 0, b'(', "Failed to find end of file name")?;
 
         let line_start = name_end + 1;
-        let Some(line_end) = memchr(b',', &line[line_start..]) else {
-            panic!(
-                "Failed to find end of line number\n  path: {}\n  line: {}\n      : {}>",
-                path.display(),
-                std::str::from_utf8(line).unwrap().escape_debug(),
-                " ".repeat(line_start)
-            );
-        };
+        let line_end = at.find(line, line_start, b',', "Failed to find end of line number")?;
 
         let column_start = line_start + line_end + 1;
-        let Some(column_end) = memchr(b')', &line[column_start..]) else {
-            panic!(
-                "Failed to find end of column number\n  path: {}\n  line: {}\n      : {}>",
-                path.display(),
-                std::str::from_utf8(line).unwrap().escape_debug(),
-                " ".repeat(column_start)
-            );
-        };
+        let column_end =
+            at.find(line, column_start, b')', "Failed to find end of column number")?;
 
-        let code_start = column_start + column_end + 11;
-        let Some(code_end) = memchr(b':', &line[code_start..]) else {
-            panic!(
-                "Failed to find end of error code\n  path: {}\n  line: {}\n      : {}>",
-                path.display(),
-                std::str::from_utf8(line).unwrap().escape_debug(),
-                " ".repeat(code_start)
-            );
+        let severity_start = column_start + column_end + 3;
+        let Some((severity, code)) = line.get(severity_start..).and_then(split_severity) else {
+            return Err(at.error(severity_start, "Failed to find error severity"));
         };
+        let code_start = line.len() - code.len();
+        let code_end = at.find(line, code_start, b':', "Failed to find end of error code")?;
 
         let message_start = code_start + code_end + 2;
-        Self {
-            file: std::str::from_utf8(&line[..name_end]).expect("file name to be UTF8"),
-            loc: if let Ok(line_num) = std::str::from_utf8(&line[line_start..line_start + line_end])
-                .expect("line number to be UTF8")
-                .parse()
-            {
-                let column = std::str::from_utf8(&line[column_start..column_start + column_end])
-                    .expect("column number to be UTF8")
-                    .parse()
-                    .expect("column number to be integer");
-                Some((line_num, column))
-            } else {
-                None
-            },
+        Ok(Self {
+            file: at.str(line, 0..name_end, "Invalid file name")?,
+            loc: at.loc(
+                line,
+                line_start..line_start + line_end,
+                column_start..column_start + column_end,
+            )?,
             length: None,
-            code: std::str::from_utf8(&line[code_start..code_start + code_end])
-                .expect("error code to be UTF8"),
-            message: std::str::from_utf8(&line[message_start..]).expect("message to be UTF8"),
+            has_inline_marker: false,
+            severity,
+            code: at.str(line, code_start..code_start + code_end, "Invalid error code")?,
+            message: at.str(line, message_start..line.len(), "Expected error message")?,
             hint: vec![],
             related: vec![],
-        }
+        })
     }
 
-    fn parse_related(path: &'_ Path, line: &'a [u8], parent: &'_ FileError<'a>) -> Self {
+    fn parse_related(
+        at: At<'_>,
+        line: &'a [u8],
+        parent: &'_ FileError<'a>,
+    ) -> Result<Self, BaselineParseError> {
         let code_start = 14;
-        let Some(code_end) = memchr2(b' ', b':', &line[code_start..]) else {
-            panic!(
-                "Failed to find end of error code\n  path: {}\n  line: {}\n      : >{}",
-                path.display(),
-                std::str::from_utf8(line).unwrap().escape_debug(),
-                " ".repeat(code_start)
-            );
+        let Some(code_end) = line.get(code_start..).and_then(|x| memchr2(b' ', b':', x)) else {
+            return Err(at.error(code_start, "Failed to find end of error code"));
         };
+        let code = at.str(line, code_start..code_start + code_end, "Invalid error code")?;
 
         if line[code_start + code_end] == b' ' {
             let name_start = code_start + code_end + 1;
             let mut delim_iter = memchr_iter(b':', &line[name_start..]);
             let Some(name_end) = delim_iter.next() else {
-                panic!(
-                    "Failed to find end of file name\n  path: {}\n  line: {}\n      : {}>",
-                    path.display(),
-                    std::str::from_utf8(line).unwrap().escape_debug(),
-                    " ".repeat(name_start)
-                );
+                return Err(at.error(name_start, "Failed to find end of file name"));
             };
 
             let line_start = name_start + name_end + 1;
             let Some(line_end) = delim_iter.next() else {
-                panic!(
-                    "Failed to find end of line number\n  path: {}\n  line: {}\n      : {}>",
-                    path.display(),
-                    std::str::from_utf8(line).unwrap().escape_debug(),
-                    " ".repeat(line_start)
-                );
+                return Err(at.error(line_start, "Failed to find end of line number"));
             };
 
             let column_start = name_start + line_end + 1;
             let Some(column_end) = delim_iter.next() else {
-                panic!(
-                    "Failed to find end of column number\n  path: {}\n  line: {}\n      : {}>",
-                    path.display(),
-                    std::str::from_utf8(line).unwrap().escape_debug(),
-                    " ".repeat(column_start)
-                );
+                return Err(at.error(column_start, "Failed to find end of column number"));
             };
 
             let message_start = name_start + column_end + 2;
-            Self {
-                file: std::str::from_utf8(&line[name_start..name_start + name_end])
-                    .expect("file name to be UTF8"),
-                loc: if let Ok(line_num) =
-                    std::str::from_utf8(&line[line_start..name_start + line_end])
-                        .expect("line number to be UTF8")
-                        .parse::<u32>()
-                {
-                    let column = std::str::from_utf8(&line[column_start..name_start + column_end])
-                        .expect("column number to be UTF8")
-                        .parse()
-                        .expect("column number to be integer");
-                    Some((line_num, column))
-                } else {
-                    None
-                },
+            Ok(Self {
+                file: at.str(line, name_start..name_start + name_end, "Invalid file name")?,
+                loc: at.loc(
+                    line,
+                    line_start..name_start + line_end,
+                    column_start..name_start + column_end,
+                )?,
                 length: None,
-                code: std::str::from_utf8(&line[code_start..code_start + code_end])
-                    .expect("error code to be UTF8"),
-                message: std::str::from_utf8(&line[message_start..]).expect("message to be UTF8"),
+                has_inline_marker: true,
+                severity: Severity::Message,
+                code,
+                message: at.str(line, message_start..line.len(), "Expected error message")?,
                 hint: vec![],
                 related: vec![],
-            }
+            })
         } else {
-            let code_end = code_start + code_end;
-            Self {
+            let message_start = code_start + code_end + 2;
+            Ok(Self {
                 file: parent.file,
                 loc: parent.loc,
                 length: parent.length,
-                code: std::str::from_utf8(&line[code_start..code_end])
-                    .expect("error code to be UTF8"),
-                message: std::str::from_utf8(&line[code_end + 2..]).expect("message to be UTF8"),
+                has_inline_marker: true,
+                severity: Severity::Message,
+                code,
+                message: at.str(line, message_start..line.len(), "Expected error message")?,
                 hint: vec![],
                 related: vec![],
-            }
+            })
         }
     }
 
-    fn parse_pretty(path: &'_ Path, line: &'a [u8]) -> Self {
-        let name_start = 5;
-        let mut delim_iter = memchr_iter(b'', &line[name_start..]);
-        let Some(name_end) = delim_iter.next() else {
-            panic!(
-                "Failed to find end of file name\n  path: {}\n  line: {}\n      : {}>",
-                path.display(),
-                std::str::from_utf8(line).unwrap().escape_debug(),
-                " ".repeat(
-                    std::str::from_utf8(&line[..name_start]).unwrap().escape_debug().count()
-                )
-            );
-        };
-
-        let Some(line_end) = delim_iter.nth(2) else {
-            panic!(
-                "Failed to find end of line number\n  path: {}\n  line: {}\n      : {}>",
-                path.display(),
-                std::str::from_utf8(line).unwrap().escape_debug(),
-                " ".repeat(
-                    std::str::from_utf8(&line[..name_start + name_end])
-                        .unwrap()
-                        .escape_debug()
-                        .count()
-                )
-            );
-        };
-
-        let Some(column_end) = delim_iter.next() else {
-            panic!(
-                "Failed to find end of column number\n  path: {}\n  line: {}\n      : {}>",
-                path.display(),
-                std::str::from_utf8(line).unwrap().escape_debug(),
-                " ".repeat(
-                    std::str::from_utf8(&line[..name_start + line_end])
-                        .unwrap()
-                        .escape_debug()
-                        .count()
-                )
-            );
-        };
+    /// Consumes the run of `!!!` lines after the underline of the error: its message with the
+    /// elaboration chain, and `related` lines, which may come in any order. The chain fills in
+    /// the hints of an error the summary listed without any.
+    ///
+    /// The run stops early at the message of the error repeated, which starts the lines of an
+    /// identical error reported again, see [`FileError::is_repeat_of`].
+    fn parse_inline(
+        &mut self,
+        path: &Path,
+        iter: &mut LineIter<'a>,
+    ) -> Result<(), BaselineParseError> {
+        self.has_inline_marker = true;
+        let fill_hints = self.hint.is_empty();
+        let mut message_seen = false;
+        while let Some((line_idx, line_start, line)) = iter.peek_starting_with(b'!') {
+            let at = At::new(path, line_idx, line_start);
+            if line.starts_with(b"!!! related") {
+                let related = Self::parse_related(at, line, self)?;
+                self.related.push(related);
+            } else if split_marker(line).is_some() {
+                let text = marker_text(line);
+                if !std::mem::replace(&mut message_seen, true) {
+                    iter.next();
+                    continue;
+                }
+                if text == self.message.as_bytes() {
+                    break;
+                }
+                if fill_hints {
+                    let (column, hint, _) = split_hint(text);
+                    let hint =
+                        std::str::from_utf8(hint).map_err(|_| at.error(0, "Invalid hint"))?;
+                    push_hint_line(&mut self.hint, column, hint);
+                }
+            }
+            iter.next();
+        }
+        Ok(())
+    }
 
-        let Some(code_start) = delim_iter.nth(2) else {
-            panic!(
-                "Failed to find start of error code\n  path: {}\n  line: {}\n      : {}>",
-                path.display(),
-                std::str::from_utf8(line).unwrap().escape_debug(),
-                " ".repeat(
-                    std::str::from_utf8(&line[..name_start + column_end])
-                        .unwrap()
-                        .escape_debug()
-                        .count()
-                )
-            );
-        };
+    /// TS sometimes reports the same error twice, when two checks flag the same node. The
+    /// repeated summary entry may share the underline of the first, each with its `!!!` lines.
+    fn is_repeat_of(&self, other: &Self) -> bool {
+        (self.file, self.loc, self.code, self.message)
+            == (other.file, other.loc, other.code, other.message)
+    }
 
-        let Some(message_start) = delim_iter.next() else {
-            panic!(
-                "Failed to find start of error message\n  path: {}\n  line: {}\n      : {}>",
-                path.display(),
-                std::str::from_utf8(line).unwrap().escape_debug(),
-                " ".repeat(
-                    std::str::from_utf8(&line[..name_start + code_start])
-                        .unwrap()
-                        .escape_debug()
-                        .count()
-                )
-            );
+    /// Parses the `file.ts:1:7 - error TS2322: message` line starting an error of a pretty
+    /// baseline.
+    fn parse_pretty(at: At<'_>, line: &'a [u8]) -> Result<Self, BaselineParseError> {
+        let line = Stripped::new(at, line);
+        let name_end = line.find(0, b':', "Failed to find end of file name")?;
+        let loc = line.loc(name_end)?;
+
+        let Some((severity, code)) = memchr::memmem::find(&line.text[name_end..], b" - ")
+            .and_then(|x| split_severity(&line.text[name_end + x + 3..]))
+        else {
+            return Err(line.error(name_end, "Failed to find start of error code"));
         };
+        let code_start = line.text.len() - code.len();
+        let code_end =
+            code_start + line.find(code_start, b':', "Failed to find end of error code")?;
 
-        Self {
-            file: std::str::from_utf8(&line[name_start..name_start + name_end])
-                .expect("file name to be UTF8"),
-            loc: if let Ok(line_num) =
-                std::str::from_utf8(&line[name_start + name_end + 10..name_start + line_end - 5])
-                    .expect("line number to be UTF8")
-                    .parse()
-            {
-                let column =
-                    std::str::from_utf8(&line[name_start + line_end + 5..name_start + column_end])
-                        .expect("column number to be UTF8")
-                        .parse()
-                        .expect("column number to be integer");
-                Some((line_num, column))
-            } else {
-                None
-            },
+        Ok(Self {
+            file: line.str(0..name_end, "Invalid file name")?,
+            loc,
             length: None,
-            code: std::str::from_utf8(
-                &line[name_start + code_start + 8..name_start + message_start - 2],
-            )
-            .expect("error code to be UTF8"),
-            message: std::str::from_utf8(&line[name_start + message_start + 4..])
-                .expect("message to be UTF8"),
+            has_inline_marker: false,
+            severity,
+            code: line.str(code_start..code_end, "Invalid error code")?,
+            message: line.str(code_end + 2..line.text.len(), "Invalid message")?,
             hint: vec![],
             related: vec![],
-        }
+        })
     }
 
+    /// Parses the 4 lines of a related error, reporting a missing line at `at`, the line before
+    /// them.
     fn parse_pretty_related<T: Iterator<Item = (usize, usize, &'a [u8])>>(
-        path: &'_ Path,
+        at: At<'_>,
         mut iter: T,
-    ) -> Self {
-        let line = iter.next().expect("related error first line").2;
-        let name_start = 7;
-        let mut delim_iter = memchr_iter(b'', &line[name_start..]);
-
-        let Some(name_end) = delim_iter.next() else {
-            panic!(
-                "Failed to find end of file name\n  path: {}\n  line: {}\n      : {}>",
-                path.display(),
-                std::str::from_utf8(line).unwrap().escape_debug(),
-                " ".repeat(
-                    std::str::from_utf8(&line[..name_start]).unwrap().escape_debug().count()
-                )
-            );
-        };
-
-        let Some(line_end) = delim_iter.nth(2) else {
-            panic!(
-                "Failed to find end of line number\n  path: {}\n  line: {}\n      : {}>",
-                path.display(),
-                std::str::from_utf8(line).unwrap().escape_debug(),
-                " ".repeat(
-                    std::str::from_utf8(&line[..name_start + name_end])
-                        .unwrap()
-                        .escape_debug()
-                        .count()
-                )
-            );
-        };
-
-        let Some(column_end) = delim_iter.next() else {
-            panic!(
-                "Failed to find end of column number\n  path: {}\n  line: {}\n      : {}>",
-                path.display(),
-                std::str::from_utf8(line).unwrap().escape_debug(),
-                " ".repeat(
-                    std::str::from_utf8(&line[..name_start + line_end])
-                        .unwrap()
-                        .escape_debug()
-                        .count()
-                )
-            );
-        };
-
-        let underline = iter.nth(1).expect("related error third line").2;
+    ) -> Result<Self, BaselineParseError> {
+        let missing = |what| at.error(0, &format!("Expected the {what} line of a related error"));
+        let (line_idx, line_start, line) = iter.next().ok_or_else(|| missing("first"))?;
+        let line = Stripped::new(At::new(at.path, line_idx, line_start), line);
+        let name_start = 2;
+        let name_end =
+            name_start + line.find(name_start, b':', "Failed to find end of file name")?;
+
+        let (underline_idx, underline_start, underline) =
+            iter.nth(1).ok_or_else(|| missing("third"))?;
+        let (message_idx, message_start, message) = iter.next().ok_or_else(|| missing("fourth"))?;
 
         let mut err = Self {
-            file: std::str::from_utf8(&line[name_start..name_start + name_end])
-                .expect("file name to be UTF8"),
-            loc: if let Ok(line_num) =
-                std::str::from_utf8(&line[name_start + name_end + 10..name_start + line_end - 5])
-                    .expect("line number to be UTF8")
-                    .parse()
-            {
-                let column =
-                    std::str::from_utf8(&line[name_start + line_end + 5..name_start + column_end])
-                        .expect("column number to be UTF8")
-                        .parse()
-                        .expect("column number to be integer");
-                Some((line_num, column))
-            } else {
-                None
-            },
+            file: line.str(name_start..name_end, "Invalid file name")?,
+            loc: line.loc(name_end)?,
             length: None,
+            has_inline_marker: false,
+            severity: Severity::Message,
             code: "",
-            message: std::str::from_utf8(
-                &iter.next().expect("related error last fourth line").2[4..],
-            )
-            .expect("message to be UTF8"),
+            message: At::new(at.path, message_idx, message_start).str(
+                message,
+                4..message.len(),
+                "Expected related error message",
+            )?,
             hint: vec![],
             related: vec![],
         };
 
         if let Some(loc) = err.loc {
-            err.length = memrchr(b'~', underline).map(
-                #[expect(clippy::cast_possible_truncation)]
-                |x| {
-                    x as u32
-                        - 17
-                        - memchr(b'', &underline[9..]).expect("delimiter after line number") as u32
-                        - loc.1
-                },
-            );
+            let at = At::new(at.path, underline_idx, underline_start);
+            err.length = Stripped::new(at, underline).underline_length(loc.1)?;
         }
 
-        err
+        Ok(err)
     }
 }
 
-impl<'a> ErrorsBaseline<'a> {
-    /// # Panics
-    pub fn parse(path: &'_ Path, data: &'a [u8]) -> Self {
-        if data[0] == 0x1B {
-            return Self::parse_formatted(path, data);
+/// Length of the error underlined up to `end` in the underline `line`, its code starting at
+/// `code_start` and the error at `column`. Every underline span is computed here, so a span
+/// that does not fit its error is reported with the underline instead of wrapping.
+fn underline_length(
+    at: At<'_>,
+    line: &[u8],
+    end: usize,
+    code_start: usize,
+    column: u32,
+) -> Result<u32, BaselineParseError> {
+    end.checked_sub(code_start)
+        .and_then(|x| x.checked_sub(usize::try_from(column).ok()?))
+        .and_then(|x| u32::try_from(x).ok())
+        .ok_or_else(|| BaselineParseError {
+            underline: Some(Box::new(UnderlineMismatch {
+                text: String::from_utf8_lossy(line).into_owned(),
+                column,
+            })),
+            ..at.error(end, "Expected underline to end after the error column")
+        })
+}
+
+impl<'a> FileError<'a> {
+    /// File the error is reported in, as written in the baseline.
+    pub fn file(&self) -> &'a str {
+        self.file
+    }
+
+    /// Severity the error is listed with, [`Severity::Message`] for related information.
+    pub fn severity(&self) -> Severity {
+        self.severity
+    }
+
+    /// Error code without the `TS` prefix, like `2304`.
+    pub fn code(&self) -> &'a str {
+        self.code
+    }
+
+    /// 1-based line and column, `None` for errors reported without a position.
+    pub fn loc(&self) -> Option<(u32, u32)> {
+        self.loc
+    }
+
+    /// Length of the underlined span, `None` when the baseline does not show it.
+    pub fn length(&self) -> Option<u32> {
+        self.length
+    }
+
+    /// Whether the error has its `!!!` line in the section of its file, rather than only
+    /// appearing in the summary.
+    pub fn has_inline_marker(&self) -> bool {
+        self.has_inline_marker
+    }
+
+    /// First line of the message, without its elaboration chain.
+    pub fn message(&self) -> &'a str {
+        self.message
+    }
+
+    /// Elaboration chain of the message as `(depth, text)` pairs. Depth 1 is nested right below
+    /// the message; depth 0 lines continue the message itself.
+    pub fn hints(&self) -> impl Iterator<Item = (u8, &str)> {
+        self.hint.iter().map(Hint::as_pair)
+    }
+
+    /// Related information attached to the error, in baseline order.
+    pub fn related(&self) -> &[Self] {
+        &self.related
+    }
+
+    /// Related information is reported by TS as `!!! related`, which we model as
+    /// [`Severity::Message`] attached to the parent diagnostic.
+    fn to_diagnostic(&self) -> Diagnostic {
+        let (line, column) = self.loc.unwrap_or_default();
+        Diagnostic {
+            severity: self.severity,
+            code: self.code.parse().unwrap_or_default(),
+            file: self.file.to_string(),
+            line,
+            column,
+            length: self.length,
+            message: join_hint(self.message, &self.hint),
+            related: self.related.iter().map(FileError::to_diagnostic).collect(),
         }
+    }
+}
 
-        let mut result = Self::default();
-        let mut iter = LineIter::new(data);
-        while let Some((_line_idx, _line_start, line)) = iter.next() {
+/// Underline lines are indented by 4 spaces and contain nothing but spaces and `~`.
+fn is_underline(line: &[u8]) -> bool {
+    line.starts_with(b"    ")
+        && line.contains(&b'~')
+        && line.iter().all(|&x| x == b' ' || x == b'~' || x == b'\r')
+}
+
+/// A message of the elaboration chain of an error, with its wrapped lines joined in.
+#[derive(Debug, PartialEq, Eq)]
+struct Hint<'a> {
+    /// Indentation of the first line, a tab counting as 2 columns.
+    column: u16,
+    depth: u8,
+    text: Cow<'a, str>,
+}
+
+impl Hint<'_> {
+    fn as_pair(&self) -> (u8, &str) {
+        (self.depth, &self.text)
+    }
+}
+
+/// Splits a hint line into its indentation column and text.
+///
+/// TS indents each level with 2 spaces and a tab counts as 2 columns. The returned flag is set
+/// for an odd number of spaces, so callers can record a warning.
+fn split_hint(line: &[u8]) -> (u16, &[u8], bool) {
+    let hint_start = line.iter().position(|&x| x != b' ' && x != b'\t').unwrap_or(line.len());
+    let indent = &line[..hint_start];
+    let tabs = memchr_iter(b'\t', indent).count();
+    let spaces = indent.len() - tabs;
+    let column = u16::try_from(tabs * 2 + spaces).unwrap_or(u16::MAX);
+    (column, &line[hint_start..], spaces % 2 == 1)
+}
+
+/// Adds a hint line to an elaboration chain.
+///
+/// The depth is relative to the closest previous line indented no further: the same column is
+/// a sibling, less is the parent. TS nests each message only one level deeper than its parent,
+/// so a line indented 4 or more columns past the previous hint continues that hint.
+fn push_hint_line<'a>(hints: &mut Vec<Hint<'a>>, column: u16, text: &'a str) {
+    if let Some(last) =
+        hints.last_mut().filter(|x| x.depth > 0 && column >= x.column.saturating_add(4))
+    {
+        let joined = last.text.to_mut();
+        joined.push('\n');
+        joined.push_str(text);
+        return;
+    }
+
+    let depth = match hints.iter().rev().find(|x| x.column <= column) {
+        Some(sibling) if sibling.column == column => sibling.depth,
+        Some(parent) => parent.depth.saturating_add(1),
+        None => u8::from(column > 0),
+    };
+    hints.push(Hint { column, depth, text: Cow::Borrowed(text) });
+}
+
+/// Joins the message with its elaboration chain the same way TS flattens `DiagnosticMessageChain`.
+/// Wrapped lines of a hint are indented 4 columns past it, the least that still reads as a wrap.
+fn join_hint(message: &str, hint: &[Hint<'_>]) -> String {
+    let mut result = message.to_string();
+    for hint in hint {
+        let indent = "  ".repeat(usize::from(hint.depth));
+        for (idx, line) in hint.text.split('\n').enumerate() {
+            result.push('\n');
+            result.push_str(&indent);
+            if idx > 0 {
+                result.push_str("    ");
+            }
+            result.push_str(line);
+        }
+    }
+
+    result
+}
+
+/// Error of the summary block of an errors baseline, see [`ErrorsBaseline::summary_iter`].
+#[derive(Debug, PartialEq)]
+pub enum SummaryEntry<'a> {
+    Config(ConfigError<'a>),
+    File(FileError<'a>),
+}
+
+/// Parses the summary block of an errors baseline one error at a time, see
+/// [`ErrorsBaseline::summary_iter`].
+pub struct SummaryIter<'p, 'a> {
+    path: &'p Path,
+    data: &'a [u8],
+    iter: LineIter<'a>,
+    pretty: bool,
+    /// Error the hint lines being read belong to, returned once the next error starts.
+    pending: Option<SummaryEntry<'a>>,
+    /// Error to return after the pending one.
+    failed: Option<BaselineParseError>,
+    done: bool,
+    /// Files with errors so far, in the order they are listed.
+    files: Vec<&'a str>,
+    warnings: Vec<ParseWarning>,
+}
+
+impl<'p, 'a> SummaryIter<'p, 'a> {
+    fn new(path: &'p Path, data: &'a [u8]) -> Self {
+        Self {
+            path,
+            data,
+            iter: LineIter::new(data),
+            pretty: data.first() == Some(&0x1B),
+            pending: None,
+            failed: None,
+            done: false,
+            files: vec![],
+            warnings: vec![],
+        }
+    }
+
+    /// Format irregularities worked around in the summary so far.
+    pub fn warnings(&self) -> &[ParseWarning] {
+        &self.warnings
+    }
+
+    fn warn(&mut self, kind: ParseWarningKind, line_idx: usize, line: &[u8]) {
+        self.warnings.push(ParseWarning::new(kind, self.path, line_idx + 1, line));
+    }
+
+    /// Fails when the errors of `file` start again after errors of another file: errors of a
+    /// file are listed next to each other, which matching them to the file blocks relies on.
+    fn check_order(&mut self, at: At<'_>, file: &'a str) -> Result<(), BaselineParseError> {
+        if self.files.last() == Some(&file) {
+            return Ok(());
+        }
+        if self.files.contains(&file) {
+            return Err(at.error(0, "Expected errors of a file to be listed together"));
+        }
+        self.files.push(file);
+        Ok(())
+    }
+
+    /// Attaches a hint or wrapped message line to the pending error, or drops it with a warning
+    /// when no error came before it.
+    fn push_hint(
+        &mut self,
+        at: At<'_>,
+        line: &'a [u8],
+        column: u16,
+        hint: &'a [u8],
+    ) -> Result<(), BaselineParseError> {
+        let hint = std::str::from_utf8(hint).map_err(|_| at.error(0, "Invalid hint"))?;
+        match &mut self.pending {
+            Some(SummaryEntry::Config(err)) => push_hint_line(&mut err.hint, column, hint),
+            Some(SummaryEntry::File(err)) => push_hint_line(&mut err.hint, column, hint),
+            None => self.warn(ParseWarningKind::OrphanHint, at.line_idx, line),
+        }
+        Ok(())
+    }
+
+    /// Reads the lines of a plain baseline up to the next error, adding hints to the pending
+    /// one. Returns `None` at the end of the summary block.
+    fn next_plain(&mut self) -> Result<Option<SummaryEntry<'a>>, BaselineParseError> {
+        while let Some((line_idx, line_start, line)) = self.iter.next() {
+            let at = At::new(self.path, line_idx, line_start);
             if line.is_empty() {
-                let (_, _, line) = iter.next().unwrap();
-                assert!(
-                    line.is_empty(),
-                    "Expected 2 empty lines at the end of summary block:\n  path: {}\n  line: {}",
-                    path.display(),
-                    std::str::from_utf8(line).unwrap().escape_debug()
-                );
-                break;
+                // The summary block ends with 2 empty lines, or right before the first file. A
+                // single empty line only separates groups of errors.
+                match self.data.get(self.iter.line_start..) {
+                    None | Some([] | [b'\n' | b'\r', ..]) => {
+                        self.iter.next();
+                        return Ok(None);
+                    }
+                    Some(next) if next.starts_with(b"====") => return Ok(None),
+                    Some(_) => continue,
+                }
             }
 
             match line[0] {
-                b' ' => {
-                    let err = if result.file_errors.is_empty() {
-                        &mut result
-                            .config_errors
-                            .last_mut()
-                            .expect("Error to be created before hint line")
-                            .hint
-                    } else {
-                        &mut result
-                            .file_errors
-                            .last_mut()
-                            .expect("Error to be created before hint line")
-                            .hint
-                    };
-
-                    let hint = trim_space_start(line);
-                    let spaces = line.len() - hint.len();
-
-                    #[expect(clippy::cast_possible_truncation)]
-                    err.push((
-                        (spaces / 2) as u8,
-                        std::str::from_utf8(hint).expect("hint to be UTF8"),
-                    ));
+                b' ' | b'\t' => {
+                    let (column, hint, odd) = split_hint(line);
+                    if odd {
+                        self.warn(ParseWarningKind::OddHintIndent, line_idx, line);
+                    }
+                    self.push_hint(at, line, column, hint)?;
                 }
                 _ => {
-                    if line.starts_with(b"error TS") {
-                        assert!(
-                            result.file_errors.is_empty(),
-                            "Expected all config errors to be before any file errors\n  path: {}\n  line: {}",
-                            path.display(),
-                            std::str::from_utf8(line).unwrap().escape_debug()
-                        );
-                        result.config_errors.push(ConfigError::parse(path, line));
-                    } else {
-                        result.file_errors.push(FileError::parse(path, line));
+                    if split_severity(line).is_some() {
+                        if !self.files.is_empty() {
+                            return Err(at.error(
+                                0,
+                                "Expected all config errors to be before any file errors",
+                            ));
+                        }
+                        let (err, malformed) = ConfigError::parse(at, line)?;
+                        if malformed {
+                            self.warn(ParseWarningKind::MalformedConfigError, line_idx, line);
+                        }
+                        return Ok(Some(SummaryEntry::Config(err)));
+                    } else if memchr::memmem::find_iter(line, b"): ")
+                        .any(|x| split_severity(&line[x + 3..]).is_some())
+                    {
+                        let err = FileError::parse(at, line)?;
+                        self.check_order(at, err.file)?;
+                        return Ok(Some(SummaryEntry::File(err)));
+                    }
+                    // Messages with a line break continue on the next line, unindented
+                    self.warn(ParseWarningKind::WrappedMessage, line_idx, line);
+                    self.push_hint(at, line, 0, line)?;
+                }
+            }
+        }
+        Ok(None)
+    }
+
+    /// Parses the next error of a pretty baseline with its hints and related errors. Returns
+    /// `None` at the first line without escape sequences, which ends the summary block.
+    fn next_pretty(&mut self) -> Result<Option<SummaryEntry<'a>>, BaselineParseError> {
+        // Lines with escape sequences are split on their text, see `Stripped`
+        let (path, data) = (self.path, self.data);
+        let Some((line_idx, line_start, line)) = self.iter.next() else {
+            return Ok(None);
+        };
+        if line.first() != Some(&0x1B) {
+            return Ok(None);
+        }
+
+        let at = At::new(path, line_idx, line_start);
+        let mut err = FileError::parse_pretty(at, line)?;
+        self.check_order(at, err.file)?;
+
+        let mut last_at = at;
+        while let Some((line_idx, line_start, line)) = self.iter.next() {
+            last_at = At::new(path, line_idx, line_start);
+            if data.get(self.iter.line_start) == Some(&0x1B) {
+                self.iter.next();
+                break;
+            }
+
+            let (column, hint, odd) = split_hint(line);
+            if odd {
+                self.warn(ParseWarningKind::OddHintIndent, line_idx, line);
+            }
+            let hint = std::str::from_utf8(hint).map_err(|_| last_at.error(0, "Invalid hint"))?;
+            push_hint_line(&mut err.hint, column, hint);
+        }
+
+        let Some((underline_idx, underline_start, underline)) = self.iter.next() else {
+            return Err(last_at.error(0, "Expected underline line after the code line"));
+        };
+        if let Some(loc) = err.loc {
+            let at = At::new(path, underline_idx, underline_start);
+            err.length = Stripped::new(at, underline).underline_length(loc.1)?;
+        }
+
+        // Next line starts a new error
+        if data.get(self.iter.line_start) == Some(&0x1B) {
+            return Ok(Some(SummaryEntry::File(err)));
+        }
+
+        // Skip empty line
+        let at = At::new(path, underline_idx, underline_start);
+        self.iter.next();
+
+        // Parse related errors
+        while data.get(self.iter.line_start..).is_some_and(|x| x.starts_with(b"  ")) {
+            let related = FileError::parse_pretty_related(at, self.iter.by_ref().take(4))?;
+            err.related.push(related);
+        }
+
+        Ok(Some(SummaryEntry::File(err)))
+    }
+}
+
+impl<'a> Iterator for SummaryIter<'_, 'a> {
+    type Item = Result<SummaryEntry<'a>, BaselineParseError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some(err) = self.failed.take() {
+            return Some(Err(err));
+        }
+        while !self.done {
+            let next = if self.pretty { self.next_pretty() } else { self.next_plain() };
+            match next {
+                Ok(Some(entry)) => {
+                    if let Some(pending) = self.pending.replace(entry) {
+                        return Some(Ok(pending));
                     }
                 }
+                Ok(None) => self.done = true,
+                Err(err) => {
+                    self.done = true;
+                    self.failed = Some(err);
+                }
+            }
+        }
+        self.pending.take().map(Ok).or_else(|| self.failed.take().map(Err))
+    }
+}
+
+impl<'a> ErrorsBaseline<'a> {
+    /// Format irregularities worked around while parsing.
+    pub fn warnings(&self) -> &[ParseWarning] {
+        &self.warnings
+    }
+
+    /// Errors reported without a file, like invalid compiler options.
+    pub fn config_errors(&self) -> &[ConfigError<'a>] {
+        &self.config_errors
+    }
+
+    /// Errors listed in the summary only, because their file has no section: lib files, or test
+    /// files older baselines leave out when they have no errors in them. Every other error has
+    /// its `!!!` line in the section of its file.
+    pub fn unmatched_summary_errors(&self) -> impl Iterator<Item = &FileError<'a>> {
+        self.file_errors.iter().filter(|x| !x.has_inline_marker)
+    }
+
+    /// Errors of `file` in baseline order, empty when it has none.
+    pub fn errors_for_file(&self, file: &str) -> &[FileError<'a>] {
+        let errors = &self.file_errors;
+        let start = errors.iter().position(|x| x.file == file).unwrap_or(errors.len());
+        let len = errors[start..].iter().take_while(|x| x.file == file).count();
+        &errors[start..start + len]
+    }
+
+    /// Number of config and file errors, not counting related information.
+    pub fn total_count(&self) -> usize {
+        self.config_errors.len() + self.file_errors.len()
+    }
+
+    /// Numbers of errors the file headers and the total of pretty baselines declare.
+    pub fn declared_counts(&self) -> &[DeclaredCount<'a>] {
+        &self.declared_counts
+    }
+
+    /// Declared numbers of errors that differ from the number of errors parsed, which points
+    /// at errors the parser dropped or a malformed baseline.
+    pub fn validate(&self) -> Vec<CountMismatch<'a>> {
+        let mismatch = |declared: &DeclaredCount<'a>| {
+            let parsed = match declared.file {
+                Some(file) if file_order::is_config_file(file) => {
+                    let underlined = self.config_errors.iter().filter(|x| x.loc.is_some());
+                    self.errors_for_file(file).len() + underlined.count()
+                }
+                Some(file) => self.errors_for_file(file).len(),
+                None => self.total_count(),
+            };
+            (parsed != declared.count).then_some(CountMismatch { declared: *declared, parsed })
+        };
+        self.declared_counts.iter().filter_map(mismatch).collect()
+    }
+
+    /// Expected diagnostics: config errors first, followed by file errors in baseline order.
+    pub fn to_diagnostics(&self) -> Vec<Diagnostic> {
+        self.config_errors
+            .iter()
+            .map(ConfigError::to_diagnostic)
+            .chain(self.file_errors.iter().map(FileError::to_diagnostic))
+            .collect()
+    }
+
+    /// Parses the summary block of `data` one error at a time, without matching the errors to
+    /// the sections of their files: the errors of plain baselines have no length, and related
+    /// errors of pretty ones no code. Errors come in baseline order; the iterator ends after
+    /// the first `Err`.
+    pub fn summary_iter<'p>(path: &'p Path, data: &'a [u8]) -> SummaryIter<'p, 'a> {
+        SummaryIter::new(path, data)
+    }
+
+    /// Collects the errors of the summary block, returning the lines after it.
+    fn parse_summary(
+        &mut self,
+        path: &Path,
+        data: &'a [u8],
+    ) -> Result<LineIter<'a>, BaselineParseError> {
+        let mut summary = SummaryIter::new(path, data);
+        for entry in summary.by_ref() {
+            match entry? {
+                SummaryEntry::Config(err) => self.config_errors.push(err),
+                SummaryEntry::File(err) => self.file_errors.push(err),
             }
         }
+        self.warnings = summary.warnings;
+        Ok(summary.iter)
+    }
+
+    /// # Errors
+    ///
+    /// Will return `Err` with the line of the baseline that does not have the expected format.
+    pub fn parse(path: &'_ Path, data: &'a [u8]) -> Result<Self, BaselineParseError> {
+        if data.first() == Some(&0x1B) {
+            return Self::parse_formatted(path, data);
+        }
+
+        let mut result = Self::default();
+        let mut iter = result.parse_summary(path, data)?;
 
         // Skip until we encounter the first file: ==== file.ts (0 errors) ====
-        let mut file = "";
-        for (_, _, line) in iter.by_ref() {
+        let mut file = None;
+        for (line_idx, line_start, line) in iter.by_ref() {
             if !line.is_empty() && line[0] == b'=' {
-                file = std::str::from_utf8(
-                    &line[5..5 + memchr(b' ', &line[5..])
-                        .expect("file name to be followed by space")],
-                )
-                .expect("file name to be UTF8");
-                if file.starts_with("./") {
-                    file = &file[2..];
-                }
+                let (name, count) = file_header(At::new(path, line_idx, line_start), line)?;
+                result
+                    .declared_counts
+                    .extend(count.map(|x| DeclaredCount::new(Some(name), x, line_idx)));
+                file = Some(name);
                 break;
             }
         }
-
-        let mut err_queue = {
-            let partition_start =
-                result.file_errors.partition_point(|x| cmp_file(x.file, file).is_lt());
-            let err = &mut result.file_errors[partition_start..];
-            let partition_end = err.partition_point(|x| cmp_file(x.file, file).is_le());
-            err[..partition_end].iter_mut().collect::<VecDeque<_>>()
+        let Some(mut file) = file else {
+            return Err(
+                end_of(path, data).error(0, "Expected a `====` file header after the errors")
+            );
         };
-        assert!(
-            err_queue.iter().all(|x| x.file == file),
-            "Expected errors to be ordered:\n  path: {}\n  file: {}",
-            path.display(),
-            file
-        );
+
+        // Indices of the errors of the current file still to be matched, in baseline order
+        let mut ranges = file_ranges(&result.file_errors);
+        let mut err_queue: VecDeque<_> = ranges.remove(file).unwrap_or_default().collect();
+        let mut config_queue: VecDeque<_> = result.config_errors.iter_mut().collect();
         let mut code_line = 0u32;
-        while let Some((_, _, line)) = iter.next() {
+        while let Some((line_idx, line_start, line)) = iter.next() {
+            let at = At::new(path, line_idx, line_start);
             if !line.is_empty() && line[0] == b'=' {
-                file = std::str::from_utf8(
-                    &line[5..5 + memchr(b' ', &line[5..])
-                        .expect("file name to be followed by space")],
-                )
-                .expect("file name to be UTF8");
-                if file.starts_with("./") {
-                    file = &file[2..];
-                }
-                err_queue = {
-                    let partition_start =
-                        result.file_errors.partition_point(|x| cmp_file(x.file, file).is_lt());
-                    let err = &mut result.file_errors[partition_start..];
-                    let partition_end = err.partition_point(|x| cmp_file(x.file, file).is_le());
-                    err[..partition_end].iter_mut().collect::<VecDeque<_>>()
-                };
-                assert!(
-                    err_queue.iter().all(|x| x.file == file),
-                    "Expected errors to be ordered:\n  path: {}\n  file: {}",
-                    path.display(),
-                    file
-                );
+                let count;
+                (file, count) = file_header(at, line)?;
+                result
+                    .declared_counts
+                    .extend(count.map(|x| DeclaredCount::new(Some(file), x, line_idx)));
+                err_queue.clear();
+                err_queue.extend(ranges.remove(file).unwrap_or_default());
                 code_line = 0;
                 continue;
             }
 
-            if err_queue.is_empty() {
+            let config_file = file_order::is_config_file(file) && !config_queue.is_empty();
+            if err_queue.is_empty() && !config_file {
                 continue;
             }
 
-            code_line += 1;
-
-            let mut err_done: Vec<usize> = vec![];
-            for (idx, err) in err_queue.iter_mut().enumerate().take_while(|(_, err)| {
-        let Some(loc) = err.loc else {
-          panic!(
-            "Expected error location to exist:\n  path: {}\n  err: {:?}\n  file: {}\n  line: {}",
-            path.display(),
-            err,
-            file,
-            std::str::from_utf8(line).unwrap().escape_debug()
-          );
-        };
-        loc.0 <= code_line
-      }) {
-        let loc = err.loc.expect("error location to exist");
-        let last_line = iter.next().expect("underline line to exist").2;
-        assert!(data.len() > iter.line_start,
-          "Expected error or code line after underline:\n  path: {}\n  err: {:?}\n  line({:>2}): {}\n  ____    : {}\n",
-          path.display(),
-          err,
-          code_line,
-          std::str::from_utf8(line).unwrap().escape_debug(),
-          std::str::from_utf8(last_line).unwrap().escape_debug(),
-        );
-        if data[iter.line_start] == b'!' {
-          err_done.push(idx);
-          if code_line == loc.0 {
-            err.length = memrchr(b'~', last_line).map(
-              #[expect(clippy::cast_possible_truncation, clippy::cast_possible_wrap)]
-              |x| (x as u32).checked_sub(2+loc.1).unwrap_or_else(|| panic!(
-                "Expected length to be positive:\n  path: {}\n  err: {:?}\n  line:  {}\n  line: {}\n  expr: {} - 2 - {} = {}",
-                path.display(),
-                err,
-                std::str::from_utf8(last_line).unwrap().escape_debug(),
-                std::str::from_utf8(line).unwrap().escape_debug(),
-                x, loc.1, x as i32 - 2 - loc.1 as i32
-              )),
-            );
-          }
-
-          while iter.line_start < data.len() && data[iter.line_start] == b'!' {
-            let (_, _, line) = iter.next().unwrap();
-            if line[4] != b'r' {
-              continue;
-            }
-
-            err.related.push(FileError::parse_related(path, line, err));
-          }
-        }
-      }
-
-            for (counter, idx) in err_done.into_iter().enumerate() {
-                err_queue.remove(idx - counter);
-            }
-        }
-
-        result
-    }
-
-    fn parse_formatted(path: &'_ Path, data: &'a [u8]) -> Self {
-        // Need to skip ANSI escape sequences: \u001b[.{1,2}m
-        // Starts with `0x1B` (ESC), followed by `[`, followed by 1-2 digits and termiated by `m`
-        let mut result = Self::default();
-        let mut iter = LineIter::new(data);
-        while let Some((_, _, line)) = iter.next() {
-            if !line.starts_with(b"[96m") {
-                break;
+            // Errors at the very start of a file may have their underline and `!!!` block right
+            // after the file header, before any code line
+            if code_line == 0
+                && !err_queue.is_empty()
+                && is_underline(line)
+                && data.get(iter.line_start) == Some(&b'!')
+            {
+                let Some((first, loc)) = err_queue.pop_front().and_then(|x| {
+                    result.file_errors[x].loc.filter(|x| x.0 == 1).map(|loc| (x, loc))
+                }) else {
+                    return Err(
+                        at.error(0, "Expected error on the first line before first code line")
+                    );
+                };
+                let err = &mut result.file_errors[first];
+                err.length = match memrchr(b'~', line) {
+                    Some(end) => Some(underline_length(at, line, end, 2, loc.1)?),
+                    None => None,
+                };
+                err.parse_inline(path, &mut iter)?;
+                while let Some(idx) = err_queue.pop_front() {
+                    let (before, rest) = result.file_errors.split_at_mut(idx);
+                    let (err, repeat) = (&before[first], &mut rest[0]);
+                    if !repeat.is_repeat_of(err) || iter.peek_starting_with(b'!').is_none() {
+                        err_queue.push_front(idx);
+                        break;
+                    }
+                    repeat.length = err.length;
+                    repeat.parse_inline(path, &mut iter)?;
+                }
+                continue;
             }
 
-            let mut err = FileError::parse_pretty(path, line);
+            code_line += 1;
 
-            while let Some((_, _, line)) = iter.next() {
-                if data[iter.line_start] == b'' {
-                    iter.next();
+            let mut err_done: Vec<usize> = vec![];
+            for idx in 0..err_queue.len() {
+                let (before, rest) = result.file_errors.split_at_mut(err_queue[idx]);
+                let previous = idx.checked_sub(1).map(|x| &before[err_queue[x]]);
+                let err = &mut rest[0];
+                let Some(loc) = err.loc else {
+                    return Err(at.error(0, "Expected error location to exist"));
+                };
+                if loc.0 > code_line {
                     break;
                 }
 
-                let hint = trim_space_start(line);
-                let spaces = line.len() - hint.len();
+                // Without an underline of its own, a repeat shares the one of the first
+                let own_underline =
+                    iter.peek_starting_with(b' ').is_some_and(|x| is_underline(x.2));
+                if let Some(first) = previous.filter(|x| err.is_repeat_of(x) && !own_underline) {
+                    err.length = first.length;
+                    if err_done.last() == Some(&(idx - 1)) {
+                        err_done.push(idx);
+                        err.parse_inline(path, &mut iter)?;
+                    }
+                    continue;
+                }
 
-                #[expect(clippy::cast_possible_truncation)]
-                err.hint.push((
-                    (spaces / 2) as u8,
-                    std::str::from_utf8(hint).expect("hint to be UTF8"),
-                ));
+                let Some((underline_idx, underline_start, last_line)) = iter.next() else {
+                    return Err(at.error(line.len(), "Expected underline line to exist"));
+                };
+                let underline_at = At::new(path, underline_idx, underline_start);
+                if data.len() <= iter.line_start {
+                    return Err(underline_at
+                        .error(last_line.len(), "Expected error or code line after underline"));
+                }
+                // An error spanning several lines is underlined on each of them, up to the end of
+                // the line until its last one. The line breaks count as one character.
+                let length = if code_line == loc.0 {
+                    match memrchr(b'~', last_line) {
+                        Some(end) => {
+                            Some(underline_length(underline_at, last_line, end, 2, loc.1)?)
+                        }
+                        None => None,
+                    }
+                } else {
+                    let squiggles = memchr_iter(b'~', last_line).count();
+                    let squiggles = u32::try_from(squiggles).unwrap_or(u32::MAX);
+                    Some(err.length.unwrap_or_default().saturating_add(squiggles))
+                };
+                if data[iter.line_start] == b'!' {
+                    err_done.push(idx);
+                    err.length = length;
+                    err.parse_inline(path, &mut iter)?;
+                } else {
+                    err.length = length.map(|x| x.saturating_add(1));
+                }
             }
 
-            let underline = iter.next().expect("underline to exist").2;
-            if let Some(loc) = err.loc {
-                err.length = memrchr(b'~', underline).map(
-                    #[expect(clippy::cast_possible_truncation)]
-                    |x| {
-                        x as u32
-                            - 13
-                            - memchr(b'', &underline[5..]).expect("delimiter after line number")
-                                as u32
-                            - loc.1
-                    },
-                );
+            for (counter, idx) in err_done.into_iter().enumerate() {
+                err_queue.remove(idx - counter);
             }
 
-            // Next line start a new error
-            if data[iter.line_start] == b'' {
-                continue;
+            // Not every config error is underlined, any of them may be next
+            let mut idx = 0;
+            while config_file && idx < config_queue.len() {
+                if config_queue[idx].parse_underlined(path, &mut iter, code_line)? {
+                    config_queue.remove(idx);
+                    idx = 0;
+                } else {
+                    idx += 1;
+                }
             }
+        }
 
-            // Skip empty line
-            iter.next();
-
-            // Parse related errors
-            while &data[iter.line_start..iter.line_start + 3] == b"  " {
-                let related = FileError::parse_pretty_related(path, iter.by_ref().take(4));
-                err.related.push(related);
-            }
+        let not_printed = ranges.into_keys().collect();
+        check_printed(end_of(path, data), &result.file_errors, &not_printed)?;
+        Ok(result)
+    }
 
-            result.file_errors.push(err);
-        }
+    fn parse_formatted(path: &'_ Path, data: &'a [u8]) -> Result<Self, BaselineParseError> {
+        let mut result = Self::default();
+        let mut iter = result.parse_summary(path, data)?;
 
         // Skip until we encounter the first file: ==== file.ts (0 errors) ====
-        let mut file = "";
-        for (_, _, line) in iter.by_ref() {
+        let mut file = None;
+        for (line_idx, line_start, line) in iter.by_ref() {
             if !line.is_empty() && line[0] == b'=' {
-                file = std::str::from_utf8(
-                    &line[5..5 + memchr(b' ', &line[5..])
-                        .expect("file name to be followed by space")],
-                )
-                .expect("file name to be UTF8");
-                if file.starts_with("./") {
-                    file = &file[2..];
-                }
+                let (name, count) = file_header(At::new(path, line_idx, line_start), line)?;
+                result
+                    .declared_counts
+                    .extend(count.map(|x| DeclaredCount::new(Some(name), x, line_idx)));
+                file = Some(name);
                 break;
             }
         }
-
-        let mut err_queue = {
-            let partition_start =
-                result.file_errors.partition_point(|x| cmp_file(x.file, file).is_lt());
-            let err = &mut result.file_errors[partition_start..];
-            let partition_end = err.partition_point(|x| cmp_file(x.file, file).is_le());
-            err[..partition_end].iter_mut().collect::<VecDeque<_>>()
+        let Some(mut file) = file else {
+            return Err(
+                end_of(path, data).error(0, "Expected a `====` file header after the errors")
+            );
         };
-        assert!(
-            err_queue.iter().all(|x| x.file == file),
-            "Expected errors to be ordered:\n  path: {}\n  file: {}",
-            path.display(),
-            file
-        );
+
+        let mut ranges = file_ranges(&result.file_errors);
+        let mut err_queue: VecDeque<_> = ranges.remove(file).unwrap_or_default().collect();
         let mut code_line = 0u32;
-        while let Some((_, _, line)) = iter.next() {
+        while let Some((line_idx, line_start, line)) = iter.next() {
+            let at = At::new(path, line_idx, line_start);
             if !line.is_empty() && line[0] == b'=' {
-                file = std::str::from_utf8(
-                    &line[5..5 + memchr(b' ', &line[5..])
-                        .expect("file name to be followed by space")],
-                )
-                .expect("file name to be UTF8");
-                if file.starts_with("./") {
-                    file = &file[2..];
-                }
-                err_queue = {
-                    let partition_start =
-                        result.file_errors.partition_point(|x| cmp_file(x.file, file).is_lt());
-                    let err = &mut result.file_errors[partition_start..];
-                    let partition_end = err.partition_point(|x| cmp_file(x.file, file).is_le());
-                    err[..partition_end].iter_mut().collect::<VecDeque<_>>()
-                };
-                assert!(
-                    err_queue.iter().all(|x| x.file == file),
-                    "Expected errors to be ordered:\n  path: {}\n  file: {}",
-                    path.display(),
-                    file
-                );
+                let count;
+                (file, count) = file_header(at, line)?;
+                result
+                    .declared_counts
+                    .extend(count.map(|x| DeclaredCount::new(Some(file), x, line_idx)));
+                err_queue.clear();
+                err_queue.extend(ranges.remove(file).unwrap_or_default());
                 code_line = 0;
                 continue;
             }
 
+            if let Some(count) = found_count(line) {
+                result.declared_counts.push(DeclaredCount::new(None, count, line_idx));
+                continue;
+            }
+
             if err_queue.is_empty() {
                 continue;
             }
@@ -715,27 +1363,44 @@ impl<'a> ErrorsBaseline<'a> {
             code_line += 1;
 
             let mut err_done: Vec<usize> = vec![];
-            for (idx, err) in err_queue
-                .iter_mut()
-                .enumerate()
-                .take_while(|(_, err)| err.loc.expect("error location to exist").0 <= code_line)
-            {
-                if data[iter.line_start] == b'!' {
+            for idx in 0..err_queue.len() {
+                let (before, rest) = result.file_errors.split_at_mut(err_queue[idx]);
+                let previous = idx.checked_sub(1).map(|x| &before[err_queue[x]]);
+                let err = &mut rest[0];
+                let Some(loc) = err.loc else {
+                    return Err(at.error(0, "Expected error location to exist"));
+                };
+                if loc.0 > code_line {
+                    break;
+                }
+
+                // Shares the `!!!` lines of the first, which were all consumed with it
+                if previous.is_some_and(|x| err.is_repeat_of(x))
+                    && err_done.last() == Some(&(idx - 1))
+                {
+                    err_done.push(idx);
+                    err.has_inline_marker = true;
+                    continue;
+                }
+
+                if data.get(iter.line_start) == Some(&b'!') {
                     err_done.push(idx);
+                    err.has_inline_marker = true;
 
                     let mut related = err.related.iter_mut();
-                    while iter.line_start < data.len() && data[iter.line_start] == b'!' {
-                        let (_, _, line) = iter.next().unwrap();
-                        if line[4] != b'r' {
+                    while let Some((line_idx, line_start, line)) = iter.next_starting_with(b'!') {
+                        if line.get(4) != Some(&b'r') {
                             continue;
                         }
 
-                        let related = related.next().expect("number of related errors to match");
-                        related.code = std::str::from_utf8(
-                            &line[14..14
-                                + memchr(b' ', &line[14..]).expect("error code to end with space")],
-                        )
-                        .expect("error code to be UTF8");
+                        let at = At::new(path, line_idx, line_start);
+                        let Some(related) = related.next() else {
+                            return Err(at.error(0, "Expected number of related errors to match"));
+                        };
+                        let code_end = 14
+                            + at.find(line, 14, b' ', "Expected error code to end with space")?;
+                        related.code = at.str(line, 14..code_end, "Invalid error code")?;
+                        related.has_inline_marker = true;
                     }
                 }
             }
@@ -745,7 +1410,9 @@ impl<'a> ErrorsBaseline<'a> {
             }
         }
 
-        result
+        let not_printed = ranges.into_keys().collect();
+        check_printed(end_of(path, data), &result.file_errors, &not_printed)?;
+        Ok(result)
     }
 }
 
@@ -755,6 +1422,183 @@ mod tests {
 
     use super::*;
 
+    fn crlf(data: &[u8]) -> Vec<u8> {
+        let mut result = Vec::with_capacity(data.len() + data.len() / 16);
+        for &x in data {
+            if x == b'\n' {
+                result.push(b'\r');
+            }
+            result.push(x);
+        }
+        result
+    }
+
+    /// Converts the summary block and the file sections (from the first `====` header on) to the
+    /// given line endings separately, like some reference baselines are.
+    fn mixed(data: &[u8], summary_crlf: bool, sections_crlf: bool) -> Vec<u8> {
+        let split = memchr::memmem::find(data, b"\n====").map_or(data.len(), |x| x + 1);
+        let (summary, sections) = data.split_at(split);
+        let mut result = if summary_crlf { crlf(summary) } else { summary.to_vec() };
+        result.extend(if sections_crlf { crlf(sections) } else { sections.to_vec() });
+        result
+    }
+
+    /// Colors of a pretty baseline as 256-color sequences, with the error severity in bold.
+    fn colors_256(data: &[u8]) -> Vec<u8> {
+        let colors: [(&[u8], &[u8]); 4] = [
+            (b"\x1b[96m", b"\x1b[38;5;14m"),
+            (b"\x1b[93m", b"\x1b[38;5;11m"),
+            (b"\x1b[91m", b"\x1b[1m\x1b[38;5;9m"),
+            (b"\x1b[90m", b"\x1b[38;5;8m"),
+        ];
+        let mut result = Vec::with_capacity(data.len() * 2);
+        let mut rest = data;
+        while let Some(&first) = rest.first() {
+            if let Some((from, to)) = colors.iter().find(|(from, _)| rest.starts_with(from)) {
+                result.extend_from_slice(to);
+                rest = &rest[from.len()..];
+            } else {
+                result.push(first);
+                rest = &rest[1..];
+            }
+        }
+        result
+    }
+
+    /// Source files of the `====` sections of a baseline, without the underlines and `!!!` lines
+    /// of their errors. Like when parsing, an error is underlined on every line up to its `!!!`
+    /// lines, which tells apart the blank underlines of empty lines.
+    fn sources(data: &[u8]) -> Vec<(&str, String)> {
+        let lines: Vec<_> = std::str::from_utf8(data).unwrap().lines().collect();
+        let is_marker = |idx: usize| lines.get(idx).is_some_and(|x| x.starts_with("!!!"));
+        let mut result: Vec<(&str, Vec<&str>)> = vec![];
+        let mut open = 0;
+        let mut idx = 0;
+        while let Some(&line) = lines.get(idx) {
+            idx += 1;
+            if let Some(header) = line.strip_prefix("==== ") {
+                result.push((header.split(' ').next().unwrap(), vec![]));
+                open = 0;
+                continue;
+            }
+            let Some((_, code)) = result.last_mut() else {
+                continue;
+            };
+            if is_underline(line.as_bytes()) && is_marker(idx) {
+                // Error on the first line, before any code line
+                while is_marker(idx) {
+                    idx += 1;
+                }
+                continue;
+            }
+            if !line.is_empty() && !line.starts_with("    ") {
+                continue;
+            }
+
+            code.push(line.strip_prefix("    ").unwrap_or(line));
+            let mut continuing = open;
+            while let Some(&next) = lines.get(idx) {
+                let blank = next.trim().is_empty();
+                if !is_underline(next.as_bytes())
+                    && !(blank && (continuing > 0 || is_marker(idx + 1)))
+                {
+                    break;
+                }
+                idx += 1;
+                let done = is_marker(idx);
+                while is_marker(idx) {
+                    idx += 1;
+                }
+                if continuing > 0 {
+                    continuing -= 1;
+                    open -= usize::from(done);
+                } else {
+                    open += usize::from(!done);
+                }
+            }
+        }
+        result.into_iter().map(|(name, lines)| (name, lines.join("\n"))).collect()
+    }
+
+    /// What the plain format keeps of a baseline, which has no lengths for related errors. The
+    /// counts of the sections are written as they are, not as declared.
+    fn plain(baseline: &ErrorsBaseline<'_>) -> impl PartialEq + std::fmt::Debug {
+        let mut diagnostics = baseline.to_diagnostics();
+        for diagnostic in &mut diagnostics {
+            for related in &mut diagnostic.related {
+                related.length = None;
+            }
+        }
+        let markers: Vec<_> = baseline.file_errors.iter().map(|x| x.has_inline_marker).collect();
+        let sections: Vec<_> = baseline.declared_counts.iter().filter_map(|x| x.file).collect();
+        (diagnostics, markers, sections)
+    }
+
+    /// Asserts that the summary iterator gives the errors of the full parse. Their hints and
+    /// related errors may come from the `!!!` lines of the sections, which it does not read.
+    fn assert_summary<'a>(path: &Path, data: &'a [u8], baseline: &ErrorsBaseline<'a>) {
+        let mut summary = ErrorsBaseline::summary_iter(path, data);
+        let (mut config_errors, mut file_errors) = (vec![], vec![]);
+        for entry in summary.by_ref() {
+            match entry.unwrap() {
+                SummaryEntry::Config(err) => config_errors.push(err),
+                SummaryEntry::File(err) => file_errors.push(err),
+            }
+        }
+        assert!(baseline.warnings.starts_with(summary.warnings()));
+
+        let config = |x: &ConfigError<'a>| (x.severity, x.code, x.message);
+        assert_eq!(
+            config_errors.iter().map(config).collect::<Vec<_>>(),
+            baseline.config_errors.iter().map(config).collect::<Vec<_>>()
+        );
+        let file = |x: &FileError<'a>| (x.severity, x.file, x.loc, x.code, x.message);
+        assert_eq!(
+            file_errors.iter().map(file).collect::<Vec<_>>(),
+            baseline.file_errors.iter().map(file).collect::<Vec<_>>()
+        );
+
+        let config_hints = config_errors.iter().zip(&baseline.config_errors);
+        for (summary, full) in config_hints.map(|(a, b)| (&a.hint, &b.hint)) {
+            assert!(summary.is_empty() || summary == full, "{summary:?} {full:?}");
+        }
+        for (summary, full) in file_errors.iter().zip(&baseline.file_errors) {
+            assert!(summary.hint.is_empty() || summary.hint == full.hint);
+            let related = |x: &FileError<'a>| (x.file, x.loc, x.message);
+            assert!(
+                summary.related.is_empty()
+                    || summary.related.iter().map(related).eq(full.related.iter().map(related))
+            );
+        }
+    }
+
+    /// Parses `data`, asserting that CRLF and mixed line endings, and 256 colors for pretty
+    /// baselines, give the same result, that it is written back to an equal baseline, and that
+    /// the summary iterator agrees with it.
+    fn parse<'a>(path: &Path, data: &'a [u8]) -> ErrorsBaseline<'a> {
+        let baseline = ErrorsBaseline::parse(path, data).unwrap();
+        assert_summary(path, data, &baseline);
+        let files = sources(data);
+        let text =
+            baseline.to_string(&|name| files.iter().find(|x| x.0 == name).map(|x| x.1.as_str()));
+        let written = ErrorsBaseline::parse(path, text.as_bytes())
+            .unwrap_or_else(|err| panic!("{err}, written as:\n{text}"));
+        assert_eq!(plain(&written), plain(&baseline), "round trip:\n{text}");
+        if data.first() == Some(&0x1B) {
+            let data = colors_256(data);
+            assert_eq!(ErrorsBaseline::parse(path, &data).unwrap(), baseline, "256 colors");
+        }
+        for (summary_crlf, sections_crlf) in [(true, true), (false, true), (true, false)] {
+            let data = mixed(data, summary_crlf, sections_crlf);
+            assert_eq!(
+                ErrorsBaseline::parse(path, &data).unwrap(),
+                baseline,
+                "summary CRLF: {summary_crlf}, sections CRLF: {sections_crlf}"
+            );
+        }
+        baseline
+    }
+
     #[test]
     fn single_file() {
         let path = PathBuf::from_str("tests/baselines/reference/unit1.errors.txt").unwrap();
@@ -781,16 +1625,24 @@ ClassDeclaration26.ts(5,1): error TS1128: Declaration or statement expected.
     }
     ~
 !!! error TS1128: Declaration or statement expected.";
-        let baseline = ErrorsBaseline::parse(&path, data);
+        let baseline = parse(&path, data);
         assert_eq!(
             baseline,
             ErrorsBaseline {
+                warnings: vec![],
+                declared_counts: vec![DeclaredCount {
+                    file: Some("ClassDeclaration26.ts"),
+                    count: 5,
+                    line: 8
+                }],
                 config_errors: vec![],
                 file_errors: vec![
                     FileError {
                         file: "ClassDeclaration26.ts",
                         loc: Some((2, 18)),
                         length: Some(3),
+                        has_inline_marker: true,
+                        severity: Severity::Error,
                         code: "1440",
                         message: "Variable declaration not allowed at this location.",
                         hint: vec![],
@@ -800,6 +1652,8 @@ ClassDeclaration26.ts(5,1): error TS1128: Declaration or statement expected.
                         file: "ClassDeclaration26.ts",
                         loc: Some((4, 5)),
                         length: Some(3),
+                        has_inline_marker: true,
+                        severity: Severity::Error,
                         code: "1068",
                         message: "Unexpected token. A constructor, method, accessor, or property was expected.",
                         hint: vec![],
@@ -809,6 +1663,8 @@ ClassDeclaration26.ts(5,1): error TS1128: Declaration or statement expected.
                         file: "ClassDeclaration26.ts",
                         loc: Some((4, 20)),
                         length: Some(1),
+                        has_inline_marker: true,
+                        severity: Severity::Error,
                         code: "1005",
                         message: "',' expected.",
                         hint: vec![],
@@ -818,6 +1674,8 @@ ClassDeclaration26.ts(5,1): error TS1128: Declaration or statement expected.
                         file: "ClassDeclaration26.ts",
                         loc: Some((4, 23)),
                         length: Some(1),
+                        has_inline_marker: true,
+                        severity: Severity::Error,
                         code: "1005",
                         message: "'=>' expected.",
                         hint: vec![],
@@ -827,6 +1685,8 @@ ClassDeclaration26.ts(5,1): error TS1128: Declaration or statement expected.
                         file: "ClassDeclaration26.ts",
                         loc: Some((5, 1)),
                         length: Some(1),
+                        has_inline_marker: true,
+                        severity: Severity::Error,
                         code: "1128",
                         message: "Declaration or statement expected.",
                         hint: vec![],
@@ -865,16 +1725,24 @@ file3.ts(3,8): error TS2503: Cannot find namespace 'x'.
         interface A { a }
     }
     "#;
-        let baseline = ErrorsBaseline::parse(&path, data);
+        let baseline = parse(&path, data);
         assert_eq!(
             baseline,
             ErrorsBaseline {
+                warnings: vec![],
+                declared_counts: vec![
+                    DeclaredCount { file: Some("file3.ts"), count: 1, line: 5 },
+                    DeclaredCount { file: Some("file1.ts"), count: 0, line: 11 },
+                    DeclaredCount { file: Some("file2.ts"), count: 1, line: 15 }
+                ],
                 config_errors: vec![],
                 file_errors: vec![
                     FileError {
                         file: "file2.ts",
                         loc: Some((5, 16)),
                         length: Some(9),
+                        has_inline_marker: true,
+                        severity: Severity::Error,
                         code: "2671",
                         message: "Cannot augment module './file1' because it resolves to a non-module entity.",
                         hint: vec![],
@@ -884,6 +1752,8 @@ file3.ts(3,8): error TS2503: Cannot find namespace 'x'.
                         file: "file3.ts",
                         loc: Some((3, 8)),
                         length: Some(1),
+                        has_inline_marker: true,
+                        severity: Severity::Error,
                         code: "2503",
                         message: "Cannot find namespace 'x'.",
                         hint: vec![],
@@ -892,6 +1762,74 @@ file3.ts(3,8): error TS2503: Cannot find namespace 'x'.
                 ]
             }
         );
+
+        assert_eq!(baseline.total_count(), 2);
+        assert!(baseline.config_errors().is_empty());
+        let errors = baseline.errors_for_file("file3.ts");
+        assert_eq!(errors.len(), 1);
+        assert_eq!(
+            (errors[0].code(), errors[0].loc(), errors[0].length()),
+            ("2503", Some((3, 8)), Some(1))
+        );
+        assert_eq!(errors[0].message(), "Cannot find namespace 'x'.");
+        assert_eq!(baseline.errors_for_file("file2.ts")[0].file(), "file2.ts");
+        assert!(baseline.errors_for_file("file1.ts").is_empty());
+    }
+
+    #[test]
+    fn error_before_first_code_line() {
+        let path = PathBuf::from_str("tests/baselines/reference/unit1.errors.txt").unwrap();
+        let data = br#"a.ts(1,1): error TS2307: Cannot find module './missing' or its corresponding type declarations.
+b.ts(1,1): error TS1208: 'b.ts' cannot be compiled under '--isolatedModules'.
+b.ts(2,5): error TS2304: Cannot find name 'y'.
+
+
+==== a.ts (1 errors) ====
+    ~~~~~~~~~~~~~~~~~~~~~~~~~~~~
+!!! error TS2307: Cannot find module './missing' or its corresponding type declarations.
+    import x from "./missing";
+    x;
+==== b.ts (2 errors) ====
+    ~~~~~
+!!! error TS1208: 'b.ts' cannot be compiled under '--isolatedModules'.
+    let a = 1;
+    a = y;
+        ~
+!!! error TS2304: Cannot find name 'y'.
+    "#;
+        let baseline = parse(&path, data);
+        assert_eq!(
+            baseline.file_errors.iter().map(|x| (x.file, x.loc, x.length)).collect::<Vec<_>>(),
+            vec![
+                ("a.ts", Some((1, 1)), Some(28)),
+                ("b.ts", Some((1, 1)), Some(5)),
+                ("b.ts", Some((2, 5)), Some(1)),
+            ]
+        );
+    }
+
+    #[test]
+    fn blank_line_in_summary() {
+        let path = PathBuf::from_str("tests/baselines/reference/unit1.errors.txt").unwrap();
+        let data = br"a.ts(1,5): error TS2304: Cannot find name 'x'.
+
+b.ts(1,5): error TS2304: Cannot find name 'y'.
+
+==== a.ts (1 errors) ====
+    x = x;
+        ~
+!!! error TS2304: Cannot find name 'x'.
+==== b.ts (1 errors) ====
+    y = y;
+        ~
+!!! error TS2304: Cannot find name 'y'.
+    ";
+        let baseline = parse(&path, data);
+        assert_eq!(
+            baseline.file_errors.iter().map(|x| (x.file, x.loc, x.length)).collect::<Vec<_>>(),
+            vec![("a.ts", Some((1, 5)), Some(1)), ("b.ts", Some((1, 5)), Some(1))]
+        );
+        assert!(baseline.warnings().is_empty());
     }
 
     #[test]
@@ -917,27 +1855,41 @@ file3.ts(3,8): error TS2503: Cannot find namespace 'x'.
         f(key: string): string;
     }
     ";
-        let baseline = ErrorsBaseline::parse(&path, data);
+        let baseline = parse(&path, data);
         assert_eq!(
             baseline,
             ErrorsBaseline {
+                warnings: vec![],
+                declared_counts: vec![DeclaredCount {
+                    file: Some("addMoreOverloadsToBaseSignature.ts"),
+                    count: 1,
+                    line: 7
+                }],
                 config_errors: vec![],
                 file_errors: vec![FileError {
                     file: "addMoreOverloadsToBaseSignature.ts",
                     loc: Some((5, 11)),
                     length: Some(3),
+                    has_inline_marker: true,
+                    severity: Severity::Error,
                     code: "2430",
                     message: "Interface 'Bar' incorrectly extends interface 'Foo'.",
                     hint: vec![
-                        (1, r"Types of property 'f' are incompatible."),
-                        (
-                            2,
-                            r"Type '(key: string) => string' is not assignable to type '() => string'."
-                        ),
-                        (
-                            3,
-                            r"Target signature provides too few arguments. Expected 1 or more, but got 0."
-                        ),
+                        Hint {
+                            column: 2,
+                            depth: 1,
+                            text: r"Types of property 'f' are incompatible.".into()
+                        },
+                        Hint {
+                            column: 4,
+                            depth: 2,
+                            text: r"Type '(key: string) => string' is not assignable to type '() => string'.".into()
+                        },
+                        Hint {
+                            column: 6,
+                            depth: 3,
+                            text: r"Target signature provides too few arguments. Expected 1 or more, but got 0.".into()
+                        },
                     ],
                     related: vec![]
                 }]
@@ -952,34 +1904,183 @@ file3.ts(3,8): error TS2503: Cannot find namespace 'x'.
 alwaysStrictNoImplicitUseStrict.ts(3,13): error TS1100: Invalid use of 'arguments' in strict mode.
 
 
-!!! error TS5102: Option 'noImplicitUseStrict' has been removed. Please remove it from your configuration.
-==== alwaysStrictNoImplicitUseStrict.ts (1 errors) ====
-    module M {
-        export function f() {
-            var arguments = [];
-                ~~~~~~~~~
-!!! error TS1100: Invalid use of 'arguments' in strict mode.
-        }
-    }";
-        let baseline = ErrorsBaseline::parse(&path, data);
+!!! error TS5102: Option 'noImplicitUseStrict' has been removed. Please remove it from your configuration.
+==== alwaysStrictNoImplicitUseStrict.ts (1 errors) ====
+    module M {
+        export function f() {
+            var arguments = [];
+                ~~~~~~~~~
+!!! error TS1100: Invalid use of 'arguments' in strict mode.
+        }
+    }";
+        let baseline = parse(&path, data);
+        assert_eq!(
+            baseline,
+            ErrorsBaseline {
+                warnings: vec![],
+                declared_counts: vec![DeclaredCount {
+                    file: Some("alwaysStrictNoImplicitUseStrict.ts"),
+                    count: 1,
+                    line: 6
+                }],
+                config_errors: vec![ConfigError {
+                    severity: Severity::Error,
+                    code: "5102",
+                    message: "Option 'noImplicitUseStrict' has been removed. Please remove it from your configuration.",
+                    hint: vec![],
+                    loc: None,
+                    length: None,
+                }],
+                file_errors: vec![FileError {
+                    file: "alwaysStrictNoImplicitUseStrict.ts",
+                    loc: Some((3, 13)),
+                    length: Some(9),
+                    has_inline_marker: true,
+                    severity: Severity::Error,
+                    code: "1100",
+                    message: "Invalid use of 'arguments' in strict mode.",
+                    hint: vec![],
+                    related: vec![]
+                }]
+            }
+        );
+
+        assert_eq!(baseline.total_count(), 2);
+        let config_errors = baseline.config_errors();
+        assert_eq!(config_errors.len(), 1);
+        assert_eq!(config_errors[0].code(), "5102");
+        assert!(
+            config_errors[0]
+                .message()
+                .starts_with("Option 'noImplicitUseStrict' has been removed.")
+        );
+        assert_eq!(config_errors[0].loc(), None);
+
+        // Underlined in the section of the config file instead, with the chain only there
+        let inline = br#"error TS5102: Option 'noImplicitUseStrict' has been removed. Please remove it from your configuration.
+alwaysStrictNoImplicitUseStrict.ts(3,13): error TS1100: Invalid use of 'arguments' in strict mode.
+
+
+==== tsconfig.json (1 errors) ====
+    {
+        "compilerOptions": {
+            "noImplicitUseStrict": true
+            ~~~~~~~~~~~~~~~~~~~~~
+!!! error TS5102: Option 'noImplicitUseStrict' has been removed. Please remove it from your configuration.
+!!! error TS5102:   Use 'alwaysStrict' instead.
+        }
+    }
+==== alwaysStrictNoImplicitUseStrict.ts (1 errors) ====
+    module M {
+        export function f() {
+            var arguments = [];
+                ~~~~~~~~~
+!!! error TS1100: Invalid use of 'arguments' in strict mode.
+        }
+    }"#;
+        let inline_baseline = parse(&path, inline);
+        let [config_error] = inline_baseline.config_errors() else {
+            panic!("Expected a single config error: {:?}", inline_baseline.config_errors());
+        };
+        assert_eq!((config_error.loc(), config_error.length()), (Some((3, 9)), Some(21)));
+        assert_eq!(
+            config_error.hints().collect::<Vec<_>>(),
+            vec![(1, "Use 'alwaysStrict' instead.")]
+        );
+        assert_eq!(inline_baseline.file_errors, baseline.file_errors);
+        assert!(inline_baseline.validate().is_empty());
+
+        // Errors of the same code are told apart by their message, not by their order
+        let same_code = br#"error TS5102: Option 'noImplicitUseStrict' has been removed. Please remove it from your configuration.
+error TS5102: Option 'out' has been removed. Please remove it from your configuration.
+
+
+==== tsconfig.json (2 errors) ====
+    {
+        "compilerOptions": {
+            "out": "a.js",
+            ~~~~~
+!!! error TS5102: Option 'out' has been removed. Please remove it from your configuration.
+!!! error TS5102:   Use 'outFile' instead.
+            "noImplicitUseStrict": true
+            ~~~~~~~~~~~~~~~~~~~~~
+!!! error TS5102: Option 'noImplicitUseStrict' has been removed. Please remove it from your configuration.
+        }
+    }
+==== a.ts (0 errors) ====
+    let a = 1;"#;
+        let same_code = parse(&path, same_code);
+        let located: Vec<_> = same_code
+            .config_errors()
+            .iter()
+            .map(|x| (x.message().split('\'').nth(1), x.loc(), x.length(), x.hints().count()))
+            .collect();
+        assert_eq!(
+            located,
+            vec![
+                (Some("noImplicitUseStrict"), Some((4, 9)), Some(21), 0),
+                (Some("out"), Some((3, 9)), Some(5), 1),
+            ]
+        );
+    }
+
+    #[test]
+    fn wrapped_config_error() {
+        let path = PathBuf::from_str("tests/baselines/reference/unit1.errors.txt").unwrap();
+        let data = b"error TS5110: Option 'module' must be set to 'NodeNext' when option 'moduleResolution' is set to 'NodeNext'.
+Use 'module: nodenext' instead.
+error TS5023
+a.ts(1,5): error TS2322: Type 'string' is not assignable to type 'number'.
+
+
+!!! error TS5110: Option 'module' must be set to 'NodeNext' when option 'moduleResolution' is set to 'NodeNext'.
+!!! error TS5110: Use 'module: nodenext' instead.
+!!! error TS5023
+==== a.ts (1 errors) ====
+    let x: number = '';
+        ~
+!!! error TS2322: Type 'string' is not assignable to type 'number'.
+    ";
+        let baseline = parse(&path, data);
+        let diagnostics = baseline.to_diagnostics();
+        assert_eq!(
+            diagnostics.iter().map(|x| (x.code, x.message.as_str())).collect::<Vec<_>>(),
+            vec![
+                (
+                    5110,
+                    "Option 'module' must be set to 'NodeNext' when option 'moduleResolution' is set to 'NodeNext'.\nUse 'module: nodenext' instead."
+                ),
+                (5023, ""),
+                (2322, "Type 'string' is not assignable to type 'number'."),
+            ]
+        );
+        assert_eq!(
+            baseline.warnings().iter().map(|x| (x.kind, x.line)).collect::<Vec<_>>(),
+            vec![
+                (ParseWarningKind::WrappedMessage, 2),
+                (ParseWarningKind::MalformedConfigError, 3)
+            ]
+        );
+
+        let data = b"  continued before any error
+Use 'module: nodenext' instead.
+a.ts(1,5): error TS2322: Type 'string' is not assignable to type 'number'.
+
+
+==== a.ts (1 errors) ====
+    let x: number = '';
+        ~
+!!! error TS2322: Type 'string' is not assignable to type 'number'.
+    ";
+        let baseline = parse(&path, data);
+        assert_eq!(baseline.to_diagnostics().len(), 1);
         assert_eq!(
-            baseline,
-            ErrorsBaseline {
-                config_errors: vec![ConfigError {
-                    code: "5102",
-                    message: "Option 'noImplicitUseStrict' has been removed. Please remove it from your configuration.",
-                    hint: vec![],
-                }],
-                file_errors: vec![FileError {
-                    file: "alwaysStrictNoImplicitUseStrict.ts",
-                    loc: Some((3, 13)),
-                    length: Some(9),
-                    code: "1100",
-                    message: "Invalid use of 'arguments' in strict mode.",
-                    hint: vec![],
-                    related: vec![]
-                }]
-            }
+            baseline.warnings().iter().map(|x| (x.kind, x.line)).collect::<Vec<_>>(),
+            vec![
+                (ParseWarningKind::OrphanHint, 1),
+                (ParseWarningKind::WrappedMessage, 2),
+                (ParseWarningKind::OrphanHint, 2)
+            ]
         );
     }
 
@@ -1002,15 +2103,22 @@ alwaysStrictNoImplicitUseStrict.ts(3,13): error TS1100: Invalid use of 'argument
 !!! related TS2594 b.d.ts:4:1: This module is declared with 'export =', and can only be used with a default import when using the 'esModuleInterop' flag.
     export var x = new Foo();
     "#;
-        let baseline = ErrorsBaseline::parse(&path, data);
+        let baseline = parse(&path, data);
         assert_eq!(
             baseline,
             ErrorsBaseline {
+                warnings: vec![],
+                declared_counts: vec![
+                    DeclaredCount { file: Some("b.d.ts"), count: 0, line: 4 },
+                    DeclaredCount { file: Some("a.ts"), count: 1, line: 10 }
+                ],
                 config_errors: vec![],
                 file_errors: vec![FileError {
                     file: "a.ts",
                     loc: Some((1, 8)),
                     length: Some(3),
+                    has_inline_marker: true,
+                    severity: Severity::Error,
                     code: "1259",
                     message: r#"Module '"b"' can only be default-imported using the 'esModuleInterop' flag"#,
                     hint: vec![],
@@ -1018,6 +2126,8 @@ alwaysStrictNoImplicitUseStrict.ts(3,13): error TS1100: Invalid use of 'argument
                         file: "b.d.ts",
                         loc: Some((4, 1)),
                         length: None,
+                        has_inline_marker: true,
+                        severity: Severity::Message,
                         code: "2594",
                         message: r"This module is declared with 'export =', and can only be used with a default import when using the 'esModuleInterop' flag.",
                         hint: vec![],
@@ -1026,6 +2136,16 @@ alwaysStrictNoImplicitUseStrict.ts(3,13): error TS1100: Invalid use of 'argument
                 }]
             }
         );
+
+        let errors = baseline.errors_for_file("a.ts");
+        assert_eq!(errors.len(), 1);
+        let related = errors[0].related();
+        assert_eq!(related.len(), 1);
+        assert_eq!(
+            (related[0].file(), related[0].code(), related[0].loc()),
+            ("b.d.ts", "2594", Some((4, 1)))
+        );
+        assert!(baseline.errors_for_file("b.d.ts").is_empty());
     }
 
     #[test]
@@ -1086,16 +2206,24 @@ constructorWithIncompleteTypeAnnotation.ts(24,29): error TS1005: ',' expected.
 !!! error TS2363: The right-hand side of an arithmetic operation must be of type 'any', 'number', 'bigint' or an enum type.
 
 "#;
-        let baseline = ErrorsBaseline::parse(&path, data);
+        let baseline = parse(&path, data);
         assert_eq!(
             baseline,
             ErrorsBaseline {
+                warnings: vec![],
+                declared_counts: vec![DeclaredCount {
+                    file: Some("constructorWithIncompleteTypeAnnotation.ts"),
+                    count: 7,
+                    line: 10
+                }],
                 config_errors: vec![],
                 file_errors: vec![
                     FileError {
                         file: "constructorWithIncompleteTypeAnnotation.ts",
                         loc: Some((11, 13)),
                         length: Some(6),
+                        has_inline_marker: true,
+                        severity: Severity::Error,
                         code: "2503",
                         message: "Cannot find namespace 'module'.",
                         hint: vec![],
@@ -1105,6 +2233,8 @@ constructorWithIncompleteTypeAnnotation.ts(24,29): error TS1005: ',' expected.
                         file: "constructorWithIncompleteTypeAnnotation.ts",
                         loc: Some((11, 13)),
                         length: Some(6),
+                        has_inline_marker: true,
+                        severity: Severity::Error,
                         code: "2580",
                         message: "Cannot find name 'module'. Do you need to install type definitions for node? Try `npm i --save-dev @types/node`.",
                         hint: vec![],
@@ -1114,6 +2244,8 @@ constructorWithIncompleteTypeAnnotation.ts(24,29): error TS1005: ',' expected.
                         file: "constructorWithIncompleteTypeAnnotation.ts",
                         loc: Some((11, 19)),
                         length: Some(1),
+                        has_inline_marker: true,
+                        severity: Severity::Error,
                         code: "1005",
                         message: "';' expected.",
                         hint: vec![],
@@ -1123,6 +2255,8 @@ constructorWithIncompleteTypeAnnotation.ts(24,29): error TS1005: ',' expected.
                         file: "constructorWithIncompleteTypeAnnotation.ts",
                         loc: Some((22, 35)),
                         length: Some(2),
+                        has_inline_marker: true,
+                        severity: Severity::Error,
                         code: "1005",
                         message: "')' expected.",
                         hint: vec![],
@@ -1130,6 +2264,8 @@ constructorWithIncompleteTypeAnnotation.ts(24,29): error TS1005: ',' expected.
                             file: "constructorWithIncompleteTypeAnnotation.ts",
                             loc: Some((22, 20)),
                             length: None,
+                            has_inline_marker: true,
+                            severity: Severity::Message,
                             code: "1007",
                             message: "The parser expected to find a ')' to match the '(' token here.",
                             hint: vec![],
@@ -1139,7 +2275,9 @@ constructorWithIncompleteTypeAnnotation.ts(24,29): error TS1005: ',' expected.
                     FileError {
                         file: "constructorWithIncompleteTypeAnnotation.ts",
                         loc: Some((22, 39)),
-                        length: None, // multi-line
+                        length: Some(50), // multi-line
+                        has_inline_marker: true,
+                        severity: Severity::Error,
                         code: "2363",
                         message: "The right-hand side of an arithmetic operation must be of type 'any', 'number', 'bigint' or an enum type.",
                         hint: vec![],
@@ -1149,6 +2287,8 @@ constructorWithIncompleteTypeAnnotation.ts(24,29): error TS1005: ',' expected.
                         file: "constructorWithIncompleteTypeAnnotation.ts",
                         loc: Some((24, 28)),
                         length: Some(1),
+                        has_inline_marker: true,
+                        severity: Severity::Error,
                         code: "1005",
                         message: "':' expected.",
                         hint: vec![],
@@ -1158,6 +2298,8 @@ constructorWithIncompleteTypeAnnotation.ts(24,29): error TS1005: ',' expected.
                         file: "constructorWithIncompleteTypeAnnotation.ts",
                         loc: Some((24, 29)),
                         length: Some(1),
+                        has_inline_marker: true,
+                        severity: Severity::Error,
                         code: "1005",
                         message: "',' expected.",
                         hint: vec![],
@@ -1168,6 +2310,111 @@ constructorWithIncompleteTypeAnnotation.ts(24,29): error TS1005: ',' expected.
         );
     }
 
+    #[test]
+    fn related_after_elaboration() {
+        let path = PathBuf::from_str("tests/baselines/reference/unit1.errors.txt").unwrap();
+        let summary = "a.ts(3,7): error TS2322: Type 'A' is not assignable to type 'B'.
+  Types of property 'x' are incompatible.
+    Type 'string' is not assignable to type 'number'.
+";
+        let sections = "
+
+==== a.ts (1 errors) ====
+    interface A { x: string }
+    interface B { x: number }
+    const b: B = a;
+          ~
+!!! error TS2322: Type 'A' is not assignable to type 'B'.
+!!! error TS2322:   Types of property 'x' are incompatible.
+!!! error TS2322:     Type 'string' is not assignable to type 'number'.
+!!! related TS6500 a.ts:2:15: The expected type comes from property 'x' which is declared here on type 'B'
+!!! related TS2728 a.ts:1:15: 'x' is declared here.
+";
+        let data = format!("{summary}{sections}");
+        let baseline = parse(&path, data.as_bytes());
+        let hints = vec![
+            (1, "Types of property 'x' are incompatible."),
+            (2, "Type 'string' is not assignable to type 'number'."),
+        ];
+        let [err] = baseline.file_errors.as_slice() else {
+            panic!("Expected a single error: {:?}", baseline.file_errors);
+        };
+        assert_eq!(err.hints().collect::<Vec<_>>(), hints);
+        assert_eq!(
+            err.related().iter().map(|x| (x.code(), x.loc(), x.message())).collect::<Vec<_>>(),
+            vec![
+                (
+                    "6500",
+                    Some((2, 15)),
+                    "The expected type comes from property 'x' which is declared here on type 'B'"
+                ),
+                ("2728", Some((1, 15)), "'x' is declared here."),
+            ]
+        );
+
+        // Without the chain in the summary, it is taken from the `!!!` lines
+        let data = format!("{}{sections}", summary.lines().next().unwrap());
+        let baseline = parse(&path, data.as_bytes());
+        assert_eq!(baseline.file_errors[0].hints().collect::<Vec<_>>(), hints);
+        assert_eq!(baseline.file_errors[0].related().len(), 2);
+    }
+
+    #[test]
+    fn repeated_error() {
+        let path = PathBuf::from_str("tests/baselines/reference/unit1.errors.txt").unwrap();
+        // Hand-written, not from a TypeScript baseline: the same error twice at one span, sharing
+        // an underline
+        let shared = b"a.tsx(2,13): error TS2304: Cannot find name 'React'.
+a.tsx(2,13): error TS2304: Cannot find name 'React'.
+a.tsx(3,1): error TS2304: Cannot find name 'y'.
+
+
+==== a.tsx (3 errors) ====
+    declare const x: number;
+    const el = <div />;
+                ~~~
+!!! error TS2304: Cannot find name 'React'.
+!!! error TS2304: Cannot find name 'React'.
+    y;
+    ~
+!!! error TS2304: Cannot find name 'y'.
+";
+        let baseline = parse(&path, shared);
+        let errors: Vec<_> = baseline
+            .file_errors
+            .iter()
+            .map(|x| (x.loc(), x.length(), x.has_inline_marker(), x.related().len()))
+            .collect();
+        assert_eq!(
+            errors,
+            vec![
+                (Some((2, 13)), Some(3), true, 0),
+                (Some((2, 13)), Some(3), true, 0),
+                (Some((3, 1)), Some(1), true, 0),
+            ]
+        );
+        assert!(baseline.validate().is_empty());
+
+        // Underlined once for each
+        let separate = b"a.tsx(2,13): error TS2304: Cannot find name 'React'.
+a.tsx(2,13): error TS2304: Cannot find name 'React'.
+a.tsx(3,1): error TS2304: Cannot find name 'y'.
+
+
+==== a.tsx (3 errors) ====
+    declare const x: number;
+    const el = <div />;
+                ~~~
+!!! error TS2304: Cannot find name 'React'.
+                ~~~
+!!! error TS2304: Cannot find name 'React'.
+    y;
+    ~
+!!! error TS2304: Cannot find name 'y'.
+";
+        assert_eq!(parse(&path, separate).file_errors, baseline.file_errors);
+    }
+
     #[test]
     fn related_without_location() {
         let path = PathBuf::from_str("tests/baselines/reference/unit1.errors.txt").unwrap();
@@ -1183,16 +2430,24 @@ regularExpressionGroupNameSuggestions.ts(1,27): error TS1532: There is no captur
 !!! error TS1532: There is no capturing group named 'Foo' in this regular expression.
 !!! related TS1369: Did you mean 'foo'?
     ";
-        let baseline = ErrorsBaseline::parse(&path, data);
+        let baseline = parse(&path, data);
         assert_eq!(
             baseline,
             ErrorsBaseline {
+                warnings: vec![],
+                declared_counts: vec![DeclaredCount {
+                    file: Some("regularExpressionGroupNameSuggestions.ts"),
+                    count: 2,
+                    line: 5
+                }],
                 config_errors: vec![],
                 file_errors: vec![
                     FileError {
                         file: "regularExpressionGroupNameSuggestions.ts",
                         loc: Some((1, 18)),
                         length: Some(5),
+                        has_inline_marker: true,
+                        severity: Severity::Error,
                         code: "1503",
                         message: "Named capturing groups are only available when targeting 'ES2018' or later.",
                         hint: vec![],
@@ -1202,6 +2457,8 @@ regularExpressionGroupNameSuggestions.ts(1,27): error TS1532: There is no captur
                         file: "regularExpressionGroupNameSuggestions.ts",
                         loc: Some((1, 27)),
                         length: Some(3),
+                        has_inline_marker: true,
+                        severity: Severity::Error,
                         code: "1532",
                         message: "There is no capturing group named 'Foo' in this regular expression.",
                         hint: vec![],
@@ -1209,6 +2466,8 @@ regularExpressionGroupNameSuggestions.ts(1,27): error TS1532: There is no captur
                             file: "regularExpressionGroupNameSuggestions.ts",
                             loc: Some((1, 27)),
                             length: Some(3),
+                            has_inline_marker: true,
+                            severity: Severity::Message,
                             code: "1369",
                             message: "Did you mean 'foo'?",
                             hint: vec![],
@@ -1251,16 +2510,25 @@ test.ts(1,19): error TS5097: An import path can only end with a '.ts' extension
 !!! error TS5097: An import path can only end with a '.ts' extension when 'allowImportingTsExtensions' is enabled.
     import { b } from "baz/main.ts";
     "#;
-        let baseline = ErrorsBaseline::parse(&path, data);
+        let baseline = parse(&path, data);
         assert_eq!(
             baseline,
             ErrorsBaseline {
+                warnings: vec![],
+                declared_counts: vec![
+                    DeclaredCount { file: Some("tsconfig.json"), count: 1, line: 5 },
+                    DeclaredCount { file: Some("dist/bar.ts"), count: 0, line: 17 },
+                    DeclaredCount { file: Some("types/main.d.ts"), count: 0, line: 20 },
+                    DeclaredCount { file: Some("test.ts"), count: 1, line: 23 }
+                ],
                 config_errors: vec![],
                 file_errors: vec![
                     FileError {
                         file: "tsconfig.json",
                         loc: Some((2, 5)),
                         length: Some(17),
+                        has_inline_marker: true,
+                        severity: Severity::Error,
                         code: "5095",
                         message: "Option 'bundler' can only be used when 'module' is set to 'preserve' or to 'es2015' or later.",
                         hint: vec![],
@@ -1270,6 +2538,8 @@ test.ts(1,19): error TS5097: An import path can only end with a '.ts' extension
                         file: "test.ts",
                         loc: Some((1, 19)),
                         length: Some(12),
+                        has_inline_marker: true,
+                        severity: Severity::Error,
                         code: "5097",
                         message: "An import path can only end with a '.ts' extension when 'allowImportingTsExtensions' is enabled.",
                         hint: vec![],
@@ -1280,6 +2550,77 @@ test.ts(1,19): error TS5097: An import path can only end with a '.ts' extension
         );
     }
 
+    #[test]
+    fn with_warning_and_suggestion() {
+        let path = PathBuf::from("a.errors.txt");
+        let data = br"warning TS5101: Option 'charset' is deprecated and will stop functioning in TypeScript 5.5.
+a.ts(1,7): warning TS6133: 'x' is declared but its value is never read.
+a.ts(2,1): suggestion TS80001: File is a CommonJS module; it may be converted to an ES module.
+
+
+!!! warning TS5101: Option 'charset' is deprecated and will stop functioning in TypeScript 5.5.
+==== a.ts (2 errors) ====
+    const x = 1;
+          ~
+!!! warning TS6133: 'x' is declared but its value is never read.
+    module.exports = {};
+    ~~~~~~
+!!! suggestion TS80001: File is a CommonJS module; it may be converted to an ES module.";
+        let baseline = parse(&path, data);
+        assert_eq!(baseline.config_errors()[0].severity(), Severity::Warning);
+        let errors: Vec<_> = baseline
+            .errors_for_file("a.ts")
+            .iter()
+            .map(|x| (x.severity(), x.code(), x.loc(), x.length(), x.has_inline_marker()))
+            .collect();
+        assert_eq!(
+            errors,
+            [
+                (Severity::Warning, "6133", Some((1, 7)), Some(1), true),
+                (Severity::Suggestion, "80001", Some((2, 1)), Some(6), true),
+            ]
+        );
+        let severities: Vec<_> = baseline.to_diagnostics().iter().map(|x| x.severity).collect();
+        assert_eq!(severities, [Severity::Warning, Severity::Warning, Severity::Suggestion]);
+    }
+
+    #[test]
+    fn with_warning_and_pretty() {
+        let path = PathBuf::from("a.errors.txt");
+        let data = br"[96ma.ts[0m:[93m1[0m:[93m7[0m - [93mwarning[0m[90m TS6133: [0m'x' is declared but its value is never read.
+
+[7m1[0m const x = 1;
+[7m [0m [93m      ~[0m
+
+[96ma.ts[0m:[93m2[0m:[93m1[0m - [90msuggestion[0m[90m TS80001: [0mFile is a CommonJS module; it may be converted to an ES module.
+
+[7m2[0m module.exports = {};
+[7m [0m [90m~~~~~~[0m
+
+
+==== a.ts (2 errors) ====
+    const x = 1;
+          ~
+!!! warning TS6133: 'x' is declared but its value is never read.
+    module.exports = {};
+    ~~~~~~
+!!! suggestion TS80001: File is a CommonJS module; it may be converted to an ES module.
+";
+        let baseline = parse(&path, data);
+        let errors: Vec<_> = baseline
+            .errors_for_file("a.ts")
+            .iter()
+            .map(|x| (x.severity(), x.code(), x.loc(), x.length(), x.has_inline_marker()))
+            .collect();
+        assert_eq!(
+            errors,
+            [
+                (Severity::Warning, "6133", Some((1, 7)), Some(1), true),
+                (Severity::Suggestion, "80001", Some((2, 1)), Some(6), true),
+            ]
+        );
+    }
+
     #[test]
     fn with_pretty() {
         let path = PathBuf::from_str("tests/baselines/reference/unit1.errors.txt").unwrap();
@@ -1301,15 +2642,26 @@ test.ts(1,19): error TS5097: An import path can only end with a '.ts' extension
 Found 1 error in multiLineContextDiagnosticWithPretty.ts[90m:2[0m
 
 ";
-        let baseline = ErrorsBaseline::parse(&path, data);
+        let baseline = parse(&path, data);
         assert_eq!(
             baseline,
             ErrorsBaseline {
+                warnings: vec![],
+                declared_counts: vec![
+                    DeclaredCount {
+                        file: Some("multiLineContextDiagnosticWithPretty.ts"),
+                        count: 1,
+                        line: 7
+                    },
+                    DeclaredCount { file: None, count: 1, line: 16 }
+                ],
                 config_errors: vec![],
                 file_errors: vec![FileError {
                     file: "multiLineContextDiagnosticWithPretty.ts",
                     loc: Some((2, 5)),
                     length: Some(1),
+                    has_inline_marker: true,
+                    severity: Severity::Error,
                     code: "2353",
                     message: r"Object literal may only specify known properties, and 'a' does not exist in type '{ c: string; }'.",
                     hint: vec![],
@@ -1343,15 +2695,22 @@ Found 1 error in multiLineContextDiagnosticWithPretty.ts[90m:2[0m
 Found 1 error in index.ts[90m:2[0m
 
 ";
-        let baseline = ErrorsBaseline::parse(&path, data);
+        let baseline = parse(&path, data);
         assert_eq!(
             baseline,
             ErrorsBaseline {
+                warnings: vec![],
+                declared_counts: vec![
+                    DeclaredCount { file: Some("index.ts"), count: 1, line: 12 },
+                    DeclaredCount { file: None, count: 1, line: 18 }
+                ],
                 config_errors: vec![],
                 file_errors: vec![FileError {
                     file: "index.ts",
                     loc: Some((2, 1)),
                     length: None,
+                    has_inline_marker: true,
+                    severity: Severity::Error,
                     code: "1005",
                     message: r"'}' expected.",
                     hint: vec![],
@@ -1359,6 +2718,8 @@ Found 1 error in index.ts[90m:2[0m
                         file: "index.ts",
                         loc: Some((1, 11)),
                         length: Some(1),
+                        has_inline_marker: true,
+                        severity: Severity::Message,
                         code: "1007",
                         message: r"The parser expected to find a '}' to match the '{' token here.",
                         hint: vec![],
@@ -1369,6 +2730,61 @@ Found 1 error in index.ts[90m:2[0m
         );
     }
 
+    #[test]
+    fn with_256_colors_and_pretty() {
+        let path = PathBuf::from_str("tests/baselines/reference/unit1.errors.txt").unwrap();
+        let data = b"\x1b[38;5;14mindex.ts\x1b[0m:\x1b[38;5;11m12\x1b[0m:\x1b[38;5;11m8\x1b[0m - \x1b[1m\x1b[38;5;9merror\x1b[0m\x1b[38;5;8m TS2345: \x1b[0mArgument of type 'string' is not assignable to parameter of type 'number'.
+
+\x1b[7m12\x1b[0m invoke(\"a\");
+\x1b[7m  \x1b[0m \x1b[1m\x1b[38;5;9m       ~~~\x1b[0m
+
+  \x1b[38;5;14mindex.ts\x1b[0m:\x1b[38;5;11m1\x1b[0m:\x1b[38;5;11m17\x1b[0m
+    \x1b[7m1\x1b[0m function invoke(x: number) {}
+    \x1b[7m \x1b[0m \x1b[38;5;14m                ~~~~~~~~~\x1b[0m
+    The expected type comes from this parameter.
+
+
+==== index.ts (1 errors) ====
+    function invoke(x: number) {}
+   \x20
+   \x20
+   \x20
+   \x20
+   \x20
+   \x20
+   \x20
+   \x20
+   \x20
+   \x20
+    invoke(\"a\");
+           ~~~
+!!! error TS2345: Argument of type 'string' is not assignable to parameter of type 'number'.
+!!! related TS6500 index.ts:1:17: The expected type comes from this parameter.
+Found 1 error in index.ts\x1b[38;5;8m:12\x1b[0m
+
+";
+        let baseline = parse(&path, data);
+        let [err] = baseline.file_errors.as_slice() else {
+            panic!("Expected a single error: {:?}", baseline.file_errors);
+        };
+        assert_eq!(
+            (err.file(), err.loc(), err.length(), err.code()),
+            ("index.ts", Some((12, 8)), Some(3), "2345")
+        );
+        assert_eq!(
+            err.message(),
+            "Argument of type 'string' is not assignable to parameter of type 'number'."
+        );
+        let [related] = err.related() else {
+            panic!("Expected a single related error: {:?}", err.related());
+        };
+        assert_eq!(
+            (related.file(), related.loc(), related.length(), related.code()),
+            ("index.ts", Some((1, 17)), Some(9), "6500")
+        );
+        assert_eq!(related.message(), "The expected type comes from this parameter.");
+    }
+
     #[test]
     fn with_hint_and_pretty() {
         let path = PathBuf::from_str("tests/baselines/reference/unit1.errors.txt").unwrap();
@@ -1401,25 +2817,36 @@ Found 1 error in index.ts[90m:2[0m
 Found 1 error in index.ts[90m:3[0m
 
 "#;
-        let baseline = ErrorsBaseline::parse(&path, data);
+        let baseline = parse(&path, data);
         assert_eq!(
             baseline,
             ErrorsBaseline {
+                warnings: vec![],
+                declared_counts: vec![
+                    DeclaredCount { file: Some("foo.d.ts"), count: 0, line: 13 },
+                    DeclaredCount { file: Some("index.ts"), count: 1, line: 17 },
+                    DeclaredCount { file: None, count: 1, line: 26 }
+                ],
                 config_errors: vec![],
                 file_errors: vec![FileError {
                     file: "index.ts",
                     loc: Some((3, 8)),
                     length: Some(3),
+                    has_inline_marker: true,
+                    severity: Severity::Error,
                     code: "2345",
                     message: r"Argument of type '{ default: () => void; }' is not assignable to parameter of type '() => void'.",
-                    hint: vec![(
-                        1,
-                        r"Type '{ default: () => void; }' provides no match for the signature '(): void'."
-                    )],
+                    hint: vec![Hint {
+                        column: 2,
+                        depth: 1,
+                        text: r"Type '{ default: () => void; }' provides no match for the signature '(): void'.".into()
+                    }],
                     related: vec![FileError {
                         file: "index.ts",
                         loc: Some((1, 1)),
                         length: Some(29),
+                        has_inline_marker: true,
+                        severity: Severity::Message,
                         code: "7038",
                         message: r"Type originates at this import. A namespace-style import cannot be called or constructed, and will cause a failure at runtime. Consider using a default import or import require here instead.",
                         hint: vec![],
@@ -1429,4 +2856,438 @@ Found 1 error in index.ts[90m:3[0m
             }
         );
     }
+
+    /// Windows checkouts of the TS repo have CRLF baselines: they must parse the same as LF ones.
+    #[test]
+    fn crlf_line_endings() {
+        let path = PathBuf::from_str("tests/baselines/reference/unit1.errors.txt").unwrap();
+        let fixtures: [&[u8]; 3] = [
+            br#"a.ts(1,1): error TS2307: Cannot find module './missing' or its corresponding type declarations.
+b.ts(1,1): error TS1208: 'b.ts' cannot be compiled under '--isolatedModules'.
+b.ts(2,5): error TS2304: Cannot find name 'y'.
+
+
+==== a.ts (1 errors) ====
+    ~~~~~~~~~~~~~~~~~~~~~~~~~~~~
+!!! error TS2307: Cannot find module './missing' or its corresponding type declarations.
+    import x from "./missing";
+    x;
+==== b.ts (2 errors) ====
+    ~~~~~
+!!! error TS1208: 'b.ts' cannot be compiled under '--isolatedModules'.
+    let a = 1;
+    a = y;
+        ~
+!!! error TS2304: Cannot find name 'y'.
+    "#,
+            br#"a.ts(1,8): error TS1259: Module '"b"' can only be default-imported using the 'esModuleInterop' flag
+
+
+==== b.d.ts (0 errors) ====
+    declare class Foo {
+    	member: string;
+    }
+    export = Foo;
+
+==== a.ts (1 errors) ====
+    import Foo from "./b";
+           ~~~
+!!! error TS1259: Module '"b"' can only be default-imported using the 'esModuleInterop' flag
+!!! related TS2594 b.d.ts:4:1: This module is declared with 'export =', and can only be used with a default import when using the 'esModuleInterop' flag.
+    export var x = new Foo();
+    "#,
+            br#"[96mindex.ts[0m:[93m3[0m:[93m8[0m - [91merror[0m[90m TS2345: [0mArgument of type '{ default: () => void; }' is not assignable to parameter of type '() => void'.
+  Type '{ default: () => void; }' provides no match for the signature '(): void'.
+
+[7m3[0m invoke(foo);
+[7m [0m [91m       ~~~[0m
+
+  [96mindex.ts[0m:[93m1[0m:[93m1[0m
+    [7m1[0m import * as foo from "./foo";
+    [7m [0m [96m~~~~~~~~~~~~~~~~~~~~~~~~~~~~~[0m
+    Type originates at this import. A namespace-style import cannot be called or constructed, and will cause a failure at runtime. Consider using a default import or import require here instead.
+
+
+==== foo.d.ts (0 errors) ====
+    declare function foo(): void;
+    declare namespace foo {}
+    export = foo;
+==== index.ts (1 errors) ====
+    import * as foo from "./foo";
+    function invoke(f: () => void) { f(); }
+    invoke(foo);
+           ~~~
+!!! error TS2345: Argument of type '{ default: () => void; }' is not assignable to parameter of type '() => void'.
+!!! error TS2345:   Type '{ default: () => void; }' provides no match for the signature '(): void'.
+!!! related TS7038 index.ts:1:1: Type originates at this import. A namespace-style import cannot be called or constructed, and will cause a failure at runtime. Consider using a default import or import require here instead.
+
+Found 1 error in index.ts[90m:3[0m
+
+"#,
+        ];
+
+        for data in fixtures {
+            let crlf_data = crlf(data);
+            let baseline = ErrorsBaseline::parse(&path, &crlf_data).unwrap();
+            assert_eq!(baseline, ErrorsBaseline::parse(&path, data).unwrap());
+            assert!(baseline.file_errors.iter().all(|x| x.length.is_some()));
+            assert!(
+                baseline
+                    .to_diagnostics()
+                    .iter()
+                    .all(|x| !x.message.contains('\r') && !x.file.contains('\r'))
+            );
+        }
+    }
+
+    #[test]
+    fn hint_indentation() {
+        let path = PathBuf::from_str("tests/baselines/reference/unit1.errors.txt").unwrap();
+        let tabs = b"a.ts(1,5): error TS2322: Type 'A' is not assignable to type 'B'.
+	Types of property 'x' are incompatible.
+		Type 'string' is not assignable to type 'number'.
+
+
+==== a.ts (1 errors) ====
+    let x: B = a;
+        ~
+!!! error TS2322: Type 'A' is not assignable to type 'B'.
+!!! error TS2322: 	Types of property 'x' are incompatible.
+!!! error TS2322: 		Type 'string' is not assignable to type 'number'.
+    ";
+        let baseline = parse(&path, tabs);
+        assert_eq!(
+            baseline.file_errors[0].hints().collect::<Vec<_>>(),
+            vec![
+                (1, "Types of property 'x' are incompatible."),
+                (2, "Type 'string' is not assignable to type 'number'."),
+            ]
+        );
+        assert!(baseline.warnings().is_empty());
+        assert_eq!(
+            baseline.to_diagnostics()[0].message,
+            "Type 'A' is not assignable to type 'B'.\n  Types of property 'x' are incompatible.\n    Type 'string' is not assignable to type 'number'."
+        );
+
+        let odd_spaces = b"a.ts(1,5): error TS2322: Type 'A' is not assignable to type 'B'.
+   Types of property 'x' are incompatible.
+      Type 'string' is not assignable to type 'number'.
+ Type 'A' is missing a property.
+
+
+==== a.ts (1 errors) ====
+    let x: B = a;
+        ~
+!!! error TS2322: Type 'A' is not assignable to type 'B'.
+!!! error TS2322:    Types of property 'x' are incompatible.
+!!! error TS2322:       Type 'string' is not assignable to type 'number'.
+!!! error TS2322:  Type 'A' is missing a property.
+    ";
+        let baseline = parse(&path, odd_spaces);
+        // Odd indentation keeps its nesting instead of being rounded down
+        assert_eq!(
+            baseline.file_errors[0].hints().collect::<Vec<_>>(),
+            vec![
+                (1, "Types of property 'x' are incompatible."),
+                (2, "Type 'string' is not assignable to type 'number'."),
+                (1, "Type 'A' is missing a property."),
+            ]
+        );
+        assert_eq!(
+            baseline.warnings().iter().map(|x| (x.kind, x.line)).collect::<Vec<_>>(),
+            vec![(ParseWarningKind::OddHintIndent, 2), (ParseWarningKind::OddHintIndent, 4)]
+        );
+    }
+
+    #[test]
+    fn elaboration_chain() {
+        let path = PathBuf::from_str("tests/baselines/reference/unit1.errors.txt").unwrap();
+        let data = b"a.ts(1,7): error TS2322: Type 'A' is not assignable to type 'B'.
+  Types of property 'a' are incompatible.
+    Type 'A1' is not assignable to type 'B1'.
+      Types of property 'b' are incompatible.
+        Type 'A2' is not assignable to type 'B2'.
+          Type 'string' is not assignable to type 'number' in the deeply nested property
+              'a.b.c' of this type.
+    Property 'c' is missing in type 'A1'.
+
+
+==== a.ts (1 errors) ====
+    const x: B = a;
+          ~
+!!! error TS2322: Type 'A' is not assignable to type 'B'.
+!!! error TS2322:   Types of property 'a' are incompatible.
+!!! error TS2322:     Type 'A1' is not assignable to type 'B1'.
+!!! error TS2322:       Types of property 'b' are incompatible.
+!!! error TS2322:         Type 'A2' is not assignable to type 'B2'.
+!!! error TS2322:           Type 'string' is not assignable to type 'number' in the deeply nested property
+!!! error TS2322:               'a.b.c' of this type.
+!!! error TS2322:     Property 'c' is missing in type 'A1'.
+";
+        let baseline = parse(&path, data);
+        assert_eq!(
+            baseline.file_errors[0].hints().collect::<Vec<_>>(),
+            vec![
+                (1, "Types of property 'a' are incompatible."),
+                (2, "Type 'A1' is not assignable to type 'B1'."),
+                (3, "Types of property 'b' are incompatible."),
+                (4, "Type 'A2' is not assignable to type 'B2'."),
+                (
+                    5,
+                    "Type 'string' is not assignable to type 'number' in the deeply nested \
+                     property\n'a.b.c' of this type."
+                ),
+                (2, "Property 'c' is missing in type 'A1'."),
+            ]
+        );
+        assert!(baseline.warnings().is_empty());
+    }
+
+    #[test]
+    fn multi_line_span() {
+        let path = PathBuf::from_str("tests/baselines/reference/unit1.errors.txt").unwrap();
+        let two_lines =
+            b"a.ts(1,9): error TS2322: Type '{ a: number; }' is not assignable to type 'string'.
+
+
+==== a.ts (1 errors) ====
+    let x = {
+            ~
+      a: 1 };
+    ~~~~~~~~
+!!! error TS2322: Type '{ a: number; }' is not assignable to type 'string'.
+    let y = x;
+";
+        let baseline = parse(&path, two_lines);
+        assert_eq!(baseline.file_errors[0].length, Some(10));
+
+        let five_lines = b"a.ts(2,3): error TS1005: '}' expected.
+a.ts(3,5): error TS2304: Cannot find name 'b'.
+
+
+==== a.ts (2 errors) ====
+    function f() {
+      if (a) {
+      ~~~~~~~~
+        b;
+    ~~~~~~
+        ~
+!!! error TS2304: Cannot find name 'b'.
+   \x20
+   \x20
+      } else {
+    ~~~~~~~~~~
+      }
+    ~~~
+!!! error TS1005: '}' expected.
+    }
+";
+        let baseline = parse(&path, five_lines);
+        let source = "if (a) {\n    b;\n\n  } else {\n  }";
+        assert_eq!(baseline.file_errors[0].length, u32::try_from(source.len()).ok());
+        assert_eq!(baseline.file_errors[1].length, Some(1));
+    }
+
+    #[test]
+    fn error_without_section() {
+        let path = PathBuf::from_str("tests/baselines/reference/unit1.errors.txt").unwrap();
+        let data = b"a.ts(1,11): error TS2300: Duplicate identifier 'ClassDecoratorContext'.
+lib.decorators.d.ts(95,11): error TS2300: Duplicate identifier 'ClassDecoratorContext'.
+
+
+==== a.ts (1 errors) ====
+    interface ClassDecoratorContext {}
+              ~~~~~~~~~~~~~~~~~~~~~
+!!! error TS2300: Duplicate identifier 'ClassDecoratorContext'.
+!!! related TS6203 lib.decorators.d.ts:95:11: 'ClassDecoratorContext' was also declared here.
+";
+        let baseline = parse(&path, data);
+        assert_eq!(baseline.total_count(), 2);
+        assert!(baseline.validate().is_empty());
+
+        let [err] = baseline.errors_for_file("a.ts") else { panic!("expected one error") };
+        assert!(err.has_inline_marker());
+        assert_eq!(err.length(), Some(21));
+        assert!(err.related()[0].has_inline_marker());
+
+        let [lib] = baseline.errors_for_file("lib.decorators.d.ts") else {
+            panic!("expected one error")
+        };
+        assert_eq!((lib.code(), lib.loc(), lib.length()), ("2300", Some((95, 11)), None));
+        assert!(!lib.has_inline_marker());
+    }
+
+    #[test]
+    fn count_mismatch() {
+        let path = PathBuf::from_str("tests/baselines/reference/unit1.errors.txt").unwrap();
+        let data = b"a.ts(1,5): error TS2322: Type 'string' is not assignable to type 'number'.
+
+
+==== a.ts (2 errors) ====
+    let x: number = '';
+        ~
+!!! error TS2322: Type 'string' is not assignable to type 'number'.
+==== b.ts (0 errors) ====
+    let y = 1;
+";
+        let baseline = parse(&path, data);
+        let mismatches = baseline.validate();
+        assert_eq!(
+            mismatches,
+            vec![CountMismatch {
+                declared: DeclaredCount { file: Some("a.ts"), count: 2, line: 4 },
+                parsed: 1
+            }]
+        );
+        let warning = mismatches[0].to_warning(&path);
+        assert_eq!((warning.kind, warning.line), (ParseWarningKind::ErrorCountMismatch, 4));
+        assert_eq!(warning.snippet, "a.ts declares 2 errors, 1 parsed");
+
+        let pretty = b"\x1b[96ma.ts\x1b[0m:\x1b[93m1\x1b[0m:\x1b[93m5\x1b[0m - \x1b[91merror\x1b[0m\x1b[90m TS2322: \x1b[0mType 'string' is not assignable to type 'number'.
+
+\x1b[7m1\x1b[0m let x: number = '';
+\x1b[7m \x1b[0m \x1b[91m    ~\x1b[0m
+
+
+==== a.ts (1 errors) ====
+    let x: number = '';
+        ~
+!!! error TS2322: Type 'string' is not assignable to type 'number'.
+
+Found 3 errors in a.ts\x1b[90m:1\x1b[0m
+
+";
+        let baseline = parse(&path, pretty);
+        let mismatches = baseline.validate();
+        assert_eq!(
+            mismatches,
+            vec![CountMismatch {
+                declared: DeclaredCount { file: None, count: 3, line: 12 },
+                parsed: 1
+            }]
+        );
+        assert_eq!(mismatches[0].to_string(), "3 errors found in total, 1 parsed");
+    }
+
+    #[test]
+    fn truncated_summary_line() {
+        let path = PathBuf::from("a.errors.txt");
+        let data = b"a.ts(1,1): error TS2304: Cannot find name 'x'.\na.ts(2,5): error TS23\n\n\n\
+                     ==== a.ts (2 errors) ====\n";
+        let err = ErrorsBaseline::parse(&path, data).unwrap_err();
+        assert_eq!(
+            err,
+            BaselineParseError {
+                which: BaselineKind::Errors,
+                path,
+                offset: Some(47 + 19),
+                line: Some(2),
+                error: "Failed to find end of error code".to_string(),
+                underline: None,
+                snippet: None,
+            }
+        );
+
+        // Offsets count the `\r` of every line before, lines are the same
+        let crlf_data = crlf(data);
+        let crlf_err = ErrorsBaseline::parse(&err.path, &crlf_data).unwrap_err();
+        assert_eq!(crlf_err.offset, Some(48 + 19));
+        assert_eq!(crlf_err.line, err.line);
+        assert_eq!(crlf_err.error, err.error);
+
+        // The summary iterator returns the errors before the malformed line, then fails
+        let path = err.path.clone();
+        let mut summary = ErrorsBaseline::summary_iter(&path, data);
+        assert!(matches!(summary.next(), Some(Ok(SummaryEntry::File(x))) if x.code == "2304"));
+        assert_eq!(summary.next(), Some(Err(err)));
+        assert_eq!(summary.next(), None);
+    }
+
+    #[test]
+    fn missing_sections() {
+        let path = PathBuf::from("a.errors.txt");
+        let data = b"a.ts(1,9): error TS2304: Cannot find name 'x'.
+b.ts(1,1): error TS1208: 'b.ts' cannot be compiled under '--isolatedModules' because it is considered a global script file.
+c.ts(2,1): error TS2322: Type 'string' is not assignable to type 'number'.
+
+
+==== a.ts (1 errors) ====
+    let a = x;
+            ~
+!!! error TS2304: Cannot find name 'x'.
+==== c.ts (1 errors) ====
+    let c: number;
+    c = \"\";
+    ~
+!!! error TS2322: Type 'string' is not assignable to type 'number'.";
+        let baseline = parse(&path, data);
+        let unmatched: Vec<_> =
+            baseline.unmatched_summary_errors().map(|x| (x.file(), x.code())).collect();
+        assert_eq!(unmatched, [("b.ts", "1208")]);
+        let c = baseline.errors_for_file("c.ts");
+        assert_eq!((c[0].loc(), c[0].length()), (Some((2, 1)), Some(1)));
+
+        // An error of a printed file must have its `!!!` line there, even past its last line
+        let unmarked = b"a.ts(1,9): error TS2304: Cannot find name 'x'.
+a.ts(5,1): error TS2304: Cannot find name 'y'.
+
+
+==== a.ts (2 errors) ====
+    let a = x;
+            ~
+!!! error TS2304: Cannot find name 'x'.
+    let b = 1;
+";
+        let err = ErrorsBaseline::parse(&path, unmarked).unwrap_err();
+        assert_eq!(err.error, "Expected error TS2304 of `a.ts` to be underlined in its section");
+    }
+
+    #[test]
+    fn long_line() {
+        let path = PathBuf::from("a.errors.txt");
+        let code = format!("{}bad;", "x+".repeat(2499) + "x+");
+        let data = format!(
+            "a.ts(1,5001): error TS2304: Cannot find name 'bad'.\n\n\n\
+             ==== a.ts (1 errors) ====\n    {code}\n    {}~~~\n\
+             !!! error TS2304: Cannot find name 'bad'.",
+            " ".repeat(5000)
+        );
+        let baseline = parse(&path, data.as_bytes());
+        let [err] = baseline.errors_for_file("a.ts") else { panic!("Expected one error") };
+        assert_eq!(err.loc(), Some((1, 5001)));
+        assert_eq!(err.length(), Some(3));
+    }
+
+    #[test]
+    fn underline_before_column() {
+        let path = PathBuf::from("a.errors.txt");
+        let data = b"a.ts(1,10): error TS2304: Cannot find name 'x'.\n\n\n\
+                     ==== a.ts (1 errors) ====\n    let a = x;\n    ~~~\n\
+                     !!! error TS2304: Cannot find name 'x'.";
+        let err = ErrorsBaseline::parse(&path, data).unwrap_err();
+        assert_eq!(
+            err.underline,
+            Some(Box::new(UnderlineMismatch { text: "    ~~~".to_string(), column: 10 }))
+        );
+        assert_eq!(err.line, Some(6));
+    }
+
+    #[test]
+    fn missing_file_header() {
+        let path = PathBuf::from("a.errors.txt");
+        let data = b"a.ts(1,1): error TS2304: Cannot find name 'x'.\n\n\n    x;\n";
+        let err = ErrorsBaseline::parse(&path, data).unwrap_err();
+        assert_eq!(
+            err,
+            BaselineParseError {
+                which: BaselineKind::Errors,
+                path,
+                offset: Some(data.len()),
+                line: Some(5),
+                error: "Expected a `====` file header after the errors".to_string(),
+                underline: None,
+                snippet: None,
+            }
+        );
+    }
 }
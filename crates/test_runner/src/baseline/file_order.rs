@@ -0,0 +1,153 @@
+//! Order TS lists diagnostics in by their file, following `compareDiagnostics`.
+//!
+//! Diagnostics without a file come first, then the ones of the config file, then the ones of
+//! the test files by path with lib files last. Paths are compared ignoring ASCII case so
+//! baselines written on Windows sort the same way; only paths that differ in nothing else are
+//! ordered by case.
+
+use std::cmp::Ordering;
+
+/// Groups of files, in the order they are listed.
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy)]
+enum Group {
+    NoFile,
+    Config,
+    Source,
+    Lib,
+}
+
+impl Group {
+    fn of(file: &str) -> Self {
+        if file.is_empty() {
+            Self::NoFile
+        } else if is_config_file(file) {
+            Self::Config
+        } else if is_lib_file(file) {
+            Self::Lib
+        } else {
+            Self::Source
+        }
+    }
+}
+
+/// Whether `file` is a `tsconfig.json`, in any directory.
+pub fn is_config_file(file: &str) -> bool {
+    file.rsplit('/').next() == Some("tsconfig.json")
+}
+
+/// Whether `file` is a lib file, like `lib.dom.d.ts`, or in a package replacing one, like
+/// `node_modules/@typescript/lib-dom/index.d.ts`.
+pub fn is_lib_file(file: &str) -> bool {
+    let name = file.rsplit('/').next().unwrap_or(file);
+    if name.starts_with("lib.") && name.ends_with(".d.ts") {
+        return true;
+    }
+    file.split('/')
+        .zip(file.split('/').skip(1))
+        .any(|(scope, package)| scope == "@typescript" && package.starts_with("lib-"))
+}
+
+/// Compares the files of two diagnostics, see the [module docs](self). This is a total order:
+/// only equal names compare equal.
+pub fn cmp(a: &str, b: &str) -> Ordering {
+    Group::of(a)
+        .cmp(&Group::of(b))
+        .then_with(|| {
+            let lower = |x: u8| x.to_ascii_lowercase();
+            a.bytes().map(lower).cmp(b.bytes().map(lower))
+        })
+        .then_with(|| a.cmp(b))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cmp::Ordering;
+
+    use super::*;
+
+    const NAMES: &[&str] = &[
+        "",
+        "tsconfig.json",
+        "/tsconfig.json",
+        "src/tsconfig.json",
+        "tsconfig.base.json",
+        "a.ts",
+        "A.ts",
+        "a.d.ts",
+        "a/b.ts",
+        "a_b.ts",
+        "B.ts",
+        "b.ts",
+        "index.ts",
+        "Index.ts",
+        "lib.ts",
+        "lib.d.ts",
+        "lib.dom.d.ts",
+        "lib.es2015.d.ts",
+        "mylib.d.ts",
+        "node_modules/@typescript/lib-dom/index.d.ts",
+        "node_modules/@typescript/lib-dom/iterable.d.ts",
+        "node_modules/@types/lib-dom/index.d.ts",
+        "node_modules/lib.d.ts/index.d.ts",
+        "node_modules/foo/index.d.ts",
+        "/.lib/lib.d.ts",
+        "𝓪.ts",
+        "é.ts",
+        "Z.ts",
+    ];
+
+    #[test]
+    fn total_order() {
+        for &a in NAMES {
+            for &b in NAMES {
+                let ab = cmp(a, b);
+                assert_eq!(ab, cmp(b, a).reverse(), "{a:?} {b:?}");
+                assert_eq!(ab == Ordering::Equal, a == b, "{a:?} {b:?}");
+                for &c in NAMES {
+                    if ab.is_le() && cmp(b, c).is_le() {
+                        assert!(cmp(a, c).is_le(), "{a:?} {b:?} {c:?}");
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn groups() {
+        let mut names = NAMES.to_vec();
+        names.sort_by(|a, b| cmp(a, b));
+        assert_eq!(
+            names,
+            [
+                "",
+                "/tsconfig.json",
+                "src/tsconfig.json",
+                "tsconfig.json",
+                "a.d.ts",
+                "A.ts",
+                "a.ts",
+                "a/b.ts",
+                "a_b.ts",
+                "B.ts",
+                "b.ts",
+                "Index.ts",
+                "index.ts",
+                "lib.ts",
+                "mylib.d.ts",
+                "node_modules/@types/lib-dom/index.d.ts",
+                "node_modules/foo/index.d.ts",
+                "node_modules/lib.d.ts/index.d.ts",
+                "tsconfig.base.json",
+                "Z.ts",
+                "é.ts",
+                "𝓪.ts",
+                "/.lib/lib.d.ts",
+                "lib.d.ts",
+                "lib.dom.d.ts",
+                "lib.es2015.d.ts",
+                "node_modules/@typescript/lib-dom/index.d.ts",
+                "node_modules/@typescript/lib-dom/iterable.d.ts",
+            ]
+        );
+    }
+}
@@ -1,5 +1,6 @@
 use memchr::{Memchr, memchr_iter};
 
+#[derive(Clone)]
 pub(super) struct LineIter<'a> {
     data: &'a [u8],
     iter: Memchr<'a>,
@@ -11,6 +12,16 @@ impl<'a> LineIter<'a> {
     pub fn new(data: &'a [u8]) -> Self {
         Self { data, iter: memchr_iter(b'\n', data), line_start: 0, line_idx: 0 }
     }
+
+    /// The next line, if it starts with `first`.
+    pub fn next_starting_with(&mut self, first: u8) -> Option<(usize, usize, &'a [u8])> {
+        if self.data.get(self.line_start) == Some(&first) { self.next() } else { None }
+    }
+
+    /// Like [`Self::next_starting_with`], without consuming the line.
+    pub fn peek_starting_with(&self, first: u8) -> Option<(usize, usize, &'a [u8])> {
+        self.clone().next_starting_with(first)
+    }
 }
 
 impl<'a> Iterator for LineIter<'a> {
@@ -20,15 +31,16 @@ impl<'a> Iterator for LineIter<'a> {
         match self.line_start.cmp(&self.data.len()) {
             std::cmp::Ordering::Less => {
                 let eol = self.iter.next().unwrap_or_else(|| self.data.len() - 1);
-                let line = &self.data[self.line_start..=eol];
-                let line_end = line.len()
-                    - if line.len() >= 2 && line[line.len() - 2] == b'\r' {
-                        2
-                    } else {
-                        usize::from(!line.is_empty() && line[line.len() - 1] == b'\n')
-                    };
-
-                let result = (self.line_idx, self.line_start, &line[..line_end]);
+                let mut line = &self.data[self.line_start..=eol];
+                // The last line may end without `\n` (or with a dangling `\r`)
+                if let Some(rest) = line.strip_suffix(b"\n") {
+                    line = rest;
+                }
+                if let Some(rest) = line.strip_suffix(b"\r") {
+                    line = rest;
+                }
+
+                let result = (self.line_idx, self.line_start, line);
                 self.line_start = eol + 1;
                 self.line_idx += 1;
 
@@ -44,3 +56,27 @@ impl<'a> Iterator for LineIter<'a> {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lines(data: &[u8]) -> Vec<(usize, &[u8])> {
+        LineIter::new(data).map(|(_, start, line)| (start, line)).collect()
+    }
+
+    #[test]
+    fn line_endings() {
+        let expected: Vec<(usize, &[u8])> = vec![(0, b"a"), (2, b"bc"), (5, b"")];
+        assert_eq!(lines(b"a\nbc\n"), expected);
+
+        let expected: Vec<(usize, &[u8])> = vec![(0, b"a"), (3, b"bc"), (7, b"")];
+        assert_eq!(lines(b"a\r\nbc\r\n"), expected);
+
+        // Last line without a line break
+        let expected: Vec<(usize, &[u8])> = vec![(0, b"a"), (3, b"x\ry"), (6, b"")];
+        assert_eq!(lines(b"a\r\nx\ry"), expected);
+        let expected: Vec<(usize, &[u8])> = vec![(0, b"a"), (3, b"bc"), (6, b"")];
+        assert_eq!(lines(b"a\r\nbc\r"), expected);
+    }
+}
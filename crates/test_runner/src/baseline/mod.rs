@@ -1,27 +1,480 @@
-use std::path::Path;
+use std::{
+    path::{Path, PathBuf},
+    time::{Duration, Instant},
+};
 
-use errors_baseline::ErrorsBaseline;
+pub use errors_baseline::{
+    ConfigError, CountMismatch, DeclaredCount, ErrorsBaseline, FileError, SummaryEntry, SummaryIter,
+};
+use symbols_baseline::SymbolsBaseline;
 use types_baseline::TypesBaseline;
+pub use writer::{ModuleTypes, write_errors_baseline, write_types_baseline};
+
+use crate::parse_warning::{ParseWarning, ParseWarningKind};
 
 mod errors_baseline;
+pub mod file_order;
 mod line_iter;
+pub mod symbols_baseline;
 pub mod types_baseline;
+mod writer;
+
+/// Baseline files a variant was compared against and the format detected while reading them.
+#[derive(Debug, Default, PartialEq, Eq, Clone)]
+pub struct BaselineSource {
+    /// `.types` baseline path relative to the repo.
+    pub types: PathBuf,
+    /// `.errors.txt` baseline path relative to the repo, if the variant has one.
+    pub errors: Option<PathBuf>,
+    /// `.symbols` baseline path relative to the repo, if the variant has one.
+    pub symbols: Option<PathBuf>,
+    /// The errors baseline was written with `@pretty: true`.
+    pub pretty_errors: bool,
+    /// The types baseline uses `\r\n` line endings.
+    pub crlf: bool,
+}
+
+impl BaselineSource {
+    /// Records the paths of the baseline files that were read and sniffs their format.
+    pub fn detect(
+        types_path: &Path,
+        types_data: &[u8],
+        errors_path: &Path,
+        errors_data: Option<&[u8]>,
+    ) -> Self {
+        Self {
+            types: types_path.to_path_buf(),
+            errors: errors_data.map(|_| errors_path.to_path_buf()),
+            symbols: None,
+            pretty_errors: errors_data.is_some_and(|x| x.first() == Some(&0x1B)),
+            crlf: memchr::memmem::find(types_data, b"\r\n").is_some(),
+        }
+    }
+}
+
+impl std::fmt::Display for BaselineSource {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.types.display())?;
+        for path in [&self.errors, &self.symbols].into_iter().flatten() {
+            write!(f, ", {}", path.display())?;
+        }
+
+        let format: Vec<_> = [(self.pretty_errors, "pretty"), (self.crlf, "crlf")]
+            .into_iter()
+            .filter_map(|(set, name)| set.then_some(name))
+            .collect();
+        if !format.is_empty() {
+            write!(f, " ({})", format.join(", "))?;
+        }
+        Ok(())
+    }
+}
+
+/// Baseline file of a variant that is parsed.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum BaselineKind {
+    Types,
+    Errors,
+    Symbols,
+}
+
+impl BaselineKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            BaselineKind::Types => "types",
+            BaselineKind::Errors => "errors",
+            BaselineKind::Symbols => "symbols",
+        }
+    }
+}
+
+impl std::fmt::Display for BaselineKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// A baseline file the parser gave up on. This is a bug (or a missing format case) in our
+/// parser, not a checker mismatch.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct BaselineParseError {
+    pub which: BaselineKind,
+    /// Baseline path as passed to the parser.
+    pub path: PathBuf,
+    /// Byte offset in the baseline where parsing failed, when the parser reports it.
+    pub offset: Option<usize>,
+    /// 1-based line of [`Self::offset`].
+    pub line: Option<usize>,
+    /// What the parser reported.
+    pub error: String,
+    /// Set when an underline does not fit the error it belongs to.
+    pub underline: Option<Box<UnderlineMismatch>>,
+    /// The line parsing failed at, when the parser reports it.
+    pub snippet: Option<String>,
+}
+
+/// An underline of an errors baseline that ends before the column of its error, or past what a
+/// length can hold.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct UnderlineMismatch {
+    /// The underline line, as written in the baseline.
+    pub text: String,
+    /// 1-based column of the error.
+    pub column: u32,
+}
+
+impl std::fmt::Display for BaselineParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Failed to parse {} baseline {}", self.which, self.path.display())?;
+        if let (Some(line), Some(offset)) = (self.line, self.offset) {
+            write!(f, " at line {line} (offset {offset})")?;
+        }
+        write!(f, ": {}", self.error)?;
+        if let Some(snippet) = &self.snippet {
+            write!(f, " in `{}`", snippet.escape_debug())?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for BaselineParseError {}
+
+/// Time spent parsing one baseline file, for `--slow-baselines`.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct BaselineTiming {
+    /// Baseline path relative to the repo.
+    pub path: PathBuf,
+    /// Size of the decoded file in bytes.
+    pub size: usize,
+    pub elapsed: Duration,
+}
+
+/// Time [`Baseline::parse`] spent in each parser.
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy)]
+pub struct ParseTimes {
+    pub types: Duration,
+    /// `None` when the variant has no errors baseline.
+    pub errors: Option<Duration>,
+    /// `None` when the variant has no symbols baseline.
+    pub symbols: Option<Duration>,
+}
 
 pub struct Baseline<'a> {
     pub types: TypesBaseline<'a>,
     pub errors: Option<ErrorsBaseline<'a>>,
+    pub symbols: Option<SymbolsBaseline<'a>>,
+    /// Why [`Self::symbols`] is `None` despite a symbols baseline, when the parser gave up on it.
+    pub symbols_warning: Option<ParseWarning>,
+    pub source: BaselineSource,
+    pub parse_times: ParseTimes,
+    /// Baselines of the same variant from the `--compare-repo` checkout, if it has them.
+    pub compare: Option<Box<Self>>,
 }
 
 impl<'a> Baseline<'a> {
+    /// Parses the baselines of a variant. `symbols` is the path and data of its symbols baseline,
+    /// if it has one: a symbols baseline the parser gives up on is left out with a
+    /// [`ParseWarningKind::UnparsableSymbols`] warning, as types and errors are what a variant
+    /// is checked against.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` with the first of the types and errors baselines the parsers gave up on.
     pub fn parse(
         types_path: &'_ Path,
         types_data: &'a [u8],
         errors_path: &'_ Path,
         errors_data: Option<&'a [u8]>,
-    ) -> Self {
-        Self {
-            types: TypesBaseline::parse(types_path, types_data),
-            errors: errors_data.map(|x| ErrorsBaseline::parse(errors_path, x)),
+        symbols: Option<(&'_ Path, &'a [u8])>,
+    ) -> Result<Self, BaselineParseError> {
+        let start = Instant::now();
+        let types = TypesBaseline::parse(types_path, types_data)?;
+        let types_time = start.elapsed();
+        trace_parse(BaselineKind::Types, types_path, types_data.len(), types_time);
+
+        let start = Instant::now();
+        let errors = errors_data.map(|x| ErrorsBaseline::parse(errors_path, x)).transpose()?;
+        let errors_time = errors.is_some().then(|| start.elapsed());
+        if let (Some(data), Some(elapsed)) = (errors_data, errors_time) {
+            trace_parse(BaselineKind::Errors, errors_path, data.len(), elapsed);
         }
+
+        let (mut symbols_baseline, mut symbols_time, mut symbols_warning) = (None, None, None);
+        if let Some((path, data)) = symbols {
+            let start = Instant::now();
+            let parsed = SymbolsBaseline::parse(path, data);
+            let elapsed = start.elapsed();
+            trace_parse(BaselineKind::Symbols, path, data.len(), elapsed);
+            match parsed {
+                Ok(parsed) => (symbols_baseline, symbols_time) = (Some(parsed), Some(elapsed)),
+                Err(err) => {
+                    let snippet = err.snippet.as_deref().unwrap_or(&err.error);
+                    let line = err.line.unwrap_or(1);
+                    let kind = ParseWarningKind::UnparsableSymbols;
+                    symbols_warning = Some(ParseWarning::new(kind, path, line, snippet.as_bytes()));
+                }
+            }
+        }
+
+        let mut source = BaselineSource::detect(types_path, types_data, errors_path, errors_data);
+        source.symbols = symbols.filter(|_| symbols_baseline.is_some()).map(|x| x.0.to_path_buf());
+        Ok(Self {
+            types,
+            errors,
+            symbols: symbols_baseline,
+            symbols_warning,
+            source,
+            parse_times: ParseTimes {
+                types: types_time,
+                errors: errors_time,
+                symbols: symbols_time,
+            },
+            compare: None,
+        })
+    }
+
+    /// Whether both baselines expect the same types and errors, regardless of their format.
+    pub fn same_expectations(&self, other: &Self) -> bool {
+        let errors = |x: &Self| x.errors.as_ref().map(ErrorsBaseline::to_diagnostics);
+        self.types.names == other.types.names
+            && self.types.files == other.types.files
+            && errors(self).unwrap_or_default() == errors(other).unwrap_or_default()
+    }
+
+    /// Per-file parse times, with the paths and sizes recorded in [`Self::source`].
+    pub fn timings(
+        &self,
+        types_size: usize,
+        errors_size: usize,
+        symbols_size: usize,
+    ) -> Vec<BaselineTiming> {
+        let types = BaselineTiming {
+            path: self.source.types.clone(),
+            size: types_size,
+            elapsed: self.parse_times.types,
+        };
+        let errors = self
+            .source
+            .errors
+            .clone()
+            .zip(self.parse_times.errors)
+            .map(|(path, elapsed)| BaselineTiming { path, size: errors_size, elapsed });
+        let symbols = self
+            .source
+            .symbols
+            .clone()
+            .zip(self.parse_times.symbols)
+            .map(|(path, elapsed)| BaselineTiming { path, size: symbols_size, elapsed });
+        std::iter::once(types).chain(errors).chain(symbols).collect()
+    }
+
+    /// Format gaps worked around while parsing the baselines.
+    pub fn warnings(&self) -> impl Iterator<Item = &ParseWarning> {
+        self.types
+            .warnings
+            .iter()
+            .chain(self.errors.iter().flat_map(ErrorsBaseline::warnings))
+            .chain(self.symbols.iter().flat_map(|x| &x.warnings))
+            .chain(&self.symbols_warning)
+    }
+}
+
+/// Reports the parse time of a baseline file as a `debug` event, for a tracing subscriber to spot
+/// pathological files as they go by. `--slow-baselines` collects the same numbers per run.
+fn trace_parse(which: BaselineKind, path: &Path, size: usize, elapsed: Duration) {
+    tracing::debug!(
+        which = which.as_str(),
+        path = %path.display(),
+        size,
+        elapsed_us = u64::try_from(elapsed.as_micros()).unwrap_or(u64::MAX),
+        "parsed baseline"
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        fmt::Write,
+        sync::{Arc, Mutex},
+    };
+
+    use oxc_index::index_vec;
+    use tracing::{
+        Event, Metadata, Subscriber,
+        field::{Field, Visit},
+        span::{Attributes, Id, Record},
+    };
+
+    use super::*;
+
+    #[test]
+    fn source() {
+        let types = b"=== a.ts ===\r\nconst a = 1;\r\n>a : 1\r\n";
+        let errors =
+            b"\x1b[96ma.ts\x1b[0m:\x1b[93m1\x1b[0m:\x1b[93m7\x1b[0m - \x1b[91merror\x1b[0m";
+        let source = BaselineSource::detect(
+            Path::new("a.types"),
+            types,
+            Path::new("a.errors.txt"),
+            Some(errors),
+        );
+        assert_eq!(
+            source,
+            BaselineSource {
+                types: PathBuf::from("a.types"),
+                errors: Some(PathBuf::from("a.errors.txt")),
+                symbols: None,
+                pretty_errors: true,
+                crlf: true,
+            }
+        );
+        assert_eq!(source.to_string(), "a.types, a.errors.txt (pretty, crlf)");
+
+        let source = BaselineSource::detect(
+            Path::new("b.types"),
+            b"=== b.ts ===\n",
+            Path::new("b.errors.txt"),
+            None,
+        );
+        assert_eq!(source.errors, None);
+        assert_eq!(source.to_string(), "b.types");
+    }
+
+    #[test]
+    fn same_expectations() {
+        let types = b"//// [a.ts] ////\n\n=== a.ts ===\nconst a = 1;\n>a : 1\n>  : ^\n";
+        let changed = b"//// [a.ts] ////\n\n=== a.ts ===\nconst a = 1;\n>a : number\n>  : ^^^^^^\n";
+        let errors = b"error TS5102: Option 'out' has been removed.\n\n\n!!! error TS5102: Option 'out' has been removed.\n==== a.ts (0 errors) ====\n    const a = 1;\n";
+        let parse = |types: &'static [u8], errors: Option<&'static [u8]>| {
+            Baseline::parse(Path::new("a.types"), types, Path::new("a.errors.txt"), errors, None)
+                .unwrap()
+        };
+
+        let baseline = parse(types, None);
+        assert!(baseline.same_expectations(&parse(types, None)));
+        assert!(!baseline.same_expectations(&parse(changed, None)));
+        assert!(!baseline.same_expectations(&parse(types, Some(errors))));
+        assert!(parse(types, Some(errors)).same_expectations(&parse(types, Some(errors))));
+    }
+
+    #[test]
+    fn symbols() {
+        let types = b"//// [a.ts] ////\n\n=== a.ts ===\nconst a = 1;\n>a : 1\n>  : ^\n";
+        let symbols = b"//// [a.ts] ////\n\n=== a.ts ===\nconst a = 1;\n>a : Symbol(a)\n";
+        let parse = |symbols: &'static [u8]| {
+            let symbols = Some((Path::new("a.symbols"), symbols));
+            Baseline::parse(Path::new("a.types"), types, Path::new("a.errors.txt"), None, symbols)
+                .unwrap()
+        };
+
+        let baseline = parse(symbols);
+        assert_eq!(baseline.warnings().count(), 0);
+        assert_eq!(baseline.symbols.map(|x| x.names), Some(index_vec!["a.ts"]));
+        assert_eq!(baseline.source.symbols, Some(PathBuf::from("a.symbols")));
+        assert_eq!(baseline.source.to_string(), "a.types, a.symbols");
+        assert!(baseline.parse_times.symbols.is_some());
+
+        // The variant is still checked against its types, without symbols
+        let baseline = parse(b">a : Symbol(a)\n");
+        assert!(baseline.symbols.is_none());
+        assert_eq!((&baseline.source.symbols, baseline.parse_times.symbols), (&None, None));
+        let warnings: Vec<_> = baseline.warnings().map(|x| (x.kind, x.line)).collect();
+        assert_eq!(warnings, [(ParseWarningKind::UnparsableSymbols, 1)]);
+        assert_eq!(baseline.types.names, index_vec!["a.ts"]);
+    }
+
+    #[test]
+    fn unparsable() {
+        let types = b"//// [a.ts] ////\n\n=== a.ts ===\nvar x = y;\n>x : any\n>  : ^^^\n";
+        let errors = b"a.ts(1): error TS2304: Cannot find name 'y'.\n";
+        let err = Baseline::parse(
+            Path::new("a.types"),
+            types,
+            Path::new("a.errors.txt"),
+            Some(errors),
+            None,
+        )
+        .err()
+        .expect("errors baseline to be unparsable");
+        assert_eq!(err.which, BaselineKind::Errors);
+        assert_eq!(err.path, PathBuf::from("a.errors.txt"));
+        assert_eq!(err.error, "Failed to find end of line number");
+        assert_eq!((err.line, err.offset), (Some(1), Some(5)));
+        assert_eq!(
+            err.to_string(),
+            "Failed to parse errors baseline a.errors.txt at line 1 (offset 5): Failed to find end \
+             of line number"
+        );
+    }
+
+    /// Collects the fields of the events of [`trace_parse`], one `name=value` list per event.
+    #[derive(Default)]
+    struct Events(Arc<Mutex<Vec<String>>>);
+
+    struct Fields(String);
+
+    impl Visit for Fields {
+        fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+            if field.name() != "elapsed_us" {
+                let separator = if self.0.is_empty() { "" } else { " " };
+                let _ = write!(self.0, "{separator}{}={value:?}", field.name());
+            }
+        }
+    }
+
+    impl Subscriber for Events {
+        fn enabled(&self, _metadata: &Metadata<'_>) -> bool {
+            true
+        }
+
+        fn new_span(&self, _span: &Attributes<'_>) -> Id {
+            Id::from_u64(1)
+        }
+
+        fn record(&self, _span: &Id, _values: &Record<'_>) {}
+
+        fn record_follows_from(&self, _span: &Id, _follows: &Id) {}
+
+        fn event(&self, event: &Event<'_>) {
+            let mut fields = Fields(String::new());
+            event.record(&mut fields);
+            self.0.lock().unwrap().push(fields.0);
+        }
+
+        fn enter(&self, _span: &Id) {}
+
+        fn exit(&self, _span: &Id) {}
+    }
+
+    #[test]
+    fn traced() {
+        let events = Events::default();
+        let collected = Arc::clone(&events.0);
+        let types = b"//// [a.ts] ////\n\n=== a.ts ===\nconst a = 1;\n>a : 1\n>  : ^\n";
+        let errors = b"a.ts(1,7): error TS2322: Type '1' is not assignable to type 'string'.\n\n\n==== a.ts (1 errors) ====\n    const a = 1;\n          ~\n!!! error TS2322: Type '1' is not assignable to type 'string'.\n";
+        let baseline = tracing::subscriber::with_default(events, || {
+            Baseline::parse(
+                Path::new("a.types"),
+                types,
+                Path::new("a.errors.txt"),
+                Some(errors),
+                None,
+            )
+        });
+
+        assert!(baseline.is_ok());
+        assert_eq!(
+            *collected.lock().unwrap(),
+            vec![
+                format!(
+                    "message=parsed baseline which=\"types\" path=a.types size={}",
+                    types.len()
+                ),
+                format!(
+                    "message=parsed baseline which=\"errors\" path=a.errors.txt size={}",
+                    errors.len()
+                ),
+            ]
+        );
     }
 }
@@ -0,0 +1,413 @@
+use core::str;
+use std::{ops::Range, path::Path};
+
+use oxc_index::IndexVec;
+
+use super::{
+    BaselineKind, BaselineParseError,
+    line_iter::LineIter,
+    types_baseline::{BaselineFileId, LineId},
+};
+use crate::parse_warning::{ParseWarning, ParseWarningKind};
+
+/// Delimiter between the expression and the symbol of an assertion.
+const SYMBOL_DELIMITER: &str = " : Symbol(";
+
+#[derive(Debug, PartialEq, Eq, Default)]
+pub struct SymbolsBaseline<'a> {
+    pub names: IndexVec<BaselineFileId, &'a str>,
+    pub files: IndexVec<BaselineFileId, SymbolBaselineFile<'a>>,
+    /// Format gaps worked around while parsing.
+    pub warnings: Vec<ParseWarning>,
+}
+
+#[derive(Debug, PartialEq, Eq, Default)]
+pub struct SymbolBaselineFile<'a> {
+    pub statements: IndexVec<LineId, &'a str>,
+    pub assertions: IndexVec<LineId, Vec<SymbolAssertion<'a>>>,
+}
+
+/// `>expr : Symbol(name, Decl(file, line, column), ...)`
+#[derive(Debug, PartialEq, Eq, Default)]
+pub struct SymbolAssertion<'a> {
+    pub expr: &'a str,
+    /// Symbol name as the checker prints it, like `Console.log`.
+    pub symbol: &'a str,
+    /// Empty for symbols without declarations, like `undefined`.
+    pub declarations: Vec<Declaration<'a>>,
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct Declaration<'a> {
+    pub file: &'a str,
+    /// 0-based line and column, `None` for lib files the harness writes as `--, --`.
+    pub loc: Option<(u32, u32)>,
+}
+
+impl<'a> SymbolsBaseline<'a> {
+    /// # Errors
+    ///
+    /// Will return `Err` if the baseline does not start with the test unit path, has an
+    /// assertion before the first file header, has more files or statements than we index, or
+    /// is not UTF-8.
+    pub fn parse(path: &'_ Path, data: &'a [u8]) -> Result<Self, BaselineParseError> {
+        let error =
+            |line_idx: usize, offset: usize, line: Option<&[u8]>, error: &str| BaselineParseError {
+                which: BaselineKind::Symbols,
+                path: path.to_path_buf(),
+                offset: Some(offset),
+                line: Some(line_idx + 1),
+                error: error.to_string(),
+                underline: None,
+                snippet: line.map(|x| String::from_utf8_lossy(x).into_owned()),
+            };
+        let text = |line_idx: usize, range: Range<usize>| {
+            let start = range.start;
+            str::from_utf8(&data[range]).map_err(|_| error(line_idx, start, None, "Invalid UTF-8"))
+        };
+        // Starts a statement with the code lines read since the previous one. Assertions
+        // without code of their own, right after a file header or another block, get an empty
+        // statement.
+        let push_code = |file: &mut SymbolBaselineFile<'a>,
+                         code: Option<(usize, Range<usize>)>,
+                         (line_idx, line_start): (usize, usize)| {
+            let code = code.map(|(line_idx, range)| text(line_idx, range)).transpose()?;
+            if file.statements.len() > LineId::MAX_INDEX {
+                return Err(error(line_idx, line_start, None, "Too many statements in file"));
+            }
+            file.statements.push(code.unwrap_or_default());
+            Ok(file.assertions.push(Vec::new()))
+        };
+
+        let mut result = Self::default();
+        let mut iter = LineIter::new(data);
+        let first = iter.next().unwrap_or((0, 0, b""));
+        if !first.2.starts_with(b"//// [") || !first.2.ends_with(b"] ////") {
+            let message = "Expected baseline to start with test unit path";
+            return Err(error(0, 0, Some(first.2), message));
+        }
+
+        // Code lines since the last assertion block
+        let mut statement: Option<(usize, Range<usize>)> = None;
+        while let Some((line_idx, line_start, line)) = iter.next() {
+            if line.is_empty() {
+                continue;
+            }
+
+            if line.len() >= 8 && line.starts_with(b"=== ") && line.ends_with(b" ===") {
+                // Code after the last assertion block of the previous file
+                if let (Some(file), Some(code)) = (result.files.last_mut(), statement.take()) {
+                    push_code(file, Some(code), (line_idx, line_start))?;
+                }
+                if result.files.len() > BaselineFileId::MAX_INDEX {
+                    return Err(error(
+                        line_idx,
+                        line_start,
+                        Some(line),
+                        "Too many files in baseline",
+                    ));
+                }
+                result.names.push(text(line_idx, line_start + 4..line_start + line.len() - 4)?);
+                result.files.push(SymbolBaselineFile::default());
+                continue;
+            }
+
+            if !line.starts_with(b">") {
+                let end = line_start + line.len();
+                statement =
+                    Some(statement.map_or((line_idx, line_start..end), |(idx, range)| {
+                        (idx, range.start..end)
+                    }));
+                continue;
+            }
+
+            let Some(file) = result.files.last_mut() else {
+                let message = "Expected file header before assertion";
+                return Err(error(line_idx, line_start, Some(line), message));
+            };
+            let id = push_code(file, statement.take(), (line_idx, line_start))?;
+            let assertions = &mut file.assertions[id];
+
+            let mut next = Some((line_idx, line_start, line));
+            while let Some((line_idx, line_start, line)) =
+                next.take().or_else(|| iter.next_starting_with(b'>'))
+            {
+                // Multi-line expressions and long lists of declarations continue on the next
+                // lines, until the symbol is closed
+                let mut end = line_start + line.len();
+                while !is_complete(&data[line_start..end]) {
+                    let mut peek = iter.clone();
+                    match peek.next() {
+                        Some((_, start, line))
+                            if !line.is_empty()
+                                && !line.starts_with(b">")
+                                && !line.starts_with(b"=== ") =>
+                        {
+                            end = start + line.len();
+                            iter = peek;
+                        }
+                        _ => break,
+                    }
+                }
+
+                let assertion = text(line_idx, line_start + 1..end)?;
+                let kind = match assertion.rsplit_once(SYMBOL_DELIMITER) {
+                    Some((expr, symbol)) => match parse_symbol(symbol) {
+                        Some((symbol, declarations)) => {
+                            assertions.push(SymbolAssertion { expr, symbol, declarations });
+                            continue;
+                        }
+                        None => ParseWarningKind::MalformedSymbol,
+                    },
+                    None => ParseWarningKind::MissingDelimiter,
+                };
+                let snippet = &data[line_start..end];
+                result.warnings.push(ParseWarning::new(kind, path, line_idx + 1, snippet));
+            }
+        }
+
+        if let (Some(file), Some((line_idx, code))) = (result.files.last_mut(), statement) {
+            let at = (line_idx, code.start);
+            push_code(file, Some((line_idx, code)), at)?;
+        }
+        Ok(result)
+    }
+}
+
+/// Whether an assertion line, with the lines it continues on, ends its symbol.
+fn is_complete(assertion: &[u8]) -> bool {
+    assertion.ends_with(b")")
+        && memchr::memmem::find(assertion, SYMBOL_DELIMITER.as_bytes()).is_some()
+}
+
+/// Splits `name, Decl(file, line, column), ...)` into the symbol name and its declarations.
+/// Entries may be separated by line breaks.
+fn parse_symbol(text: &str) -> Option<(&str, Vec<Declaration<'_>>)> {
+    let body = text.strip_suffix(')')?;
+    let Some((name, declarations)) = body.split_once("Decl(") else {
+        return Some((body, Vec::new()));
+    };
+    let name = name.trim_end().strip_suffix(',')?;
+
+    let declarations = declarations
+        .split("Decl(")
+        .map(|decl| {
+            let decl = decl.trim_end();
+            let decl = decl.strip_suffix(',').unwrap_or(decl).trim_end().strip_suffix(')')?;
+            // File names may contain commas, the position never does
+            let mut parts = decl.rsplitn(3, ',').map(str::trim);
+            let (column, line, file) = (parts.next()?, parts.next()?, parts.next()?);
+            let loc = if (line, column) == ("--", "--") {
+                None
+            } else {
+                Some((line.parse().ok()?, column.parse().ok()?))
+            };
+            Some(Declaration { file, loc })
+        })
+        .collect::<Option<_>>()?;
+    Some((name, declarations))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use oxc_index::index_vec;
+
+    use super::*;
+
+    fn parse(data: &[u8]) -> SymbolsBaseline<'_> {
+        SymbolsBaseline::parse(Path::new("a.symbols"), data).unwrap()
+    }
+
+    fn decl(file: &str, line: u32, column: u32) -> Declaration<'_> {
+        Declaration { file, loc: Some((line, column)) }
+    }
+
+    #[test]
+    fn single_file() {
+        let baseline = parse(
+            b"//// [tests/cases/compiler/a.ts] ////
+
+=== a.ts ===
+// comment
+var x = 1;
+>x : Symbol(x, Decl(a.ts, 1, 3))
+
+function f(a: number) {
+>f : Symbol(f, Decl(a.ts, 3, 10))
+>a : Symbol(a, Decl(a.ts, 3, 11))
+
+    return a;
+>a : Symbol(a, Decl(a.ts, 3, 11))
+}
+",
+        );
+        assert_eq!(
+            baseline,
+            SymbolsBaseline {
+                names: index_vec!["a.ts"],
+                files: index_vec![SymbolBaselineFile {
+                    statements: index_vec![
+                        "// comment\nvar x = 1;",
+                        "function f(a: number) {",
+                        "    return a;",
+                        "}",
+                    ],
+                    assertions: index_vec![
+                        vec![SymbolAssertion {
+                            expr: "x",
+                            symbol: "x",
+                            declarations: vec![decl("a.ts", 1, 3)]
+                        }],
+                        vec![
+                            SymbolAssertion {
+                                expr: "f",
+                                symbol: "f",
+                                declarations: vec![decl("a.ts", 3, 10)]
+                            },
+                            SymbolAssertion {
+                                expr: "a",
+                                symbol: "a",
+                                declarations: vec![decl("a.ts", 3, 11)]
+                            },
+                        ],
+                        vec![SymbolAssertion {
+                            expr: "a",
+                            symbol: "a",
+                            declarations: vec![decl("a.ts", 3, 11)]
+                        }],
+                        vec![],
+                    ],
+                }],
+                warnings: vec![],
+            }
+        );
+    }
+
+    #[test]
+    fn multiple_declarations() {
+        let baseline = parse(
+            b"//// [tests/cases/compiler/merged.ts] ////
+
+=== a.ts ===
+interface I { a: string }
+>I : Symbol(I, Decl(a.ts, 0, 0), Decl(b.ts, 0, 0))
+>a : Symbol(I.a, Decl(a.ts, 0, 13))
+
+=== b.ts ===
+interface I { b: string }
+>I : Symbol(I, Decl(a.ts, 0, 0), Decl(b.ts, 0, 0),
+  Decl(c, d.ts, 0, 0))
+>b : Symbol(I.b, Decl(b.ts, 0, 13))
+
+console.log(1);
+>console.log : Symbol(Console.log, Decl(lib.dom.d.ts, --, --))
+>console : Symbol(console, Decl(lib.dom.d.ts, --, --), Decl(lib.dom.d.ts, --, --))
+>log : Symbol(Console.log, Decl(lib.dom.d.ts, --, --))
+",
+        );
+        assert_eq!(baseline.names, index_vec!["a.ts", "b.ts"]);
+        assert_eq!(baseline.warnings, vec![]);
+
+        let merged = &baseline.files[BaselineFileId::new(1)].assertions[LineId::new(0)][0];
+        assert_eq!(merged.expr, "I");
+        assert_eq!(merged.symbol, "I");
+        assert_eq!(
+            merged.declarations,
+            vec![decl("a.ts", 0, 0), decl("b.ts", 0, 0), decl("c, d.ts", 0, 0)]
+        );
+
+        let lib = Declaration { file: "lib.dom.d.ts", loc: None };
+        let console = &baseline.files[BaselineFileId::new(1)].assertions[LineId::new(1)];
+        assert_eq!(console.len(), 3);
+        assert_eq!(console[0].expr, "console.log");
+        assert_eq!(console[0].symbol, "Console.log");
+        assert_eq!(console[1].declarations, vec![lib, lib]);
+    }
+
+    #[test]
+    fn no_declarations() {
+        let baseline = parse(
+            b"//// [tests/cases/compiler/a.ts] ////
+
+=== a.ts ===
+var u = undefined;
+>u : Symbol(u, Decl(a.ts, 0, 3))
+>undefined : Symbol(undefined)
+
+function f() { return arguments; }
+>f : Symbol(f, Decl(a.ts, 0, 18))
+>arguments : Symbol(arguments)
+
+a
+  .b;
+>a  .b : Symbol(b)
+",
+        );
+        let assertions = &baseline.files[BaselineFileId::new(0)].assertions;
+        let undefined = &assertions[LineId::new(0)][1];
+        assert_eq!((undefined.expr, undefined.symbol), ("undefined", "undefined"));
+        assert_eq!(undefined.declarations, vec![]);
+        let arguments = &assertions[LineId::new(1)][1];
+        assert_eq!((arguments.expr, arguments.symbol), ("arguments", "arguments"));
+        assert_eq!(arguments.declarations, vec![]);
+        assert_eq!(baseline.files[BaselineFileId::new(0)].statements[LineId::new(2)], "a\n  .b;");
+        assert_eq!(assertions[LineId::new(2)][0].expr, "a  .b");
+    }
+
+    #[test]
+    fn multi_line_expression() {
+        let baseline = parse(
+            b"//// [tests/cases/compiler/a.ts] ////
+
+=== a.ts ===
+a
+  .b;
+>a
+  .b : Symbol(A.b, Decl(a.ts, 0, 0))
+>a : Symbol(a)
+",
+        );
+        let assertions = &baseline.files[BaselineFileId::new(0)].assertions[LineId::new(0)];
+        assert_eq!(assertions.len(), 2);
+        assert_eq!(assertions[0].expr, "a\n  .b");
+        assert_eq!(assertions[0].declarations, vec![decl("a.ts", 0, 0)]);
+        assert_eq!(assertions[1].expr, "a");
+    }
+
+    #[test]
+    fn malformed_assertions() {
+        let baseline = parse(
+            b"//// [tests/cases/compiler/a.ts] ////
+
+=== a.ts ===
+var x;
+>x : any
+>x : Symbol(x, Decl(a.ts, one, 4))
+>x : Symbol(x, Decl(a.ts, 0, 4))
+",
+        );
+        let kinds: Vec<_> = baseline.warnings.iter().map(|x| (x.kind, x.line)).collect();
+        assert_eq!(
+            kinds,
+            [(ParseWarningKind::MissingDelimiter, 5), (ParseWarningKind::MalformedSymbol, 6)]
+        );
+        let assertions = &baseline.files[BaselineFileId::new(0)].assertions[LineId::new(0)];
+        assert_eq!(assertions.len(), 1);
+        assert_eq!(assertions[0].declarations, vec![decl("a.ts", 0, 4)]);
+    }
+
+    #[test]
+    fn unparsable() {
+        let path = PathBuf::from("a.symbols");
+        let err = SymbolsBaseline::parse(&path, b"=== a.ts ===\n").unwrap_err();
+        assert_eq!(err.which, BaselineKind::Symbols);
+        assert_eq!(err.error, "Expected baseline to start with test unit path");
+
+        let err =
+            SymbolsBaseline::parse(&path, b"//// [a.ts] ////\n\nx;\n>x : Symbol(x)\n").unwrap_err();
+        assert_eq!(err.error, "Expected file header before assertion");
+        assert_eq!((err.line, err.offset), (Some(4), Some(21)));
+    }
+}
@@ -1,11 +1,15 @@
 use core::str;
-use std::{fmt::Write, ops::Add, path::Path};
+use std::path::Path;
 
 use memchr::memchr;
-use oxc::syntax::identifier::is_identifier_part;
+use oxc::{span::Span, syntax::identifier::is_identifier_part};
 use oxc_index::IndexVec;
 
-use super::line_iter::LineIter;
+use super::{BaselineKind, BaselineParseError, line_iter::LineIter};
+use crate::{
+    line_index::utf16_offset,
+    parse_warning::{ParseWarning, ParseWarningKind},
+};
 
 oxc_index::define_index_type! {
   pub struct LineId = u16;
@@ -19,6 +23,8 @@ oxc_index::define_index_type! {
 pub struct TypesBaseline<'a> {
     pub names: IndexVec<BaselineFileId, &'a str>,
     pub files: IndexVec<BaselineFileId, TypeBaselineFile<'a>>,
+    /// Format gaps worked around while parsing.
+    pub warnings: Vec<ParseWarning>,
 }
 
 #[derive(Debug, PartialEq, Eq, Default)]
@@ -33,28 +39,152 @@ pub struct Assertion<'a> {
     pub expected_type: &'a str,
 }
 
+/// A baseline statement located in the module source text, for resolving the spans of its
+/// assertions with [`Assertion::resolve_span`].
+#[derive(Debug)]
+pub struct StatementSource<'a> {
+    pub text: &'a str,
+    /// Offset of `text` in the module source text.
+    pub start: u32,
+    /// Spans of the assertions resolved so far, relative to `text`.
+    resolved: Vec<(usize, usize)>,
+}
+
+impl<'a> StatementSource<'a> {
+    pub fn new(source_text: &'a str, span: Span) -> Self {
+        Self::with_buffer(source_text, span, Vec::new())
+    }
+
+    /// Like [`StatementSource::new`], reusing the buffer [`StatementSource::into_buffer`]
+    /// returned for a previous statement.
+    pub fn with_buffer(source_text: &'a str, span: Span, mut buffer: Vec<(usize, usize)>) -> Self {
+        buffer.clear();
+        Self { text: span.source_text(source_text), start: span.start, resolved: buffer }
+    }
+
+    pub fn into_buffer(self) -> Vec<(usize, usize)> {
+        self.resolved
+    }
+}
+
+impl Assertion<'_> {
+    /// Span of the asserted expression in the module source text. Assertions of a statement
+    /// must be resolved in baseline order.
+    ///
+    /// The underline's `:` column gives the width of the expression, which the split pinned
+    /// `expr` to, and the harness writes the expression with its line breaks dropped. The span
+    /// covers that width of source text, line breaks aside, from where it lines up with `expr`.
+    /// The harness lists nodes in pre-order, so the span starts at or after the previous
+    /// assertion's, at the first token no earlier assertion resolved to: the second `a` of
+    /// `a + a` gets the second occurrence. Failing that, we fall back to the first verbatim
+    /// occurrence anywhere in the statement. Returns `None` if both fail.
+    pub fn resolve_span(&self, statement: &mut StatementSource<'_>) -> Option<Span> {
+        if self.expr.is_empty() {
+            return None;
+        }
+
+        let from = statement.resolved.last().map_or(0, |x| x.0);
+        let text = statement.text;
+        let unresolved = |&(start, end): &(usize, usize)| {
+            is_token_boundary(text, start, end) && !statement.resolved.contains(&(start, end))
+        };
+        let (start, end) = text[from..]
+            .char_indices()
+            .filter_map(|(offset, _)| {
+                let start = from + offset;
+                Some((start, span_end(text, start, self.expr)?))
+            })
+            .find(unresolved)
+            .or_else(|| {
+                text.match_indices(self.expr)
+                    .map(|(start, _)| (start, start + self.expr.len()))
+                    .find(unresolved)
+            })?;
+        statement.resolved.push((start, end));
+
+        let start = statement.start.checked_add(u32::try_from(start).ok()?)?;
+        let end = statement.start.checked_add(u32::try_from(end).ok()?)?;
+        Some(Span::new(start, end))
+    }
+}
+
+/// End of the span of `expr` from `start` in `text`, skipping the line breaks the harness
+/// dropped from the expression. `None` if the text there does not line up with `expr`.
+fn span_end(text: &str, start: usize, expr: &str) -> Option<usize> {
+    let bytes = text.as_bytes();
+    let mut end = start;
+    for (idx, &expected) in expr.as_bytes().iter().enumerate() {
+        while idx > 0 && matches!(bytes.get(end), Some(b'\r' | b'\n')) {
+            end += 1;
+        }
+        if bytes.get(end) != Some(&expected) {
+            return None;
+        }
+        end += 1;
+    }
+    Some(end)
+}
+
+/// Whether `text[start..end]` does not cut an identifier in half.
+fn is_token_boundary(text: &str, start: usize, end: usize) -> bool {
+    let joins = |a: Option<char>, b: Option<char>| {
+        a.is_some_and(is_identifier_part) && b.is_some_and(is_identifier_part)
+    };
+    let expr = &text[start..end];
+    !joins(text[..start].chars().next_back(), expr.chars().next())
+        && !joins(expr.chars().next_back(), text[end..].chars().next())
+}
+
+impl<'a> TypeBaselineFile<'a> {
+    /// Starts a statement, `None` if the file has as many as [`LineId`] can index.
+    fn push_statement(&mut self, text: &'a str) -> Option<LineId> {
+        if self.statements.len() > LineId::MAX_INDEX {
+            return None;
+        }
+        self.statements.push(text);
+        Some(self.assertions.push(Vec::new()))
+    }
+}
+
 impl<'a> TypesBaseline<'a> {
-    /// # Panics
-    pub fn parse(path: &'_ Path, data: &'a [u8]) -> Self {
+    /// # Errors
+    ///
+    /// Will return `Err` if the baseline does not start with the test unit path, has a broken
+    /// file header or an assertion before the first one, has more files or statements than
+    /// we index, or has text that is not UTF-8.
+    pub fn parse(path: &'_ Path, data: &'a [u8]) -> Result<Self, BaselineParseError> {
+        let error =
+            |(line_idx, line_start, line): (usize, usize, &[u8]), error: &str| BaselineParseError {
+                which: BaselineKind::Types,
+                path: path.to_path_buf(),
+                offset: Some(line_start),
+                line: Some(line_idx + 1),
+                error: error.to_string(),
+                underline: None,
+                snippet: Some(String::from_utf8_lossy(line).into_owned()),
+            };
+        let utf8 =
+            |at, text: &'a [u8], what: &str| str::from_utf8(text).map_err(|_| error(at, what));
+        // Statement text, reported at the line that ends it
+        let statement = |at, start: usize, end: usize| {
+            let text = data
+                .get(start..end)
+                .ok_or_else(|| error(at, "Expected statement to end after it starts"))?;
+            utf8(at, text, "Expected statement to be UTF-8")
+        };
+
         let mut result = Self::default();
         let mut iter = LineIter::new(data);
 
-        {
-            let line = iter.next();
-            assert!(
-                line.is_some()
-                    && line.unwrap().2.starts_with(b"//// [")
-                    && line.unwrap().2.ends_with(b"] ////"),
-                "Expected baseline to start with test unit path\n  path: {}\n  line: {}",
-                path.display(),
-                str::from_utf8(line.unwrap().2).unwrap_or_default().escape_debug()
-            );
+        let first = iter.next().unwrap_or((0, 0, b""));
+        if !first.2.starts_with(b"//// [") || !first.2.ends_with(b"] ////") {
+            return Err(error(first, "Expected baseline to start with test unit path"));
         }
 
         let mut expr_start: Option<usize> = None;
         let mut expr_end = None;
 
-        while let Some((_line_idx, line_start, line)) = iter.next() {
+        while let Some(at @ (_line_idx, line_start, line)) = iter.next() {
             if line.is_empty() {
                 if expr_end.is_some() {
                     expr_end = Some(line_start);
@@ -63,78 +193,26 @@ impl<'a> TypesBaseline<'a> {
             }
 
             if line.starts_with(b"=== ") {
-                assert!(
-                    line.ends_with(b" ==="),
-                    "Expected filename header\n  path: {}\n  line: {}",
-                    path.display(),
-                    str::from_utf8(line).unwrap_or_default().escape_debug()
-                );
-
-                if let Some(expr_start) = expr_start {
+                if line.len() < 8 || !line.ends_with(b" ===") {
+                    return Err(error(at, "Expected file name header"));
+                }
+
+                if let (Some(baseline), Some(expr_start), Some(expr_end)) =
+                    (result.files.last_mut(), expr_start, expr_end)
+                {
                     if expr_start < line_start {
-                        if let Some(expr_end) = expr_end {
-                            let expr = {
-                                assert!(
-                                    expr_start <= expr_end,
-                                    "expression bounds reversed:\n  path: {}\n  bounds: [{}, {})\n  data: {}\n      : {}\n      : {}\n  line: {}",
-                                    path.display(),
-                                    expr_start,
-                                    expr_end,
-                                    str::from_utf8(
-                                        &data[expr_start.saturating_add_signed(-32)
-                                            ..expr_start.add(30).clamp(0, data.len())]
-                                    )
-                                    .unwrap_or_default()
-                                    .escape_debug(),
-                                    data.iter()
-                                        .enumerate()
-                                        .skip(expr_start.saturating_add_signed(-32))
-                                        .take(62)
-                                        .fold(String::new(), |mut output, (i, &c)| {
-                                            let _ = write!(
-                                                output,
-                                                "{}{}",
-                                                if c == b'\n' { " " } else { "" },
-                                                (i % 10)
-                                            );
-                                            output
-                                        }),
-                                    data.iter()
-                                        .enumerate()
-                                        .skip(expr_start.saturating_add_signed(-32))
-                                        .take(62)
-                                        .fold(String::new(), |mut output, (i, &c)| {
-                                            let _ = write!(
-                                                output,
-                                                "{}{}",
-                                                if c == b'\n' { " " } else { "" },
-                                                if i == expr_start {
-                                                    "["
-                                                } else if i == expr_end {
-                                                    ")"
-                                                } else {
-                                                    " "
-                                                }
-                                            );
-                                            output
-                                        }),
-                                    str::from_utf8(line).unwrap_or_default().escape_debug(),
-                                );
-                                let expr = &data[expr_start..expr_end];
-                                str::from_utf8(expr).expect("Expression to be UTF8")
-                            };
-                            let baseline = result.files.last_mut().unwrap();
-                            baseline.statements.push(expr);
-                            baseline.assertions.push(Vec::new());
-                        }
+                        baseline
+                            .push_statement(statement(at, expr_start, expr_end)?)
+                            .ok_or_else(|| error(at, "Too many statements in file"))?;
                     }
                 }
 
-                let name = &line[4..line.len() - 4];
-                result.names.push(str::from_utf8(name).unwrap());
+                if result.files.len() > BaselineFileId::MAX_INDEX {
+                    return Err(error(at, "Too many files in baseline"));
+                }
+                result.names.push(utf8(at, &line[4..line.len() - 4], "Invalid file name")?);
                 result.files.push(TypeBaselineFile::default());
 
-                // println!("New file: {} @ {}", result.names.last().unwrap(), iter.line_start);
                 expr_start = Some(iter.line_start);
             }
 
@@ -148,101 +226,48 @@ impl<'a> TypesBaseline<'a> {
             }
 
             // Add assertion
-            let Some(baseline) = result.files.last_mut() else {
-                panic!(
-                    "Expected baseline file to exist\n  path: {}\n  line: {}",
-                    path.display(),
-                    str::from_utf8(line).unwrap_or_default().escape_debug()
-                );
-            };
-
-            let expr = {
-                // let Some(expr_end) = expr_end else {
-                //   panic!(
-                //     "Expected expr_end to be set:\n  path: {}\n  file: {}\n  line: {}\n  expr: {}",
-                //     path.display(),
-                //     result.names.last().unwrap(),
-                //     str::from_utf8(line).unwrap_or_default().escape_debug(),
-                //     str::from_utf8(&data[expr_start.unwrap()..line_start])
-                //       .unwrap_or_default()
-                //       .escape_debug(),
-                //   );
-                // };
-                let expr = &data[expr_start.expect("expr_start to exist")
-                    ..expr_end.unwrap_or(expr_start.unwrap())];
-                str::from_utf8(expr).expect("Expression to be UTF8")
+            let (Some(baseline), Some(start)) = (result.files.last_mut(), expr_start) else {
+                return Err(error(at, "Expected file name header before assertion"));
             };
+            let expr = statement(at, start, expr_end.unwrap_or(start))?;
             expr_end = None;
-            baseline.statements.push(expr);
-            baseline.assertions.push(Vec::new());
+            let id = baseline
+                .push_statement(expr)
+                .ok_or_else(|| error(at, "Too many statements in file"))?;
 
-            let mut line = line;
+            let mut at = at;
             loop {
-                let (line_idx, line_start, underline) =
-                    iter.next().expect("assertion should be followed by underline");
-
-                let has_underline = underline.starts_with(b"> ");
-                let Some(delim) = memchr(b':', if has_underline { underline } else { line }) else {
-                    panic!(
-                        "assertion should contain delimiter:\n  path: {}\n  name:{}\n  line: {}\n  underline: {}",
-                        path.display(),
-                        result.names.last().unwrap(),
-                        str::from_utf8(line).unwrap_or_default().escape_debug(),
-                        str::from_utf8(underline).unwrap_or_default().escape_debug()
-                    );
+                let (line_idx, _, line) = at;
+                let Some(next @ (_line_idx, _line_start, underline)) = iter.next() else {
+                    return Err(error(at, "Expected assertion to be followed by a line"));
                 };
 
-                assert!(
-                    delim <= line.len(),
-                    "delimiter should be in bounds\n  path: {}\n line:      {}\n  underline: {}",
-                    path.display(),
-                    str::from_utf8(line).unwrap_or_default().escape_debug(),
-                    str::from_utf8(underline).unwrap_or_default().escape_debug()
-                );
-
-                let (expr, expected_type) = {
-                    let offset = 1 + str::from_utf8(&line[1..])
-                        .expect("line to be UTF8")
-                        .char_indices()
-                        .scan(1usize, |acc, (offset, ch)| {
-                            if *acc >= delim {
-                                None
-                            } else {
-                                *acc += ch.len_utf16();
-                                Some(offset)
-                            }
-                        })
-                        .last()
-                        .expect("Delimitier to be within line bounds");
-
-                    let assertion = match str::from_utf8(&line[1..offset]) {
-                        Ok(assertion) => assertion,
-                        Err(err) => panic!(
-                            "Expected assertion to be UTF8:\n  path: {}\n  idx: delim={}, offset={}, valid={}, error_len={:?}\n  line: {}\n      : >{}",
-                            path.display(),
-                            delim,
-                            offset,
-                            err.valid_up_to(),
-                            err.error_len(),
-                            str::from_utf8(line).unwrap_or_default().escape_debug(),
-                            String::from_utf8_lossy(&line[1..offset]).escape_debug(),
-                        ),
-                    };
-
-                    (
-                        assertion,
-                        str::from_utf8(&line[offset + 3..]).expect("expected type to be UTF8"),
-                    )
+                let has_underline = underline.starts_with(b"> ");
+                let text = utf8(at, &line[1..], "Expected assertion to be UTF-8")?;
+                let delim = if has_underline { memchr(b':', underline) } else { None };
+                let aligned = delim.and_then(|x| split_at_underline(text, x));
+                // One warning per assertion, at its own line
+                let warning = match aligned.or_else(|| split_assertion(text, None)) {
+                    Some((expr, expected_type)) => {
+                        baseline.assertions[id].push(Assertion { expr, expected_type });
+                        (has_underline && aligned.is_none())
+                            .then_some(ParseWarningKind::UnalignedUnderline)
+                    }
+                    None => Some(ParseWarningKind::MissingDelimiter),
                 };
-                baseline.assertions.last_mut().unwrap().push(Assertion { expr, expected_type });
+                if let Some(kind) = warning {
+                    result.warnings.push(ParseWarning::new(kind, path, line_idx + 1, line));
+                }
 
-                let (_line_idx, line_start, next_line) = if has_underline {
-                    iter.next().expect("assertion block should be followed by new line")
+                let next @ (_line_idx, line_start, next_line) = if has_underline {
+                    iter.next().ok_or_else(|| {
+                        error(next, "Expected assertion block to be followed by a line")
+                    })?
                 } else {
-                    (line_idx, line_start, underline)
+                    next
                 };
                 if next_line.starts_with(b">") {
-                    line = next_line;
+                    at = next;
                     continue;
                 }
 
@@ -251,10 +276,77 @@ impl<'a> TypesBaseline<'a> {
             }
         }
 
-        result
+        Ok(result)
     }
 }
 
+/// Splits an assertion line (without the leading `>`) into expression and expected type.
+///
+/// The harness pads the underline with spaces for every UTF-16 unit of the expression, so its
+/// `:` column pins the delimiter down exactly. Without an underline (or if it does not line up)
+/// we take the last ` : ` that leaves a balanced expression on the left and a balanced type on
+/// the right: `a ? b : c : number` or `x : T extends U ? X : Y`.
+fn split_assertion(text: &str, underline_delim: Option<usize>) -> Option<(&str, &str)> {
+    if let Some(split) = underline_delim.and_then(|x| split_at_underline(text, x)) {
+        return Some(split);
+    }
+
+    text.match_indices(" : ")
+        .map(|(offset, _)| (&text[..offset], &text[offset + 3..]))
+        .filter(|(expr, expected_type)| is_balanced(expr) && is_balanced(expected_type))
+        .last()
+        .or_else(|| text.split_once(" : "))
+}
+
+/// Splits the assertion at the ` : ` the underline `:` column points to, if it lines up.
+fn split_at_underline(text: &str, underline_delim: usize) -> Option<(&str, &str)> {
+    // Underline columns count the leading `>` and the space before `:`
+    let offset = underline_delim.checked_sub(2).and_then(|x| utf16_offset(text, x))?;
+    text[offset..].starts_with(" : ").then(|| (&text[..offset], &text[offset + 3..]))
+}
+
+/// Brackets are closed, string literals terminated and every top-level `:` belongs to a
+/// conditional (`?`), which is good enough to tell expressions and types apart at ` : `.
+fn is_balanced(text: &str) -> bool {
+    let bytes = text.as_bytes();
+    let mut brackets = Vec::new();
+    let mut conditionals = 0usize;
+    let mut idx = 0;
+    while idx < bytes.len() {
+        match bytes[idx] {
+            quote @ (b'"' | b'\'' | b'`') => {
+                idx += 1;
+                while idx < bytes.len() && bytes[idx] != quote {
+                    idx += if bytes[idx] == b'\\' { 2 } else { 1 };
+                }
+                if idx >= bytes.len() {
+                    return false;
+                }
+            }
+            open @ (b'(' | b'[' | b'{') => brackets.push(open),
+            b')' if brackets.pop() != Some(b'(') => return false,
+            b']' if brackets.pop() != Some(b'[') => return false,
+            b'}' if brackets.pop() != Some(b'{') => return false,
+            // `?.` and `??` are not conditionals
+            b'?' if brackets.is_empty() => match bytes.get(idx + 1) {
+                Some(b'?') => idx += 1,
+                Some(b'.') if !bytes.get(idx + 2).is_some_and(u8::is_ascii_digit) => {}
+                _ => conditionals += 1,
+            },
+            b':' if brackets.is_empty() => {
+                let Some(rest) = conditionals.checked_sub(1) else {
+                    return false;
+                };
+                conditionals = rest;
+            }
+            _ => {}
+        }
+        idx += 1;
+    }
+
+    brackets.is_empty() && conditionals == 0
+}
+
 #[cfg(test)]
 mod tests {
     use std::{path::PathBuf, str::FromStr};
@@ -262,6 +354,7 @@ mod tests {
     use oxc_index::index_vec;
 
     use super::*;
+    use crate::line_index::LineIndex;
 
     #[test]
     fn single_file() {
@@ -286,10 +379,11 @@ function foo<T, U>(t: T, u: U) {
 >u : U
 >  : ^
 ";
-        let baseline = TypesBaseline::parse(&path, data);
+        let baseline = TypesBaseline::parse(&path, data).unwrap();
         assert_eq!(
             baseline,
             TypesBaseline {
+                warnings: vec![],
                 names: index_vec!["a.ts"],
                 files: index_vec![TypeBaselineFile {
                     statements: index_vec![
@@ -333,10 +427,11 @@ const b = 123;
 >    : ^^^
 
 ";
-        let baseline = TypesBaseline::parse(&path, data);
+        let baseline = TypesBaseline::parse(&path, data).unwrap();
         assert_eq!(
             baseline,
             TypesBaseline {
+                warnings: vec![],
                 names: index_vec!["a.ts", "b.ts"],
                 files: index_vec![
                     TypeBaselineFile {
@@ -378,10 +473,11 @@ const b = 123;
 >    : ^^^
 
 ";
-        let baseline = TypesBaseline::parse(&path, data);
+        let baseline = TypesBaseline::parse(&path, data).unwrap();
         assert_eq!(
             baseline,
             TypesBaseline {
+                warnings: vec![],
                 names: index_vec!["a.ts", "b.ts"],
                 files: index_vec![
                     TypeBaselineFile {
@@ -418,10 +514,11 @@ const b = 123;
 >    : ^^^
 
 ";
-        let baseline = TypesBaseline::parse(&path, data);
+        let baseline = TypesBaseline::parse(&path, data).unwrap();
         assert_eq!(
             baseline,
             TypesBaseline {
+                warnings: vec![],
                 names: index_vec!["a.ts", "b.ts"],
                 files: index_vec![
                     TypeBaselineFile { statements: index_vec![""], assertions: index_vec![vec![]] },
@@ -458,10 +555,11 @@ const a = 5;
 >5 : 5
 >  : ^
 "#;
-        let baseline = TypesBaseline::parse(&path, data);
+        let baseline = TypesBaseline::parse(&path, data).unwrap();
         assert_eq!(
             baseline,
             TypesBaseline {
+                warnings: vec![],
                 names: index_vec!["a.ts", "b.ts"],
                 files: index_vec![
                     TypeBaselineFile {
@@ -514,10 +612,11 @@ const a = 5;
 >5 : 5
 >  : ^
 "#;
-        let baseline = TypesBaseline::parse(&path, data);
+        let baseline = TypesBaseline::parse(&path, data).unwrap();
         assert_eq!(
             baseline,
             TypesBaseline {
+                warnings: vec![],
                 names: index_vec!["a.ts"],
                 files: index_vec![TypeBaselineFile {
                     statements: index_vec![
@@ -569,10 +668,11 @@ g.prototype.m = function () {
 
 };
 ";
-        let baseline = TypesBaseline::parse(&path, data);
+        let baseline = TypesBaseline::parse(&path, data).unwrap();
         assert_eq!(
             baseline,
             TypesBaseline {
+                warnings: vec![],
                 names: index_vec!["a.ts"],
                 files: index_vec![TypeBaselineFile {
                     statements: index_vec!["g.prototype.m = function () {", "  this;"],
@@ -620,10 +720,11 @@ const b = 123;
 >    : ^^^
 
 ";
-        let baseline = TypesBaseline::parse(&path, data);
+        let baseline = TypesBaseline::parse(&path, data).unwrap();
         assert_eq!(
             baseline,
             TypesBaseline {
+                warnings: vec![],
                 names: index_vec!["a.ts", "b.ts"],
                 files: index_vec![
                     TypeBaselineFile {
@@ -676,10 +777,11 @@ const a = 5;
 >React : any
 >      : ^^^
 ";
-        let baseline = TypesBaseline::parse(&path, data);
+        let baseline = TypesBaseline::parse(&path, data).unwrap();
         assert_eq!(
             baseline,
             TypesBaseline {
+                warnings: vec![],
                 names: index_vec!["a.ts", "b.ts"],
                 files: index_vec![
                     TypeBaselineFile {
@@ -732,10 +834,11 @@ const 𝘳𝘦𝘨𝘦𝘹 = /(?𝘴𝘪-𝘮:^𝘧𝘰𝘰.)/𝘨𝘮𝘶;
 >/(?𝘴𝘪-𝘮:^𝘧𝘰𝘰.)/𝘨𝘮𝘶 : RegExp
 >                            : ^^^^^^
 "#.as_bytes();
-        let baseline = TypesBaseline::parse(&path, data);
+        let baseline = TypesBaseline::parse(&path, data).unwrap();
         assert_eq!(
             baseline,
             TypesBaseline {
+                warnings: vec![],
                 names: index_vec!["a.ts"],
                 files: index_vec![TypeBaselineFile {
                     statements: index_vec![
@@ -767,4 +870,259 @@ const 𝘳𝘦𝘨𝘦𝘹 = /(?𝘴𝘪-𝘮:^𝘧𝘰𝘰.)/𝘨𝘮𝘶;
             }
         );
     }
+
+    #[test]
+    fn delimiter_inside_expression() {
+        let path = PathBuf::from_str("tests/baselines/reference/unit1.types").unwrap();
+        let data = br#"//// [tests/cases/compiler/unit1.ts] ////
+
+=== a.ts ===
+const x = c ? a : b;
+>x : number
+>  : ^^^^^^
+>c ? a : b : number
+>          : ^^^^^^
+>c : boolean
+>a : number
+>b : number
+
+const o = c ? { a: "" } : { a: "x" };
+>o : { a: string; }
+>c ? { a: "" } : { a: "x" } : { a: string; }
+>c : boolean
+
+declare function f<T>(x: T): T extends string ? "s" : "o";
+>f : <T>(x: T) => T extends string ? "s" : "o"
+>  : ^ ^^ ^^ ^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^^
+>x : T
+"#;
+        let baseline = TypesBaseline::parse(&path, data).unwrap();
+        assert_eq!(
+            baseline.files[BaselineFileId::new(0)].assertions,
+            index_vec![
+                vec![
+                    Assertion { expr: "x", expected_type: "number" },
+                    Assertion { expr: "c ? a : b", expected_type: "number" },
+                    Assertion { expr: "c", expected_type: "boolean" },
+                    Assertion { expr: "a", expected_type: "number" },
+                    Assertion { expr: "b", expected_type: "number" },
+                ],
+                vec![
+                    Assertion { expr: "o", expected_type: "{ a: string; }" },
+                    Assertion {
+                        expr: r#"c ? { a: "" } : { a: "x" }"#,
+                        expected_type: "{ a: string; }"
+                    },
+                    Assertion { expr: "c", expected_type: "boolean" },
+                ],
+                vec![
+                    Assertion {
+                        expr: "f",
+                        expected_type: r#"<T>(x: T) => T extends string ? "s" : "o""#
+                    },
+                    Assertion { expr: "x", expected_type: "T" },
+                ],
+            ]
+        );
+    }
+
+    #[test]
+    fn warnings() {
+        let path = PathBuf::from_str("tests/baselines/reference/unit1.types").unwrap();
+        let data = br"//// [tests/cases/compiler/unit1.ts] ////
+
+=== a.ts ===
+const x = y;
+>x : number
+>      : ^^^^^^
+>y
+>  : ^^^^^^
+
+";
+        let baseline = TypesBaseline::parse(&path, data).unwrap();
+        assert_eq!(
+            baseline.files.raw[0].assertions.raw[0],
+            vec![Assertion { expr: "x", expected_type: "number" }]
+        );
+        assert_eq!(
+            baseline
+                .warnings
+                .iter()
+                .map(|x| (x.kind, x.line, x.snippet.as_str()))
+                .collect::<Vec<_>>(),
+            vec![
+                (ParseWarningKind::UnalignedUnderline, 5, ">x : number"),
+                (ParseWarningKind::MissingDelimiter, 7, ">y"),
+            ]
+        );
+    }
+
+    #[test]
+    fn missing_header() {
+        let path = PathBuf::from("a.types");
+        let data = b"=== a.ts ===\nconst a = 1;\n>a : 1\n>  : ^\n";
+        let err = TypesBaseline::parse(&path, data).unwrap_err();
+        assert_eq!(err.which, BaselineKind::Types);
+        assert_eq!(err.error, "Expected baseline to start with test unit path");
+        assert_eq!((err.line, err.offset), (Some(1), Some(0)));
+        assert_eq!(err.snippet.as_deref(), Some("=== a.ts ==="));
+        assert_eq!(
+            err.to_string(),
+            "Failed to parse types baseline a.types at line 1 (offset 0): Expected baseline to \
+             start with test unit path in `=== a.ts ===`"
+        );
+    }
+
+    #[test]
+    fn missing_delimiter() {
+        let path = PathBuf::from("a.types");
+        let data = b"//// [a.ts] ////\n\n=== a.ts ===\nx;\n>y\n\ny;\n>y : any\n>  : ^^^\n";
+        let baseline = TypesBaseline::parse(&path, data).unwrap();
+        assert_eq!(baseline.files.raw[0].statements.raw, ["x;", "y;"]);
+        assert_eq!(
+            baseline.files.raw[0].assertions.raw,
+            [vec![], vec![Assertion { expr: "y", expected_type: "any" }]]
+        );
+        let warnings: Vec<_> =
+            baseline.warnings.iter().map(|x| (x.kind, x.line, x.snippet.as_str())).collect();
+        assert_eq!(warnings, [(ParseWarningKind::MissingDelimiter, 5, ">y")]);
+    }
+
+    #[test]
+    fn unparsable() {
+        let path = PathBuf::from("a.types");
+        let parse = |data: &'static [u8]| {
+            let err = TypesBaseline::parse(&path, data).unwrap_err();
+            (err.error, err.line, err.offset, err.snippet.unwrap_or_default())
+        };
+
+        assert_eq!(
+            parse(b"//// [a.ts] ////\n\n>a : 1\n>  : ^\n"),
+            (
+                "Expected file name header before assertion".to_string(),
+                Some(3),
+                Some(18),
+                ">a : 1".to_string()
+            )
+        );
+        assert_eq!(
+            parse(b"//// [a.ts] ////\n\n=== a.ts\nconst a = 1;\n"),
+            ("Expected file name header".to_string(), Some(3), Some(18), "=== a.ts".to_string())
+        );
+        assert_eq!(
+            parse(b"//// [a.ts] ////\n\n=== ===\n"),
+            ("Expected file name header".to_string(), Some(3), Some(18), "=== ===".to_string())
+        );
+        assert_eq!(
+            parse(b"//// [a.ts] ////\n\n=== a.ts ===\n\xff;\n>a : 1\n>  : ^\n").0,
+            "Expected statement to be UTF-8"
+        );
+    }
+
+    #[test]
+    fn resolve_span() {
+        let source_text = "let x;\nconst ab = a + a;\n";
+        let mut statement = StatementSource::new(source_text, Span::new(7, 24));
+        let assertion = |expr| Assertion { expr, expected_type: "number" };
+        let spans: Vec<_> = ["ab", "a + a", "a", "a", "a", "b"]
+            .map(|x| assertion(x).resolve_span(&mut statement).map(|x| x.source_text(source_text)))
+            .into_iter()
+            .collect();
+        assert_eq!(spans, vec![Some("ab"), Some("a + a"), Some("a"), Some("a"), None, None]);
+
+        let mut statement = StatementSource::new(source_text, Span::new(7, 24));
+        assert_eq!(assertion("a").resolve_span(&mut statement), Some(Span::new(18, 19)));
+        // Not after the previous assertion, found anywhere in the statement instead
+        assert_eq!(assertion("ab").resolve_span(&mut statement), Some(Span::new(13, 15)));
+        assert_eq!(assertion("ab").resolve_span(&mut statement), None);
+
+        // The harness drops the line breaks of multi-line expressions
+        let source_text = "f({\n  a: 1,\r\n  b: a\n});\n";
+        let mut statement = StatementSource::new(source_text, Span::new(0, 22));
+        let spans: Vec<_> = ["f({  a: 1,  b: a})", "f", "{  a: 1,  b: a}", "a", "1", "b", "a"]
+            .map(|x| assertion(x).resolve_span(&mut statement).map(|x| x.source_text(source_text)))
+            .into_iter()
+            .collect();
+        assert_eq!(
+            spans,
+            vec![
+                Some("f({\n  a: 1,\r\n  b: a\n})"),
+                Some("f"),
+                Some("{\n  a: 1,\r\n  b: a\n}"),
+                Some("a"),
+                Some("1"),
+                Some("b"),
+                Some("a"),
+            ]
+        );
+    }
+
+    #[test]
+    fn astral_plane_columns() {
+        // U+1D4EA takes 2 UTF-16 units, the underline columns count both
+        let path = PathBuf::from_str("tests/baselines/reference/unit1.types").unwrap();
+        let data = r#"//// [tests/cases/compiler/unit1.ts] ////
+
+=== a.ts ===
+const 𝓪 = "𝓪 : ", b = 𝓪 ? 𝓪 : b;
+>𝓪 : "𝓪 : "
+>   : ^^^^^^
+>"𝓪 : " : "𝓪 : "
+>        : ^^^^^^
+>b : any
+>  : ^^^
+>𝓪 ? 𝓪 : b : any
+>            : ^^^
+>𝓪 : "𝓪 : "
+>   : ^^^^^^
+>𝓪 : "𝓪 : "
+>   : ^^^^^^
+>b : any
+>  : ^^^
+
+"#
+        .as_bytes();
+        let baseline = TypesBaseline::parse(&path, data).unwrap();
+        assert_eq!(baseline.warnings, vec![]);
+        let source_text = baseline.files[BaselineFileId::new(0)].statements[LineId::new(0)];
+        let assertions = &baseline.files[BaselineFileId::new(0)].assertions[LineId::new(0)];
+        let split: Vec<_> = assertions.iter().map(|x| (x.expr, x.expected_type)).collect();
+        assert_eq!(
+            split,
+            vec![
+                ("𝓪", r#""𝓪 : ""#),
+                (r#""𝓪 : ""#, r#""𝓪 : ""#),
+                ("b", "any"),
+                ("𝓪 ? 𝓪 : b", "any"),
+                ("𝓪", r#""𝓪 : ""#),
+                ("𝓪", r#""𝓪 : ""#),
+                ("b", "any"),
+            ]
+        );
+
+        // Spans resolve to the occurrences after the astral plane characters
+        let len = u32::try_from(source_text.len()).unwrap();
+        let mut statement = StatementSource::new(source_text, Span::new(0, len));
+        let lines = LineIndex::new(source_text);
+        let columns: Vec<_> = assertions
+            .iter()
+            .map(|x| {
+                x.resolve_span(&mut statement).map(|x| lines.utf16_column(source_text, x.start))
+            })
+            .collect();
+        assert_eq!(
+            columns,
+            vec![Some(7), Some(12), Some(21), Some(25), Some(25), Some(30), Some(35)]
+        );
+    }
+
+    #[test]
+    fn split_assertion_fallbacks() {
+        // Misaligned underline falls back to the heuristic
+        assert_eq!(split_assertion("a ? b : c : number", Some(4)), Some(("a ? b : c", "number")));
+        // Astral plane characters take 2 UTF-16 units in the underline
+        assert_eq!(split_assertion("\u{1F600}x : any", Some(5)), Some(("\u{1F600}x", "any")));
+        // Unbalanced on both sides, keep the first delimiter
+        assert_eq!(split_assertion("<p>don't</p> : any", None), Some(("<p>don't</p>", "any")));
+    }
 }
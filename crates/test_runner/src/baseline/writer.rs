@@ -0,0 +1,357 @@
+use std::fmt::Write;
+
+use type_info::Diagnostic;
+
+use super::{ErrorsBaseline, file_order};
+use crate::{
+    compare::ActualType,
+    line_index::{LineIndex, utf16_len},
+};
+
+/// Visited nodes of a module, in visit order.
+pub struct ModuleTypes<'a> {
+    pub name: &'a str,
+    pub source_text: &'a str,
+    pub types: &'a [ActualType<'a>],
+}
+
+/// Writes a `.types` baseline the way the TS harness does.
+///
+/// Every source line is written once, followed by the `>expr : type` entries of the nodes that
+/// start on it. Entries with a [placeholder](ActualType::placeholder) type get `?` instead of `^`
+/// in the marker line below them, so the entry itself still diffs cleanly against the reference.
+pub fn write_types_baseline(test_path: &str, modules: &[ModuleTypes<'_>]) -> String {
+    let mut out = format!("//// [{test_path}] ////\n\n");
+    for module in modules {
+        out.push_str("=== ");
+        out.push_str(module.name);
+        out.push_str(" ===\n");
+
+        let lines: Vec<_> =
+            module.source_text.split('\n').map(|x| x.trim_end_matches('\r')).collect();
+        let mut written: Option<usize> = None;
+        for node in module.types {
+            let start = (node.span.start as usize).min(module.source_text.len());
+            let line = module.source_text[..start].matches('\n').count();
+            match written {
+                None => push_lines(&mut out, &lines[..=line]),
+                Some(written) if line > written => {
+                    if !is_filler(lines.get(written + 1)) {
+                        out.push('\n');
+                    }
+                    push_lines(&mut out, &lines[written + 1..=line]);
+                }
+                Some(_) => {}
+            }
+            written = Some(written.map_or(line, |x| x.max(line)));
+
+            let text: String = node.text.split('\n').map(|x| x.trim_end_matches('\r')).collect();
+            let marker = if node.placeholder { "?" } else { "^" };
+            let _ = writeln!(out, ">{text} : {}", node.type_name);
+            let _ = writeln!(
+                out,
+                ">{} : {}",
+                " ".repeat(text.chars().count()),
+                marker.repeat(node.type_name.chars().count())
+            );
+        }
+
+        let rest = written.map_or(0, |x| x + 1);
+        if rest < lines.len() {
+            if !is_filler(lines.get(rest)) {
+                out.push('\n');
+            }
+            out.push_str(&lines[rest..].join("\n"));
+        }
+        out.push('\n');
+    }
+
+    out
+}
+
+fn push_lines(out: &mut String, lines: &[&str]) {
+    for line in lines {
+        out.push_str(line);
+        out.push('\n');
+    }
+}
+
+/// Blank and lone brace lines are not separated from the previous entries by an empty line.
+fn is_filler(line: Option<&&str>) -> bool {
+    line.is_some_and(|x| matches!(x.trim(), "" | "{" | "}" | "|"))
+}
+
+/// Writes an `.errors.txt` baseline the way the TS harness does, or `None` when there are no
+/// diagnostics (TS does not write the file then).
+///
+/// `files` are the test unit files in the order their sections are written. Diagnostics must
+/// reference them by name; locations are 1-based with UTF-16 columns, like [`Diagnostic`].
+pub fn write_errors_baseline(diagnostics: &[Diagnostic], files: &[(&str, &str)]) -> Option<String> {
+    if diagnostics.is_empty() {
+        return None;
+    }
+
+    let mut diagnostics: Vec<_> = diagnostics.iter().collect();
+    diagnostics.sort_by(|a, b| {
+        file_order::cmp(&a.file, &b.file).then_with(|| {
+            (a.line, a.column, a.length, a.code, &a.message)
+                .cmp(&(b.line, b.column, b.length, b.code, &b.message))
+        })
+    });
+    Some(write_errors(&diagnostics, files))
+}
+
+impl ErrorsBaseline<'_> {
+    /// Writes the baseline back in the plain `.errors.txt` format, listing the errors in the order
+    /// of the baseline.
+    ///
+    /// `sources` gives the text of a file to write its section with. Sections are written in the
+    /// order of the `====` headers of the baseline, leaving out files without a text. Related
+    /// errors lose their length, which the plain format does not show, and config errors are
+    /// listed before the sections rather than underlined in the one of `tsconfig.json`.
+    pub fn to_string<'s>(&self, sources: &dyn Fn(&str) -> Option<&'s str>) -> String {
+        let diagnostics = self.to_diagnostics();
+        let files: Vec<_> = self
+            .declared_counts()
+            .iter()
+            .filter_map(|x| x.file)
+            .filter_map(|file| Some((file, sources(file)?)))
+            .collect();
+        write_errors(&diagnostics.iter().collect::<Vec<_>>(), &files)
+    }
+}
+
+/// Writes the diagnostics in the given order, see [`write_errors_baseline`].
+fn write_errors(diagnostics: &[&Diagnostic], files: &[(&str, &str)]) -> String {
+    let mut out = String::new();
+    for diagnostic in diagnostics {
+        if diagnostic.has_location() {
+            let _ = write!(out, "{}({},{}): ", diagnostic.file, diagnostic.line, diagnostic.column);
+        }
+        let _ =
+            writeln!(out, "{} TS{}: {}", diagnostic.severity, diagnostic.code, diagnostic.message);
+    }
+    out.push_str("\n\n");
+
+    let mut lines = Vec::new();
+    for diagnostic in diagnostics.iter().filter(|x| !x.has_location()) {
+        push_message(&mut lines, diagnostic);
+    }
+
+    for &(name, source_text) in files {
+        let line_index = LineIndex::new(source_text);
+        let file_errors: Vec<_> = diagnostics
+            .iter()
+            .filter(|x| x.has_location() && x.file == name)
+            .map(|&x| (x, span_utf16(x, source_text, &line_index)))
+            .collect();
+        lines.push(format!("==== {name} ({} errors) ====", file_errors.len()));
+
+        let source_lines: Vec<_> = source_text.split('\n').collect();
+        let mut line_start = 0;
+        for (idx, line) in source_lines.iter().enumerate() {
+            let line = line.strip_suffix('\r').unwrap_or(line);
+            let is_last = idx + 1 == source_lines.len();
+            let line_len = utf16_len(line);
+            let next_line_start = line_start + utf16_len(source_lines[idx]) + usize::from(!is_last);
+            lines.push(format!("    {line}"));
+
+            for &(diagnostic, (start, end)) in &file_errors {
+                if end < line_start || (start >= next_line_start && !is_last) {
+                    continue;
+                }
+
+                let squiggle_start = start.saturating_sub(line_start);
+                let length = (end - start).saturating_sub(line_start.saturating_sub(start));
+                let indent = blank_out(line, squiggle_start);
+                let squiggle = length.min(line_len.saturating_sub(squiggle_start));
+                lines.push(format!("    {indent}{}", "~".repeat(squiggle)));
+
+                if is_last || next_line_start > end {
+                    push_message(&mut lines, diagnostic);
+                }
+            }
+
+            line_start = next_line_start;
+        }
+    }
+
+    out.push_str(&lines.join("\n"));
+    out
+}
+
+/// The first `units` UTF-16 units of `line` with everything but whitespace replaced by spaces.
+fn blank_out(line: &str, units: usize) -> String {
+    let mut result = String::new();
+    let mut len = 0;
+    for ch in line.chars().take_while(|x| {
+        len += x.len_utf16();
+        len <= units
+    }) {
+        if ch.is_whitespace() {
+            result.push(ch);
+        } else {
+            result.extend(std::iter::repeat_n(' ', ch.len_utf16()));
+        }
+    }
+
+    result
+}
+
+fn push_message(lines: &mut Vec<String>, diagnostic: &Diagnostic) {
+    let prefix = format!("!!! {} TS{}: ", diagnostic.severity, diagnostic.code);
+    lines.extend(
+        diagnostic
+            .message
+            .split('\n')
+            .map(|x| x.trim_end_matches('\r'))
+            .filter(|x| !x.is_empty())
+            .map(|x| format!("{prefix}{x}")),
+    );
+    for related in &diagnostic.related {
+        let location = if related.has_location() {
+            format!(" {}:{}:{}", related.file, related.line, related.column)
+        } else {
+            String::new()
+        };
+        lines.push(format!("!!! related TS{}{location}: {}", related.code, related.message));
+    }
+}
+
+/// Start and end of the diagnostic in UTF-16 units from the start of `source_text`.
+fn span_utf16(diagnostic: &Diagnostic, source_text: &str, lines: &LineIndex) -> (usize, usize) {
+    let line_start = lines.line_start(diagnostic.line).map_or(source_text.len(), |x| x as usize);
+    let start =
+        utf16_len(&source_text[..line_start]) + diagnostic.column.saturating_sub(1) as usize;
+    (start, start + diagnostic.length.unwrap_or_default() as usize)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{borrow::Cow, path::Path};
+
+    use oxc::span::Span;
+
+    use super::*;
+    use crate::baseline::ErrorsBaseline;
+
+    #[test]
+    fn types() {
+        let source_text = "class C {\n    x;\n}\nvar c = new C();\n";
+        let node = |text: &'static str, nth: usize, type_name: &'static str, placeholder: bool| {
+            let start = source_text.match_indices(text).nth(nth).unwrap().0;
+            ActualType {
+                span: Span::sized(
+                    u32::try_from(start).unwrap(),
+                    u32::try_from(text.len()).unwrap(),
+                ),
+                kind: Cow::Borrowed("Node"),
+                text,
+                type_name: Cow::Borrowed(type_name),
+                placeholder,
+            }
+        };
+        let types = [
+            node("C", 0, "C", false),
+            node("x", 0, "any", true),
+            node("c", 1, "C", false),
+            node("new C()", 0, "C", false),
+            node("C", 1, "typeof C", false),
+        ];
+
+        let modules = [ModuleTypes { name: "a.ts", source_text, types: &types }];
+        assert_eq!(
+            write_types_baseline("tests/cases/compiler/a.ts", &modules),
+            "//// [tests/cases/compiler/a.ts] ////
+
+=== a.ts ===
+class C {
+>C : C
+>  : ^
+
+    x;
+>x : any
+>  : ???
+}
+var c = new C();
+>c : C
+>  : ^
+>new C() : C
+>        : ^
+>C : typeof C
+>  : ^^^^^^^^
+
+"
+        );
+    }
+
+    fn round_trip(name: &str, source_text: &str, reference: &[u8]) {
+        let diagnostics =
+            ErrorsBaseline::parse(Path::new(name), reference).unwrap().to_diagnostics();
+        assert_eq!(
+            write_errors_baseline(&diagnostics, &[(name, source_text)]).as_deref(),
+            std::str::from_utf8(reference).ok()
+        );
+    }
+
+    #[test]
+    fn errors() {
+        round_trip(
+            "errorElaboration.ts",
+            include_str!("../../tests/corpus/tests/cases/compiler/errorElaboration.ts"),
+            include_bytes!(
+                "../../tests/corpus/tests/baselines/reference/errorElaboration.errors.txt"
+            ),
+        );
+        round_trip(
+            "errorRelatedInformation.ts",
+            include_str!("../../tests/corpus/tests/cases/compiler/errorRelatedInformation.ts"),
+            include_bytes!(
+                "../../tests/corpus/tests/baselines/reference/errorRelatedInformation.errors.txt"
+            ),
+        );
+        assert_eq!(write_errors_baseline(&[], &[("a.ts", "let a = 1;\n")]), None);
+    }
+
+    #[test]
+    fn errors_baseline_to_string() {
+        let name = "errorRelatedInformation.ts";
+        let source_text =
+            include_str!("../../tests/corpus/tests/cases/compiler/errorRelatedInformation.ts");
+        let reference = include_str!(
+            "../../tests/corpus/tests/baselines/reference/errorRelatedInformation.errors.txt"
+        );
+        let baseline = ErrorsBaseline::parse(Path::new(name), reference.as_bytes()).unwrap();
+        let sources = |file: &str| (file == name).then_some(source_text);
+        assert_eq!(baseline.to_string(&sources), reference);
+
+        // Without its text the section is left out, its errors only listed in the summary
+        let written = baseline.to_string(&|_| None);
+        assert!(!written.contains("===="));
+        assert!(written.starts_with(&reference[..reference.find("\n\n\n").unwrap()]));
+    }
+
+    #[test]
+    fn astral_plane_columns() {
+        // The error is after U+1D4EA on the same line, which takes 2 UTF-16 units
+        let source_text = "const 𝓪 = 1; 𝓪 = 2;\n";
+        let reference = "\
+a.ts(1,15): error TS2588: Cannot assign to '𝓪' because it is a constant.
+
+
+==== a.ts (1 errors) ====
+    const 𝓪 = 1; 𝓪 = 2;
+                  ~~
+!!! error TS2588: Cannot assign to '𝓪' because it is a constant.
+    ";
+        round_trip("a.ts", source_text, reference.as_bytes());
+
+        let diagnostics = ErrorsBaseline::parse(Path::new("a.ts"), reference.as_bytes())
+            .unwrap()
+            .to_diagnostics();
+        let offset = u32::try_from(source_text.rfind('𝓪').unwrap()).unwrap();
+        let lines = LineIndex::new(source_text);
+        assert_eq!(diagnostics[0].column, lines.utf16_column(source_text, offset));
+        assert_eq!(lines.utf16_offset(source_text, 1, diagnostics[0].column), Some(offset));
+    }
+}
@@ -0,0 +1,424 @@
+use std::cmp::Ordering;
+
+use type_info::Diagnostic;
+
+use super::{MatchOptions, Scratch};
+use crate::baseline::{ErrorsBaseline, file_order};
+
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct ErrorComparison {
+    /// Expected diagnostics that were produced.
+    pub matched: Vec<Diagnostic>,
+    /// Expected diagnostics that were not produced.
+    pub missing: Vec<Diagnostic>,
+    /// Produced diagnostics that were not expected.
+    pub unexpected: Vec<Diagnostic>,
+    /// Matched diagnostics whose elaboration chain has the same text but different nesting.
+    ///
+    /// Advisory only: baselines indent hints with tabs or odd spaces often enough that their
+    /// depth cannot be trusted, so this does not fail the comparison.
+    pub hint_depth_differences: usize,
+}
+
+impl ErrorComparison {
+    pub fn is_match(&self) -> bool {
+        self.missing.is_empty() && self.unexpected.is_empty()
+    }
+
+    /// Keeps only the diagnostics reported in files `keep` accepts. Config errors have no file.
+    ///
+    /// [`Self::hint_depth_differences`] still counts the dropped diagnostics.
+    pub fn retain_files(&mut self, keep: impl Fn(&str) -> bool) {
+        for diagnostics in [&mut self.matched, &mut self.missing, &mut self.unexpected] {
+            diagnostics.retain(|x| keep(&x.file));
+        }
+    }
+}
+
+type Key<'a> = (&'a str, u32, u32, u32);
+
+fn key(diagnostic: &Diagnostic) -> Key<'_> {
+    (&diagnostic.file, diagnostic.line, diagnostic.column, diagnostic.code)
+}
+
+/// Orders keys by file like the baseline lists them, see [`file_order`], then by position and
+/// code.
+fn cmp_keys(a: Key<'_>, b: Key<'_>) -> Ordering {
+    file_order::cmp(a.0, b.0).then_with(|| (a.1, a.2, a.3).cmp(&(b.1, b.2, b.3)))
+}
+
+/// Flattens diagnostics into the list of entries taking part in the comparison.
+fn collect<'a>(
+    diagnostics: &'a [Diagnostic],
+    options: MatchOptions,
+    out: &mut Vec<&'a Diagnostic>,
+) {
+    for diagnostic in diagnostics {
+        if options.include_non_errors {
+            out.push(diagnostic);
+            collect(&diagnostic.related, options, out);
+        } else if diagnostic.severity.is_error() {
+            out.push(diagnostic);
+        }
+    }
+}
+
+/// Whether `actual` can be paired with `expected` having the same key, according to `options`.
+fn same_details(expected: &Diagnostic, actual: &Diagnostic, options: MatchOptions) -> bool {
+    (!options.compare_messages || expected.message == actual.message)
+        && (!options.compare_severities || expected.severity == actual.severity)
+        && (!options.compare_lengths
+            || expected.length.is_none()
+            || expected.length == actual.length)
+}
+
+/// Whether the messages only differ in the indentation of their elaboration lines.
+fn differs_in_hint_depth(expected: &str, actual: &str) -> bool {
+    expected != actual
+        && expected.lines().map(str::trim_start).eq(actual.lines().map(str::trim_start))
+}
+
+/// Matches expected and produced diagnostics on `(file, line, column, code)`, ignoring order.
+///
+/// # Examples
+///
+/// ```
+/// use std::path::Path;
+///
+/// use test_runner::{
+///     ErrorsBaseline,
+///     compare::{MatchOptions, Scratch, compare_errors},
+/// };
+/// use type_info::Diagnostic;
+///
+/// let data = b"a.ts(1,9): error TS2304: Cannot find name 'x'.\n\n\n==== a.ts (1 errors) ====\n    let y = x;\n            ~\n!!! error TS2304: Cannot find name 'x'.\n";
+/// let baseline = ErrorsBaseline::parse(Path::new("a.errors.txt"), data).unwrap();
+/// let actual = Diagnostic {
+///     code: 2304,
+///     file: "a.ts".to_string(),
+///     line: 1,
+///     column: 9,
+///     ..Diagnostic::default()
+/// };
+///
+/// let mut scratch = Scratch::default();
+/// let options = MatchOptions::default();
+/// assert!(compare_errors(&baseline, &[actual], options, &mut scratch).is_match());
+/// assert_eq!(compare_errors(&baseline, &[], options, &mut scratch).missing.len(), 1);
+/// ```
+pub fn compare_errors(
+    expected: &ErrorsBaseline<'_>,
+    actual: &[Diagnostic],
+    options: MatchOptions,
+    scratch: &mut Scratch,
+) -> ErrorComparison {
+    compare_diagnostics(&expected.to_diagnostics(), actual, options, scratch)
+}
+
+impl ErrorsBaseline<'_> {
+    /// Compares the produced diagnostics with the ones of the baseline, see [`compare_errors`].
+    pub fn diff(&self, actual: &[Diagnostic], options: MatchOptions) -> ErrorComparison {
+        compare_errors(self, actual, options, &mut Scratch::default())
+    }
+}
+
+/// Like [`compare_errors`], with the expected diagnostics already taken out of the baseline,
+/// e.g. only the ones of a single file.
+pub fn compare_diagnostics(
+    expected: &[Diagnostic],
+    actual: &[Diagnostic],
+    options: MatchOptions,
+    scratch: &mut Scratch,
+) -> ErrorComparison {
+    let mut expected_entries = Vec::new();
+    collect(expected, options, &mut expected_entries);
+    let mut actual_entries = Vec::new();
+    collect(actual, options, &mut actual_entries);
+
+    // Produced diagnostics by key, then in the order they were produced
+    let Scratch { order, consumed, .. } = scratch;
+    order.clear();
+    order.extend(0..actual_entries.len());
+    order.sort_unstable_by(|&a, &b| {
+        cmp_keys(key(actual_entries[a]), key(actual_entries[b])).then(a.cmp(&b))
+    });
+    consumed.clear();
+    consumed.resize(actual_entries.len(), false);
+
+    let mut result = ErrorComparison::default();
+    for diagnostic in expected_entries {
+        let wanted = key(diagnostic);
+        let start =
+            order.partition_point(|&idx| cmp_keys(key(actual_entries[idx]), wanted).is_lt());
+        let found = order[start..]
+            .iter()
+            .copied()
+            .take_while(|&idx| key(actual_entries[idx]) == wanted)
+            .find(|&idx| !consumed[idx] && same_details(diagnostic, actual_entries[idx], options));
+        if let Some(idx) = found {
+            consumed[idx] = true;
+            if differs_in_hint_depth(&diagnostic.message, &actual_entries[idx].message) {
+                result.hint_depth_differences += 1;
+            }
+            result.matched.push(diagnostic.clone());
+        } else {
+            result.missing.push(diagnostic.clone());
+        }
+    }
+
+    result.unexpected = actual_entries
+        .into_iter()
+        .zip(consumed.iter())
+        .filter(|&(_, &consumed)| !consumed)
+        .map(|(diagnostic, _)| diagnostic.clone())
+        .collect();
+
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{path::PathBuf, str::FromStr};
+
+    use type_info::Severity;
+
+    use super::*;
+
+    const DATA: &[u8] = br#"a.ts(1,8): error TS1259: Module '"b"' can only be default-imported using the 'esModuleInterop' flag
+
+
+==== b.d.ts (0 errors) ====
+    declare class Foo {
+    	member: string;
+    }
+    export = Foo;
+
+==== a.ts (1 errors) ====
+    import Foo from "./b";
+           ~~~
+!!! error TS1259: Module '"b"' can only be default-imported using the 'esModuleInterop' flag
+!!! related TS2594 b.d.ts:4:1: This module is declared with 'export =', and can only be used with a default import when using the 'esModuleInterop' flag.
+    export var x = new Foo();
+    "#;
+
+    fn actual(severity: Severity, file: &str, line: u32, column: u32, code: u32) -> Diagnostic {
+        Diagnostic { severity, code, file: file.to_string(), line, column, ..Diagnostic::default() }
+    }
+
+    #[test]
+    fn related_ignored_by_default() {
+        let path = PathBuf::from_str("tests/baselines/reference/unit1.errors.txt").unwrap();
+        let baseline = ErrorsBaseline::parse(&path, DATA).unwrap();
+        let result = compare_errors(
+            &baseline,
+            &[actual(Severity::Error, "a.ts", 1, 8, 1259)],
+            MatchOptions::default(),
+            &mut Scratch::default(),
+        );
+
+        assert!(result.is_match());
+        assert_eq!(result.matched.len(), 1);
+        assert_eq!(result.matched[0].related[0].severity, Severity::Message);
+    }
+
+    #[test]
+    fn related_compared_when_requested() {
+        let path = PathBuf::from_str("tests/baselines/reference/unit1.errors.txt").unwrap();
+        let baseline = ErrorsBaseline::parse(&path, DATA).unwrap();
+        let result = compare_errors(
+            &baseline,
+            &[actual(Severity::Error, "a.ts", 1, 8, 1259)],
+            MatchOptions { include_non_errors: true, ..Default::default() },
+            &mut Scratch::default(),
+        );
+
+        assert!(!result.is_match());
+        assert_eq!(result.missing.len(), 1);
+        assert_eq!(result.missing[0].code, 2594);
+        assert_eq!(result.missing[0].severity, Severity::Message);
+    }
+
+    #[test]
+    fn non_errors_do_not_fail_by_default() {
+        let path = PathBuf::from_str("tests/baselines/reference/unit1.errors.txt").unwrap();
+        let baseline = ErrorsBaseline::parse(&path, DATA).unwrap();
+        let diagnostics = [
+            actual(Severity::Error, "a.ts", 1, 8, 1259),
+            actual(Severity::Warning, "a.ts", 2, 1, 6133),
+            actual(Severity::Suggestion, "a.ts", 2, 1, 80001),
+        ];
+
+        assert!(
+            compare_errors(
+                &baseline,
+                &diagnostics,
+                MatchOptions::default(),
+                &mut Scratch::default()
+            )
+            .is_match()
+        );
+
+        let result = compare_errors(
+            &baseline,
+            &diagnostics,
+            MatchOptions { include_non_errors: true, ..Default::default() },
+            &mut Scratch::default(),
+        );
+        assert_eq!(result.unexpected.iter().map(|x| x.code).collect::<Vec<_>>(), vec![6133, 80001]);
+    }
+
+    #[test]
+    fn error_mismatch() {
+        let path = PathBuf::from_str("tests/baselines/reference/unit1.errors.txt").unwrap();
+        let baseline = ErrorsBaseline::parse(&path, DATA).unwrap();
+        let result = compare_errors(
+            &baseline,
+            &[actual(Severity::Error, "a.ts", 1, 8, 2307)],
+            MatchOptions::default(),
+            &mut Scratch::default(),
+        );
+
+        assert_eq!(result.missing.iter().map(|x| x.code).collect::<Vec<_>>(), vec![1259]);
+        assert_eq!(result.unexpected.iter().map(|x| x.code).collect::<Vec<_>>(), vec![2307]);
+    }
+
+    #[test]
+    fn retain_files() {
+        let path = PathBuf::from_str("tests/baselines/reference/unit1.errors.txt").unwrap();
+        let baseline = ErrorsBaseline::parse(&path, DATA).unwrap();
+        let mut result = compare_errors(
+            &baseline,
+            &[actual(Severity::Error, "b.d.ts", 4, 1, 2309)],
+            MatchOptions::default(),
+            &mut Scratch::default(),
+        );
+        assert!(!result.is_match());
+
+        result.retain_files(|x| x == "b.d.ts");
+        assert!(result.missing.is_empty());
+        assert_eq!(result.unexpected.len(), 1);
+
+        result.retain_files(|x| x == "a.ts");
+        assert!(result.is_match());
+    }
+
+    #[test]
+    fn hint_depth_advisory() {
+        let path = PathBuf::from_str("tests/baselines/reference/unit1.errors.txt").unwrap();
+        let data = b"a.ts(1,5): error TS2322: Type 'string' is not assignable to type 'number'.
+\tType 'a' is not assignable.
+
+
+==== a.ts (1 errors) ====
+    let x: number = '';
+        ~
+!!! error TS2322: Type 'string' is not assignable to type 'number'.
+!!! error TS2322: \tType 'a' is not assignable.
+    ";
+        let baseline = ErrorsBaseline::parse(&path, data).unwrap();
+        let actual = |message: &str| Diagnostic {
+            message: message.to_string(),
+            ..actual(Severity::Error, "a.ts", 1, 5, 2322)
+        };
+
+        let same = compare_errors(
+            &baseline,
+            &[actual(
+                "Type 'string' is not assignable to type 'number'.\n  Type 'a' is not assignable.",
+            )],
+            MatchOptions::default(),
+            &mut Scratch::default(),
+        );
+        assert!(same.is_match());
+        assert_eq!(same.hint_depth_differences, 0);
+
+        let deeper = compare_errors(
+            &baseline,
+            &[actual(
+                "Type 'string' is not assignable to type 'number'.\n    Type 'a' is not assignable.",
+            )],
+            MatchOptions::default(),
+            &mut Scratch::default(),
+        );
+        assert!(deeper.is_match());
+        assert_eq!(deeper.hint_depth_differences, 1);
+    }
+
+    #[test]
+    fn diff() {
+        let path = PathBuf::from_str("tests/baselines/reference/unit1.errors.txt").unwrap();
+        let baseline = ErrorsBaseline::parse(&path, DATA).unwrap();
+        let expected = actual(Severity::Error, "a.ts", 1, 8, 1259);
+        let extra = actual(Severity::Error, "a.ts", 2, 1, 2304);
+        let options = MatchOptions::default();
+
+        let exact = baseline.diff(&[expected.clone()], options);
+        assert!(exact.is_match());
+        assert_eq!(exact.matched.len(), 1);
+
+        let missing = baseline.diff(&[], options);
+        assert_eq!(missing.missing.iter().map(|x| x.code).collect::<Vec<_>>(), vec![1259]);
+        assert!(missing.unexpected.is_empty());
+
+        // Produced in another order than the baseline lists them
+        let unexpected = baseline.diff(&[extra, expected], options);
+        assert_eq!(unexpected.matched.len(), 1);
+        assert!(unexpected.missing.is_empty());
+        assert_eq!(unexpected.unexpected.iter().map(|x| x.code).collect::<Vec<_>>(), vec![2304]);
+
+        let other_code = baseline.diff(&[actual(Severity::Error, "a.ts", 1, 8, 1192)], options);
+        assert_eq!(other_code.missing.iter().map(|x| x.code).collect::<Vec<_>>(), vec![1259]);
+        assert_eq!(other_code.unexpected.iter().map(|x| x.code).collect::<Vec<_>>(), vec![1192]);
+    }
+
+    #[test]
+    fn messages_and_lengths() {
+        let path = PathBuf::from_str("tests/baselines/reference/unit1.errors.txt").unwrap();
+        let baseline = ErrorsBaseline::parse(&path, DATA).unwrap();
+        let message =
+            "Module '\"b\"' can only be default-imported using the 'esModuleInterop' flag";
+        let produced = |message: &str, length| Diagnostic {
+            message: message.to_string(),
+            length,
+            ..actual(Severity::Error, "a.ts", 1, 8, 1259)
+        };
+        let messages = MatchOptions { compare_messages: true, ..Default::default() };
+        let lengths = MatchOptions { compare_lengths: true, ..Default::default() };
+
+        assert!(
+            baseline
+                .diff(&[produced("Other message", Some(2))], MatchOptions::default())
+                .is_match()
+        );
+        assert!(baseline.diff(&[produced(message, None)], messages).is_match());
+        let result = baseline.diff(&[produced("Other message", Some(3))], messages);
+        assert_eq!((result.missing.len(), result.unexpected.len()), (1, 1));
+
+        assert!(baseline.diff(&[produced("", Some(3))], lengths).is_match());
+        assert!(!baseline.diff(&[produced("", Some(2))], lengths).is_match());
+    }
+
+    #[test]
+    fn severities() {
+        let path = PathBuf::from_str("tests/baselines/reference/unit1.errors.txt").unwrap();
+        let data = b"a.ts(1,7): warning TS6133: 'x' is declared but its value is never read.
+
+
+==== a.ts (1 errors) ====
+    const x = 1;
+          ~
+!!! warning TS6133: 'x' is declared but its value is never read.";
+        let baseline = ErrorsBaseline::parse(&path, data).unwrap();
+        let warning = actual(Severity::Warning, "a.ts", 1, 7, 6133);
+        let error = actual(Severity::Error, "a.ts", 1, 7, 6133);
+        let any = MatchOptions { include_non_errors: true, ..Default::default() };
+        let exact = MatchOptions { compare_severities: true, ..any };
+
+        // Warnings are left out of the comparison by default
+        assert!(baseline.diff(&[], MatchOptions::default()).is_match());
+        assert!(baseline.diff(&[warning], exact).is_match());
+        assert!(baseline.diff(&[error.clone()], any).is_match());
+        let result = baseline.diff(&[error], exact);
+        assert_eq!((result.missing.len(), result.unexpected.len()), (1, 1));
+    }
+}
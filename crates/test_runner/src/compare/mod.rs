@@ -0,0 +1,56 @@
+mod errors;
+mod module;
+mod suppression;
+mod types;
+
+pub use errors::{ErrorComparison, compare_diagnostics, compare_errors};
+pub use module::{
+    DiagnosticsByFile, ModuleBaseline, compare_module, compare_modules, strip_base_url,
+};
+use oxc::span::Span;
+pub use suppression::{SUPPRESSIONS, Suppression, is_suppressed, suppress_diagnostics};
+pub use types::{ActualType, AssertionResult, StatementComparison, TypeComparison, compare_types};
+
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy)]
+pub struct MatchOptions {
+    /// Also compare warnings, suggestions and messages (including related information).
+    ///
+    /// By default only [`type_info::Severity::Error`] diagnostics can cause a mismatch.
+    pub include_non_errors: bool,
+    /// Treat statements whose expressions we visit in a different order than the baseline
+    /// lists them as mismatches.
+    pub check_order: bool,
+    /// Only pair diagnostics with the same message, elaboration chain included.
+    pub compare_messages: bool,
+    /// Only pair diagnostics with the same underline length, when the baseline shows it.
+    pub compare_lengths: bool,
+    /// Only pair diagnostics with the same severity, like a warning listed in the baseline with
+    /// a produced error. Needs [`Self::include_non_errors`] to see more than errors.
+    pub compare_severities: bool,
+}
+
+/// Buffers the comparison functions reuse from one call to the next, so comparing a variant
+/// does not allocate per statement or per diagnostic. Workers keep one each.
+#[derive(Debug, Default)]
+pub struct Scratch {
+    /// Span of every baseline statement in the source text.
+    spans: Vec<Option<Span>>,
+    /// Statement each visited node belongs to.
+    placement: Vec<usize>,
+    /// Indices of visited nodes sorted by statement, or of produced diagnostics sorted by key.
+    order: Vec<usize>,
+    /// Produced diagnostics already paired with an expected one.
+    consumed: Vec<bool>,
+    /// Buffer of the [`StatementSource`](crate::baseline::types_baseline::StatementSource) of
+    /// the statement being compared.
+    resolved: Vec<(usize, usize)>,
+    text: String,
+}
+
+impl Scratch {
+    /// `text` without whitespace, as expression texts are compared when the harness echoed them
+    /// differently. Valid until the scratch is used again.
+    pub fn normalize(&mut self, text: &str) -> &str {
+        types::normalize_into(&mut self.text, text)
+    }
+}
@@ -0,0 +1,210 @@
+use type_info::Diagnostic;
+
+use super::{ActualType, MatchOptions, Scratch, compare_diagnostics, compare_types};
+use crate::{
+    baseline::{
+        file_order,
+        types_baseline::{TypeBaselineFile, TypesBaseline},
+    },
+    line_index::LineIndex,
+    report::ModuleReport,
+};
+
+/// Diagnostics sorted by file (see [`file_order`]), in their original order within a file, so
+/// every module is handed its own without scanning all of them.
+#[derive(Debug, Default, PartialEq, Eq, Clone)]
+pub struct DiagnosticsByFile(Vec<Diagnostic>);
+
+impl DiagnosticsByFile {
+    pub fn new(mut diagnostics: Vec<Diagnostic>) -> Self {
+        diagnostics.sort_by(|a, b| file_order::cmp(&a.file, &b.file));
+        Self(diagnostics)
+    }
+
+    /// Diagnostics reported in `file`.
+    pub fn file(&self, file: &str) -> &[Diagnostic] {
+        let start = self.0.partition_point(|x| file_order::cmp(&x.file, file).is_lt());
+        let len = self.0[start..].partition_point(|x| x.file == file);
+        &self.0[start..start + len]
+    }
+
+    /// Diagnostics reported outside of `files`, including the ones without a file.
+    pub fn except(&self, files: &[&str]) -> Vec<Diagnostic> {
+        self.0.iter().filter(|x| !files.contains(&x.file.as_str())).cloned().collect()
+    }
+}
+
+/// The sections of a variant's baselines a module is compared against, looked up once per
+/// module.
+#[derive(Debug, Clone, Copy)]
+pub struct ModuleBaseline<'b, 'a> {
+    pub types: &'b TypeBaselineFile<'a>,
+    /// Diagnostics the errors baseline expects in the module.
+    pub errors: &'b [Diagnostic],
+}
+
+impl<'b, 'a> ModuleBaseline<'b, 'a> {
+    /// Looks the module up by its name, or by its name relative to `@baseUrl` (see
+    /// [`strip_base_url`]). The diagnostics are the ones in the baseline file found.
+    ///
+    /// Returns `None` if the types baseline has no file for the module.
+    pub fn resolve(
+        types: &'b TypesBaseline<'a>,
+        errors: &'b DiagnosticsByFile,
+        name: &str,
+        base_url: Option<&str>,
+    ) -> Option<Self> {
+        let idx = types.names.position(|&x| x == name).or_else(|| {
+            let name = strip_base_url(name, base_url)?;
+            types.names.position(|&x| x == name)
+        })?;
+        Some(Self { types: &types.files[idx], errors: errors.file(types.names[idx]) })
+    }
+}
+
+/// Name of a module under `@baseUrl` relative to it, like the baselines name it: `src/a.ts` for
+/// `/project/src/a.ts` with `@baseUrl: /project`. `None` outside of `base_url`.
+pub fn strip_base_url<'n>(name: &'n str, base_url: Option<&str>) -> Option<&'n str> {
+    let rest = name.strip_prefix(base_url?.trim_end_matches('/'))?;
+    rest.strip_prefix('/').filter(|x| !x.is_empty())
+}
+
+/// Compares the visited nodes and the diagnostics produced in a module with its baselines, so
+/// both end up in one [`ModuleReport`] sharing the module's [`LineIndex`].
+pub fn compare_module<'a>(
+    name: &'a str,
+    source_text: &'a str,
+    baseline: ModuleBaseline<'_, 'a>,
+    types: Vec<ActualType<'a>>,
+    diagnostics: &[Diagnostic],
+    options: MatchOptions,
+    scratch: &mut Scratch,
+) -> ModuleReport<'a> {
+    ModuleReport {
+        name,
+        lines: LineIndex::new(source_text),
+        types: compare_types(baseline.types, source_text, types, scratch),
+        errors: compare_diagnostics(baseline.errors, diagnostics, options, scratch),
+    }
+}
+
+/// Compares every module, given as `(name, source_text, nodes)`, with its file in the types
+/// baseline and the diagnostics `expected` in it. Returns `None` if the types baseline lacks one
+/// of the modules.
+///
+/// Modules are looked up like [`ModuleBaseline::resolve`] does.
+pub fn compare_modules<'a>(
+    types: &TypesBaseline<'a>,
+    expected: &DiagnosticsByFile,
+    actual: Vec<(&'a str, &'a str, Vec<ActualType<'a>>)>,
+    diagnostics: &DiagnosticsByFile,
+    base_url: Option<&str>,
+    options: MatchOptions,
+    scratch: &mut Scratch,
+) -> Option<Vec<ModuleReport<'a>>> {
+    actual
+        .into_iter()
+        .map(|(name, source_text, nodes)| {
+            let baseline = ModuleBaseline::resolve(types, expected, name, base_url)?;
+            let diagnostics = diagnostics.file(name);
+            Some(compare_module(name, source_text, baseline, nodes, diagnostics, options, scratch))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use oxc::span::Span;
+    use oxc_index::index_vec;
+
+    use std::path::Path;
+
+    use super::*;
+    use crate::{TestUnit, baseline::types_baseline::Assertion};
+
+    fn diagnostic(file: &str, line: u32, code: u32) -> Diagnostic {
+        Diagnostic { code, file: file.to_string(), line, column: 1, ..Diagnostic::default() }
+    }
+
+    #[test]
+    fn by_file() {
+        let diagnostics = DiagnosticsByFile::new(vec![
+            diagnostic("b.ts", 2, 1),
+            diagnostic("a.ts", 1, 2),
+            diagnostic("", 0, 3),
+            diagnostic("b.ts", 1, 4),
+        ]);
+        let codes = |x: &[Diagnostic]| x.iter().map(|x| x.code).collect::<Vec<_>>();
+        assert_eq!(codes(diagnostics.file("a.ts")), vec![2]);
+        assert_eq!(codes(diagnostics.file("b.ts")), vec![1, 4]);
+        assert!(diagnostics.file("c.ts").is_empty());
+        assert_eq!(codes(&diagnostics.except(&["a.ts", "b.ts"])), vec![3]);
+    }
+
+    #[test]
+    fn modules() {
+        let source_text = "const a = 5;\n";
+        let baseline = TypesBaseline {
+            names: index_vec!["a.ts"],
+            files: index_vec![TypeBaselineFile {
+                statements: index_vec!["const a = 5;"],
+                assertions: index_vec![vec![Assertion { expr: "a", expected_type: "number" }]],
+            }],
+            warnings: vec![],
+        };
+        let node = ActualType {
+            span: Span::new(6, 7),
+            kind: "Identifier".into(),
+            text: "a",
+            type_name: "number".into(),
+            placeholder: false,
+        };
+        let expected = DiagnosticsByFile::new(vec![diagnostic("a.ts", 1, 2322)]);
+        let produced = DiagnosticsByFile::default();
+
+        let actual = vec![("a.ts", source_text, vec![node])];
+        let options = MatchOptions::default();
+        let mut scratch = Scratch::default();
+        let modules =
+            compare_modules(&baseline, &expected, actual, &produced, None, options, &mut scratch)
+                .unwrap();
+        assert_eq!(modules[0].name, "a.ts");
+        assert!(modules[0].types.is_match());
+        assert_eq!(modules[0].errors.missing.len(), 1);
+
+        let actual = vec![("b.ts", source_text, vec![])];
+        assert!(
+            compare_modules(&baseline, &expected, actual, &produced, None, options, &mut scratch)
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn base_url() {
+        assert_eq!(strip_base_url("/project/src/a.ts", Some("/project")), Some("src/a.ts"));
+        assert_eq!(strip_base_url("/project/src/a.ts", Some("/project/")), Some("src/a.ts"));
+        assert_eq!(strip_base_url("/a.ts", Some("/")), Some("a.ts"));
+        assert_eq!(strip_base_url("/projectX/a.ts", Some("/project")), None);
+        assert_eq!(strip_base_url("/project/src/a.ts", None), None);
+
+        let unit = TestUnit::parse(
+            Path::new("baseUrl.ts"),
+            b"// @baseUrl: /project\n// @filename: /project/src/a.ts\nconst a = 5;\n",
+        );
+        assert_eq!(unit.settings.base_url.as_deref(), Some("/project"));
+        let baseline = TypesBaseline {
+            names: index_vec!["src/a.ts"],
+            files: index_vec![TypeBaselineFile {
+                statements: index_vec!["const a = 5;"],
+                assertions: index_vec![vec![Assertion { expr: "a", expected_type: "number" }]],
+            }],
+            warnings: vec![],
+        };
+        let expected = DiagnosticsByFile::new(vec![diagnostic("src/a.ts", 1, 2322)]);
+        let base_url = unit.settings.base_url.as_deref();
+        let name = unit.file_names[unit.file_names.last_idx()];
+        let resolved = ModuleBaseline::resolve(&baseline, &expected, name, base_url).unwrap();
+        assert_eq!(resolved.errors.len(), 1);
+        assert!(ModuleBaseline::resolve(&baseline, &expected, name, None).is_none());
+    }
+}
@@ -0,0 +1,143 @@
+use type_info::Diagnostic;
+
+use crate::{TestVariant, test_unit::TestVariationProp};
+
+/// A diagnostic TS does not report while `option` is set to `value`.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct Suppression {
+    pub code: u32,
+    pub option: TestVariationProp,
+    /// Option value disabling the diagnostic, compared case-insensitively.
+    pub value: &'static str,
+}
+
+const fn suppressed(code: u32, option: TestVariationProp, value: &'static str) -> Suppression {
+    Suppression { code, option, value }
+}
+
+/// Diagnostics switched off by compiler options.
+///
+/// We do not honor these options yet, while TS writes a separate errors baseline for every
+/// variant. Dropping the diagnostics the variant disables keeps them from showing up as
+/// unexpected.
+pub const SUPPRESSIONS: &[Suppression] = &[
+    // Variable implicitly has an 'any' type.
+    suppressed(7005, TestVariationProp::NoImplicitAny, "false"),
+    // Parameter implicitly has an 'any' type.
+    suppressed(7006, TestVariationProp::NoImplicitAny, "false"),
+    // Member implicitly has an 'any' type.
+    suppressed(7008, TestVariationProp::NoImplicitAny, "false"),
+    // Function lacking return type annotation implicitly has an 'any' return type.
+    suppressed(7010, TestVariationProp::NoImplicitAny, "false"),
+    // Function expression lacking return type annotation implicitly has an 'any' return type.
+    suppressed(7011, TestVariationProp::NoImplicitAny, "false"),
+    // Element implicitly has an 'any' type because index expression is not of type 'number'.
+    suppressed(7015, TestVariationProp::NoImplicitAny, "false"),
+    // Could not find a declaration file for module.
+    suppressed(7016, TestVariationProp::NoImplicitAny, "false"),
+    // Rest parameter implicitly has an 'any[]' type.
+    suppressed(7019, TestVariationProp::NoImplicitAny, "false"),
+    // Binding element implicitly has an 'any' type.
+    suppressed(7031, TestVariationProp::NoImplicitAny, "false"),
+    // Variable implicitly has type 'any' in some locations where its type cannot be determined.
+    suppressed(7034, TestVariationProp::NoImplicitAny, "false"),
+    // Element implicitly has an 'any' type because expression can't be used to index type.
+    suppressed(7053, TestVariationProp::NoImplicitAny, "false"),
+    // Object is possibly 'null'.
+    suppressed(2531, TestVariationProp::StrictNullChecks, "false"),
+    // Object is possibly 'undefined'.
+    suppressed(2532, TestVariationProp::StrictNullChecks, "false"),
+    // Object is possibly 'null' or 'undefined'.
+    suppressed(2533, TestVariationProp::StrictNullChecks, "false"),
+    // 'x' is possibly 'null'.
+    suppressed(18047, TestVariationProp::StrictNullChecks, "false"),
+    // 'x' is possibly 'undefined'.
+    suppressed(18048, TestVariationProp::StrictNullChecks, "false"),
+    // 'x' is possibly 'null' or 'undefined'.
+    suppressed(18049, TestVariationProp::StrictNullChecks, "false"),
+    // This member must have an 'override' modifier.
+    suppressed(4114, TestVariationProp::NoImplicitOverride, "false"),
+    // This member must have an 'override' modifier because it overrides an abstract method.
+    suppressed(4116, TestVariationProp::NoImplicitOverride, "false"),
+    // Property comes from an index signature, so it must be accessed with ['x'].
+    suppressed(4111, TestVariationProp::NoPropertyAccessFromIndexSignature, "false"),
+    // Cannot find module or type declarations for side-effect import.
+    suppressed(2882, TestVariationProp::NoUncheckedSideEffectImports, "false"),
+    // Module can only be default-imported using the 'esModuleInterop' flag.
+    suppressed(1259, TestVariationProp::ESModuleInterop, "true"),
+];
+
+/// Value of the option in the variant. Options `strict` turns on follow it unless set.
+fn option_value<'a>(variant: &'a TestVariant<'_>, option: TestVariationProp) -> Option<&'a str> {
+    variant.get(option).or_else(|| match option {
+        TestVariationProp::NoImplicitAny
+        | TestVariationProp::StrictNullChecks
+        | TestVariationProp::StrictBuiltinIteratorReturn
+        | TestVariationProp::UseUnknownInCatchVariables => variant.get(TestVariationProp::Strict),
+        _ => None,
+    })
+}
+
+/// Whether the options of the variant keep TS from reporting `code`.
+pub fn is_suppressed(code: u32, variant: &TestVariant<'_>) -> bool {
+    SUPPRESSIONS.iter().filter(|x| x.code == code).any(|x| {
+        option_value(variant, x.option).is_some_and(|value| value.eq_ignore_ascii_case(x.value))
+    })
+}
+
+/// Drops the diagnostics the variant's options switch off. Returns how many were dropped.
+pub fn suppress_diagnostics(diagnostics: &mut Vec<Diagnostic>, variant: &TestVariant<'_>) -> usize {
+    let len = diagnostics.len();
+    diagnostics.retain(|x| !is_suppressed(x.code, variant));
+    len - diagnostics.len()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use super::*;
+    use crate::TestUnit;
+
+    fn with_variant(directives: &str, f: impl FnOnce(&TestVariant<'_>)) {
+        let data = format!("{directives}let a;\n");
+        let unit = TestUnit::parse(Path::new("tests/cases/compiler/unit.ts"), data.as_bytes());
+        let variant = unit.variations.iter().next().expect("variant to exist");
+        f(&variant);
+    }
+
+    #[test]
+    fn every_entry() {
+        for entry in SUPPRESSIONS {
+            let option = <&str>::from(entry.option);
+            let other = if entry.value == "true" { "false" } else { "true" };
+            with_variant(&format!("// @{option}: {}\n", entry.value.to_uppercase()), |variant| {
+                assert!(is_suppressed(entry.code, variant), "{entry:?}");
+            });
+            with_variant(&format!("// @{option}: {other}\n"), |variant| {
+                assert!(!is_suppressed(entry.code, variant), "{entry:?}");
+            });
+            with_variant("", |variant| assert!(!is_suppressed(entry.code, variant), "{entry:?}"));
+        }
+    }
+
+    #[test]
+    fn strict_family() {
+        with_variant("// @strict: false\n", |variant| {
+            assert!(is_suppressed(7006, variant));
+            assert!(is_suppressed(18048, variant));
+            assert!(!is_suppressed(4114, variant));
+        });
+        with_variant("// @strict: false\n// @noImplicitAny: true\n", |variant| {
+            assert!(!is_suppressed(7006, variant));
+            assert!(is_suppressed(18048, variant));
+        });
+
+        with_variant("// @strict: true\n// @strictNullChecks: false\n", |variant| {
+            let diagnostic = |code| Diagnostic { code, ..Diagnostic::default() };
+            let mut diagnostics = vec![diagnostic(7006), diagnostic(18048), diagnostic(2322)];
+            assert_eq!(suppress_diagnostics(&mut diagnostics, variant), 1);
+            assert_eq!(diagnostics, vec![diagnostic(7006), diagnostic(2322)]);
+        });
+    }
+}
@@ -0,0 +1,483 @@
+use std::borrow::Cow;
+
+use oxc::span::Span;
+
+use super::Scratch;
+use crate::baseline::types_baseline::{StatementSource, TypeBaselineFile};
+
+/// A node visited by the type visitor together with the type we computed for it.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct ActualType<'a> {
+    pub span: Span,
+    pub kind: Cow<'a, str>,
+    pub text: &'a str,
+    pub type_name: Cow<'a, str>,
+    /// `type_name` is a stand-in, the type was not computed.
+    pub placeholder: bool,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct AssertionResult<'a> {
+    pub expr: &'a str,
+    pub expected_type: &'a str,
+    /// Visited node with the same expression text, if any.
+    pub actual: Option<ActualType<'a>>,
+    /// Position of `actual` among the nodes visited within the statement.
+    pub visit_index: Option<usize>,
+    /// `actual` was only found after normalizing both expression texts, with newlines collapsed to
+    /// spaces and without `\r`.
+    pub normalized: bool,
+}
+
+impl AssertionResult<'_> {
+    pub fn is_match(&self) -> bool {
+        self.actual.as_ref().is_some_and(|x| x.type_name == self.expected_type)
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct StatementComparison<'a> {
+    pub statement: &'a str,
+    /// Location of the statement in the module source text.
+    pub span: Option<Span>,
+    pub assertions: Vec<AssertionResult<'a>>,
+    /// Visited nodes within the statement that the baseline has no assertion for.
+    pub unexpected: Vec<ActualType<'a>>,
+}
+
+impl<'a> StatementComparison<'a> {
+    /// Whether the visitor reached the matched expressions in the baseline order.
+    pub fn is_ordered(&self) -> bool {
+        self.assertions.iter().filter_map(|x| x.visit_index).is_sorted()
+    }
+
+    /// Matched expressions in baseline order and in our visit order, when the two differ.
+    pub fn order_divergence(&self) -> Option<(Vec<&'a str>, Vec<&'a str>)> {
+        if self.is_ordered() {
+            return None;
+        }
+
+        let mut visited: Vec<_> =
+            self.assertions.iter().filter_map(|x| Some((x.visit_index?, x.expr))).collect();
+        let expected = visited.iter().map(|x| x.1).collect();
+        visited.sort_unstable_by_key(|x| x.0);
+        Some((expected, visited.into_iter().map(|x| x.1).collect()))
+    }
+}
+
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct TypeComparison<'a> {
+    pub statements: Vec<StatementComparison<'a>>,
+    /// Visited nodes that do not belong to any baseline statement.
+    pub unplaced: Vec<ActualType<'a>>,
+}
+
+impl TypeComparison<'_> {
+    pub fn checked(&self) -> usize {
+        self.statements.iter().map(|x| x.assertions.len()).sum()
+    }
+
+    pub fn matched(&self) -> usize {
+        self.statements.iter().flat_map(|x| &x.assertions).filter(|x| x.is_match()).count()
+    }
+
+    pub fn mismatched(&self) -> usize {
+        self.checked() - self.matched()
+    }
+
+    /// Number of assertions paired with a node only after normalizing the expression texts.
+    pub fn normalized(&self) -> usize {
+        self.statements.iter().flat_map(|x| &x.assertions).filter(|x| x.normalized).count()
+    }
+
+    pub fn is_match(&self) -> bool {
+        self.mismatched() == 0
+            && self.unplaced.is_empty()
+            && self.statements.iter().all(|x| x.unexpected.is_empty())
+    }
+
+    /// Number of statements whose expressions we visit in a different order than the baseline.
+    pub fn out_of_order(&self) -> usize {
+        self.statements.iter().filter(|x| !x.is_ordered()).count()
+    }
+}
+
+/// [`Scratch::placement`] of nodes outside every baseline statement.
+const UNPLACED: usize = usize::MAX;
+
+/// Finds each baseline statement in the source text, in order.
+///
+/// Statements are consecutive source lines, so we look for the first line of each statement at
+/// or after the end of the previous one and extend the span over the remaining lines.
+fn locate_statements(source_text: &str, statements: &[&str], out: &mut Vec<Option<Span>>) {
+    let mut cursor = 0usize;
+    out.clear();
+    out.extend(statements.iter().map(|statement| {
+        let mut lines = statement.lines().map(|x| x.trim_end_matches('\r'));
+        let first = lines.next().filter(|x| !x.is_empty())?;
+        let line_count = 1 + lines.count();
+
+        let mut line_start = cursor;
+        while line_start < source_text.len() {
+            let line_end =
+                source_text[line_start..].find('\n').map_or(source_text.len(), |x| line_start + x);
+            if source_text[line_start..line_end].trim_end_matches('\r') == first {
+                let mut end = line_end;
+                for _ in 1..line_count {
+                    if end >= source_text.len() {
+                        break;
+                    }
+                    end = source_text[end + 1..]
+                        .find('\n')
+                        .map_or(source_text.len(), |x| end + 1 + x);
+                }
+
+                cursor = end;
+                let start = u32::try_from(line_start).ok()?;
+                let end = u32::try_from(end).ok()?;
+                return Some(Span::new(start, end));
+            }
+
+            line_start = line_end + 1;
+        }
+
+        None
+    }));
+}
+
+/// Expression text with newlines collapsed to spaces and without `\r`, for comparing texts the
+/// harness echoed differently: it collapses the newlines of template literals. The lines it
+/// joins elsewhere (`function () {  this;}`) are left to [span
+/// resolution](crate::baseline::types_baseline::Assertion::resolve_span).
+fn normalized(text: &str) -> impl Iterator<Item = char> {
+    text.chars().filter(|&x| x != '\r').map(|x| if x == '\n' { ' ' } else { x })
+}
+
+/// Writes the [normalized] `text` into `out`.
+pub fn normalize_into<'s>(out: &'s mut String, text: &str) -> &'s str {
+    out.clear();
+    out.extend(normalized(text));
+    out
+}
+
+/// Matches visited nodes against the baseline assertions statement by statement.
+///
+/// Nodes are assigned to the statement whose source lines contain the node start, then each
+/// assertion consumes the first remaining node at its [resolved
+/// span](crate::baseline::types_baseline::Assertion::resolve_span), or failing that, the first
+/// remaining node with the same expression text, exactly or once normalized.
+///
+/// # Examples
+///
+/// ```
+/// use std::path::Path;
+///
+/// use oxc::span::Span;
+/// use test_runner::{
+///     TypesBaseline,
+///     compare::{ActualType, Scratch, compare_types},
+/// };
+///
+/// let data = b"//// [a.ts] ////\n\n=== a.ts ===\nconst a = 5;\n>a : 5\n>  : ^\n>5 : 5\n>  : ^\n";
+/// let baseline = TypesBaseline::parse(Path::new("a.types"), data).unwrap();
+/// let source_text = "const a = 5;\n";
+/// let node = |start, text: &'static str, type_name: &'static str| ActualType {
+///     span: Span::new(start, start + 1),
+///     kind: "Identifier".into(),
+///     text,
+///     type_name: type_name.into(),
+///     placeholder: false,
+/// };
+///
+/// let actual = vec![node(6, "a", "5"), node(10, "5", "number")];
+/// let result =
+///     compare_types(&baseline.files.raw[0], source_text, actual, &mut Scratch::default());
+/// assert_eq!((result.checked(), result.matched()), (2, 1));
+/// ```
+pub fn compare_types<'a>(
+    baseline: &TypeBaselineFile<'a>,
+    source_text: &'a str,
+    actual: Vec<ActualType<'a>>,
+    scratch: &mut Scratch,
+) -> TypeComparison<'a> {
+    let Scratch { spans, placement, order, resolved, text, .. } = scratch;
+    locate_statements(source_text, &baseline.statements.raw, spans);
+    placement.clear();
+    placement.extend(actual.iter().map(|node| {
+        spans
+            .iter()
+            .position(|x| x.is_some_and(|x| x.start <= node.span.start && node.span.start <= x.end))
+            .unwrap_or(UNPLACED)
+    }));
+
+    // Group the nodes by statement, in visit order within each statement
+    let mut nodes: Vec<Option<ActualType<'a>>> = actual.into_iter().map(Some).collect();
+    order.clear();
+    order.extend((0..nodes.len()).filter(|&idx| placement[idx] != UNPLACED));
+    order.sort_unstable_by_key(|&idx| (placement[idx], idx));
+
+    let mut result = TypeComparison {
+        unplaced: (0..nodes.len())
+            .filter(|&idx| placement[idx] == UNPLACED)
+            .filter_map(|idx| nodes[idx].take())
+            .collect(),
+        ..TypeComparison::default()
+    };
+
+    let mut rest = &order[..];
+    for (statement_idx, ((&statement, assertions), &span)) in
+        baseline.statements.iter().zip(&baseline.assertions).zip(spans.iter()).enumerate()
+    {
+        let (ids, tail) = rest.split_at(rest.partition_point(|&x| placement[x] == statement_idx));
+        rest = tail;
+
+        let mut statement_source =
+            span.map(|x| StatementSource::with_buffer(source_text, x, std::mem::take(resolved)));
+        let assertions = assertions
+            .iter()
+            .map(|assertion| {
+                let resolved = statement_source.as_mut().and_then(|x| assertion.resolve_span(x));
+                let visit_index = resolved
+                    .and_then(|span| find_node(ids, &nodes, |x| x.span == span))
+                    .or_else(|| find_node(ids, &nodes, |x| x.text == assertion.expr));
+                let normalized_index = if visit_index.is_none() {
+                    let expr = normalize_into(text, assertion.expr);
+                    find_node(ids, &nodes, |x| normalized(x.text).eq(expr.chars()))
+                } else {
+                    None
+                };
+                let visit_index = visit_index.or(normalized_index);
+                AssertionResult {
+                    expr: assertion.expr,
+                    expected_type: assertion.expected_type,
+                    actual: visit_index.and_then(|idx| nodes[ids[idx]].take()),
+                    visit_index,
+                    normalized: normalized_index.is_some(),
+                }
+            })
+            .collect();
+        if let Some(statement_source) = statement_source {
+            *resolved = statement_source.into_buffer();
+        }
+
+        result.statements.push(StatementComparison {
+            statement,
+            span,
+            assertions,
+            unexpected: ids.iter().filter_map(|&idx| nodes[idx].take()).collect(),
+        });
+    }
+
+    result
+}
+
+/// Position in `ids`, the nodes of a statement, of the first node not taken yet matching `pred`.
+fn find_node(
+    ids: &[usize],
+    nodes: &[Option<ActualType<'_>>],
+    pred: impl Fn(&ActualType<'_>) -> bool,
+) -> Option<usize> {
+    ids.iter().position(|&idx| nodes[idx].as_ref().is_some_and(&pred))
+}
+
+#[cfg(test)]
+mod tests {
+    use oxc_index::index_vec;
+
+    use super::*;
+    use crate::baseline::types_baseline::Assertion;
+
+    fn node<'a>(
+        source_text: &'a str,
+        text: &'a str,
+        nth: usize,
+        type_name: &'a str,
+    ) -> ActualType<'a> {
+        let start = source_text.match_indices(text).nth(nth).unwrap().0;
+        let span =
+            Span::new(u32::try_from(start).unwrap(), u32::try_from(start + text.len()).unwrap());
+        ActualType {
+            span,
+            kind: "Node".into(),
+            text,
+            type_name: type_name.into(),
+            placeholder: false,
+        }
+    }
+
+    #[test]
+    fn statement_scoped() {
+        let source_text = "const a = 5;\n\nconst b = a;\n";
+        let baseline = TypeBaselineFile {
+            statements: index_vec!["const a = 5;", "const b = a;"],
+            assertions: index_vec![
+                vec![
+                    Assertion { expr: "a", expected_type: "number" },
+                    Assertion { expr: "5", expected_type: "5" },
+                ],
+                vec![
+                    Assertion { expr: "b", expected_type: "number" },
+                    Assertion { expr: "a", expected_type: "number" },
+                ],
+            ],
+        };
+        let actual = vec![
+            node(source_text, "a", 0, "number"),
+            node(source_text, "5", 0, "any"),
+            node(source_text, "b", 0, "number"),
+            node(source_text, "a", 1, "number"),
+        ];
+
+        let result = compare_types(&baseline, source_text, actual, &mut Scratch::default());
+        assert_eq!(result.statements[0].span, Some(Span::new(0, 12)));
+        assert_eq!(result.statements[1].span, Some(Span::new(14, 26)));
+        assert_eq!(result.checked(), 4);
+        assert_eq!(result.matched(), 3);
+        assert_eq!(result.statements[0].assertions[1].actual.as_ref().unwrap().type_name, "any");
+        assert_eq!(result.statements[1].assertions[1].actual.as_ref().unwrap().span.start, 24);
+        assert!(result.unplaced.is_empty());
+    }
+
+    #[test]
+    fn missing_and_unexpected() {
+        let source_text = "foo(bar);\r\n";
+        let baseline = TypeBaselineFile {
+            statements: index_vec!["foo(bar);"],
+            assertions: index_vec![vec![
+                Assertion { expr: "foo(bar)", expected_type: "void" },
+                Assertion { expr: "foo", expected_type: "() => void" },
+            ]],
+        };
+        let actual =
+            vec![node(source_text, "foo", 0, "() => void"), node(source_text, "bar", 0, "any")];
+
+        let result = compare_types(&baseline, source_text, actual, &mut Scratch::default());
+        assert_eq!(result.statements[0].span, Some(Span::new(0, 10)));
+        assert!(result.statements[0].assertions[0].actual.is_none());
+        assert!(result.statements[0].assertions[1].is_match());
+        assert_eq!(result.statements[0].unexpected.len(), 1);
+        assert_eq!(result.statements[0].unexpected[0].text, "bar");
+        assert!(!result.is_match());
+    }
+
+    #[test]
+    fn visit_order() {
+        let source_text = "x.y = z;\n";
+        let baseline = TypeBaselineFile {
+            statements: index_vec!["x.y = z;"],
+            assertions: index_vec![vec![
+                Assertion { expr: "x.y = z", expected_type: "number" },
+                Assertion { expr: "x.y", expected_type: "number" },
+                Assertion { expr: "x", expected_type: "{ y: number; }" },
+                Assertion { expr: "y", expected_type: "number" },
+                Assertion { expr: "z", expected_type: "number" },
+            ]],
+        };
+        let actual = vec![
+            node(source_text, "x.y = z", 0, "number"),
+            node(source_text, "z", 0, "number"),
+            node(source_text, "x.y", 0, "number"),
+            node(source_text, "x", 0, "{ y: number; }"),
+        ];
+
+        let result = compare_types(&baseline, source_text, actual, &mut Scratch::default());
+        let statement = &result.statements[0];
+        assert_eq!(
+            statement.assertions.iter().map(|x| x.visit_index).collect::<Vec<_>>(),
+            vec![Some(0), Some(2), Some(3), None, Some(1)]
+        );
+        assert!(!statement.is_ordered());
+        assert_eq!(result.out_of_order(), 1);
+        assert_eq!(
+            statement.order_divergence(),
+            Some((vec!["x.y = z", "x.y", "x", "z"], vec!["x.y = z", "z", "x.y", "x"]))
+        );
+    }
+
+    #[test]
+    fn same_text_twice() {
+        let source_text = "const b = a + a;\n";
+        let baseline = TypeBaselineFile {
+            statements: index_vec!["const b = a + a;"],
+            assertions: index_vec![vec![
+                Assertion { expr: "b", expected_type: "number" },
+                Assertion { expr: "a + a", expected_type: "number" },
+                Assertion { expr: "a", expected_type: "1" },
+                Assertion { expr: "a", expected_type: "2" },
+            ]],
+        };
+        let actual = vec![
+            node(source_text, "b", 0, "number"),
+            node(source_text, "a + a", 0, "number"),
+            node(source_text, "a", 1, "2"),
+            node(source_text, "a", 0, "1"),
+        ];
+
+        let result = compare_types(&baseline, source_text, actual, &mut Scratch::default());
+        assert_eq!(result.matched(), 4);
+        assert_eq!(
+            result.statements[0].assertions.iter().map(|x| x.visit_index).collect::<Vec<_>>(),
+            vec![Some(0), Some(1), Some(3), Some(2)]
+        );
+    }
+
+    #[test]
+    fn normalized_text() {
+        let source_text = "g.prototype.m = function () {\r\n  this;\r\n};\r\nlet t = `a\r\nb`;\r\n";
+        let baseline = TypeBaselineFile {
+            statements: index_vec![
+                "g.prototype.m = function () {\r\n  this;\r\n};",
+                "let t = `a\r\nb`;"
+            ],
+            assertions: index_vec![
+                vec![
+                    Assertion {
+                        expr: "g.prototype.m = function () {  this;}",
+                        expected_type: "() => void",
+                    },
+                    Assertion { expr: "g.prototype.m", expected_type: "any" },
+                ],
+                vec![
+                    Assertion { expr: "t", expected_type: "string" },
+                    Assertion { expr: "`a b`", expected_type: "string" },
+                ]
+            ],
+        };
+        let actual = vec![
+            node(source_text, "g.prototype.m = function () {\r\n  this;\r\n}", 0, "() => void"),
+            node(source_text, "g.prototype.m", 0, "any"),
+            node(source_text, "t", 4, "string"),
+            node(source_text, "`a\r\nb`", 0, "string"),
+        ];
+
+        // Joined lines resolve to a span, the newline of the template literal does not
+        let result = compare_types(&baseline, source_text, actual, &mut Scratch::default());
+        assert_eq!(result.matched(), 4);
+        assert_eq!(result.normalized(), 1);
+        assert!(!result.statements[0].assertions[0].normalized);
+        assert!(result.statements[1].assertions[1].normalized);
+        assert!(result.is_match());
+
+        // Other whitespace is kept
+        assert_eq!(normalize_into(&mut String::new(), "`a\r\n  b` + c"), "`a   b` + c");
+    }
+
+    #[test]
+    fn multi_line_statement() {
+        let source_text = "class C {\n    public x;\n}\n\nconst a = 5;\n";
+        let baseline = TypeBaselineFile {
+            statements: index_vec!["class C {", "    public x;", "}\n\nconst a = 5;"],
+            assertions: index_vec![
+                vec![Assertion { expr: "C", expected_type: "C" }],
+                vec![Assertion { expr: "x", expected_type: "any" }],
+                vec![Assertion { expr: "a", expected_type: "number" }],
+            ],
+        };
+
+        let result = compare_types(&baseline, source_text, vec![], &mut Scratch::default());
+        assert_eq!(
+            result.statements.iter().map(|x| x.span).collect::<Vec<_>>(),
+            vec![Some(Span::new(0, 9)), Some(Span::new(10, 23)), Some(Span::new(24, 39))]
+        );
+    }
+}
@@ -0,0 +1,217 @@
+use std::path::{Path, PathBuf};
+
+use oxc::allocator::Allocator;
+use type_info::{LibStore, ProgramCache};
+
+use crate::{
+    Baseline, BaselineSource, OptionsError, RunOptions, TestOutcome, TestUnit, TestVariant,
+    VariantParseError,
+    compare::{Scratch, strip_base_url},
+    discover::{FileReadError, read_file, read_test_file},
+    runner::run_test,
+};
+
+/// Arguments of `test-runner compare`: a test case and its baselines given as files, for
+/// comparing them outside of a TypeScript checkout.
+#[derive(Debug, Default, PartialEq, Eq, Clone)]
+pub struct CompareFilesOptions {
+    pub case: PathBuf,
+    pub types: PathBuf,
+    pub errors: Option<PathBuf>,
+    /// Variant the baselines are for, named like `(strict=true)`. Required when the case has
+    /// several.
+    pub variant: Option<String>,
+}
+
+impl CompareFilesOptions {
+    /// Parses the arguments after `compare`.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` on unknown arguments, missing option values or a missing `--case` or
+    /// `--types`
+    pub fn parse(args: impl IntoIterator<Item = String>) -> Result<Self, OptionsError> {
+        let mut case = None;
+        let mut types = None;
+        let mut result = Self::default();
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--case" => {
+                    let value = args.next().ok_or(OptionsError::MissingValue("--case"))?;
+                    case = Some(PathBuf::from(value));
+                }
+                "--types" => {
+                    let value = args.next().ok_or(OptionsError::MissingValue("--types"))?;
+                    types = Some(PathBuf::from(value));
+                }
+                "--errors" => {
+                    let value = args.next().ok_or(OptionsError::MissingValue("--errors"))?;
+                    result.errors = Some(PathBuf::from(value));
+                }
+                "--variant" => {
+                    let value = args.next().ok_or(OptionsError::MissingValue("--variant"))?;
+                    result.variant = Some(value);
+                }
+                _ => return Err(OptionsError::UnknownArgument(arg)),
+            }
+        }
+        result.case = case.ok_or(OptionsError::MissingOption("--case"))?;
+        result.types = types.ok_or(OptionsError::MissingOption("--types"))?;
+        Ok(result)
+    }
+}
+
+#[derive(Debug)]
+pub enum CompareFilesError {
+    Read(PathBuf, FileReadError),
+    Variant(VariantParseError),
+    /// The case has several variants and `--variant` does not pick one; their names.
+    AmbiguousVariant(Vec<String>),
+    /// The types baseline has a section for a file the case does not have, like the baseline of
+    /// a case renamed since.
+    UnknownFile {
+        section: String,
+        files: Vec<String>,
+    },
+}
+
+impl std::fmt::Display for CompareFilesError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CompareFilesError::Read(path, err) => write!(f, "{}: {err}", path.display()),
+            CompareFilesError::Variant(err) => err.fmt(f),
+            CompareFilesError::AmbiguousVariant(names) => {
+                write!(f, "Pick one of the variants with --variant: {}", names.join(", "))
+            }
+            CompareFilesError::UnknownFile { section, files } => write!(
+                f,
+                "The types baseline has a section for '{section}', which is not a file of the \
+                 case: {}",
+                files.join(", ")
+            ),
+        }
+    }
+}
+
+impl std::error::Error for CompareFilesError {}
+
+impl From<VariantParseError> for CompareFilesError {
+    fn from(value: VariantParseError) -> Self {
+        Self::Variant(value)
+    }
+}
+
+/// Compares a variant of the test case with the baselines given in `options`.
+///
+/// Like a `--test` run, the outcome's log has the per-file checklist. Paths are reported
+/// relative to the directory of the case.
+///
+/// # Errors
+///
+/// Will return `Err` if a file cannot be read, the variant is unknown or ambiguous, or the types
+/// baseline has sections for files the case does not have
+pub fn compare_files(options: &CompareFilesOptions) -> Result<TestOutcome, CompareFilesError> {
+    let data = read_test_file(&options.case)
+        .map_err(|err| CompareFilesError::Read(options.case.clone(), err))?;
+    let unit = TestUnit::parse(&options.case, &data);
+    let variant = if let Some(name) = &options.variant {
+        TestVariant::parse_name(name, &unit.variations)?
+    } else {
+        let mut variants = unit.variations.iter();
+        if let (Some(variant), None) = (variants.next(), variants.next()) {
+            variant
+        } else {
+            let names = unit.variations.iter().map(|x| x.name).collect();
+            return Err(CompareFilesError::AmbiguousVariant(names));
+        }
+    };
+
+    let read = |path: &Path| {
+        read_file(path).map_err(|err| CompareFilesError::Read(path.to_path_buf(), err))
+    };
+    let types_data = read(&options.types)?;
+    let errors_data = options.errors.as_deref().map(read).transpose()?;
+    let errors_file = options.errors.clone().unwrap_or_default();
+
+    let run_options = RunOptions {
+        repo: options.case.parent().unwrap_or(Path::new("")).to_path_buf(),
+        test: Some(options.case.clone()),
+        ..RunOptions::default()
+    };
+    let errors_bytes = errors_data.as_ref().map(String::as_bytes);
+    let baseline =
+        Baseline::parse(&options.types, types_data.as_bytes(), &errors_file, errors_bytes, None);
+    let baseline = match baseline {
+        Ok(baseline) => baseline,
+        Err(err) => {
+            let name = options.case.file_name().map(PathBuf::from).unwrap_or_default();
+            let log =
+                format!("⚠  {}{}  baseline unparsable\n    {err}\n", name.display(), variant.name);
+            let source = BaselineSource::detect(
+                &options.types,
+                types_data.as_bytes(),
+                &errors_file,
+                errors_bytes,
+            );
+            let mut outcome = TestOutcome::unparsable(name, variant.name, source, err);
+            outcome.log = log;
+            return Ok(outcome);
+        }
+    };
+    // Baselines are looked up by the names of the modules, like `run_test` does
+    let base_url = unit.settings.base_url.as_deref();
+    let is_unit_file = |section: &str| {
+        unit.file_names
+            .iter()
+            .any(|&x| x == section || strip_base_url(x, base_url) == Some(section))
+    };
+    if let Some(&section) = baseline.types.names.iter().find(|&&x| !is_unit_file(x)) {
+        let files = unit.file_names.iter().map(ToString::to_string).collect();
+        return Err(CompareFilesError::UnknownFile { section: section.to_string(), files });
+    }
+
+    let alloc = Allocator::default();
+    let cache = ProgramCache::new(&alloc);
+    let libs = LibStore::new();
+    Ok(run_test(&unit, &variant, &baseline, &run_options, &cache, &libs, &mut Scratch::default()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> Result<CompareFilesOptions, OptionsError> {
+        CompareFilesOptions::parse(args.iter().map(ToString::to_string))
+    }
+
+    #[test]
+    fn options() {
+        let options = parse(&["--types", "a.types", "--case", "a.ts"]).unwrap();
+        assert_eq!(options.case, PathBuf::from("a.ts"));
+        assert_eq!(options.types, PathBuf::from("a.types"));
+        assert_eq!(options.errors, None);
+
+        let options = parse(&[
+            "--case",
+            "a.ts",
+            "--types",
+            "t",
+            "--errors",
+            "e",
+            "--variant",
+            "(strict=true)",
+        ])
+        .unwrap();
+        assert_eq!(options.errors, Some(PathBuf::from("e")));
+        assert_eq!(options.variant.as_deref(), Some("(strict=true)"));
+
+        assert_eq!(parse(&["--case", "a.ts"]), Err(OptionsError::MissingOption("--types")));
+        assert_eq!(parse(&["--types", "t"]), Err(OptionsError::MissingOption("--case")));
+        assert_eq!(parse(&["--case"]), Err(OptionsError::MissingValue("--case")));
+        assert_eq!(
+            parse(&["--case", "a.ts", "--types", "t", "/ts"]),
+            Err(OptionsError::UnknownArgument("/ts".to_string()))
+        );
+    }
+}
@@ -0,0 +1,170 @@
+use type_info::{Diagnostic, Severity, TsConfig};
+
+use crate::{TestUnit, TestVariant};
+
+/// Compiler options TypeScript 5.5 removed. Setting one is TS5102.
+pub const REMOVED_OPTIONS: &[&str] = &[
+    "charset",
+    "importsNotUsedAsValues",
+    "keyofStringsOnly",
+    "noImplicitUseStrict",
+    "noStrictGenericChecks",
+    "out",
+    "preserveValueImports",
+    "suppressExcessPropertyErrors",
+    "suppressImplicitAnyIndexErrors",
+];
+
+/// `module` values `moduleResolution: bundler` works with.
+const BUNDLER_MODULES: &[&str] = &["preserve", "es6", "es2015", "es2020", "es2022", "esnext"];
+
+/// Config errors TypeScript reports for the options of the variant, already merged with the
+/// unit's `tsconfig.json` (`config`, with its name), and for the removed options the unit sets.
+///
+/// Without a config the errors have no location. With one they are located at the option in
+/// the config, or at its `"compilerOptions"` when a test directive set the option, like `tsc`
+/// does.
+pub fn config_errors(
+    unit: &TestUnit<'_>,
+    variant: &TestVariant<'_>,
+    config: Option<(&str, &TsConfig)>,
+) -> Vec<Diagnostic> {
+    let source = config.and_then(|(name, _)| {
+        let id = unit.file_names.position(|&x| x == name)?;
+        Some((name, unit.file_contents[id]))
+    });
+    let error = |code, option: &str, message: String| {
+        let mut result =
+            Diagnostic { severity: Severity::Error, code, message, ..Diagnostic::default() };
+        if let Some((name, source_text)) = source {
+            locate(&mut result, name, source_text, option);
+        }
+        result
+    };
+    let is_true = |value: Option<&str>| value.is_some_and(|x| x.eq_ignore_ascii_case("true"));
+
+    let mut result = Vec::new();
+    let config_options = config.iter().flat_map(|(_, config)| &config.compiler_options);
+    let removed = config_options
+        .filter(|(_, value)| !value.is_empty() && value != "false")
+        .filter_map(|(name, _)| REMOVED_OPTIONS.iter().find(|x| x.eq_ignore_ascii_case(name)))
+        .chain(
+            unit.settings
+                .removed_options
+                .iter()
+                .filter_map(|name| REMOVED_OPTIONS.iter().find(|x| x.eq_ignore_ascii_case(name))),
+        );
+    for option in removed {
+        let message = format!(
+            "Option '{option}' has been removed. Please remove it from your configuration."
+        );
+        result.push(error(5102, option, message));
+    }
+
+    if variant.module_resolution.is_some_and(|x| x.eq_ignore_ascii_case("bundler")) {
+        // Without `module`, `es2015` and later targets default to `es2015`
+        let module = variant.module.or(match variant.target {
+            Some(x) if !x.eq_ignore_ascii_case("es3") && !x.eq_ignore_ascii_case("es5") => {
+                Some("es2015")
+            }
+            _ => None,
+        });
+        if !module.is_some_and(|x| BUNDLER_MODULES.iter().any(|y| x.eq_ignore_ascii_case(y))) {
+            let message = "Option 'bundler' can only be used when 'module' is set to 'preserve' \
+                           or to 'es2015' or later.";
+            result.push(error(5095, "moduleResolution", message.to_string()));
+        }
+    }
+
+    if is_true(variant.allow_importing_ts_extensions)
+        && !is_true(variant.no_emit)
+        && !is_true(config.and_then(|(_, x)| x.compiler_option("emitDeclarationOnly")))
+    {
+        let message = "Option 'allowImportingTsExtensions' can only be used when either 'noEmit' \
+                       or 'emitDeclarationOnly' is set.";
+        result.push(error(5096, "allowImportingTsExtensions", message.to_string()));
+    }
+    result
+}
+
+/// Locates `diagnostic` at the key of `option` in the config, or at `"compilerOptions"`.
+fn locate(diagnostic: &mut Diagnostic, name: &str, source_text: &str, option: &str) {
+    let find = |key: &str| {
+        let key = format!("\"{key}\"");
+        let start = source_text.to_ascii_lowercase().find(&key.to_ascii_lowercase())?;
+        Some((start, key.len()))
+    };
+    let Some((start, len)) = find(option).or_else(|| find("compilerOptions")) else {
+        return;
+    };
+    let line_start = source_text[..start].rfind('\n').map_or(0, |x| x + 1);
+    let utf16_len = |text: &str| u32::try_from(text.encode_utf16().count()).unwrap_or(u32::MAX);
+    diagnostic.file = name.to_string();
+    diagnostic.line =
+        u32::try_from(source_text[..start].matches('\n').count() + 1).unwrap_or(u32::MAX);
+    diagnostic.column = utf16_len(&source_text[line_start..start]) + 1;
+    diagnostic.length = Some(utf16_len(&source_text[start..start + len]));
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use super::*;
+
+    fn errors(data: &[u8]) -> Vec<(u32, String, u32, u32)> {
+        let unit = TestUnit::parse(Path::new("a.ts"), data);
+        let config = crate::runner::unit_config(&unit).map(|(name, x)| (name, x.unwrap()));
+        let config = config.as_ref().map(|(name, config)| (*name, config));
+        let variant = unit.variations.iter().next().unwrap();
+        let variant = match config {
+            Some((_, config)) => variant.with_compiler_options(&config.compiler_options),
+            None => variant,
+        };
+        config_errors(&unit, &variant, config)
+            .into_iter()
+            .map(|x| (x.code, x.file, x.line, x.column))
+            .collect()
+    }
+
+    #[test]
+    fn removed_options() {
+        let data = b"// @noImplicitUseStrict: true\n// @out: a.js\nlet a;\n";
+        let expected = vec![(5102, String::new(), 0, 0), (5102, String::new(), 0, 0)];
+        assert_eq!(errors(data), expected);
+        assert!(errors(b"// @keyofStringsOnly: false\nlet a;\n").is_empty());
+
+        let data = b"// @filename: /tsconfig.json\n\
+                     {\n  \"compilerOptions\": {\n    \"charset\": \"utf8\"\n  }\n}\n\
+                     // @filename: /a.ts\nlet a;\n";
+        assert_eq!(errors(data), vec![(5102, "/tsconfig.json".to_string(), 3, 5)]);
+    }
+
+    #[test]
+    fn bundler_without_module() {
+        let error = (5095, String::new(), 0, 0);
+        assert_eq!(errors(b"// @moduleResolution: bundler\nlet a;\n"), vec![error.clone()]);
+        let data = b"// @moduleResolution: bundler\n// @module: commonjs\nlet a;\n";
+        assert_eq!(errors(data), vec![error]);
+        assert!(errors(b"// @moduleResolution: bundler\n// @module: esnext\nlet a;\n").is_empty());
+        assert!(errors(b"// @moduleResolution: bundler\n// @target: es2020\nlet a;\n").is_empty());
+
+        // At `"compilerOptions"` when the directive sets the option
+        let data = b"// @moduleResolution: bundler\n// @filename: tsconfig.json\n\
+                     {\n    \"compilerOptions\": {}\n}\n// @filename: a.ts\nlet a;\n";
+        assert_eq!(errors(data), vec![(5095, "tsconfig.json".to_string(), 2, 5)]);
+    }
+
+    #[test]
+    fn ts_extensions_without_no_emit() {
+        let error = (5096, String::new(), 0, 0);
+        let data = b"// @allowImportingTsExtensions: true\nlet a;\n";
+        assert_eq!(errors(data), vec![error]);
+        let data = b"// @allowImportingTsExtensions: true\n// @noEmit: true\nlet a;\n";
+        assert!(errors(data).is_empty());
+        let data = b"// @allowImportingTsExtensions: true\n// @filename: /tsconfig.json\n\
+                     { \"compilerOptions\": { \"emitDeclarationOnly\": true } }\n\
+                     // @filename: /a.ts\nlet a;\n";
+        assert!(errors(data).is_empty());
+    }
+}
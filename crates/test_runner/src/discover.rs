@@ -6,7 +6,16 @@ use std::{
     sync::{Arc, Condvar, Mutex},
 };
 
-use crate::{Baseline, TestUnit, TestVariant};
+use type_info::{LibStore, ProgramCache};
+
+use crate::{
+    Baseline, RunOptions, RunSummary, Sinks, TestOutcome, TestUnit, TestVariant,
+    compare::Scratch,
+    human::HumanCount,
+    pipeline::{PipelineConfig, run_pipeline},
+    runner::relative_path,
+    test_list::TestList,
+};
 
 const THREADS: u8 = 24;
 
@@ -80,7 +89,7 @@ fn quick_walk(paths: Vec<PathBuf>) -> impl Iterator<Item = PathBuf> {
 }
 
 #[derive(Debug)]
-enum FileReadError {
+pub enum FileReadError {
     IO(std::io::Error),
     FromUtf8Error(std::str::Utf8Error),
     FromUtf16Error(std::string::FromUtf16Error),
@@ -116,90 +125,315 @@ impl From<std::string::FromUtf16Error> for FileReadError {
     }
 }
 
-fn read_file(path: &Path) -> Result<String, FileReadError> {
-    let data = read(path)?;
+pub fn read_file(path: &Path) -> Result<String, FileReadError> {
+    Ok(String::from_utf8(read_test_file(path)?).map_err(|err| err.utf8_error())?)
+}
+
+/// Reads a test case without its byte order mark, converting UTF-16 to UTF-8.
+///
+/// Unlike [`read_file`], UTF-8 is not validated: [`TestUnit::parse`] checks every file of the
+/// unit on its own, so one corrupted file does not take the others down with it.
+pub fn read_test_file(path: &Path) -> Result<Vec<u8>, FileReadError> {
+    let mut data = read(path)?;
     let result = match data.get(0..3) {
         // UTF8
-        Some([0xef, 0xbb, 0xbf]) => str::from_utf8(&data[3..])?.to_string(),
+        Some([0xef, 0xbb, 0xbf]) => {
+            data.drain(..3);
+            data
+        }
         // UTF16 BE
         Some([0xfe, 0xff, _]) => {
             let data: Vec<_> =
                 data[2..].chunks(2).map(|x| u16::from_be_bytes([x[0], x[1]])).collect();
-            String::from_utf16(&data)?
+            String::from_utf16(&data)?.into_bytes()
         }
         // UTF16 LE
         Some([0xff, 0xfe, _]) => {
             let data: Vec<_> =
                 data[2..].chunks(2).map(|x| u16::from_le_bytes([x[0], x[1]])).collect();
-            String::from_utf16(&data)?
+            String::from_utf16(&data)?.into_bytes()
         }
         // Anything else
-        _ => str::from_utf8(&data)?.to_string(),
+        _ => data,
     };
 
     Ok(result)
 }
 
+pub fn discover_files(options: &RunOptions) -> Vec<PathBuf> {
+    let test_paths: Vec<_> = options
+        .case_roots()
+        .into_iter()
+        .filter(|x| {
+            let exists = x.is_dir();
+            if !exists {
+                eprintln!("⚠  Cases directory not found: {}", x.display());
+            }
+            exists
+        })
+        .collect();
+    let mut files: Vec<_> = quick_walk(test_paths)
+        .filter(|test_file| {
+            // Ignore these tests
+            !(test_file.ends_with("compiler/TransportStream.ts")
+                || test_file.ends_with("compiler/checkJsFiles6.ts")
+                || test_file.ends_with("compiler/jsFileCompilationWithoutJsExtensions.ts"))
+        })
+        .collect();
+    files.sort();
+    files.dedup();
+    files
+}
+
+/// Test cases to run: the `--tests-json` list in its order, or every discovered one. Listed
+/// cases missing from the repo are returned separately.
+///
 /// # Panics
-pub fn discover<F: Fn(&TestUnit<'_>, &TestVariant<'_>, &Baseline<'_>, &Path)>(repo: &Path, run: F) {
-    let test_paths = vec![repo.join("tests/cases/compiler"), repo.join("tests/cases/conformance")];
-    let discovered_files = {
-        let mut files: Vec<_> = quick_walk(test_paths).collect();
-        files.sort();
-        files
+///
+/// If the list cannot be read, which [`RepoInfo::validate`](crate::RepoInfo::validate) reports
+/// before anything runs
+pub fn test_files(options: &RunOptions) -> (Vec<PathBuf>, Vec<PathBuf>) {
+    let Some(path) = &options.tests_json else {
+        return (discover_files(options), Vec::new());
     };
-    for test_file in discovered_files {
-        // Ignore these 2 tests
-        if test_file.ends_with("compiler/corrupted.ts")
-            || test_file.ends_with("compiler/TransportStream.ts")
-            || test_file.ends_with("compiler/checkJsFiles6.ts")
-            || test_file.ends_with("compiler/jsFileCompilationWithoutJsExtensions.ts")
-        {
-            continue;
+    match TestList::read(path, &options.repo) {
+        Ok(list) => (list.files, list.missing),
+        Err(err) => {
+            panic!("Failed to read test list:\n  path: {}\n  error: {err}", path.display())
+        }
+    }
+}
+
+/// # Panics
+pub fn discover<
+    F: Fn(
+            &TestUnit<'_>,
+            &TestVariant<'_>,
+            &Baseline<'_>,
+            &RunOptions,
+            &ProgramCache<'_>,
+            &LibStore,
+            &mut Scratch,
+        ) -> TestOutcome
+        + Sync,
+>(
+    options: &RunOptions,
+    run: F,
+) -> RunSummary {
+    let mut summary = RunSummary::default();
+    let (files, missing) = test_files(options);
+    summary.missing_tests = missing;
+    let sinks = Sinks::open(options);
+    run_pipeline(options, &files, options.pipeline_config(), &run, &sinks, &mut summary);
+    summary.finish();
+    sinks.finish(&summary);
+    summary
+}
+
+/// Prints every test case (or only `options.test`) with the number of variants it expands to.
+///
+/// # Panics
+pub fn list(options: &RunOptions) {
+    let repo = options.repo.as_path();
+    let files = if let Some(test) = &options.test {
+        vec![repo.join(test)]
+    } else {
+        let (files, missing) = test_files(options);
+        for path in missing {
+            println!("{}  not found", path.display());
         }
+        files
+    };
 
-        let Ok(data) = read_file(&test_file) else {
-            panic!("Failed to read test file: {}", test_file.strip_prefix(repo).unwrap().display());
+    let mut total = 0;
+    for test_file in &files {
+        let Ok(data) = read_test_file(test_file) else {
+            panic!("Failed to read test file: {}", test_file.display());
         };
-        let unit = TestUnit::parse(&test_file, data.as_bytes());
+        let unit = TestUnit::parse_with_limit(test_file, &data, options.max_variation_values());
+        let path = relative_path(test_file, repo).display();
         if unit.settings.no_types_and_symbols {
+            println!("{path}  skipped (noTypesAndSymbols)");
+            continue;
+        }
+        if unit.settings.multi_pass {
+            println!("{path}  skipped (multi-pass)");
             continue;
         }
 
-        let name = test_file.file_stem().expect("path to be a file");
-        for variant in unit.variations.iter() {
-            let variant_name = &variant.name;
-            let types_file = get_baseline_path(repo, name, variant_name, "types");
-            let Ok(types_data) = read_file(&types_file) else {
-                panic!(
-                    "Failed to read types baseline file:\n  case: {}\n  baseline: {}\n  variant: {:?}",
-                    test_file.strip_prefix(repo).unwrap().display(),
-                    types_file.strip_prefix(repo).unwrap().display(),
-                    variant
-                );
-            };
-
-            let errors_file = get_baseline_path(repo, name, variant_name, "errors.txt");
-            let errors_data = read_file(&errors_file).ok();
-
-            let baseline = Baseline::parse(
-                types_file.strip_prefix(repo).unwrap(),
-                types_data.as_bytes(),
-                errors_file.strip_prefix(repo).unwrap(),
-                errors_data.as_ref().map(std::string::String::as_bytes),
-            );
-
-            run(&unit, &variant, &baseline, repo);
+        let variants: Vec<_> =
+            unit.variations.iter().filter(|x| options.matches_variant(x)).collect();
+        total += variants.len();
+        println!("{path}  {}", variants.len());
+        if options.verbose > 0 {
+            for variant in variants {
+                println!("  {}", variant.name);
+            }
         }
     }
+
+    println!("{} test cases, {} variants", HumanCount(files.len()), HumanCount(total));
 }
 
-fn get_baseline_path(repo: &Path, name: &OsStr, variant: &str, kind: &str) -> PathBuf {
+/// Why the `--test` case cannot be run.
+#[derive(Debug)]
+pub enum SingleTestError {
+    NotFound(PathBuf),
+    Read(PathBuf, FileReadError),
+    /// An `--only-file` the test case does not have, and the files it has.
+    UnknownOnlyFile(String, Vec<String>),
+}
+
+impl std::fmt::Display for SingleTestError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SingleTestError::NotFound(path) => write!(f, "Test file not found: {}", path.display()),
+            SingleTestError::Read(path, err) => {
+                write!(f, "Failed to read test file {}: {err}", path.display())
+            }
+            SingleTestError::UnknownOnlyFile(name, files) => {
+                write!(f, "Unknown --only-file: {name}\n  files: {}", files.join(", "))
+            }
+        }
+    }
+}
+
+impl std::error::Error for SingleTestError {}
+
+/// Runs all variants of a single test case, one at a time.
+///
+/// # Errors
+///
+/// Will return `Err` if the test case does not exist or lacks a file of `--only-file`, before
+/// anything is run
+///
+/// # Panics
+pub fn run_single<
+    F: Fn(
+            &TestUnit<'_>,
+            &TestVariant<'_>,
+            &Baseline<'_>,
+            &RunOptions,
+            &ProgramCache<'_>,
+            &LibStore,
+            &mut Scratch,
+        ) -> TestOutcome
+        + Sync,
+>(
+    options: &RunOptions,
+    test_file: &Path,
+    run: F,
+) -> Result<RunSummary, SingleTestError> {
+    let test_file = options.repo.join(test_file);
+    if !test_file.is_file() {
+        return Err(SingleTestError::NotFound(test_file));
+    }
+    if !options.only_files.is_empty() {
+        let data = read_test_file(&test_file)
+            .map_err(|err| SingleTestError::Read(test_file.clone(), err))?;
+        let unit = TestUnit::parse(&test_file, &data);
+        if let Some(name) =
+            options.only_files.iter().find(|&x| !unit.file_names.iter().any(|y| y == x))
+        {
+            let files = unit.file_names.iter().map(ToString::to_string).collect();
+            return Err(SingleTestError::UnknownOnlyFile(name.clone(), files));
+        }
+    }
+
+    let mut summary = RunSummary::default();
+    let config = PipelineConfig { readers: 1, workers: 1, bound: 1, ..options.pipeline_config() };
+    let sinks = Sinks::open(options);
+    run_pipeline(options, &[test_file], config, &run, &sinks, &mut summary);
+    summary.finish();
+    sinks.finish(&summary);
+    Ok(summary)
+}
+
+/// Baseline files a variant is compared against, named after the test case and the variant
+/// like TypeScript names them.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct BaselineCandidates {
+    pub types: PathBuf,
+    pub errors: PathBuf,
+    pub symbols: PathBuf,
+}
+
+impl BaselineCandidates {
+    /// Every candidate, in the order they are consulted.
+    pub fn all(&self) -> [&Path; 3] {
+        [&self.types, &self.errors, &self.symbols]
+    }
+}
+
+/// Baseline files in `baseline_root` the variant `variant` of the test case `name` (its file
+/// stem) would be compared against, whether they exist or not.
+///
+/// The single place the naming rules live: the runner, the auditor and `--compare-repo` all look
+/// baselines up through it.
+pub fn baseline_candidates(
+    baseline_root: &Path,
+    name: &OsStr,
+    variant: &str,
+) -> BaselineCandidates {
+    BaselineCandidates {
+        types: get_baseline_path(baseline_root, name, variant, "types"),
+        errors: get_baseline_path(baseline_root, name, variant, "errors.txt"),
+        symbols: get_baseline_path(baseline_root, name, variant, "symbols"),
+    }
+}
+
+/// Whether TypeScript only wrote an emit baseline (`.js`) for the variant: the test is about
+/// emit output, which a type-checking runner has nothing to compare against.
+pub fn is_emit_only(baseline_root: &Path, name: &OsStr, variant: &str) -> bool {
+    let candidates = baseline_candidates(baseline_root, name, variant);
+    !candidates.types.is_file()
+        && !candidates.errors.is_file()
+        && get_baseline_path(baseline_root, name, variant, "js").is_file()
+}
+
+/// Start of the pointer file git-lfs leaves in place of a file whose content was not fetched.
+const LFS_POINTER: &str = "version https://git-lfs.github.com/spec/";
+
+/// Whether a baseline is a git-lfs pointer rather than the baseline itself, because the repo
+/// was checked out without `git lfs pull`.
+pub fn is_lfs_pointer(data: &str) -> bool {
+    data.starts_with(LFS_POINTER)
+}
+
+pub fn get_baseline_path(baseline_root: &Path, name: &OsStr, variant: &str, kind: &str) -> PathBuf {
     // let filename = format!("{}{}.{}", name, variant, kind);
     let mut filename = OsString::with_capacity(name.len() + variant.len() + kind.len() + 1);
     filename.push(name);
     filename.push(variant);
     filename.push(".");
     filename.push(kind);
-    repo.join("tests/baselines/reference").join(filename)
+    baseline_root.join(filename)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::SELF_TEST_CORPUS;
+
+    #[test]
+    fn candidates() {
+        let root = Path::new(SELF_TEST_CORPUS).join("tests/baselines/reference");
+        let name = OsStr::new("strictVariants");
+        let candidates = baseline_candidates(&root, name, "(strict=true)");
+        assert_eq!(candidates.types, root.join("strictVariants(strict=true).types"));
+        assert_eq!(candidates.errors, root.join("strictVariants(strict=true).errors.txt"));
+        assert_eq!(candidates.symbols, root.join("strictVariants(strict=true).symbols"));
+        assert!(candidates.types.is_file() && candidates.errors.is_file());
+
+        let candidates = baseline_candidates(&root, OsStr::new("anyVariables"), "");
+        assert_eq!(
+            candidates.all(),
+            [
+                root.join("anyVariables.types").as_path(),
+                &root.join("anyVariables.errors.txt"),
+                &root.join("anyVariables.symbols")
+            ]
+        );
+        assert!(candidates.types.is_file() && !candidates.errors.is_file());
+    }
 }
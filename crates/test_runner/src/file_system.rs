@@ -1,20 +1,147 @@
-use std::{io, path::Path};
+use std::{
+    io,
+    path::{Path, PathBuf},
+};
 
 use oxc_resolver::FileSystem;
+use rustc_hash::FxHashMap;
 
 use crate::TestUnit;
 
+/// Serves the files of a test unit, plus extra virtual files layered on top of them.
 pub struct TestFileSystem<'a> {
     pub unit: &'a TestUnit<'a>,
+    /// Files the unit did not declare, consulted before the unit's own files.
+    overlays: FxHashMap<PathBuf, String>,
+    symlinks: FxHashMap<PathBuf, PathBuf>,
+    /// Directories on disk by the path they are served at, see
+    /// [`TestFileSystemBuilder::with_dir`].
+    dirs: Vec<(PathBuf, PathBuf)>,
+    case_insensitive: bool,
+}
+
+impl<'a> TestFileSystem<'a> {
+    /// Serves the unit's files as they are.
+    pub fn new(unit: &'a TestUnit<'a>) -> Self {
+        Self::builder(unit).build()
+    }
+
+    pub fn builder(unit: &'a TestUnit<'a>) -> TestFileSystemBuilder<'a> {
+        TestFileSystemBuilder {
+            unit,
+            files: Vec::new(),
+            symlinks: Vec::new(),
+            dirs: Vec::new(),
+            case_insensitive: false,
+        }
+    }
+
+    fn key(&self, path: &Path) -> PathBuf {
+        key(path, self.case_insensitive)
+    }
+
+    fn lookup(&self, path: &Path) -> Option<&str> {
+        let mut path = self.key(path);
+        if let Some(target) = self.symlinks.get(&path) {
+            path = target.clone();
+        }
+
+        if let Some(contents) = self.overlays.get(&path) {
+            return Some(contents);
+        }
+
+        let file_id = self.unit.file_names.position(|x| self.key(Path::new(x)) == path)?;
+        Some(self.unit.file_contents[file_id])
+    }
+
+    /// Reads the file at `path` from the directory on disk served there.
+    fn read_dir_file(&self, path: &Path) -> Option<io::Result<String>> {
+        self.dirs.iter().find_map(|(at, dir)| {
+            let name = path.strip_prefix(at).ok()?;
+            // Wrapping the UTF-8 error keeps where the file stopped being valid
+            let bytes = std::fs::read(dir.join(name));
+            Some(bytes.and_then(|x| {
+                String::from_utf8(x).map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))
+            }))
+        })
+    }
+}
+
+fn key(path: &Path, case_insensitive: bool) -> PathBuf {
+    if case_insensitive {
+        PathBuf::from(path.to_string_lossy().to_lowercase())
+    } else {
+        path.to_path_buf()
+    }
+}
+
+/// Collects virtual files and symlinks to layer over a test unit. See [`TestFileSystem::builder`].
+pub struct TestFileSystemBuilder<'a> {
+    unit: &'a TestUnit<'a>,
+    files: Vec<(PathBuf, String)>,
+    symlinks: Vec<(PathBuf, PathBuf)>,
+    dirs: Vec<(PathBuf, PathBuf)>,
+    case_insensitive: bool,
+}
+
+impl<'a> TestFileSystemBuilder<'a> {
+    /// Adds a file, shadowing a unit file at the same path.
+    #[must_use]
+    pub fn with_file(mut self, path: impl Into<PathBuf>, contents: impl Into<String>) -> Self {
+        self.files.push((path.into(), contents.into()));
+        self
+    }
+
+    /// Makes `from` resolve to `to`, which may be a unit file or an added one.
+    #[must_use]
+    pub fn with_symlink(mut self, from: impl Into<PathBuf>, to: impl Into<PathBuf>) -> Self {
+        self.symlinks.push((from.into(), to.into()));
+        self
+    }
+
+    /// Serves the files of `dir` on disk under `at`, read when asked for. Unit and added files
+    /// shadow them.
+    #[must_use]
+    pub fn with_dir(mut self, at: impl Into<PathBuf>, dir: impl Into<PathBuf>) -> Self {
+        self.dirs.push((at.into(), dir.into()));
+        self
+    }
+
+    /// Matches paths regardless of case, like `@useCaseSensitiveFileNames: false`.
+    #[must_use]
+    pub const fn case_insensitive(mut self, case_insensitive: bool) -> Self {
+        self.case_insensitive = case_insensitive;
+        self
+    }
+
+    pub fn build(self) -> TestFileSystem<'a> {
+        let case_insensitive = self.case_insensitive;
+        TestFileSystem {
+            unit: self.unit,
+            overlays: self
+                .files
+                .into_iter()
+                .map(|(path, contents)| (key(&path, case_insensitive), contents))
+                .collect(),
+            symlinks: self
+                .symlinks
+                .into_iter()
+                .map(|(from, to)| (key(&from, case_insensitive), key(&to, case_insensitive)))
+                .collect(),
+            dirs: self.dirs,
+            case_insensitive,
+        }
+    }
 }
 
 impl FileSystem for &TestFileSystem<'_> {
     fn read_to_string(&self, path: &std::path::Path) -> std::io::Result<String> {
-        let Some(file_id) = self.unit.file_names.position(|x| Path::new(x) == path) else {
-            return Err(io::Error::from(io::ErrorKind::NotFound));
-        };
-
-        Ok(self.unit.file_contents[file_id].to_string())
+        match self.lookup(path) {
+            Some(contents) => Ok(contents.to_string()),
+            None => self
+                .read_dir_file(path)
+                .unwrap_or_else(|| Err(io::Error::from(io::ErrorKind::NotFound))),
+        }
     }
 
     #[expect(clippy::todo)]
@@ -35,3 +162,79 @@ impl FileSystem for &TestFileSystem<'_> {
         todo!()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::string::FromUtf8Error;
+
+    use super::*;
+
+    const UNIT: &[u8] = b"// @filename: a.ts
+export const a = 1;
+// @filename: B.ts
+export const b = 2;
+";
+
+    fn read(fs: &TestFileSystem<'_>, path: &str) -> Option<String> {
+        fs.read_to_string(Path::new(path)).ok()
+    }
+
+    #[test]
+    fn overlay_shadows_unit_file() {
+        let unit = TestUnit::parse(Path::new("tests/cases/compiler/unit.ts"), UNIT);
+        let fs = TestFileSystem::new(&unit);
+        assert_eq!(read(&fs, "a.ts").as_deref(), Some("export const a = 1;\n"));
+        assert_eq!(read(&fs, "lib.d.ts"), None);
+
+        let fs = TestFileSystem::builder(&unit)
+            .with_file("a.ts", "export const a = 'shadowed';\n")
+            .with_file("lib.d.ts", "interface Array<T> {}\n")
+            .build();
+        assert_eq!(read(&fs, "a.ts").as_deref(), Some("export const a = 'shadowed';\n"));
+        assert_eq!(read(&fs, "lib.d.ts").as_deref(), Some("interface Array<T> {}\n"));
+        assert_eq!(read(&fs, "B.ts").as_deref(), Some("export const b = 2;\n"));
+    }
+
+    #[test]
+    fn symlinks_and_case() {
+        let unit = TestUnit::parse(Path::new("tests/cases/compiler/unit.ts"), UNIT);
+        let fs = TestFileSystem::builder(&unit)
+            .with_file("package.json", "{}")
+            .with_symlink("node_modules/b/index.ts", "B.ts")
+            .with_symlink("node_modules/b/package.json", "package.json")
+            .build();
+        assert_eq!(read(&fs, "node_modules/b/index.ts").as_deref(), Some("export const b = 2;\n"));
+        assert_eq!(read(&fs, "node_modules/b/package.json").as_deref(), Some("{}"));
+        assert_eq!(read(&fs, "b.ts"), None);
+
+        let fs = TestFileSystem::builder(&unit)
+            .with_file("Package.json", "{}")
+            .case_insensitive(true)
+            .build();
+        assert_eq!(read(&fs, "b.ts").as_deref(), Some("export const b = 2;\n"));
+        assert_eq!(read(&fs, "PACKAGE.JSON").as_deref(), Some("{}"));
+    }
+
+    #[test]
+    fn dirs() {
+        let dir = std::env::temp_dir().join(format!("type-runner-fs-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("lib.d.ts"), "interface Array<T> {}\n").unwrap();
+        std::fs::write(dir.join("latin1.d.ts"), b"declare var caf\xe9: number;\n").unwrap();
+
+        let unit = TestUnit::parse(Path::new("tests/cases/compiler/unit.ts"), UNIT);
+        let fs = TestFileSystem::builder(&unit).with_dir("/.lib", &dir).build();
+        let lib = read(&fs, "/.lib/lib.d.ts");
+        let missing = (&fs).read_to_string(Path::new("/.lib/lib.es5.d.ts"));
+        let latin1 = (&fs).read_to_string(Path::new("/.lib/latin1.d.ts"));
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(lib.as_deref(), Some("interface Array<T> {}\n"));
+        assert_eq!(missing.unwrap_err().kind(), io::ErrorKind::NotFound);
+        let latin1 = latin1.unwrap_err();
+        let utf8_error = latin1.get_ref().and_then(|x| x.downcast_ref::<FromUtf8Error>());
+        assert_eq!(utf8_error.map(|x| x.utf8_error().valid_up_to()), Some(15));
+        assert_eq!(read(&fs, "lib.d.ts"), None);
+        assert_eq!(read(&fs, "a.ts").as_deref(), Some("export const a = 1;\n"));
+    }
+}
@@ -0,0 +1,122 @@
+use std::{fmt, time::Duration};
+
+/// A duration as a person reads it: `512µs`, `12.3ms`, `4.5s`, `1m 23.4s`, `2h 0m 5.0s`.
+///
+/// Padding and alignment of the format spec apply to the whole text.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct HumanDuration(pub Duration);
+
+impl fmt::Display for HumanDuration {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let micros = self.0.as_micros();
+        if micros < 1000 {
+            return f.pad(&format!("{micros}µs"));
+        }
+        // Rounded before picking the unit, so 999.96ms reads 1.0s rather than 1000.0ms
+        let tenth_millis = (micros + 50) / 100;
+        if tenth_millis < 10_000 {
+            return f.pad(&format!("{}.{}ms", tenth_millis / 10, tenth_millis % 10));
+        }
+
+        let tenth_secs = (micros + 50_000) / 100_000;
+        let (minutes, tenth_secs) = (tenth_secs / 600, tenth_secs % 600);
+        let secs = format!("{}.{}s", tenth_secs / 10, tenth_secs % 10);
+        match (minutes / 60, minutes % 60) {
+            (0, 0) => f.pad(&secs),
+            (0, minutes) => f.pad(&format!("{minutes}m {secs}")),
+            (hours, minutes) => f.pad(&format!("{hours}h {minutes}m {secs}")),
+        }
+    }
+}
+
+/// A size in bytes with a binary unit: `512 B`, `12.3 KiB`, `4.0 MiB`.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct HumanSize(pub usize);
+
+impl fmt::Display for HumanSize {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        const UNITS: &[&str] = &["KiB", "MiB", "GiB", "TiB"];
+
+        let bytes = self.0 as u128;
+        if bytes < 1024 {
+            return f.pad(&format!("{bytes} B"));
+        }
+        let mut unit = 1024;
+        for (idx, name) in UNITS.iter().enumerate() {
+            let tenths = (bytes * 10 + unit / 2) / unit;
+            if tenths < 10 * 1024 || idx == UNITS.len() - 1 {
+                return f.pad(&format!("{}.{} {name}", tenths / 10, tenths % 10));
+            }
+            unit *= 1024;
+        }
+        unreachable!("the last unit to take any size")
+    }
+}
+
+/// A count with thousands separators: `12,345`. Always `,`, whatever the locale.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct HumanCount(pub usize);
+
+impl fmt::Display for HumanCount {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let digits = self.0.to_string();
+        let mut out = String::with_capacity(digits.len() + digits.len() / 3);
+        for (idx, digit) in digits.chars().enumerate() {
+            if idx > 0 && (digits.len() - idx) % 3 == 0 {
+                out.push(',');
+            }
+            out.push(digit);
+        }
+        f.pad(&out)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn durations() {
+        let cases = [
+            (Duration::from_nanos(900), "0µs"),
+            (Duration::from_micros(512), "512µs"),
+            (Duration::from_micros(12_345), "12.3ms"),
+            (Duration::from_micros(999_960), "1.0s"),
+            (Duration::from_millis(4_540), "4.5s"),
+            (Duration::from_millis(59_960), "1m 0.0s"),
+            (Duration::from_millis(83_400), "1m 23.4s"),
+            (Duration::from_secs(7205), "2h 0m 5.0s"),
+        ];
+        for (duration, expected) in cases {
+            assert_eq!(HumanDuration(duration).to_string(), expected);
+        }
+        assert_eq!(format!("{:>8}", HumanDuration(Duration::from_millis(5))), "   5.0ms");
+    }
+
+    #[test]
+    fn sizes() {
+        let cases = [
+            (0, "0 B"),
+            (1023, "1023 B"),
+            (1024, "1.0 KiB"),
+            (12_595, "12.3 KiB"),
+            (1024 * 1024 - 1, "1.0 MiB"),
+            (12_897_485, "12.3 MiB"),
+            (5 << 40, "5.0 TiB"),
+            (5 << 50, "5120.0 TiB"),
+        ];
+        for (size, expected) in cases {
+            assert_eq!(HumanSize(size).to_string(), expected);
+        }
+    }
+
+    #[test]
+    fn counts() {
+        let cases =
+            [(0, "0"), (999, "999"), (1000, "1,000"), (12_345, "12,345"), (1_234_567, "1,234,567")];
+        for (count, expected) in cases {
+            assert_eq!(HumanCount(count).to_string(), expected);
+        }
+        assert_eq!(format!("{:>7}", HumanCount(1234)), "  1,234");
+    }
+}
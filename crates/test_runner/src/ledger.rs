@@ -0,0 +1,184 @@
+use std::{
+    fmt,
+    path::{Path, PathBuf},
+};
+
+use rustc_hash::FxHashMap;
+
+use crate::human::HumanCount;
+
+/// Why a variant produced by the variations of a unit was not run.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum SkipReason {
+    /// `--variant-filter`.
+    Filtered,
+    /// TypeScript only has emit baselines for the variant.
+    EmitOnly,
+    /// The baselines are git-lfs pointers.
+    LfsPointer,
+    /// The unit applies `@edit` passes.
+    MultiPass,
+    /// The unit has `@noTypesAndSymbols`, there is no types baseline to compare.
+    NoTypesAndSymbols,
+    /// The variant was already scheduled.
+    Duplicate,
+}
+
+impl SkipReason {
+    pub const ALL: &[SkipReason] = &[
+        SkipReason::Filtered,
+        SkipReason::EmitOnly,
+        SkipReason::LfsPointer,
+        SkipReason::MultiPass,
+        SkipReason::NoTypesAndSymbols,
+        SkipReason::Duplicate,
+    ];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            SkipReason::Filtered => "filtered",
+            SkipReason::EmitOnly => "emit_only",
+            SkipReason::LfsPointer => "lfs_pointer",
+            SkipReason::MultiPass => "multi_pass",
+            SkipReason::NoTypesAndSymbols => "no_types_and_symbols",
+            SkipReason::Duplicate => "duplicate",
+        }
+    }
+}
+
+/// What happened to the variants of a unit.
+#[derive(Debug, Default, PartialEq, Eq, Clone)]
+pub struct UnitAccount {
+    /// Variants produced by the unit's variations, once per time the unit was read.
+    pub produced: usize,
+    /// Variants not run, indexed like [`SkipReason::ALL`].
+    pub skipped: [usize; SkipReason::ALL.len()],
+    /// Outcomes recorded.
+    pub executed: usize,
+}
+
+impl UnitAccount {
+    pub fn is_balanced(&self) -> bool {
+        self.produced == self.skipped.iter().sum::<usize>() + self.executed
+    }
+}
+
+/// Per-unit accounting of produced, skipped and executed variants, so a variant lost between
+/// discovery and the outcomes is reported instead of silently missing from the counts.
+///
+/// Readers and workers keep their own ledger, merged at the end of the run like the rest of
+/// the [`RunSummary`].
+///
+/// [`RunSummary`]: crate::RunSummary
+#[derive(Debug, Default, PartialEq, Eq, Clone)]
+pub struct VariantLedger {
+    /// Accounts by unit path relative to the repo.
+    units: FxHashMap<PathBuf, UnitAccount>,
+}
+
+impl VariantLedger {
+    pub fn produced(&mut self, unit: &Path, count: usize) {
+        self.account(unit).produced += count;
+    }
+
+    pub fn skipped(&mut self, unit: &Path, reason: SkipReason, count: usize) {
+        self.account(unit).skipped[reason as usize] += count;
+    }
+
+    pub fn executed(&mut self, unit: &Path) {
+        self.account(unit).executed += 1;
+    }
+
+    pub fn merge(&mut self, other: &Self) {
+        for (unit, other) in &other.units {
+            let account = self.account(unit);
+            account.produced += other.produced;
+            for (count, other) in account.skipped.iter_mut().zip(other.skipped) {
+                *count += other;
+            }
+            account.executed += other.executed;
+        }
+    }
+
+    /// Units whose produced variants are not all skipped or executed, sorted by path.
+    pub fn mismatches(&self) -> Vec<LedgerMismatch> {
+        let mut result: Vec<_> = self
+            .units
+            .iter()
+            .filter(|(_, account)| !account.is_balanced())
+            .map(|(path, account)| LedgerMismatch { path: path.clone(), account: account.clone() })
+            .collect();
+        result.sort_unstable_by(|a, b| a.path.cmp(&b.path));
+        result
+    }
+
+    fn account(&mut self, unit: &Path) -> &mut UnitAccount {
+        if !self.units.contains_key(unit) {
+            self.units.insert(unit.to_path_buf(), UnitAccount::default());
+        }
+        self.units.get_mut(unit).expect("account to exist")
+    }
+}
+
+/// A unit whose variants do not add up, see [`VariantLedger::mismatches`].
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct LedgerMismatch {
+    pub path: PathBuf,
+    pub account: UnitAccount,
+}
+
+impl fmt::Display for LedgerMismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let account = &self.account;
+        let skipped: usize = account.skipped.iter().sum();
+        write!(
+            f,
+            "{}: produced {}, skipped {}",
+            self.path.display(),
+            HumanCount(account.produced),
+            HumanCount(skipped)
+        )?;
+        if skipped > 0 {
+            let reasons: Vec<_> = SkipReason::ALL
+                .iter()
+                .filter(|&&x| account.skipped[x as usize] > 0)
+                .map(|&x| format!("{} {}", HumanCount(account.skipped[x as usize]), x.as_str()))
+                .collect();
+            write!(f, " ({})", reasons.join(", "))?;
+        }
+        write!(f, ", executed {}", HumanCount(account.executed))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mismatches() {
+        let (a, b, c) = (Path::new("a.ts"), Path::new("b.ts"), Path::new("c.ts"));
+        let mut reader = VariantLedger::default();
+        reader.produced(a, 3);
+        reader.skipped(a, SkipReason::Filtered, 1);
+        reader.produced(b, 2);
+        reader.skipped(b, SkipReason::MultiPass, 2);
+        reader.produced(c, 4);
+        reader.skipped(c, SkipReason::Filtered, 1);
+        reader.skipped(c, SkipReason::EmitOnly, 1);
+
+        let mut worker = VariantLedger::default();
+        worker.executed(a);
+        worker.executed(a);
+        worker.executed(c);
+
+        let mut ledger = VariantLedger::default();
+        ledger.merge(&reader);
+        ledger.merge(&worker);
+        let mismatches = ledger.mismatches();
+        assert_eq!(mismatches.len(), 1);
+        assert_eq!(
+            mismatches[0].to_string(),
+            "c.ts: produced 4, skipped 2 (1 filtered, 1 emit_only), executed 1"
+        );
+    }
+}
@@ -1,12 +1,72 @@
+mod audit;
 pub(crate) mod baseline;
 mod byte_utils;
+pub mod compare;
+mod compare_files;
+mod config_check;
 mod discover;
 mod file_system;
+mod human;
+mod ledger;
+mod line_index;
+mod options;
+mod outcome;
+mod output;
+mod parse_warning;
+mod pipeline;
+mod quarantine;
+mod repo;
+mod report;
 mod runner;
+mod sink;
+mod smoke;
+mod summary;
+mod test_list;
 mod test_unit;
 mod type_visitor;
 
-pub use baseline::Baseline;
-pub use discover::discover;
+pub use audit::{BaselineAudit, audit_baselines};
+pub use baseline::{
+    Baseline, BaselineKind, BaselineParseError, BaselineSource, BaselineTiming, ConfigError,
+    CountMismatch, DeclaredCount, ErrorsBaseline, FileError, ParseTimes, SummaryEntry, SummaryIter,
+    UnderlineMismatch, file_order,
+    symbols_baseline::{Declaration, SymbolAssertion, SymbolBaselineFile, SymbolsBaseline},
+    types_baseline::{Assertion, TypeBaselineFile, TypesBaseline},
+};
+pub use compare_files::{CompareFilesError, CompareFilesOptions, compare_files};
+pub use discover::{
+    BaselineCandidates, SingleTestError, baseline_candidates, discover, list, run_single,
+};
+pub use file_system::{TestFileSystem, TestFileSystemBuilder};
+pub use human::{HumanCount, HumanDuration, HumanSize};
+pub use ledger::{LedgerMismatch, SkipReason, UnitAccount, VariantLedger};
+pub use line_index::LineIndex;
+pub use options::{
+    EffectiveConfig, OPTIONS, OptionArity, OptionDef, OptionSource, OptionsError, RunOptions,
+    SELF_TEST_CORPUS,
+};
+pub use outcome::{
+    AssertionStats, EntryStrategy, ErrorStats, ExpectedChange, OutcomeKind, ParserPanic,
+    ProgramTiming, RepoComparison, TestOutcome,
+};
+pub use output::{Output, OutputOrder};
+pub use parse_warning::{ParseWarning, ParseWarningKind, ParseWarningStats};
+pub use pipeline::{PipelineConfig, run_pipeline};
+pub use quarantine::{
+    DEFAULT_QUARANTINE_EXPIRY, Quarantine, QuarantineEntry, QuarantineError, QuarantineReport,
+    Snapshot, check_quarantine, read_history,
+};
+pub use repo::{RepoError, RepoInfo};
+pub use report::{
+    ModuleReport, TestReport, VerboseSingleTestReporter, render_type_comparison,
+    render_types_side_by_side,
+};
 pub use runner::run_test;
-pub use test_unit::{TestSettings, TestUnit, TestVariant};
+pub use sink::{JsonLinesSink, OutcomeSink, SharedSink, SinkSpec, Sinks, SummaryJsonSink};
+pub use smoke::{SmokeFailure, SmokeReport, smoke};
+pub use summary::{RunStatus, RunSummary, SUMMARY_VERSION};
+pub use test_list::{TestList, TestListError};
+pub use test_unit::{
+    DEFAULT_MAX_VARIATION_VALUES, TestSettings, TestUnit, TestVariant, VariantConstraint,
+    VariantConstraintError, VariantParseError,
+};
@@ -0,0 +1,117 @@
+use memchr::memchr_iter;
+
+/// Start offsets of the lines of a source text.
+///
+/// Maps byte offsets to the 1-based lines and UTF-16 columns diagnostics and baselines use.
+/// Lines end at `\n`, as [`type_info::Diagnostic::from_oxc`] counts them.
+#[derive(Debug, Default, PartialEq, Eq, Clone)]
+pub struct LineIndex {
+    starts: Vec<u32>,
+}
+
+impl LineIndex {
+    pub fn new(text: &str) -> Self {
+        let starts = std::iter::once(0)
+            .chain(memchr_iter(b'\n', text.as_bytes()).map(|x| x + 1))
+            .map(|x| u32::try_from(x).unwrap_or(u32::MAX))
+            .collect();
+        Self { starts }
+    }
+
+    /// 1-based line of the byte at `offset`.
+    pub fn line(&self, offset: u32) -> u32 {
+        u32::try_from(self.starts.partition_point(|&x| x <= offset)).unwrap_or(u32::MAX)
+    }
+
+    /// Byte offset the 1-based `line` starts at, `None` past the last line.
+    pub fn line_start(&self, line: u32) -> Option<u32> {
+        self.starts.get(line.checked_sub(1)? as usize).copied()
+    }
+
+    /// 1-based UTF-16 column of the byte at `offset` of `text`, the text the index is built from.
+    /// Characters outside the Basic Multilingual Plane take two columns, like in TypeScript.
+    pub fn utf16_column(&self, text: &str, offset: u32) -> u32 {
+        let offset = offset.min(u32::try_from(text.len()).unwrap_or(u32::MAX));
+        let line_start = self.starts[self.line(offset) as usize - 1];
+        let column = utf16_len(&text[line_start as usize..offset as usize]) + 1;
+        u32::try_from(column).unwrap_or(u32::MAX)
+    }
+
+    /// Byte offset of the 1-based `line` and UTF-16 `column` of `text`, the text the index is
+    /// built from. `None` if the column is past the end of the line or in the middle of a
+    /// character.
+    pub fn utf16_offset(&self, text: &str, line: u32, column: u32) -> Option<u32> {
+        let start = self.line_start(line)? as usize;
+        let end = self.line_start(line + 1).map_or(text.len(), |x| x as usize - 1);
+        let offset = utf16_offset(&text[start..end], column.checked_sub(1)? as usize)?;
+        u32::try_from(start + offset).ok()
+    }
+}
+
+/// Number of UTF-16 units of `text`.
+pub fn utf16_len(text: &str) -> usize {
+    text.chars().map(char::len_utf16).sum()
+}
+
+/// Byte offset of the character starting at `units` UTF-16 units into `text`, `None` if that is
+/// in the middle of a character or past the end.
+pub fn utf16_offset(text: &str, units: usize) -> Option<usize> {
+    let mut acc = 0;
+    for (offset, ch) in text.char_indices() {
+        if acc == units {
+            return Some(offset);
+        }
+        if acc > units {
+            return None;
+        }
+        acc += ch.len_utf16();
+    }
+
+    (acc == units).then_some(text.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lines() {
+        let index = LineIndex::new("let a;\r\n\nlet b;");
+        assert_eq!(index.line(0), 1);
+        assert_eq!(index.line(7), 1);
+        assert_eq!(index.line(8), 2);
+        assert_eq!(index.line(9), 3);
+        assert_eq!(index.line(100), 3);
+        assert_eq!(LineIndex::new("").line(0), 1);
+    }
+
+    #[test]
+    fn utf16_columns() {
+        // U+1D4F1 is outside the BMP: 4 bytes, 2 UTF-16 units
+        let text = "let a;\nconst \u{1D4F1}x = 'ü'; \u{1D4F1};\n";
+        let index = LineIndex::new(text);
+        let second_line = text.find("const").unwrap();
+        let offsets: Vec<_> = text[second_line..]
+            .char_indices()
+            .take_while(|&(_, ch)| ch != '\n')
+            .map(|(offset, _)| u32::try_from(second_line + offset).unwrap())
+            .collect();
+        // Every character maps to its column and back
+        for &offset in &offsets {
+            let column = index.utf16_column(text, offset);
+            assert_eq!(index.utf16_offset(text, 2, column), Some(offset));
+        }
+
+        let x = u32::try_from(text.find('x').unwrap()).unwrap();
+        assert_eq!(index.utf16_column(text, x), 9);
+        assert_eq!(index.utf16_column(text, x - 4), 7);
+        // Between the surrogates of U+1D4F1
+        assert_eq!(index.utf16_offset(text, 2, 8), None);
+        let semicolon = u32::try_from(text.rfind(';').unwrap()).unwrap();
+        assert_eq!(index.utf16_column(text, semicolon), 20);
+        assert_eq!(index.utf16_offset(text, 2, 21), Some(semicolon + 1));
+        assert_eq!(index.utf16_offset(text, 2, 22), None);
+        assert_eq!(index.utf16_offset(text, 4, 1), None);
+        assert_eq!(index.line_start(3), Some(u32::try_from(text.len()).unwrap()));
+    }
+}
@@ -1,11 +1,142 @@
-use std::{env, path::Path};
+use std::{env, process::ExitCode};
 
-use test_runner::{discover, run_test};
+use test_runner::{
+    CompareFilesOptions, HumanCount, RepoInfo, RunOptions, RunStatus, RunSummary, audit_baselines,
+    check_quarantine, compare_files, discover, list, run_single, run_test, smoke,
+};
 
-fn main() {
-    let Some(arg) = env::args().nth(1) else {
-        panic!("Missing path to TypeScript repo");
+fn main() -> ExitCode {
+    let mut args = env::args().skip(1).peekable();
+    if args.next_if(|x| x == "compare").is_some() {
+        return compare(args).into();
+    }
+
+    let options = match RunOptions::parse_with_env(args, |x| env::var(x).ok()) {
+        Ok(options) => options,
+        Err(err) => return usage_error(&err),
+    };
+    if options.print_config {
+        print!("{}", options.config);
+        return ExitCode::SUCCESS;
+    }
+
+    let repo = match RepoInfo::validate(&options) {
+        Ok(repo) => repo,
+        Err(err) => return usage_error(&err),
+    };
+    println!("{repo}");
+
+    if options.list {
+        list(&options);
+        return ExitCode::SUCCESS;
+    }
+
+    if options.smoke {
+        let report = smoke(&options);
+        println!("{report}");
+        return report.status().into();
+    }
+
+    if options.audit_baselines {
+        print!("{}", audit_baselines(&options));
+        return ExitCode::SUCCESS;
+    }
+
+    let summary = if let Some(test) = &options.test {
+        match run_single(&options, test, run_test) {
+            Ok(summary) => summary,
+            Err(err) => return usage_error(&err),
+        }
+    } else {
+        discover(&options, run_test)
+    };
+
+    println!("{summary}");
+    if summary.lfs_pointers > 0 {
+        println!(
+            "⚠  The baselines of {} variants are git-lfs pointers, run `git lfs pull` in {}",
+            HumanCount(summary.lfs_pointers),
+            options.repo.display()
+        );
+    }
+    if !summary.missing_tests.is_empty() {
+        println!(
+            "⚠  {} test cases of the --tests-json list were not found:",
+            HumanCount(summary.missing_tests.len())
+        );
+        for path in &summary.missing_tests {
+            println!("  {}", path.display());
+        }
+    }
+    if !summary.ledger_mismatches.is_empty() {
+        println!(
+            "⚠  The variants of {} units do not add up to the skipped and run ones:",
+            HumanCount(summary.ledger_mismatches.len())
+        );
+        for mismatch in &summary.ledger_mismatches {
+            println!("  {mismatch}");
+        }
+    }
+    if options.show_parse_warnings {
+        print!("{}", summary.parse_warnings);
+    }
+    if options.stats_by_entry_strategy {
+        print!("{}", summary.entry_strategy_breakdown());
+    }
+    if let Some(limit) = options.slow_baselines {
+        print!("{}", summary.slow_baselines_report(limit));
+    }
+    if options.timings {
+        print!("{}", summary.slow_units_report(10));
+    }
+
+    if let Some(path) = &options.report_parser_panics {
+        if let Err(err) = summary.write_parser_panics(path) {
+            panic!("Failed to write parser panics:\n  path: {}\n  error: {err}", path.display());
+        }
+    }
+
+    if let (Some(path), Some(history)) = (&options.quarantine, &options.snapshot_history) {
+        let expiry = options.quarantine_expiry();
+        match check_quarantine(path, history, expiry, options.prune_quarantine) {
+            Ok(report) => print!("{report}"),
+            Err(err) => {
+                panic!(
+                    "Failed to check the quarantine:\n  path: {}\n  error: {err}",
+                    path.display()
+                )
+            }
+        }
+    }
+
+    summary.status().into()
+}
+
+/// Reports invalid options or an invalid repo, for which nothing is run.
+fn usage_error(err: &dyn std::fmt::Display) -> ExitCode {
+    eprintln!("{err}");
+    RunStatus::Usage.into()
+}
+
+/// `test-runner compare`: compares a test case with baselines given as files, without a repo.
+fn compare(args: impl Iterator<Item = String>) -> RunStatus {
+    let options = match CompareFilesOptions::parse(args) {
+        Ok(options) => options,
+        Err(err) => {
+            eprintln!("{err}");
+            return RunStatus::Usage;
+        }
     };
-    let repo = Path::new(&arg);
-    discover(repo, run_test);
+    match compare_files(&options) {
+        Ok(outcome) => {
+            print!("{}", outcome.log);
+            let mut summary = RunSummary::default();
+            summary.add(&outcome);
+            summary.status()
+        }
+        Err(err) => {
+            eprintln!("Failed to compare {}: {err}", options.case.display());
+            RunStatus::Usage
+        }
+    }
 }
@@ -0,0 +1,681 @@
+use std::{
+    io::IsTerminal,
+    num::NonZeroUsize,
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    DEFAULT_QUARANTINE_EXPIRY, OutputOrder, PipelineConfig, SinkSpec, TestVariant,
+    VariantConstraint, VariantConstraintError, compare::MatchOptions,
+    test_unit::DEFAULT_MAX_VARIATION_VALUES, type_visitor::DEFAULT_MAX_DEPTH,
+};
+
+mod table;
+
+pub use table::{EffectiveConfig, OPTIONS, OptionArity, OptionDef, OptionSource};
+
+const DEFAULT_CASES_DIRS: &[&str] = &["tests/cases/compiler", "tests/cases/conformance"];
+const CONFORMANCE_DIR: &str = "tests/cases/conformance";
+const DEFAULT_BASELINE_DIR: &str = "tests/baselines/reference";
+const DEFAULT_READERS: usize = 2;
+/// Deeply nested test cases overflow the default 2 MiB thread stack in debug builds.
+const DEFAULT_STACK_SIZE_MIB: usize = 16;
+
+/// Small corpus of test cases and baselines bundled with the runner, laid out like the TS repo.
+pub const SELF_TEST_CORPUS: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/corpus");
+
+#[derive(Debug, Default, PartialEq, Eq, Clone)]
+pub struct RunOptions {
+    /// Path to the TypeScript repo checkout.
+    pub repo: PathBuf,
+    /// Verbosity level: number of `-v` flags.
+    pub verbose: u8,
+    /// Run a single test case (path to the test file, relative to the repo or absolute).
+    pub test: Option<PathBuf>,
+    /// Print discovered test cases with their variant counts instead of running them.
+    pub list: bool,
+    /// Report baselines that are missing or that no discovered test looks up instead of running
+    /// tests.
+    pub audit_baselines: bool,
+    /// Only build the program of every variant, without reading baselines, and report how many
+    /// could not be built instead of running tests.
+    pub smoke: bool,
+    /// Where outcomes and the end-of-run summary are written. Library consumers can add their
+    /// own with [`SinkSpec::Custom`].
+    pub sinks: Vec<SinkSpec>,
+    /// Directories to discover test cases in, relative to the repo or absolute. `default` stands
+    /// for the compiler and conformance tests, which are used when this is empty.
+    pub cases_dirs: Vec<PathBuf>,
+    /// Feature areas of the conformance tests (`jsx` for `tests/cases/conformance/jsx`) or
+    /// `compiler` to discover test cases in instead of the default ones, see [`Self::areas`].
+    pub areas: Vec<String>,
+    /// Directory with the reference baselines, relative to the repo or absolute.
+    pub baseline_dir: Option<PathBuf>,
+    /// Fail statements whose expressions we visit in a different order than the baseline.
+    pub check_order: bool,
+    /// Only match expected errors with produced ones having the same message.
+    pub compare_messages: bool,
+    /// Only match expected errors with produced ones having the same underline length.
+    pub compare_lengths: bool,
+    /// Run against [`SELF_TEST_CORPUS`] instead of a TypeScript checkout.
+    pub self_test: bool,
+    /// Only run variants matching all of these constraints.
+    pub variant_filter: Vec<VariantConstraint>,
+    /// Print every file of the single test with baseline and actual types side by side.
+    pub dump_types: bool,
+    /// Compare the errors baseline against the diagnostics of programs that failed to be
+    /// created instead of giving up on the test.
+    pub compare_program_errors: bool,
+    /// Number of threads reading test cases and baselines.
+    pub readers: Option<usize>,
+    /// Number of threads running tests. Defaults to the available parallelism.
+    pub jobs: Option<usize>,
+    /// Number of read test variants allowed to wait for a free worker.
+    pub queue_bound: Option<usize>,
+    /// Stack size of worker threads in MiB.
+    pub stack_size: Option<usize>,
+    /// Statement and expression nesting past which a test is abandoned as a resource limit.
+    pub max_depth: Option<usize>,
+    /// Write the baselines we produce for every variant run into this directory.
+    pub emit_actual: Option<PathBuf>,
+    /// Print the most common parse warnings with examples after the run.
+    pub show_parse_warnings: bool,
+    /// Second TypeScript checkout whose baselines are compared against as well.
+    pub compare_repo: Option<PathBuf>,
+    /// Write the variants the oxc parser panicked on, with their diagnostics, to this path.
+    pub report_parser_panics: Option<PathBuf>,
+    /// Print outcome counts per entry strategy after the run.
+    pub stats_by_entry_strategy: bool,
+    /// Only compare the types and errors of these files of the single test. The whole program is
+    /// still built.
+    pub only_files: Vec<String>,
+    /// Print this many of the slowest baseline files to parse, with their sizes, after the run.
+    pub slow_baselines: Option<usize>,
+    /// Time building the programs, and print the units slowest to build after the run.
+    pub timings: bool,
+    /// Values a single test directive may vary over; the rest are dropped with a parse warning.
+    pub max_variation_values: Option<usize>,
+    /// Order the output of the variants is printed in. Defaults to completion order on a
+    /// terminal and discovery order otherwise.
+    pub output_order: Option<OutputOrder>,
+    /// JSON array of test case paths relative to `tests/cases`, like the lists TypeScript's CI
+    /// shards its tests with. Exactly these cases are run, in this order.
+    pub tests_json: Option<PathBuf>,
+    /// File of known failures whose stale entries are reported after the run, see
+    /// [`crate::Quarantine`]. Requires [`Self::snapshot_history`].
+    pub quarantine: Option<PathBuf>,
+    /// Directory of outcome files written by `--sink jsonl=`, named after the date of their run.
+    pub snapshot_history: Option<PathBuf>,
+    /// Number of recent snapshots a quarantined test has to pass in to be reported as stale.
+    pub quarantine_expiry: Option<usize>,
+    /// Remove the stale entries from the quarantine file.
+    pub prune_quarantine: bool,
+    /// Print the effective options and where they came from instead of running tests.
+    pub print_config: bool,
+    /// Options given on the command line or in the environment.
+    pub config: EffectiveConfig,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum OptionsError {
+    MissingRepo,
+    MissingValue(&'static str),
+    /// A required option is not given.
+    MissingOption(&'static str),
+    UnknownArgument(String),
+    /// The option only makes sense when running a single test with `--test`.
+    RequiresTest(&'static str),
+    /// Expected a positive number.
+    InvalidCount(&'static str, String),
+    VariantFilter(VariantConstraintError),
+    /// `--sink` value that is not `jsonl=<path>` or `summary=<path>`.
+    InvalidSink(String),
+    /// `--output-order` value that is not `completion` or `discovery`.
+    InvalidOutputOrder(String),
+    /// The first option only makes sense together with the second one.
+    Requires(&'static str, &'static str),
+    /// Environment variable whose value is not valid for its option.
+    InvalidEnv(String, String),
+}
+
+impl std::fmt::Display for OptionsError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            OptionsError::MissingRepo => f.write_str("Missing path to TypeScript repo"),
+            OptionsError::MissingValue(name) => write!(f, "Missing value for {name}"),
+            OptionsError::MissingOption(name) => write!(f, "Missing {name}"),
+            OptionsError::UnknownArgument(arg) => write!(f, "Unknown argument: {arg}"),
+            OptionsError::RequiresTest(name) => write!(f, "{name} requires --test"),
+            OptionsError::InvalidCount(name, value) => {
+                write!(f, "Expected a positive number for {name}, got: {value}")
+            }
+            OptionsError::VariantFilter(err) => err.fmt(f),
+            OptionsError::InvalidSink(value) => {
+                write!(f, "Expected jsonl=<path> or summary=<path> for --sink, got: {value}")
+            }
+            OptionsError::InvalidOutputOrder(value) => {
+                write!(f, "Expected completion or discovery for --output-order, got: {value}")
+            }
+            OptionsError::Requires(name, other) => write!(f, "{name} requires {other}"),
+            OptionsError::InvalidEnv(var, value) => write!(f, "Invalid value for {var}: {value}"),
+        }
+    }
+}
+
+impl std::error::Error for OptionsError {}
+
+impl From<VariantConstraintError> for OptionsError {
+    fn from(value: VariantConstraintError) -> Self {
+        Self::VariantFilter(value)
+    }
+}
+
+impl RunOptions {
+    /// Parses command line arguments (without the binary name).
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` on unknown arguments, missing option values or missing repo path
+    pub fn parse(args: impl IntoIterator<Item = String>) -> Result<Self, OptionsError> {
+        Self::parse_with_env(args, |_| None)
+    }
+
+    /// Parses command line arguments (without the binary name), taking the options they do not
+    /// give from the `TYPE_RUNNER_*` variables `env` looks up, see [`OptionDef::env_var`].
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` on unknown arguments, missing option values, invalid environment
+    /// variables or missing repo path
+    pub fn parse_with_env(
+        args: impl IntoIterator<Item = String>,
+        env: impl Fn(&str) -> Option<String>,
+    ) -> Result<Self, OptionsError> {
+        let mut parser = ArgParser::default();
+        parser.parse(args, OptionSource::CommandLine)?;
+        for (idx, def) in OPTIONS.iter().enumerate() {
+            if parser.result.config.is_given(idx) {
+                continue;
+            }
+            if let Some(value) = env(&def.env_var()) {
+                parser.parse(def.env_args(&value)?, OptionSource::Env)?;
+            }
+        }
+        parser.finish()
+    }
+
+    /// Test case roots to discover tests in.
+    pub fn case_roots(&self) -> Vec<PathBuf> {
+        if self.cases_dirs.is_empty() {
+            return self.default_case_roots();
+        }
+
+        let mut result = vec![];
+        for dir in &self.cases_dirs {
+            if dir == Path::new("default") {
+                result.extend(self.default_case_roots());
+            } else {
+                result.push(self.repo.join(dir));
+            }
+        }
+
+        result
+    }
+
+    /// The compiler and conformance tests, or only the directories of [`Self::areas`].
+    fn default_case_roots(&self) -> Vec<PathBuf> {
+        if self.areas.is_empty() {
+            return DEFAULT_CASES_DIRS.iter().map(|x| self.repo.join(x)).collect();
+        }
+        self.areas.iter().map(|x| self.area_dir(x)).collect()
+    }
+
+    /// Directory of a feature area: `compiler` or a subdirectory of the conformance tests.
+    pub fn area_dir(&self, area: &str) -> PathBuf {
+        if area == "compiler" {
+            self.repo.join(DEFAULT_CASES_DIRS[0])
+        } else {
+            self.repo.join(CONFORMANCE_DIR).join(area)
+        }
+    }
+
+    /// Feature areas of the repo that `--area` accepts, sorted: `compiler` and the
+    /// subdirectories of the conformance tests.
+    pub fn available_areas(&self) -> Vec<String> {
+        let conformance = self.repo.join(CONFORMANCE_DIR).read_dir().into_iter().flatten();
+        let mut result: Vec<_> = conformance
+            .filter_map(Result::ok)
+            .filter(|x| x.path().is_dir())
+            .filter_map(|x| x.file_name().into_string().ok())
+            .chain(self.area_dir("compiler").is_dir().then(|| "compiler".to_string()))
+            .collect();
+        result.sort();
+        result
+    }
+
+    pub fn baseline_root(&self) -> PathBuf {
+        self.repo.join(self.baseline_dir())
+    }
+
+    /// Baseline directory of the `--compare-repo` checkout, at the same relative path.
+    pub fn compare_baseline_root(&self) -> Option<PathBuf> {
+        self.compare_repo.as_ref().map(|x| x.join(self.baseline_dir()))
+    }
+
+    fn baseline_dir(&self) -> &Path {
+        self.baseline_dir.as_deref().unwrap_or(Path::new(DEFAULT_BASELINE_DIR))
+    }
+
+    pub fn matches_variant(&self, variant: &TestVariant<'_>) -> bool {
+        self.variant_filter.iter().all(|x| x.matches(variant))
+    }
+
+    /// Whether the types and errors of the test file `name` are compared, see
+    /// [`Self::only_files`].
+    pub fn compares_file(&self, name: &str) -> bool {
+        self.only_files.is_empty() || self.only_files.iter().any(|x| x == name)
+    }
+
+    pub fn match_options(&self) -> MatchOptions {
+        MatchOptions {
+            check_order: self.check_order,
+            compare_messages: self.compare_messages,
+            compare_lengths: self.compare_lengths,
+            ..Default::default()
+        }
+    }
+
+    pub fn pipeline_config(&self) -> PipelineConfig {
+        let workers = self
+            .jobs
+            .unwrap_or_else(|| std::thread::available_parallelism().map_or(1, NonZeroUsize::get));
+        PipelineConfig {
+            readers: self.readers.unwrap_or(DEFAULT_READERS),
+            workers,
+            bound: self.queue_bound.unwrap_or(workers * 4),
+            stack_size: self.stack_size.unwrap_or(DEFAULT_STACK_SIZE_MIB) << 20,
+        }
+    }
+
+    /// See [`Self::output_order`].
+    pub fn output_order(&self) -> OutputOrder {
+        self.output_order.unwrap_or_else(|| {
+            if std::io::stdout().is_terminal() {
+                OutputOrder::Completion
+            } else {
+                OutputOrder::Discovery
+            }
+        })
+    }
+
+    pub fn max_depth(&self) -> usize {
+        self.max_depth.unwrap_or(DEFAULT_MAX_DEPTH)
+    }
+
+    pub fn quarantine_expiry(&self) -> usize {
+        self.quarantine_expiry.unwrap_or(DEFAULT_QUARANTINE_EXPIRY)
+    }
+
+    pub fn max_variation_values(&self) -> usize {
+        self.max_variation_values.unwrap_or(DEFAULT_MAX_VARIATION_VALUES)
+    }
+
+    /// Single test runs print the per-file checklist unless asked otherwise.
+    pub fn is_verbose_single_test(&self) -> bool {
+        self.test.is_some() || self.verbose >= 2
+    }
+}
+
+/// State of [`RunOptions::parse_with_env`] while the command line and the environment are parsed.
+#[derive(Default)]
+struct ArgParser {
+    result: RunOptions,
+    repo: Option<PathBuf>,
+}
+
+impl ArgParser {
+    fn parse(
+        &mut self,
+        args: impl IntoIterator<Item = String>,
+        source: OptionSource,
+    ) -> Result<(), OptionsError> {
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            if let Some((idx, def)) = OptionDef::find(&arg) {
+                let value = match def.arity {
+                    OptionArity::Flag | OptionArity::Count => String::new(),
+                    OptionArity::Value | OptionArity::Repeated => {
+                        args.next().ok_or(OptionsError::MissingValue(def.name))?
+                    }
+                };
+                self.result.config.record(idx, source, value.clone());
+                self.apply(def.name, value)?;
+            } else if arg.starts_with("-v") && arg[1..].bytes().all(|x| x == b'v') {
+                for _ in 1..arg.len() {
+                    self.parse(["--verbose".to_string()], source)?;
+                }
+            } else if arg.starts_with('-') || self.repo.is_some() {
+                return Err(OptionsError::UnknownArgument(arg));
+            } else {
+                self.parse(["--repo".to_string(), arg], source)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn apply(&mut self, name: &'static str, value: String) -> Result<(), OptionsError> {
+        let result = &mut self.result;
+        match name {
+            "--repo" => {
+                if self.repo.is_some() {
+                    return Err(OptionsError::UnknownArgument(value));
+                }
+                self.repo = Some(PathBuf::from(value));
+            }
+            "--verbose" => result.verbose = result.verbose.saturating_add(1),
+            "--test" => result.test = Some(PathBuf::from(value)),
+            "--list" => result.list = true,
+            "--audit-baselines" => result.audit_baselines = true,
+            "--smoke" => result.smoke = true,
+            "--check-order" => result.check_order = true,
+            "--compare-messages" => result.compare_messages = true,
+            "--compare-lengths" => result.compare_lengths = true,
+            "--self-test" => result.self_test = true,
+            "--dump-types" => result.dump_types = true,
+            "--compare-program-errors" => result.compare_program_errors = true,
+            "--show-parse-warnings" => result.show_parse_warnings = true,
+            "--stats-by-entry-strategy" => result.stats_by_entry_strategy = true,
+            "--timings" => result.timings = true,
+            "--prune-quarantine" => result.prune_quarantine = true,
+            "--print-config" => result.print_config = true,
+            "--readers" => result.readers = Some(parse_count(name, value)?),
+            "--jobs" => result.jobs = Some(parse_count(name, value)?),
+            "--queue-bound" => result.queue_bound = Some(parse_count(name, value)?),
+            "--stack-size" => result.stack_size = Some(parse_count(name, value)?),
+            "--slow-baselines" => result.slow_baselines = Some(parse_count(name, value)?),
+            "--max-variation-values" => {
+                result.max_variation_values = Some(parse_count(name, value)?);
+            }
+            "--quarantine-expiry" => result.quarantine_expiry = Some(parse_count(name, value)?),
+            "--max-depth" => result.max_depth = Some(parse_count(name, value)?),
+            "--variant-filter" => result.variant_filter.push(VariantConstraint::parse(&value)?),
+            "--cases-dir" => result.cases_dirs.push(PathBuf::from(value)),
+            "--area" => result.areas.push(value),
+            "--only-file" => result.only_files.push(value),
+            "--baseline-dir" => result.baseline_dir = Some(PathBuf::from(value)),
+            "--tests-json" => result.tests_json = Some(PathBuf::from(value)),
+            "--quarantine" => result.quarantine = Some(PathBuf::from(value)),
+            "--snapshot-history" => result.snapshot_history = Some(PathBuf::from(value)),
+            "--compare-repo" => result.compare_repo = Some(PathBuf::from(value)),
+            "--emit-actual" => result.emit_actual = Some(PathBuf::from(value)),
+            "--report-parser-panics" => result.report_parser_panics = Some(PathBuf::from(value)),
+            "--summary-json" => result.sinks.push(SinkSpec::SummaryJson(PathBuf::from(value))),
+            "--sink" => result.sinks.push(SinkSpec::parse(&value)?),
+            "--output-order" => {
+                let order =
+                    OutputOrder::parse(&value).ok_or(OptionsError::InvalidOutputOrder(value))?;
+                result.output_order = Some(order);
+            }
+            _ => unreachable!("{name} is in OPTIONS without being parsed"),
+        }
+        Ok(())
+    }
+
+    fn finish(self) -> Result<RunOptions, OptionsError> {
+        let mut result = self.result;
+        // `--self-test` on the command line wins over a repo in the environment
+        let repo_from_env = matches!(result.config.get("--repo"), Some((OptionSource::Env, _)));
+        result.repo = match self.repo {
+            Some(_) if result.self_test && repo_from_env => PathBuf::from(SELF_TEST_CORPUS),
+            Some(repo) if !result.self_test => repo,
+            None if result.self_test => PathBuf::from(SELF_TEST_CORPUS),
+            Some(repo) => return Err(OptionsError::UnknownArgument(repo.display().to_string())),
+            None => return Err(OptionsError::MissingRepo),
+        };
+        if result.dump_types && result.test.is_none() {
+            return Err(OptionsError::RequiresTest("--dump-types"));
+        }
+        if !result.only_files.is_empty() && result.test.is_none() {
+            return Err(OptionsError::RequiresTest("--only-file"));
+        }
+        if result.quarantine.is_some() && result.snapshot_history.is_none() {
+            return Err(OptionsError::Requires("--quarantine", "--snapshot-history"));
+        }
+        if (result.prune_quarantine || result.quarantine_expiry.is_some())
+            && result.quarantine.is_none()
+        {
+            let name =
+                if result.prune_quarantine { "--prune-quarantine" } else { "--quarantine-expiry" };
+            return Err(OptionsError::Requires(name, "--quarantine"));
+        }
+        Ok(result)
+    }
+}
+
+fn parse_count(name: &'static str, value: String) -> Result<usize, OptionsError> {
+    match value.parse::<NonZeroUsize>() {
+        Ok(count) => Ok(count.get()),
+        Err(_) => Err(OptionsError::InvalidCount(name, value)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> Result<RunOptions, OptionsError> {
+        RunOptions::parse(args.iter().map(ToString::to_string))
+    }
+
+    #[test]
+    fn case_roots() {
+        let options = parse(&["/ts"]).unwrap();
+        assert_eq!(
+            options.case_roots(),
+            vec![
+                PathBuf::from("/ts/tests/cases/compiler"),
+                PathBuf::from("/ts/tests/cases/conformance")
+            ]
+        );
+        assert_eq!(options.baseline_root(), PathBuf::from("/ts/tests/baselines/reference"));
+
+        let options =
+            parse(&["/ts", "--cases-dir", "tests/cases/projects", "--cases-dir", "/private"])
+                .unwrap();
+        assert_eq!(
+            options.case_roots(),
+            vec![PathBuf::from("/ts/tests/cases/projects"), PathBuf::from("/private")]
+        );
+
+        let options = parse(&[
+            "--cases-dir",
+            "default",
+            "--cases-dir",
+            "extra",
+            "--baseline-dir",
+            "/b",
+            "/ts",
+        ])
+        .unwrap();
+        assert_eq!(options.case_roots().len(), 3);
+        assert_eq!(options.case_roots()[2], PathBuf::from("/ts/extra"));
+        assert_eq!(options.baseline_root(), PathBuf::from("/b"));
+
+        assert_eq!(parse(&["/ts", "--cases-dir"]), Err(OptionsError::MissingValue("--cases-dir")));
+        assert_eq!(
+            parse(&["/ts", "--dump-types"]),
+            Err(OptionsError::RequiresTest("--dump-types"))
+        );
+        assert_eq!(
+            parse(&["/ts", "--only-file", "a.ts"]),
+            Err(OptionsError::RequiresTest("--only-file"))
+        );
+
+        let options =
+            parse(&["/ts", "--test", "a.ts", "--only-file", "a.ts", "--only-file", "b.ts"])
+                .unwrap();
+        assert!(options.compares_file("b.ts"));
+        assert!(!options.compares_file("c.ts"));
+        assert!(parse(&["/ts"]).unwrap().compares_file("c.ts"));
+    }
+
+    #[test]
+    fn areas() {
+        let options = parse(&["/ts", "--area", "jsx", "--area", "compiler"]).unwrap();
+        assert_eq!(
+            options.case_roots(),
+            vec![
+                PathBuf::from("/ts/tests/cases/conformance/jsx"),
+                PathBuf::from("/ts/tests/cases/compiler")
+            ]
+        );
+
+        // `default` stands for the areas next to other directories
+        let options =
+            parse(&["/ts", "--area", "es6", "--cases-dir", "default", "--cases-dir", "x"]).unwrap();
+        assert_eq!(
+            options.case_roots(),
+            vec![PathBuf::from("/ts/tests/cases/conformance/es6"), PathBuf::from("/ts/x")]
+        );
+    }
+
+    #[test]
+    fn pipeline_config() {
+        let options = parse(&["/ts", "-j", "3", "--readers", "1"]).unwrap();
+        assert_eq!(
+            options.pipeline_config(),
+            PipelineConfig { readers: 1, workers: 3, bound: 12, stack_size: 16 << 20 }
+        );
+
+        let options =
+            parse(&["/ts", "--jobs", "8", "--queue-bound", "2", "--stack-size", "64"]).unwrap();
+        assert_eq!(options.pipeline_config().bound, 2);
+        assert_eq!(options.pipeline_config().stack_size, 64 << 20);
+
+        assert_eq!(
+            parse(&["/ts", "--jobs", "0"]),
+            Err(OptionsError::InvalidCount("--jobs", "0".to_string()))
+        );
+    }
+
+    #[test]
+    fn output_order() {
+        let options = parse(&["/ts", "--output-order", "discovery"]).unwrap();
+        assert_eq!(options.output_order(), OutputOrder::Discovery);
+        assert_eq!(
+            parse(&["/ts", "--output-order", "random"]),
+            Err(OptionsError::InvalidOutputOrder("random".to_string()))
+        );
+    }
+
+    #[test]
+    fn match_options() {
+        let options = parse(&["/ts", "--compare-messages", "--compare-lengths"]).unwrap();
+        let match_options = options.match_options();
+        assert!(match_options.compare_messages && match_options.compare_lengths);
+        assert!(!match_options.check_order);
+    }
+
+    #[test]
+    fn quarantine() {
+        let options =
+            parse(&["/ts", "--quarantine", "q.txt", "--snapshot-history", "history"]).unwrap();
+        assert_eq!(options.quarantine_expiry(), DEFAULT_QUARANTINE_EXPIRY);
+        assert!(!options.prune_quarantine);
+        assert_eq!(
+            parse(&["/ts", "--quarantine", "q.txt"]),
+            Err(OptionsError::Requires("--quarantine", "--snapshot-history"))
+        );
+        assert_eq!(
+            parse(&["/ts", "--prune-quarantine"]),
+            Err(OptionsError::Requires("--prune-quarantine", "--quarantine"))
+        );
+        assert_eq!(
+            parse(&["/ts", "--quarantine-expiry", "5"]),
+            Err(OptionsError::Requires("--quarantine-expiry", "--quarantine"))
+        );
+    }
+
+    #[test]
+    fn every_option_is_parsed() {
+        for def in OPTIONS {
+            let mut args = vec!["/ts", def.name];
+            match def.name {
+                "--repo" => args = vec!["--repo", "/ts"],
+                "--dump-types" => args.extend(["--test", "a.ts"]),
+                "--only-file" => args.extend(["a.ts", "--test", "a.ts"]),
+                "--quarantine" => args.extend(["q.txt", "--snapshot-history", "h"]),
+                "--quarantine-expiry" | "--prune-quarantine" => {
+                    if def.arity == OptionArity::Value {
+                        args.push("2");
+                    }
+                    args.extend(["--quarantine", "q.txt", "--snapshot-history", "h"]);
+                }
+                "--self-test" => args = vec![def.name],
+                "--variant-filter" => args.push("strict=true"),
+                "--sink" => args.push("jsonl=out.jsonl"),
+                "--output-order" => args.push("discovery"),
+                _ if def.arity == OptionArity::Value || def.arity == OptionArity::Repeated => {
+                    args.push("1");
+                }
+                _ => {}
+            }
+            let options = parse(&args).unwrap_or_else(|err| panic!("{}: {err}", def.name));
+            assert!(options.config.get(def.name).is_some(), "{}", def.name);
+        }
+    }
+
+    #[test]
+    fn env() {
+        let env = |var: &str| {
+            match var {
+                "TYPE_RUNNER_JOBS" => Some("4".into()),
+                "TYPE_RUNNER_READERS" => Some("3".into()),
+                "TYPE_RUNNER_CASES_DIR" => std::env::join_paths(["a", "b"]).ok(),
+                "TYPE_RUNNER_CHECK_ORDER" => Some("true".into()),
+                "TYPE_RUNNER_LIST" => Some("0".into()),
+                "TYPE_RUNNER_VERBOSE" => Some("2".into()),
+                "TYPE_RUNNER_REPO" => Some("/env".into()),
+                _ => None,
+            }
+            .map(|x| x.to_string_lossy().into_owned())
+        };
+        let parse_env =
+            |args: &[&str]| RunOptions::parse_with_env(args.iter().map(ToString::to_string), env);
+
+        let options = parse_env(&["/ts", "--jobs", "8"]).unwrap();
+        assert_eq!(options.repo, PathBuf::from("/ts"));
+        assert_eq!(options.jobs, Some(8));
+        assert_eq!(options.readers, Some(3));
+        assert_eq!(options.cases_dirs, vec![PathBuf::from("a"), PathBuf::from("b")]);
+        assert!(options.check_order);
+        assert!(!options.list);
+        assert_eq!(options.verbose, 2);
+        assert_eq!(options.config.get("--jobs").unwrap().0, OptionSource::CommandLine);
+        assert_eq!(options.config.get("--readers").unwrap().0, OptionSource::Env);
+        assert_eq!(options.config.get("--list"), None);
+
+        let options = parse_env(&["-v", "--cases-dir", "c"]).unwrap();
+        assert_eq!(options.repo, PathBuf::from("/env"));
+        assert_eq!(options.verbose, 1);
+        assert_eq!(options.cases_dirs, vec![PathBuf::from("c")]);
+        assert_eq!(parse_env(&["--self-test"]).unwrap().repo, PathBuf::from(SELF_TEST_CORPUS));
+
+        let config = parse_env(&["/ts", "-vvv"]).unwrap().config.to_string();
+        // Columns are aligned, compare with single spaces
+        let line = |name: &str| {
+            let line = config.lines().find(|x| x.starts_with(&format!("{name} "))).unwrap();
+            line.split_whitespace().collect::<Vec<_>>().join(" ")
+        };
+        assert_eq!(line("--verbose"), "--verbose TYPE_RUNNER_VERBOSE 3 (command line)");
+        assert_eq!(line("--readers"), "--readers TYPE_RUNNER_READERS 3 (environment)");
+        assert_eq!(line("--list"), "--list TYPE_RUNNER_LIST default");
+        assert_eq!(config.lines().count(), OPTIONS.len());
+
+        let invalid = |_: &str| Some("yes".to_string());
+        assert_eq!(
+            RunOptions::parse_with_env(["/ts".to_string()], invalid),
+            Err(OptionsError::InvalidEnv("TYPE_RUNNER_SELF_TEST".to_string(), "yes".to_string()))
+        );
+    }
+}
@@ -0,0 +1,178 @@
+use std::fmt;
+
+use super::OptionsError;
+
+/// How an option takes its value.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum OptionArity {
+    /// `--list`. In the environment `1` or `true` sets it, `0`, `false` or nothing does not.
+    Flag,
+    /// `--jobs 8`.
+    Value,
+    /// `--cases-dir a --cases-dir b`. In the environment the values are separated like in `PATH`.
+    Repeated,
+    /// `-vv`. In the environment the number of times it is given.
+    Count,
+}
+
+/// An option of the command line. When the command line does not give it, it is read from the
+/// environment variable named after it, see [`OptionDef::env_var`].
+#[derive(Debug, PartialEq, Eq)]
+pub struct OptionDef {
+    pub name: &'static str,
+    /// Short form, like `-j` for `--jobs`.
+    pub short: Option<&'static str>,
+    pub arity: OptionArity,
+}
+
+impl OptionDef {
+    const fn new(name: &'static str, arity: OptionArity) -> Self {
+        Self { name, short: None, arity }
+    }
+
+    const fn with_short(name: &'static str, short: &'static str, arity: OptionArity) -> Self {
+        Self { name, short: Some(short), arity }
+    }
+
+    /// The option with the name or short form `arg`, and its index in [`OPTIONS`].
+    pub fn find(arg: &str) -> Option<(usize, &'static Self)> {
+        OPTIONS.iter().enumerate().find(|(_, x)| x.name == arg || x.short == Some(arg))
+    }
+
+    /// `TYPE_RUNNER_` followed by the name in upper snake case: `--jobs` is `TYPE_RUNNER_JOBS`.
+    pub fn env_var(&self) -> String {
+        let name = self.name.trim_start_matches('-').replace('-', "_").to_ascii_uppercase();
+        format!("TYPE_RUNNER_{name}")
+    }
+
+    /// Command line arguments equivalent to the value of the option's environment variable.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if a flag or count has an invalid value
+    pub fn env_args(&self, value: &str) -> Result<Vec<String>, OptionsError> {
+        let invalid = || OptionsError::InvalidEnv(self.env_var(), value.to_string());
+        let name = self.name.to_string();
+        Ok(match self.arity {
+            OptionArity::Flag => match value {
+                "1" | "true" => vec![name],
+                "" | "0" | "false" => vec![],
+                _ => return Err(invalid()),
+            },
+            OptionArity::Value => vec![name, value.to_string()],
+            OptionArity::Repeated => std::env::split_paths(value)
+                .filter(|x| !x.as_os_str().is_empty())
+                .flat_map(|x| [name.clone(), x.display().to_string()])
+                .collect(),
+            OptionArity::Count => vec![name; value.parse().map_err(|_| invalid())?],
+        })
+    }
+}
+
+/// Every option of `test-runner`, in the order `--print-config` lists them. The command line
+/// parser and the environment variables are both derived from it.
+pub const OPTIONS: &[OptionDef] = &[
+    OptionDef::new("--repo", OptionArity::Value),
+    OptionDef::new("--self-test", OptionArity::Flag),
+    OptionDef::with_short("--verbose", "-v", OptionArity::Count),
+    OptionDef::new("--test", OptionArity::Value),
+    OptionDef::new("--only-file", OptionArity::Repeated),
+    OptionDef::new("--list", OptionArity::Flag),
+    OptionDef::new("--audit-baselines", OptionArity::Flag),
+    OptionDef::new("--smoke", OptionArity::Flag),
+    OptionDef::new("--cases-dir", OptionArity::Repeated),
+    OptionDef::new("--area", OptionArity::Repeated),
+    OptionDef::new("--baseline-dir", OptionArity::Value),
+    OptionDef::new("--tests-json", OptionArity::Value),
+    OptionDef::new("--variant-filter", OptionArity::Repeated),
+    OptionDef::new("--max-variation-values", OptionArity::Value),
+    OptionDef::new("--check-order", OptionArity::Flag),
+    OptionDef::new("--compare-messages", OptionArity::Flag),
+    OptionDef::new("--compare-lengths", OptionArity::Flag),
+    OptionDef::new("--dump-types", OptionArity::Flag),
+    OptionDef::new("--compare-program-errors", OptionArity::Flag),
+    OptionDef::new("--compare-repo", OptionArity::Value),
+    OptionDef::new("--emit-actual", OptionArity::Value),
+    OptionDef::new("--summary-json", OptionArity::Value),
+    OptionDef::new("--sink", OptionArity::Repeated),
+    OptionDef::new("--output-order", OptionArity::Value),
+    OptionDef::new("--show-parse-warnings", OptionArity::Flag),
+    OptionDef::new("--stats-by-entry-strategy", OptionArity::Flag),
+    OptionDef::new("--slow-baselines", OptionArity::Value),
+    OptionDef::new("--timings", OptionArity::Flag),
+    OptionDef::new("--report-parser-panics", OptionArity::Value),
+    OptionDef::new("--quarantine", OptionArity::Value),
+    OptionDef::new("--snapshot-history", OptionArity::Value),
+    OptionDef::new("--quarantine-expiry", OptionArity::Value),
+    OptionDef::new("--prune-quarantine", OptionArity::Flag),
+    OptionDef::new("--readers", OptionArity::Value),
+    OptionDef::with_short("--jobs", "-j", OptionArity::Value),
+    OptionDef::new("--queue-bound", OptionArity::Value),
+    OptionDef::new("--stack-size", OptionArity::Value),
+    OptionDef::new("--max-depth", OptionArity::Value),
+    OptionDef::new("--print-config", OptionArity::Flag),
+];
+
+/// Where the value of an option came from.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum OptionSource {
+    CommandLine,
+    Env,
+}
+
+/// The options given on the command line or in the environment, with their values, printed by
+/// `--print-config`.
+#[derive(Debug, Default, PartialEq, Eq, Clone)]
+pub struct EffectiveConfig {
+    /// Source and values of every option given, indexed like [`OPTIONS`].
+    given: Vec<Option<(OptionSource, Vec<String>)>>,
+}
+
+impl EffectiveConfig {
+    /// Whether the option at `idx` in [`OPTIONS`] is given.
+    pub fn is_given(&self, idx: usize) -> bool {
+        self.given.get(idx).is_some_and(Option::is_some)
+    }
+
+    /// Records a value of the option at `idx` in [`OPTIONS`]. Flags and counts record an empty
+    /// value every time they are given.
+    pub fn record(&mut self, idx: usize, source: OptionSource, value: String) {
+        if self.given.len() <= idx {
+            self.given.resize(OPTIONS.len(), None);
+        }
+        self.given[idx].get_or_insert_with(|| (source, Vec::new())).1.push(value);
+    }
+
+    /// Values of the option named `name` and where they came from.
+    pub fn get(&self, name: &str) -> Option<(OptionSource, &[String])> {
+        let (idx, _) = OptionDef::find(name)?;
+        let (source, values) = self.given.get(idx)?.as_ref()?;
+        Some((*source, values))
+    }
+}
+
+impl fmt::Display for EffectiveConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let env_vars: Vec<_> = OPTIONS.iter().map(OptionDef::env_var).collect();
+        let name_width = OPTIONS.iter().map(|x| x.name.len()).max().unwrap_or_default();
+        let env_width = env_vars.iter().map(String::len).max().unwrap_or_default();
+        for (idx, def) in OPTIONS.iter().enumerate() {
+            write!(f, "{:<name_width$}  {:<env_width$}  ", def.name, env_vars[idx])?;
+            let Some((source, values)) = self.given.get(idx).and_then(Option::as_ref) else {
+                writeln!(f, "default")?;
+                continue;
+            };
+            match def.arity {
+                OptionArity::Flag => f.write_str("true")?,
+                OptionArity::Count => write!(f, "{}", values.len())?,
+                OptionArity::Value => f.write_str(values.last().map_or("", String::as_str))?,
+                OptionArity::Repeated => f.write_str(&values.join(", "))?,
+            }
+            match source {
+                OptionSource::CommandLine => writeln!(f, "  (command line)")?,
+                OptionSource::Env => writeln!(f, "  (environment)")?,
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,437 @@
+use std::{path::PathBuf, time::Duration};
+
+use serde_json::{Value, json};
+use type_info::{Diagnostic, TSProgram};
+
+use crate::{
+    baseline::{BaselineParseError, BaselineSource},
+    report::TestReport,
+};
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum OutcomeKind {
+    Passed,
+    Failed,
+    /// `TSProgram` could not be created, nothing was compared.
+    ProgramError,
+    /// The test exceeded a safety limit (e.g. AST nesting depth) and was abandoned.
+    ResourceLimit,
+    /// The oxc parser gave up on a file the TS suite expects to parse with errors.
+    ParserPanicked,
+    /// One of the baselines could not be parsed, nothing was run. A bug in our parser rather
+    /// than a checker mismatch.
+    BaselineUnparsable,
+}
+
+impl OutcomeKind {
+    pub const ALL: &[OutcomeKind] = &[
+        OutcomeKind::Passed,
+        OutcomeKind::Failed,
+        OutcomeKind::ProgramError,
+        OutcomeKind::ResourceLimit,
+        OutcomeKind::ParserPanicked,
+        OutcomeKind::BaselineUnparsable,
+    ];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            OutcomeKind::Passed => "passed",
+            OutcomeKind::Failed => "failed",
+            OutcomeKind::ProgramError => "program_error",
+            OutcomeKind::ResourceLimit => "resource_limit",
+            OutcomeKind::ParserPanicked => "parser_panicked",
+            OutcomeKind::BaselineUnparsable => "baseline_unparsable",
+        }
+    }
+
+    /// Inverse of [`Self::as_str`].
+    pub fn parse(value: &str) -> Option<Self> {
+        Self::ALL.iter().copied().find(|x| x.as_str() == value)
+    }
+}
+
+impl std::fmt::Display for OutcomeKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy)]
+pub struct AssertionStats {
+    pub checked: usize,
+    pub matched: usize,
+    /// Assertions paired with a node only after normalizing whitespace in the expression.
+    pub normalized: usize,
+}
+
+impl AssertionStats {
+    pub fn mismatched(&self) -> usize {
+        self.checked - self.matched
+    }
+}
+
+impl std::ops::AddAssign for AssertionStats {
+    fn add_assign(&mut self, rhs: Self) {
+        self.checked += rhs.checked;
+        self.matched += rhs.matched;
+        self.normalized += rhs.normalized;
+    }
+}
+
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy)]
+pub struct ErrorStats {
+    pub matched: usize,
+    pub missing: usize,
+    pub unexpected: usize,
+}
+
+impl std::ops::AddAssign for ErrorStats {
+    fn add_assign(&mut self, rhs: Self) {
+        self.matched += rhs.matched;
+        self.missing += rhs.missing;
+        self.unexpected += rhs.unexpected;
+    }
+}
+
+/// How the baselines of the `--compare-repo` checkout relate to the primary ones.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum ExpectedChange {
+    /// Both checkouts expect the same types and errors.
+    Same,
+    /// The checkouts expect different types or errors.
+    Different,
+    /// The other checkout has no baseline for the variant.
+    Missing,
+}
+
+impl ExpectedChange {
+    pub const ALL: &[ExpectedChange] =
+        &[ExpectedChange::Same, ExpectedChange::Different, ExpectedChange::Missing];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ExpectedChange::Same => "same",
+            ExpectedChange::Different => "different",
+            ExpectedChange::Missing => "missing",
+        }
+    }
+}
+
+impl std::fmt::Display for ExpectedChange {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Result of comparing the same actual types and errors against the `--compare-repo` baselines.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct RepoComparison {
+    pub expected: ExpectedChange,
+    /// Outcome against the other checkout's baselines, if it has them.
+    pub other: Option<OutcomeKind>,
+}
+
+impl RepoComparison {
+    /// Whether the variant would pass in one checkout and not in the other.
+    pub fn outcome_changed(&self, kind: OutcomeKind) -> bool {
+        self.other.is_some_and(|x| (x == OutcomeKind::Passed) != (kind == OutcomeKind::Passed))
+    }
+
+    /// Whether the variant passes against the other checkout's baselines but not against the
+    /// primary ones.
+    pub fn regressed(&self, kind: OutcomeKind) -> bool {
+        self.other == Some(OutcomeKind::Passed) && kind != OutcomeKind::Passed
+    }
+}
+
+/// How the root files of a multi-file test were picked.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum EntryStrategy {
+    /// Every file of the unit is a root file.
+    AllFiles,
+    /// Only the last file, because of `@noImplicitReferences`.
+    LastFileOnly,
+    /// The unit has a `tsconfig.json`, which decides the root files.
+    TsconfigDriven,
+    /// Only the last file, which pulls in the others with `require(` or `/// <reference path`.
+    ReferenceClosure,
+}
+
+impl EntryStrategy {
+    pub const ALL: &[EntryStrategy] = &[
+        EntryStrategy::AllFiles,
+        EntryStrategy::LastFileOnly,
+        EntryStrategy::TsconfigDriven,
+        EntryStrategy::ReferenceClosure,
+    ];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            EntryStrategy::AllFiles => "all_files",
+            EntryStrategy::LastFileOnly => "last_file_only",
+            EntryStrategy::TsconfigDriven => "tsconfig_driven",
+            EntryStrategy::ReferenceClosure => "reference_closure",
+        }
+    }
+}
+
+impl std::fmt::Display for EntryStrategy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// A file the oxc parser gave up on, see [`OutcomeKind::ParserPanicked`].
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct ParserPanic {
+    pub file: String,
+    /// Diagnostics reported before the parser gave up.
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+/// Cost of building the program of a variant with `--timings`, summed over its root files.
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy)]
+pub struct ProgramTiming {
+    pub parse: Duration,
+    pub semantic: Duration,
+    /// Bytes of source text.
+    pub source_len: usize,
+    /// Bytes of the allocator used by the modules.
+    pub allocated: usize,
+}
+
+impl ProgramTiming {
+    pub fn of(program: &TSProgram<'_>) -> Self {
+        let mut result = Self::default();
+        for stats in program.root_modules().filter_map(|id| program.stats.get(id)) {
+            result.parse += stats.parse;
+            result.semantic += stats.semantic;
+            result.source_len += stats.source_len;
+            result.allocated += stats.allocated;
+        }
+        result
+    }
+
+    pub fn total(&self) -> Duration {
+        self.parse + self.semantic
+    }
+}
+
+/// Result of running a single test variant.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct TestOutcome {
+    /// Test case path relative to the repo.
+    pub path: PathBuf,
+    pub variant: String,
+    pub kind: OutcomeKind,
+    pub assertions: AssertionStats,
+    pub errors: ErrorStats,
+    pub baseline: BaselineSource,
+    /// Set when running with `--compare-repo`.
+    pub compare: Option<RepoComparison>,
+    /// Set when the parser panicked, even if the errors baseline still matched.
+    pub parser_panic: Option<ParserPanic>,
+    /// Set when the outcome is [`OutcomeKind::BaselineUnparsable`].
+    pub baseline_error: Option<BaselineParseError>,
+    /// How the root files were picked, once known.
+    pub entry_strategy: Option<EntryStrategy>,
+    /// Files the comparison was restricted to with `--only-file`. Empty when every file was
+    /// compared; a pass with files listed here is not a pass of the whole variant.
+    pub only_files: Vec<String>,
+    /// Set with `--timings` once the program is built.
+    pub timing: Option<ProgramTiming>,
+    /// What the runner prints for the variant, written out in the run's
+    /// [`RunOptions::output_order`](crate::RunOptions::output_order).
+    pub log: String,
+}
+
+impl TestOutcome {
+    pub fn from_report(report: &TestReport<'_>) -> Self {
+        let mut errors = ErrorStats::default();
+        for comparison in report.error_comparisons() {
+            errors += ErrorStats {
+                matched: comparison.matched.len(),
+                missing: comparison.missing.len(),
+                unexpected: comparison.unexpected.len(),
+            };
+        }
+
+        let mut assertions = AssertionStats::default();
+        for module in &report.modules {
+            assertions += AssertionStats {
+                checked: module.types.checked(),
+                matched: module.types.matched(),
+                normalized: module.types.normalized(),
+            };
+        }
+
+        Self {
+            path: report.path.to_path_buf(),
+            variant: report.variant.to_string(),
+            kind: if report.is_match() { OutcomeKind::Passed } else { OutcomeKind::Failed },
+            assertions,
+            errors,
+            baseline: report.baseline.clone(),
+            compare: None,
+            parser_panic: None,
+            baseline_error: None,
+            entry_strategy: None,
+            only_files: Vec::new(),
+            timing: None,
+            log: String::new(),
+        }
+    }
+
+    /// Outcome of a variant whose baselines could not be parsed. `source` lists the baseline
+    /// files that were read.
+    pub fn unparsable(
+        path: PathBuf,
+        variant: String,
+        source: BaselineSource,
+        error: BaselineParseError,
+    ) -> Self {
+        Self {
+            path,
+            variant,
+            kind: OutcomeKind::BaselineUnparsable,
+            assertions: AssertionStats::default(),
+            errors: ErrorStats::default(),
+            baseline: source,
+            compare: None,
+            parser_panic: None,
+            baseline_error: Some(error),
+            entry_strategy: None,
+            only_files: Vec::new(),
+            timing: None,
+            log: String::new(),
+        }
+    }
+
+    pub fn to_json(&self) -> Value {
+        json!({
+            "path": self.path,
+            "variant": self.variant,
+            "outcome": self.kind.as_str(),
+            "entry_strategy": self.entry_strategy.map(EntryStrategy::as_str),
+            "only_files": self.only_files,
+            "assertions": {
+                "checked": self.assertions.checked,
+                "matched": self.assertions.matched,
+                "normalized": self.assertions.normalized,
+            },
+            "errors": {
+                "matched": self.errors.matched,
+                "missing": self.errors.missing,
+                "unexpected": self.errors.unexpected,
+            },
+            "baseline": {
+                "types": self.baseline.types,
+                "errors": self.baseline.errors,
+                "symbols": self.baseline.symbols,
+                "pretty_errors": self.baseline.pretty_errors,
+                "crlf": self.baseline.crlf,
+            },
+            "compare": self.compare.map(|x| json!({
+                "expected": x.expected.as_str(),
+                "outcome": x.other.map(OutcomeKind::as_str),
+            })),
+            "parser_panic": self.parser_panic.as_ref().map(|x| json!({
+                "file": x.file,
+                "diagnostic": x.diagnostics.first().map(|x| x.message.as_str()),
+            })),
+            "baseline_error": self.baseline_error.as_ref().map(|x| json!({
+                "which": x.which.as_str(),
+                "path": x.path,
+                "line": x.line,
+                "offset": x.offset,
+                "error": x.error,
+                "snippet": x.snippet,
+            })),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::baseline::BaselineKind;
+
+    #[test]
+    fn json_baseline() {
+        let outcome = TestOutcome {
+            path: PathBuf::from("tests/cases/compiler/a.ts"),
+            variant: "(strict=true)".to_string(),
+            kind: OutcomeKind::Failed,
+            assertions: AssertionStats { checked: 2, matched: 1, normalized: 0 },
+            errors: ErrorStats::default(),
+            baseline: BaselineSource {
+                types: PathBuf::from("tests/baselines/reference/a(strict=true).types"),
+                errors: None,
+                symbols: Some(PathBuf::from("tests/baselines/reference/a(strict=true).symbols")),
+                pretty_errors: false,
+                crlf: true,
+            },
+            compare: Some(RepoComparison {
+                expected: ExpectedChange::Different,
+                other: Some(OutcomeKind::Passed),
+            }),
+            parser_panic: None,
+            baseline_error: None,
+            entry_strategy: Some(EntryStrategy::ReferenceClosure),
+            only_files: vec!["a.ts".to_string()],
+            timing: None,
+            log: String::new(),
+        };
+
+        let json = outcome.to_json();
+        assert_eq!(json["outcome"], "failed");
+        assert_eq!(json["entry_strategy"], "reference_closure");
+        assert_eq!(json["only_files"], json!(["a.ts"]));
+        assert_eq!(
+            json["baseline"],
+            json!({
+                "types": "tests/baselines/reference/a(strict=true).types",
+                "errors": null,
+                "symbols": "tests/baselines/reference/a(strict=true).symbols",
+                "pretty_errors": false,
+                "crlf": true,
+            })
+        );
+        assert_eq!(json["compare"], json!({ "expected": "different", "outcome": "passed" }));
+        assert!(outcome.compare.unwrap().outcome_changed(outcome.kind));
+        assert_eq!(json["parser_panic"], Value::Null);
+        assert_eq!(json["baseline_error"], Value::Null);
+    }
+
+    #[test]
+    fn json_baseline_error() {
+        let outcome = TestOutcome::unparsable(
+            PathBuf::from("tests/cases/compiler/a.ts"),
+            String::new(),
+            BaselineSource::default(),
+            BaselineParseError {
+                which: BaselineKind::Errors,
+                path: PathBuf::from("tests/baselines/reference/a.errors.txt"),
+                offset: Some(5),
+                line: Some(1),
+                error: "Failed to find end of file name".to_string(),
+                underline: None,
+                snippet: None,
+            },
+        );
+
+        let json = outcome.to_json();
+        assert_eq!(json["outcome"], "baseline_unparsable");
+        assert_eq!(
+            json["baseline_error"],
+            json!({
+                "which": "errors",
+                "path": "tests/baselines/reference/a.errors.txt",
+                "line": 1,
+                "offset": 5,
+                "error": "Failed to find end of file name",
+                "snippet": null,
+            })
+        );
+    }
+}
@@ -0,0 +1,211 @@
+use std::{
+    collections::VecDeque,
+    io::Write,
+    sync::{Condvar, Mutex, PoisonError},
+};
+
+/// Order the per-variant output of a run is printed in, see [`RunOptions::output_order`].
+///
+/// [`RunOptions::output_order`]: crate::RunOptions::output_order
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum OutputOrder {
+    /// As variants finish. The default on a terminal.
+    Completion,
+    /// In discovery order, whatever order variants finish in, so the logs of two runs can be
+    /// diffed. The default when the output is redirected.
+    Discovery,
+}
+
+impl OutputOrder {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "completion" => Some(Self::Completion),
+            "discovery" => Some(Self::Discovery),
+            _ => None,
+        }
+    }
+}
+
+/// Output of the variants of a test file, waiting for the files before it.
+#[derive(Debug, Default)]
+struct PendingFile {
+    /// Logs by the position of their variant among the unit's variations.
+    logs: Vec<(usize, String)>,
+    /// Number of variants sent to the workers, once the file is read.
+    expected: Option<usize>,
+}
+
+impl PendingFile {
+    fn is_complete(&self) -> bool {
+        self.expected == Some(self.logs.len())
+    }
+}
+
+#[derive(Debug, Default)]
+struct State {
+    /// First file whose output is not written yet.
+    next_file: usize,
+    /// Files from `next_file` on.
+    pending: VecDeque<PendingFile>,
+    /// A reader or worker panicked, the output of the files it held never arrives.
+    abandoned: bool,
+}
+
+/// Writes the logs of [`TestOutcome`]s in the [`OutputOrder`] of the run.
+///
+/// In discovery order logs are buffered until every file before theirs is written. Readers ask
+/// for a slot before reading a file, so at most `window` files are buffered at once.
+///
+/// [`TestOutcome`]: crate::TestOutcome
+pub struct Output<W: Write> {
+    order: OutputOrder,
+    window: usize,
+    state: Mutex<State>,
+    /// Signalled whenever files are written or the output is abandoned.
+    written: Condvar,
+    out: Mutex<W>,
+}
+
+impl<W: Write> Output<W> {
+    pub fn new(order: OutputOrder, window: usize, out: W) -> Self {
+        Self {
+            order,
+            window: window.max(1),
+            state: Mutex::new(State::default()),
+            written: Condvar::new(),
+            out: Mutex::new(out),
+        }
+    }
+
+    /// Blocks until the output of `file` can be buffered. Returns `false` once the output is
+    /// abandoned, and the file should not be read.
+    ///
+    /// # Panics
+    ///
+    /// When a thread panicked while holding the lock
+    pub fn wait_for_slot(&self, file: usize) -> bool {
+        if self.order == OutputOrder::Completion {
+            return true;
+        }
+        let state = self.state.lock().expect("no thread to panic while holding the output");
+        let state = self
+            .written
+            .wait_while(state, |x| !x.abandoned && file >= x.next_file + self.window)
+            .expect("no thread to panic while holding the output");
+        !state.abandoned
+    }
+
+    /// Records that `variants` variants of `file` were sent to the workers.
+    ///
+    /// # Panics
+    ///
+    /// When a thread panicked while holding the lock
+    pub fn file_read(&self, file: usize, variants: usize) {
+        if self.order == OutputOrder::Completion {
+            return;
+        }
+        let mut state = self.state.lock().expect("no thread to panic while holding the output");
+        state.pending_file(file).expected = Some(variants);
+        self.flush(&mut state);
+    }
+
+    /// Writes the log of the variant at `variant` of `file`, or buffers it until it is its turn.
+    ///
+    /// # Panics
+    ///
+    /// When writing fails
+    pub fn write(&self, file: usize, variant: usize, log: String) {
+        if self.order == OutputOrder::Completion {
+            self.write_all(&log);
+            return;
+        }
+        let mut state = self.state.lock().expect("no thread to panic while holding the output");
+        state.pending_file(file).logs.push((variant, log));
+        self.flush(&mut state);
+    }
+
+    /// Wakes up readers waiting for a slot, after a thread panicked. The panic may have happened
+    /// while writing, so a poisoned lock is taken over.
+    pub fn abandon(&self) {
+        self.state.lock().unwrap_or_else(PoisonError::into_inner).abandoned = true;
+        self.written.notify_all();
+    }
+
+    /// # Panics
+    ///
+    /// When a thread panicked while writing
+    pub fn into_inner(self) -> W {
+        self.out.into_inner().expect("no thread to panic while writing")
+    }
+
+    /// Writes the complete files at the front of the buffer.
+    fn flush(&self, state: &mut State) {
+        let mut written = false;
+        while state.pending.front().is_some_and(PendingFile::is_complete) {
+            let mut file = state.pending.pop_front().expect("front file to exist");
+            file.logs.sort_unstable_by_key(|&(variant, _)| variant);
+            for (_, log) in &file.logs {
+                self.write_all(log);
+            }
+            state.next_file += 1;
+            written = true;
+        }
+        if written {
+            self.written.notify_all();
+        }
+    }
+
+    fn write_all(&self, log: &str) {
+        let mut out = self.out.lock().expect("no thread to panic while writing");
+        if let Err(err) = out.write_all(log.as_bytes()) {
+            panic!("Failed to write test output:\n  error: {err}");
+        }
+    }
+}
+
+impl State {
+    fn pending_file(&mut self, file: usize) -> &mut PendingFile {
+        let idx = file - self.next_file;
+        if self.pending.len() <= idx {
+            self.pending.resize_with(idx + 1, PendingFile::default);
+        }
+        &mut self.pending[idx]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn discovery_order() {
+        let output = Output::new(OutputOrder::Discovery, 2, Vec::new());
+        assert!(output.wait_for_slot(1));
+        output.write(1, 0, "b0\n".to_string());
+        output.file_read(1, 1);
+        output.write(0, 1, "a1\n".to_string());
+        output.file_read(0, 2);
+        output.write(0, 0, "a0\n".to_string());
+        // A file without variants does not hold the ones after it back
+        output.file_read(2, 0);
+        output.write(3, 0, "d0\n".to_string());
+        output.file_read(3, 1);
+        assert_eq!(String::from_utf8(output.into_inner()).unwrap(), "a0\na1\nb0\nd0\n");
+
+        let output = Output::new(OutputOrder::Completion, 2, Vec::new());
+        output.write(1, 0, "b0\n".to_string());
+        output.write(0, 0, "a0\n".to_string());
+        assert_eq!(String::from_utf8(output.into_inner()).unwrap(), "b0\na0\n");
+    }
+
+    #[test]
+    fn abandon() {
+        let output = Output::new(OutputOrder::Discovery, 1, Vec::new());
+        assert!(output.wait_for_slot(0));
+        std::thread::scope(|s| {
+            let reader = s.spawn(|| output.wait_for_slot(1));
+            output.abandon();
+            assert!(!reader.join().unwrap());
+        });
+    }
+}
@@ -0,0 +1,224 @@
+use std::path::{Path, PathBuf};
+
+use serde_json::Value;
+
+use crate::human::HumanCount;
+
+/// Number of examples kept per warning kind in [`ParseWarningStats`].
+const EXAMPLES_PER_KIND: usize = 3;
+
+/// Format gap a parser worked around instead of panicking.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum ParseWarningKind {
+    /// Errors baseline hint indented with an odd number of spaces; its depth was rounded down.
+    OddHintIndent,
+    /// Types baseline underline does not line up with a ` : ` of its assertion; the delimiter
+    /// was guessed.
+    UnalignedUnderline,
+    /// Types or symbols baseline assertion without ` : ` (` : Symbol(`); the assertion was
+    /// skipped.
+    MissingDelimiter,
+    /// Test directive expecting `true` or `false` got something else; the default was kept.
+    InvalidBoolean,
+    /// `@link` directive without a target; the symlink was skipped.
+    MalformedLink,
+    /// Errors baseline summary line continuing the message of the previous error; it was
+    /// appended to the message.
+    WrappedMessage,
+    /// Errors baseline hint or wrapped message line before any error; it was dropped.
+    OrphanHint,
+    /// Errors baseline config error without `:` after its code; the message was left empty.
+    MalformedConfigError,
+    /// Test case file whose content is not UTF-8; the file was left out of the unit.
+    InvalidUtf8,
+    /// Test directive listing more values than a unit may vary over; the extra values were
+    /// dropped.
+    TooManyVariationValues,
+    /// Test variant scheduled a second time, through overlapping case roots, symlinks or
+    /// variants of a unit with the same name; it was run once.
+    DuplicateScheduled,
+    /// Errors baseline file header or pretty total declaring another number of errors than
+    /// were parsed; the parsed errors were kept.
+    ErrorCountMismatch,
+    /// Symbols baseline assertion whose symbol is not `Symbol(name, Decl(file, line, column))`;
+    /// the assertion was skipped.
+    MalformedSymbol,
+    /// Symbols baseline the parser gave up on; the variant was run without symbols.
+    UnparsableSymbols,
+}
+
+impl ParseWarningKind {
+    pub const ALL: &[ParseWarningKind] = &[
+        ParseWarningKind::OddHintIndent,
+        ParseWarningKind::UnalignedUnderline,
+        ParseWarningKind::MissingDelimiter,
+        ParseWarningKind::InvalidBoolean,
+        ParseWarningKind::MalformedLink,
+        ParseWarningKind::WrappedMessage,
+        ParseWarningKind::OrphanHint,
+        ParseWarningKind::MalformedConfigError,
+        ParseWarningKind::InvalidUtf8,
+        ParseWarningKind::TooManyVariationValues,
+        ParseWarningKind::DuplicateScheduled,
+        ParseWarningKind::ErrorCountMismatch,
+        ParseWarningKind::MalformedSymbol,
+        ParseWarningKind::UnparsableSymbols,
+    ];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ParseWarningKind::OddHintIndent => "odd_hint_indent",
+            ParseWarningKind::UnalignedUnderline => "unaligned_underline",
+            ParseWarningKind::MissingDelimiter => "missing_delimiter",
+            ParseWarningKind::InvalidBoolean => "invalid_boolean",
+            ParseWarningKind::MalformedLink => "malformed_link",
+            ParseWarningKind::WrappedMessage => "wrapped_message",
+            ParseWarningKind::OrphanHint => "orphan_hint",
+            ParseWarningKind::MalformedConfigError => "malformed_config_error",
+            ParseWarningKind::InvalidUtf8 => "invalid_utf8",
+            ParseWarningKind::TooManyVariationValues => "too_many_variation_values",
+            ParseWarningKind::DuplicateScheduled => "duplicate_scheduled",
+            ParseWarningKind::ErrorCountMismatch => "error_count_mismatch",
+            ParseWarningKind::MalformedSymbol => "malformed_symbol",
+            ParseWarningKind::UnparsableSymbols => "unparsable_symbols",
+        }
+    }
+}
+
+impl std::fmt::Display for ParseWarningKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// A spot where a test case or baseline did not match the expected format.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct ParseWarning {
+    pub kind: ParseWarningKind,
+    pub path: PathBuf,
+    /// 1-based line number.
+    pub line: usize,
+    /// The offending line.
+    pub snippet: String,
+}
+
+impl ParseWarning {
+    pub fn new(kind: ParseWarningKind, path: &Path, line: usize, snippet: &[u8]) -> Self {
+        let snippet = String::from_utf8_lossy(snippet).trim_end_matches('\r').to_string();
+        Self { kind, path: path.to_path_buf(), line, snippet }
+    }
+}
+
+impl std::fmt::Display for ParseWarning {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{}: {}", self.path.display(), self.line, self.snippet.escape_debug())
+    }
+}
+
+/// Parse warnings of a run: counts per kind and the first few examples of each.
+#[derive(Debug, Default, Clone)]
+pub struct ParseWarningStats {
+    /// Number of warnings per kind, indexed like [`ParseWarningKind::ALL`].
+    pub counts: [usize; ParseWarningKind::ALL.len()],
+    pub examples: Vec<ParseWarning>,
+}
+
+impl ParseWarningStats {
+    pub fn add<'a>(&mut self, warnings: impl IntoIterator<Item = &'a ParseWarning>) {
+        for warning in warnings {
+            let count = &mut self.counts[warning.kind as usize];
+            *count += 1;
+            if *count <= EXAMPLES_PER_KIND {
+                self.examples.push(warning.clone());
+            }
+        }
+    }
+
+    pub fn merge(&mut self, other: &Self) {
+        for kind in ParseWarningKind::ALL {
+            let count = self.count(*kind);
+            let room = EXAMPLES_PER_KIND.saturating_sub(count);
+            self.examples
+                .extend(other.examples.iter().filter(|x| x.kind == *kind).take(room).cloned());
+        }
+        for (count, other) in self.counts.iter_mut().zip(other.counts) {
+            *count += other;
+        }
+    }
+
+    pub fn count(&self, kind: ParseWarningKind) -> usize {
+        self.counts[kind as usize]
+    }
+
+    pub fn total(&self) -> usize {
+        self.counts.iter().sum()
+    }
+
+    /// Kinds that occurred, most frequent first.
+    pub fn top_kinds(&self) -> Vec<ParseWarningKind> {
+        let mut kinds: Vec<_> =
+            ParseWarningKind::ALL.iter().copied().filter(|&x| self.count(x) > 0).collect();
+        kinds.sort_by_key(|&x| std::cmp::Reverse(self.count(x)));
+        kinds
+    }
+
+    pub fn to_json(&self) -> Value {
+        ParseWarningKind::ALL
+            .iter()
+            .map(|&x| (x.as_str().to_string(), self.count(x).into()))
+            .collect::<serde_json::Map<_, _>>()
+            .into()
+    }
+}
+
+impl std::fmt::Display for ParseWarningStats {
+    /// Top kinds with their examples, one per line.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "{} parse warnings:", HumanCount(self.total()))?;
+        for kind in self.top_kinds() {
+            writeln!(f, "  {} {kind}", HumanCount(self.count(kind)))?;
+            for example in self.examples.iter().filter(|x| x.kind == kind) {
+                writeln!(f, "    {example}")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stats() {
+        let warning = |kind, line| ParseWarning::new(kind, Path::new("a.ts"), line, b"// @x: y\r");
+        let mut first = ParseWarningStats::default();
+        first.add(
+            &(1..=4).map(|x| warning(ParseWarningKind::InvalidBoolean, x)).collect::<Vec<_>>(),
+        );
+        let mut second = ParseWarningStats::default();
+        second.add(&[
+            warning(ParseWarningKind::MalformedLink, 7),
+            warning(ParseWarningKind::InvalidBoolean, 9),
+        ]);
+
+        first.merge(&second);
+        assert_eq!(first.total(), 6);
+        assert_eq!(first.count(ParseWarningKind::InvalidBoolean), 5);
+        assert_eq!(
+            first.top_kinds(),
+            vec![ParseWarningKind::InvalidBoolean, ParseWarningKind::MalformedLink]
+        );
+        assert_eq!(
+            first.to_string(),
+            "6 parse warnings:
+  5 invalid_boolean
+    a.ts:1: // @x: y
+    a.ts:2: // @x: y
+    a.ts:3: // @x: y
+  1 malformed_link
+    a.ts:7: // @x: y
+"
+        );
+    }
+}
@@ -0,0 +1,485 @@
+use std::{
+    io::Stdout,
+    path::{Path, PathBuf},
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicUsize, Ordering},
+        mpsc::{Receiver, SyncSender, sync_channel},
+    },
+    thread::{Scope, ScopedJoinHandle},
+    time::Instant,
+};
+
+use oxc::allocator::Allocator;
+use rustc_hash::FxHashSet;
+use type_info::{LibStore, ProgramCache};
+
+use crate::{
+    Baseline, BaselineSource, ExpectedChange, OutcomeKind, ParseWarning, ParseWarningKind,
+    RepoComparison, RunOptions, RunSummary, Sinks, TestOutcome, TestUnit, TestVariant,
+    compare::Scratch,
+    discover::{baseline_candidates, is_emit_only, is_lfs_pointer, read_file, read_test_file},
+    ledger::SkipReason,
+    output::Output,
+    runner::relative_path,
+};
+
+/// Thread pool sizes of [`run_pipeline`].
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct PipelineConfig {
+    /// Threads reading test cases and baselines.
+    pub readers: usize,
+    /// Threads running the type checker.
+    pub workers: usize,
+    /// Number of read variants waiting for a worker before readers block.
+    pub bound: usize,
+    /// Stack size of worker threads in bytes.
+    pub stack_size: usize,
+}
+
+/// Units (with no variant) and variants scheduled so far, by canonical unit path, so no test
+/// runs twice, see [`ParseWarningKind::DuplicateScheduled`].
+type Scheduled = Mutex<FxHashSet<(PathBuf, Option<String>)>>;
+
+/// Test case source shared by all variants of the unit.
+struct UnitSource {
+    path: PathBuf,
+    data: Vec<u8>,
+}
+
+/// A variant with its baselines read, ready to be parsed and run.
+struct WorkItem {
+    unit: Arc<UnitSource>,
+    /// Position of the unit among the files of the run.
+    file: usize,
+    /// Position of the variant among the unit's variations.
+    variant: usize,
+    types_file: PathBuf,
+    types_data: String,
+    errors_file: PathBuf,
+    errors_data: Option<String>,
+    symbols_file: PathBuf,
+    symbols_data: Option<String>,
+    /// Baselines of the variant in the `--compare-repo` checkout.
+    compare: Option<OtherBaseline>,
+}
+
+/// Baselines read from the `--compare-repo` checkout, paths relative to that checkout.
+struct OtherBaseline {
+    types_file: PathBuf,
+    types_data: String,
+    errors_file: PathBuf,
+    errors_data: Option<String>,
+}
+
+/// Reads `files` on a pool of reader threads and runs their variants on a pool of workers.
+///
+/// Readers hand variants over through a bounded channel, so they stay at most
+/// [`PipelineConfig::bound`] variants ahead of the workers. Time readers spend blocked on a full
+/// channel and workers spend waiting on an empty one is added to `summary`. Every outcome is
+/// recorded into `sinks` as soon as it is produced, its log is printed in the
+/// [`RunOptions::output_order`]; in discovery order readers stay at most
+/// [`PipelineConfig::bound`] files ahead of the output.
+///
+/// Consecutive variants of a unit a worker runs are given the same [`ProgramCache`], so they
+/// parse its files once. Every unit a worker runs is given the same [`LibStore`], so lib files
+/// are parsed once per worker.
+///
+/// # Panics
+///
+/// Re-raises the panic of any reader or worker thread.
+pub fn run_pipeline<F>(
+    options: &RunOptions,
+    files: &[PathBuf],
+    config: PipelineConfig,
+    run: &F,
+    sinks: &Sinks,
+    summary: &mut RunSummary,
+) where
+    F: Fn(
+            &TestUnit<'_>,
+            &TestVariant<'_>,
+            &Baseline<'_>,
+            &RunOptions,
+            &ProgramCache<'_>,
+            &LibStore,
+            &mut Scratch,
+        ) -> TestOutcome
+        + Sync,
+{
+    let next_file = AtomicUsize::new(0);
+    let (sender, receiver) = sync_channel::<WorkItem>(config.bound);
+    let receiver = Arc::new(Mutex::new(receiver));
+    let output = Output::new(options.output_order(), config.bound, std::io::stdout());
+    let scheduled = Scheduled::default();
+
+    let partial: Vec<RunSummary> = std::thread::scope(|s| {
+        let mut threads = Vec::with_capacity(config.readers + config.workers);
+        for _ in 0..config.readers.max(1) {
+            let sender = sender.clone();
+            let (next_file, output, scheduled) = (&next_file, &output, &scheduled);
+            threads.push(
+                s.spawn(move || read_files(options, files, next_file, &sender, output, scheduled)),
+            );
+        }
+        drop(sender);
+
+        threads.extend(spawn_workers(s, config, || {
+            let receiver = Arc::clone(&receiver);
+            let output = &output;
+            move || run_items(options, &receiver, run, sinks, output)
+        }));
+        drop(receiver);
+
+        join_all(threads)
+    });
+
+    for x in &partial {
+        summary.merge(x);
+    }
+}
+
+/// Spawns the [`PipelineConfig::workers`] threads running the type checker in `s`, with the
+/// configured stack size. Each one runs a closure `worker` returns.
+pub fn spawn_workers<'scope, W, R>(
+    s: &'scope Scope<'scope, '_>,
+    config: PipelineConfig,
+    mut worker: impl FnMut() -> W,
+) -> Vec<ScopedJoinHandle<'scope, R>>
+where
+    W: FnOnce() -> R + Send + 'scope,
+    R: Send + 'scope,
+{
+    let mut threads = Vec::with_capacity(config.workers.max(1));
+    for _ in 0..config.workers.max(1) {
+        let thread = std::thread::Builder::new()
+            .name("worker".to_string())
+            .stack_size(config.stack_size)
+            .spawn_scoped(s, worker())
+            .expect("failed to spawn worker thread");
+        threads.push(thread);
+    }
+    threads
+}
+
+/// Results of `threads`, re-raising the panic of any of them.
+pub fn join_all<R>(threads: Vec<ScopedJoinHandle<'_, R>>) -> Vec<R> {
+    threads
+        .into_iter()
+        .map(|x| x.join().unwrap_or_else(|err| std::panic::resume_unwind(err)))
+        .collect()
+}
+
+/// Abandons the output when its thread panics, so readers waiting for a slot do not wait on
+/// output that never arrives.
+struct AbandonOnPanic<'a>(&'a Output<Stdout>);
+
+impl Drop for AbandonOnPanic<'_> {
+    fn drop(&mut self) {
+        if std::thread::panicking() {
+            self.0.abandon();
+        }
+    }
+}
+
+fn read_files(
+    options: &RunOptions,
+    files: &[PathBuf],
+    next_file: &AtomicUsize,
+    sender: &SyncSender<WorkItem>,
+    output: &Output<Stdout>,
+    scheduled: &Scheduled,
+) -> RunSummary {
+    let _guard = AbandonOnPanic(output);
+    let mut summary = RunSummary::default();
+    loop {
+        let file = next_file.fetch_add(1, Ordering::Relaxed);
+        let Some(test_file) = files.get(file) else {
+            break;
+        };
+        if !output.wait_for_slot(file) {
+            break;
+        }
+        let Some(sent) = read_unit(options, test_file, file, sender, scheduled, &mut summary)
+        else {
+            break;
+        };
+        output.file_read(file, sent);
+    }
+    summary
+}
+
+/// Sends every variant of the unit to the workers. Returns the number of variants sent, or
+/// `None` once all workers are gone.
+fn read_unit(
+    options: &RunOptions,
+    test_file: &Path,
+    file: usize,
+    sender: &SyncSender<WorkItem>,
+    scheduled: &Scheduled,
+    summary: &mut RunSummary,
+) -> Option<usize> {
+    // Resolves `./`, `..` and symlinks, so a unit reached through two case roots is one unit
+    let canonical = test_file.canonicalize().unwrap_or_else(|_| test_file.to_path_buf());
+    let schedule = |variant: Option<&str>| {
+        let key = (canonical.clone(), variant.map(String::from));
+        scheduled.lock().expect("no reader to panic while scheduling").insert(key)
+    };
+    // The warnings and skips of a unit read again are already counted
+    let first_read = schedule(None);
+    summary.units += usize::from(first_read);
+    let repo = options.repo.as_path();
+    let Ok(data) = read_test_file(test_file) else {
+        panic!("Failed to read test file: {}", relative_path(test_file, repo).display());
+    };
+    let source = Arc::new(UnitSource { path: test_file.to_path_buf(), data });
+    let unit =
+        TestUnit::parse_with_limit(&source.path, &source.data, options.max_variation_values());
+    if first_read {
+        summary.parse_warnings.add(&unit.warnings);
+    }
+    let unit_path = relative_path(test_file, repo);
+    let produced = unit.variations.iter().len();
+    summary.ledger.produced(unit_path, produced);
+    if unit.settings.no_types_and_symbols {
+        summary.ledger.skipped(unit_path, SkipReason::NoTypesAndSymbols, produced);
+        return Some(0);
+    }
+    if unit.settings.multi_pass {
+        summary.multi_pass += usize::from(first_read);
+        summary.ledger.skipped(unit_path, SkipReason::MultiPass, produced);
+        return Some(0);
+    }
+
+    let baseline_root = options.baseline_root();
+    let compare_root = options.compare_baseline_root();
+    let name = test_file.file_stem().expect("path to be a file");
+    let mut sent = 0;
+    for (idx, variant) in unit.variations.iter().enumerate() {
+        if !schedule(Some(&variant.name)) {
+            let name = variant.name.as_bytes();
+            let warning =
+                ParseWarning::new(ParseWarningKind::DuplicateScheduled, test_file, 1, name);
+            summary.parse_warnings.add([&warning]);
+            summary.duplicates += 1;
+            summary.ledger.skipped(unit_path, SkipReason::Duplicate, 1);
+            continue;
+        }
+        if !options.matches_variant(&variant) {
+            summary.filtered += 1;
+            summary.ledger.skipped(unit_path, SkipReason::Filtered, 1);
+            continue;
+        }
+
+        let variant_name = &variant.name;
+        let candidates = baseline_candidates(&baseline_root, name, variant_name);
+        let Ok(types_data) = read_file(&candidates.types) else {
+            if is_emit_only(&baseline_root, name, variant_name) {
+                summary.emit_only += 1;
+                summary.ledger.skipped(unit_path, SkipReason::EmitOnly, 1);
+                continue;
+            }
+            panic!(
+                "Failed to read types baseline file:\n  case: {}\n  baseline: {}\n  variant: {:?}",
+                relative_path(test_file, repo).display(),
+                relative_path(&candidates.types, repo).display(),
+                variant
+            );
+        };
+
+        let errors_data = read_file(&candidates.errors).ok();
+        if is_lfs_pointer(&types_data) || errors_data.as_deref().is_some_and(is_lfs_pointer) {
+            summary.lfs_pointers += 1;
+            summary.ledger.skipped(unit_path, SkipReason::LfsPointer, 1);
+            continue;
+        }
+        // Types and errors are what a variant is checked against, a symbols baseline that was
+        // not fetched only leaves it without symbols
+        let symbols_data = read_file(&candidates.symbols).ok().filter(|x| !is_lfs_pointer(x));
+
+        let compare = options.compare_repo.as_deref().zip(compare_root.as_deref()).and_then(
+            |(compare_repo, root)| {
+                let other = baseline_candidates(root, name, variant_name);
+                let types_data = read_file(&other.types).ok()?;
+                let errors_data = read_file(&other.errors).ok();
+                // Baselines that were not fetched count as missing
+                if is_lfs_pointer(&types_data) || errors_data.as_deref().is_some_and(is_lfs_pointer)
+                {
+                    return None;
+                }
+                Some(OtherBaseline {
+                    types_data,
+                    errors_data,
+                    types_file: relative_path(&other.types, compare_repo).to_path_buf(),
+                    errors_file: relative_path(&other.errors, compare_repo).to_path_buf(),
+                })
+            },
+        );
+
+        let item = WorkItem {
+            unit: Arc::clone(&source),
+            file,
+            variant: idx,
+            types_file: relative_path(&candidates.types, repo).to_path_buf(),
+            types_data,
+            errors_file: relative_path(&candidates.errors, repo).to_path_buf(),
+            errors_data,
+            symbols_file: relative_path(&candidates.symbols, repo).to_path_buf(),
+            symbols_data,
+            compare,
+        };
+
+        let start = Instant::now();
+        let result = sender.send(item);
+        summary.reader_idle += start.elapsed();
+        result.ok()?;
+        sent += 1;
+    }
+
+    Some(sent)
+}
+
+fn run_items<F>(
+    options: &RunOptions,
+    receiver: &Mutex<Receiver<WorkItem>>,
+    run: &F,
+    sinks: &Sinks,
+    output: &Output<Stdout>,
+) -> RunSummary
+where
+    F: Fn(
+        &TestUnit<'_>,
+        &TestVariant<'_>,
+        &Baseline<'_>,
+        &RunOptions,
+        &ProgramCache<'_>,
+        &LibStore,
+        &mut Scratch,
+    ) -> TestOutcome,
+{
+    let _guard = AbandonOnPanic(output);
+    let mut summary = RunSummary::default();
+    let mut scratch = Scratch::default();
+    let libs = LibStore::new();
+    let recv = |summary: &mut RunSummary| {
+        let start = Instant::now();
+        let item = receiver.lock().expect("no worker to panic while waiting").recv();
+        summary.worker_idle += start.elapsed();
+        item.ok()
+    };
+    let mut alloc = Allocator::default();
+    let mut next = recv(&mut summary);
+    while let Some(mut item) = next.take() {
+        // Variants of a unit that reach this worker one after the other share its parsed modules
+        alloc.reset();
+        let cache = ProgramCache::new(&alloc);
+        loop {
+            'item: {
+                let unit = TestUnit::parse_with_limit(
+                    &item.unit.path,
+                    &item.unit.data,
+                    options.max_variation_values(),
+                );
+                let variant = unit.variations.iter().nth(item.variant).expect("variant to exist");
+                let errors_data = item.errors_data.as_ref().map(String::as_bytes);
+                let baseline = Baseline::parse(
+                    &item.types_file,
+                    item.types_data.as_bytes(),
+                    &item.errors_file,
+                    errors_data,
+                    item.symbols_data.as_ref().map(|x| (item.symbols_file.as_path(), x.as_bytes())),
+                );
+                let mut baseline = match baseline {
+                    Ok(baseline) => baseline,
+                    Err(err) => {
+                        let path = relative_path(&item.unit.path, &options.repo);
+                        let log = format!(
+                            "⚠  {}{}  baseline unparsable\n    {err}\n",
+                            path.display(),
+                            variant.name
+                        );
+                        let mut source = BaselineSource::detect(
+                            &item.types_file,
+                            item.types_data.as_bytes(),
+                            &item.errors_file,
+                            errors_data,
+                        );
+                        source.symbols =
+                            item.symbols_data.is_some().then(|| item.symbols_file.clone());
+                        let mut outcome = TestOutcome::unparsable(
+                            path.to_path_buf(),
+                            variant.name.clone(),
+                            source,
+                            err,
+                        );
+                        outcome.compare = compare_unparsable(options, &item);
+                        outcome.log = log;
+                        sinks.record(&outcome);
+                        summary.add(&outcome);
+                        summary.ledger.executed(path);
+                        output.write(item.file, item.variant, outcome.log);
+                        break 'item;
+                    }
+                };
+                summary.parse_warnings.add(baseline.warnings());
+                if let Some(errors) = &baseline.errors {
+                    let mismatches = errors.validate();
+                    let warnings = mismatches.iter().map(|x| x.to_warning(&item.errors_file));
+                    summary.parse_warnings.add(&warnings.collect::<Vec<_>>());
+                }
+                if let Some(limit) = options.slow_baselines {
+                    let errors_size = item.errors_data.as_ref().map_or(0, String::len);
+                    let symbols_size = item.symbols_data.as_ref().map_or(0, String::len);
+                    let types_size = item.types_data.len();
+                    for timing in baseline.timings(types_size, errors_size, symbols_size) {
+                        summary.add_baseline_timing(timing, limit);
+                    }
+                }
+                // An unparsable baseline of the other checkout is treated like a missing one, its
+                // symbols are not compared
+                baseline.compare = item.compare.as_ref().and_then(|other| {
+                    let other = Baseline::parse(
+                        &other.types_file,
+                        other.types_data.as_bytes(),
+                        &other.errors_file,
+                        other.errors_data.as_ref().map(String::as_bytes),
+                        None,
+                    );
+                    other.ok().map(Box::new)
+                });
+
+                let outcome = run(&unit, &variant, &baseline, options, &cache, &libs, &mut scratch);
+                sinks.record(&outcome);
+                summary.add(&outcome);
+                summary.ledger.executed(relative_path(&item.unit.path, &options.repo));
+                output.write(item.file, item.variant, outcome.log);
+            }
+
+            next = recv(&mut summary);
+            match next.take_if(|x| Arc::ptr_eq(&x.unit, &item.unit)) {
+                Some(x) => item = x,
+                None => break,
+            }
+        }
+    }
+    summary
+}
+
+/// Classifies the `--compare-repo` baselines of a variant whose own baselines are unparsable:
+/// identical files are just as unparsable, different ones are not compared.
+fn compare_unparsable(options: &RunOptions, item: &WorkItem) -> Option<RepoComparison> {
+    options.compare_repo.as_ref()?;
+    let Some(other) = &item.compare else {
+        return Some(RepoComparison { expected: ExpectedChange::Missing, other: None });
+    };
+
+    let same = other.types_data == item.types_data && other.errors_data == item.errors_data;
+    Some(if same {
+        RepoComparison {
+            expected: ExpectedChange::Same,
+            other: Some(OutcomeKind::BaselineUnparsable),
+        }
+    } else {
+        RepoComparison { expected: ExpectedChange::Different, other: None }
+    })
+}
@@ -0,0 +1,464 @@
+use std::{
+    fmt, fs,
+    path::{Path, PathBuf},
+};
+
+use rustc_hash::FxHashMap;
+use serde_json::Value;
+
+use crate::{HumanCount, OutcomeKind};
+
+/// Number of recent snapshots a quarantined test has to pass in before its entry is stale.
+pub const DEFAULT_QUARANTINE_EXPIRY: usize = 3;
+
+#[derive(Debug)]
+pub enum QuarantineError {
+    IO(std::io::Error),
+    /// The line of the quarantine file, 1-based, is not `<test> <date> <signature>`.
+    Malformed(usize),
+    /// The snapshot file name does not start with a `YYYY-MM-DD` date.
+    SnapshotName(PathBuf),
+    /// The line of the snapshot, 1-based, is not an outcome written by `--sink jsonl=`.
+    InvalidSnapshot(PathBuf, usize),
+}
+
+impl fmt::Display for QuarantineError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            QuarantineError::IO(err) => err.fmt(f),
+            QuarantineError::Malformed(line) => {
+                write!(f, "Expected <test> <YYYY-MM-DD> <outcome> at line {line}")
+            }
+            QuarantineError::SnapshotName(path) => {
+                write!(f, "Expected a snapshot name starting with YYYY-MM-DD: {}", path.display())
+            }
+            QuarantineError::InvalidSnapshot(path, line) => {
+                write!(f, "Expected an outcome at line {line} of {}", path.display())
+            }
+        }
+    }
+}
+
+impl std::error::Error for QuarantineError {}
+
+impl From<std::io::Error> for QuarantineError {
+    fn from(value: std::io::Error) -> Self {
+        Self::IO(value)
+    }
+}
+
+/// A known failure in a [`Quarantine`] file.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct QuarantineEntry {
+    /// Test case path relative to the repo.
+    pub path: PathBuf,
+    /// Variant name like `(strict=true)`, or `None` for every variant of the test.
+    pub variant: Option<String>,
+    /// Date of the snapshot the entry was added for, `YYYY-MM-DD`.
+    pub added: String,
+    /// Outcome the test failed with when it was added.
+    pub signature: OutcomeKind,
+    /// 0-based line of the entry in the file.
+    line: usize,
+}
+
+impl QuarantineEntry {
+    fn parse(line: &str, idx: usize) -> Result<Option<Self>, QuarantineError> {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            return Ok(None);
+        }
+        let malformed = || QuarantineError::Malformed(idx + 1);
+        let mut fields = line.split_whitespace();
+        let (Some(test), Some(added), Some(signature), None) =
+            (fields.next(), fields.next(), fields.next(), fields.next())
+        else {
+            return Err(malformed());
+        };
+        if !is_date(added) {
+            return Err(malformed());
+        }
+        let signature = OutcomeKind::parse(signature).ok_or_else(malformed)?;
+        let (path, variant) = match test.find('(') {
+            Some(start) if test.ends_with(')') => (&test[..start], Some(test[start..].to_string())),
+            _ => (test, None),
+        };
+        Ok(Some(Self {
+            path: PathBuf::from(path),
+            variant,
+            added: added.to_string(),
+            signature,
+            line: idx,
+        }))
+    }
+}
+
+impl fmt::Display for QuarantineEntry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}{}  quarantined as {} on {}",
+            self.path.display(),
+            self.variant.as_deref().unwrap_or_default(),
+            self.signature,
+            self.added
+        )
+    }
+}
+
+/// A file of known failures, with the date they were added and the outcome they failed with.
+///
+/// Every entry is a `<test> <date> <signature>` line, where the test is its path relative to
+/// the repo, optionally followed by a variant name, and the signature is the outcome:
+///
+/// ```text
+/// # Needs control flow narrowing
+/// tests/cases/compiler/narrowing.ts(strict=true)  2026-10-01  failed
+/// ```
+///
+/// Lines starting with `#` and blank lines are comments, kept as they are when stale entries
+/// are removed.
+#[derive(Debug, Default, PartialEq, Eq, Clone)]
+pub struct Quarantine {
+    /// Lines of the file with their line endings.
+    lines: Vec<String>,
+    pub entries: Vec<QuarantineEntry>,
+}
+
+impl Quarantine {
+    /// # Errors
+    ///
+    /// Will return `Err` if the file cannot be read or an entry is malformed
+    pub fn read(path: &Path) -> Result<Self, QuarantineError> {
+        Self::parse(&fs::read_to_string(path)?)
+    }
+
+    /// # Errors
+    ///
+    /// Will return `Err` if an entry is malformed
+    pub fn parse(data: &str) -> Result<Self, QuarantineError> {
+        let lines: Vec<String> = data.split_inclusive('\n').map(ToString::to_string).collect();
+        let mut entries = Vec::new();
+        for (idx, line) in lines.iter().enumerate() {
+            entries.extend(QuarantineEntry::parse(line, idx)?);
+        }
+        Ok(Self { lines, entries })
+    }
+
+    /// Entries whose tests passed in each of the last `expiry` snapshots of `history`, taken no
+    /// earlier than the entry was added. None expire until `expiry` snapshots are recorded.
+    pub fn stale(&self, history: &[Snapshot], expiry: usize) -> Vec<&QuarantineEntry> {
+        if expiry == 0 || history.len() < expiry {
+            return Vec::new();
+        }
+        let recent = &history[history.len() - expiry..];
+        self.entries
+            .iter()
+            .filter(|entry| recent.iter().all(|x| x.date >= entry.added && x.passed(entry)))
+            .collect()
+    }
+
+    /// The file without `entries`, with every other line as it was.
+    pub fn without(&self, entries: &[&QuarantineEntry]) -> String {
+        self.lines
+            .iter()
+            .enumerate()
+            .filter(|(idx, _)| !entries.iter().any(|x| x.line == *idx))
+            .map(|(_, line)| line.as_str())
+            .collect()
+    }
+}
+
+/// Outcomes of a past run, written by `--sink jsonl=` to a file named after the date of the
+/// run, like `2026-10-17.jsonl`.
+#[derive(Debug, Default, PartialEq, Eq, Clone)]
+pub struct Snapshot {
+    /// Date the file name starts with.
+    pub date: String,
+    /// Outcomes by test path, with the variant they are for and whether every file of the test
+    /// was checked, unlike with `--only-file`.
+    outcomes: FxHashMap<PathBuf, Vec<(String, OutcomeKind, bool)>>,
+}
+
+impl Snapshot {
+    /// # Errors
+    ///
+    /// Will return `Err` if the file name does not start with a date or a line is not an
+    /// outcome
+    pub fn parse(path: &Path, data: &str) -> Result<Self, QuarantineError> {
+        let date = path.file_name().and_then(|x| x.to_str()).and_then(|x| x.get(..10));
+        let Some(date) = date.filter(|x| is_date(x)) else {
+            return Err(QuarantineError::SnapshotName(path.to_path_buf()));
+        };
+
+        let mut result = Self { date: date.to_string(), outcomes: FxHashMap::default() };
+        for (idx, line) in data.lines().enumerate().filter(|(_, x)| !x.trim().is_empty()) {
+            let outcome = serde_json::from_str::<Value>(line).ok().and_then(|x| {
+                let path = x.get("path")?.as_str()?;
+                let variant = x.get("variant")?.as_str()?;
+                let kind = OutcomeKind::parse(x.get("outcome")?.as_str()?)?;
+                let all_files = match x.get("only_files") {
+                    None | Some(Value::Null) => true,
+                    Some(files) => files.as_array()?.is_empty(),
+                };
+                Some((PathBuf::from(path), variant.to_string(), kind, all_files))
+            });
+            let Some((path, variant, kind, all_files)) = outcome else {
+                return Err(QuarantineError::InvalidSnapshot(path.to_path_buf(), idx + 1));
+            };
+            result.outcomes.entry(path).or_default().push((variant, kind, all_files));
+        }
+        Ok(result)
+    }
+
+    /// Whether the snapshot ran the test of `entry` and every variant it covers passed. A pass
+    /// restricted to some files with `--only-file` is not a pass of the test.
+    pub fn passed(&self, entry: &QuarantineEntry) -> bool {
+        let Some(outcomes) = self.outcomes.get(&entry.path) else {
+            return false;
+        };
+        let mut covered = outcomes
+            .iter()
+            .filter(|(variant, _, _)| entry.variant.as_ref().is_none_or(|x| x == variant))
+            .peekable();
+        covered.peek().is_some()
+            && covered.all(|&(_, kind, all_files)| kind == OutcomeKind::Passed && all_files)
+    }
+}
+
+/// Reads the last `count` snapshots in `dir`, oldest first. Snapshots are the `.jsonl` files
+/// of the directory, ordered by name.
+///
+/// # Errors
+///
+/// Will return `Err` if the directory or one of the snapshots cannot be read
+pub fn read_history(dir: &Path, count: usize) -> Result<Vec<Snapshot>, QuarantineError> {
+    let mut paths = Vec::new();
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.extension().is_some_and(|x| x == "jsonl") {
+            paths.push(path);
+        }
+    }
+    paths.sort_unstable();
+    let skip = paths.len().saturating_sub(count);
+    paths[skip..].iter().map(|x| Snapshot::parse(x, &fs::read_to_string(x)?)).collect()
+}
+
+/// Stale entries of a quarantine file, see [`check_quarantine`].
+#[derive(Debug, Default, PartialEq, Eq, Clone)]
+pub struct QuarantineReport {
+    pub path: PathBuf,
+    pub entries: usize,
+    /// Snapshots read, at most `expiry`.
+    pub snapshots: usize,
+    pub expiry: usize,
+    pub stale: Vec<QuarantineEntry>,
+    /// The stale entries were removed from the file.
+    pub pruned: bool,
+}
+
+impl fmt::Display for QuarantineReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.snapshots < self.expiry {
+            return writeln!(
+                f,
+                "Quarantine: {} entries, none expire until {} snapshots are recorded ({} so far)",
+                HumanCount(self.entries),
+                self.expiry,
+                self.snapshots
+            );
+        }
+        writeln!(
+            f,
+            "Quarantine: {} entries, {} passed in the last {} snapshots",
+            HumanCount(self.entries),
+            HumanCount(self.stale.len()),
+            self.expiry
+        )?;
+        for entry in &self.stale {
+            writeln!(f, "  {entry}")?;
+        }
+        if self.pruned {
+            writeln!(f, "Removed them from {}", self.path.display())
+        } else if !self.stale.is_empty() {
+            writeln!(f, "Run with --prune-quarantine to remove them")
+        } else {
+            Ok(())
+        }
+    }
+}
+
+/// Reports the entries of the quarantine file at `path` whose tests passed in the last `expiry`
+/// snapshots of `history`, and removes them from the file if `prune` is set.
+///
+/// # Errors
+///
+/// Will return `Err` if the quarantine file or the snapshots cannot be read or are malformed,
+/// or the pruned file cannot be written
+pub fn check_quarantine(
+    path: &Path,
+    history: &Path,
+    expiry: usize,
+    prune: bool,
+) -> Result<QuarantineReport, QuarantineError> {
+    let quarantine = Quarantine::read(path)?;
+    let snapshots = read_history(history, expiry)?;
+    let stale = quarantine.stale(&snapshots, expiry);
+    let pruned = prune && !stale.is_empty();
+    if pruned {
+        fs::write(path, quarantine.without(&stale))?;
+    }
+    Ok(QuarantineReport {
+        path: path.to_path_buf(),
+        entries: quarantine.entries.len(),
+        snapshots: snapshots.len(),
+        expiry,
+        stale: stale.into_iter().cloned().collect(),
+        pruned,
+    })
+}
+
+fn is_date(value: &str) -> bool {
+    value.len() == 10
+        && value.bytes().enumerate().all(|(idx, x)| match idx {
+            4 | 7 => x == b'-',
+            _ => x.is_ascii_digit(),
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const QUARANTINE: &str = "\
+# Needs narrowing
+tests/cases/compiler/a.ts(strict=true)  2026-10-01  failed
+
+tests/cases/compiler/b.ts  2026-10-01  parser_panicked
+tests/cases/compiler/c.ts  2026-10-10  failed  # added later
+";
+
+    fn snapshot(date: &str, outcomes: &[(&str, &str, &str)]) -> Snapshot {
+        let data = outcomes
+            .iter()
+            .map(|(path, variant, outcome)| {
+                serde_json::json!({ "path": path, "variant": variant, "outcome": outcome })
+                    .to_string()
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
+        Snapshot::parse(Path::new(&format!("{date}.jsonl")), &data).unwrap()
+    }
+
+    #[test]
+    fn parse() {
+        assert!(matches!(Quarantine::parse(QUARANTINE), Err(QuarantineError::Malformed(5))));
+
+        let quarantine = Quarantine::parse(&QUARANTINE.replace("  # added later", "")).unwrap();
+        assert_eq!(quarantine.entries.len(), 3);
+        assert_eq!(quarantine.entries[0].path, PathBuf::from("tests/cases/compiler/a.ts"));
+        assert_eq!(quarantine.entries[0].variant.as_deref(), Some("(strict=true)"));
+        assert_eq!(quarantine.entries[1].variant, None);
+        assert_eq!(quarantine.entries[1].signature, OutcomeKind::ParserPanicked);
+        assert_eq!(quarantine.entries[2].added, "2026-10-10");
+
+        assert!(matches!(
+            Quarantine::parse("a.ts 2026-1-01 failed\n"),
+            Err(QuarantineError::Malformed(1))
+        ));
+        assert!(matches!(
+            Quarantine::parse("a.ts 2026-10-01 flaky\n"),
+            Err(QuarantineError::Malformed(1))
+        ));
+        assert!(matches!(
+            Snapshot::parse(Path::new("latest.jsonl"), ""),
+            Err(QuarantineError::SnapshotName(_))
+        ));
+        assert!(matches!(
+            Snapshot::parse(Path::new("2026-10-17.jsonl"), "\n{}\n"),
+            Err(QuarantineError::InvalidSnapshot(_, 2))
+        ));
+    }
+
+    #[test]
+    fn stale() {
+        let quarantine = Quarantine::parse(&QUARANTINE.replace("  # added later", "")).unwrap();
+        let passing = [
+            ("tests/cases/compiler/a.ts", "(strict=true)", "passed"),
+            ("tests/cases/compiler/a.ts", "(strict=false)", "failed"),
+            ("tests/cases/compiler/b.ts", "(target=es5)", "passed"),
+            ("tests/cases/compiler/b.ts", "(target=es2015)", "passed"),
+            ("tests/cases/compiler/c.ts", "", "passed"),
+        ];
+        let mut b_failing = passing;
+        b_failing[3].2 = "parser_panicked";
+        let history = [
+            snapshot("2026-10-05", &b_failing),
+            snapshot("2026-10-09", &passing),
+            snapshot("2026-10-12", &passing),
+        ];
+
+        let stale = quarantine.stale(&history, 3);
+        assert_eq!(stale, vec![&quarantine.entries[0]]);
+        // c.ts only passed in snapshots taken since it was added
+        let stale = quarantine.stale(&history, 2);
+        assert_eq!(stale, vec![&quarantine.entries[0], &quarantine.entries[1]]);
+        assert!(quarantine.stale(&history[..1], 2).is_empty());
+        // A test missing from a snapshot did not pass in it
+        let history = [snapshot("2026-10-12", &passing[1..])];
+        assert_eq!(
+            quarantine.stale(&history, 1),
+            vec![&quarantine.entries[1], &quarantine.entries[2]]
+        );
+
+        // A pass of only some files of b.ts does not count towards expiring its entry
+        let restricted = passing
+            .iter()
+            .map(|&(path, variant, outcome)| {
+                let only_files: &[&str] = if path.ends_with("b.ts") { &["b.ts"] } else { &[] };
+                let line = serde_json::json!({
+                    "path": path, "variant": variant, "outcome": outcome, "only_files": only_files
+                });
+                line.to_string()
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
+        let restricted = Snapshot::parse(Path::new("2026-10-12.jsonl"), &restricted).unwrap();
+        let history = [snapshot("2026-10-09", &passing), restricted];
+        assert_eq!(quarantine.stale(&history, 2), vec![&quarantine.entries[0]]);
+
+        let pruned = quarantine.without(&[&quarantine.entries[0], &quarantine.entries[2]]);
+        assert_eq!(
+            pruned,
+            "# Needs narrowing\n\ntests/cases/compiler/b.ts  2026-10-01  parser_panicked\n"
+        );
+    }
+
+    #[test]
+    fn prune() {
+        let dir =
+            std::env::temp_dir().join(format!("type-runner-quarantine-{}", std::process::id()));
+        let history = dir.join("history");
+        fs::create_dir_all(&history).unwrap();
+        let path = dir.join("quarantine.txt");
+        fs::write(&path, "# known\ntests/cases/compiler/a.ts  2026-10-01  failed\n").unwrap();
+        let outcome = r#"{"path":"tests/cases/compiler/a.ts","variant":"","outcome":"passed"}"#;
+        for date in ["2026-10-02", "2026-10-03"] {
+            fs::write(history.join(format!("{date}.jsonl")), outcome).unwrap();
+        }
+        fs::write(history.join("notes.txt"), "").unwrap();
+
+        let report = check_quarantine(&path, &history, 3, true).unwrap();
+        assert_eq!(report.snapshots, 2);
+        assert!(report.stale.is_empty());
+
+        let report = check_quarantine(&path, &history, 2, false).unwrap();
+        assert_eq!(report.stale.len(), 1);
+        assert!(!report.pruned);
+        let report = check_quarantine(&path, &history, 2, true).unwrap();
+        assert!(report.pruned);
+        assert_eq!(fs::read_to_string(&path).unwrap(), "# known\n");
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,219 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use serde_json::Value;
+
+use crate::{RunOptions, TestList};
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum RepoError {
+    NotFound(PathBuf),
+    /// Repo path and the required paths missing in it.
+    Missing(PathBuf, Vec<PathBuf>),
+    /// `--area` that is not a directory of the repo, and the available ones.
+    UnknownArea(String, Vec<String>),
+    /// `--tests-json` list that cannot be read or is not a list of paths, and why.
+    TestList(PathBuf, String),
+}
+
+impl std::fmt::Display for RepoError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RepoError::NotFound(repo) => {
+                write!(f, "TypeScript repo not found: {}", repo.display())
+            }
+            RepoError::Missing(repo, missing) => {
+                write!(f, "Not a TypeScript checkout: {}\n  missing:", repo.display())?;
+                for path in missing {
+                    write!(f, "\n    {}", path.strip_prefix(repo).unwrap_or(path).display())?;
+                }
+                Ok(())
+            }
+            RepoError::UnknownArea(area, available) => {
+                write!(f, "Unknown area: {area}\n  available: {}", available.join(", "))
+            }
+            RepoError::TestList(path, err) => {
+                write!(f, "Failed to read test list {}: {err}", path.display())
+            }
+        }
+    }
+}
+
+impl std::error::Error for RepoError {}
+
+/// Version of the TypeScript checkout the tests were run against.
+#[derive(Debug, Default, PartialEq, Eq, Clone)]
+pub struct RepoInfo {
+    /// `version` field of the repo's `package.json`.
+    pub version: Option<String>,
+    /// Commit hash `HEAD` points to.
+    pub commit: Option<String>,
+}
+
+impl RepoInfo {
+    /// Checks that `options` point to a TypeScript checkout (or a directory laid out like one)
+    /// and reads its version. `--smoke` runs only need the test cases. A `--compare-repo` needs
+    /// its test cases and baselines.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` listing every required path that does not exist, or if the
+    /// `--tests-json` list cannot be read
+    pub fn validate(options: &RunOptions) -> Result<Self, RepoError> {
+        let repo = options.repo.as_path();
+        if !repo.is_dir() {
+            return Err(RepoError::NotFound(repo.to_path_buf()));
+        }
+
+        let mut required = Vec::new();
+        if !options.smoke {
+            required.extend([repo.join("package.json"), options.baseline_root()]);
+        }
+        if options.cases_dirs.is_empty()
+            || options.cases_dirs.iter().any(|x| x == Path::new("default"))
+        {
+            required.push(repo.join("tests/cases"));
+        }
+
+        let missing: Vec<_> = required.into_iter().filter(|x| !x.exists()).collect();
+        if !missing.is_empty() {
+            return Err(RepoError::Missing(repo.to_path_buf(), missing));
+        }
+
+        if let Some(compare) = &options.compare_repo {
+            if !compare.is_dir() {
+                return Err(RepoError::NotFound(compare.clone()));
+            }
+            let mut required = vec![compare.join("tests/cases")];
+            required.extend(options.compare_baseline_root());
+            let missing: Vec<_> = required.into_iter().filter(|x| !x.exists()).collect();
+            if !missing.is_empty() {
+                return Err(RepoError::Missing(compare.clone(), missing));
+            }
+        }
+
+        if let Some(area) = options.areas.iter().find(|x| !options.area_dir(x).is_dir()) {
+            return Err(RepoError::UnknownArea(area.clone(), options.available_areas()));
+        }
+
+        if let Some(path) = &options.tests_json {
+            if let Err(err) = TestList::read(path, repo) {
+                return Err(RepoError::TestList(path.clone(), err.to_string()));
+            }
+        }
+
+        Ok(Self::read(repo))
+    }
+
+    pub fn read(repo: &Path) -> Self {
+        let version = fs::read_to_string(repo.join("package.json"))
+            .ok()
+            .and_then(|x| serde_json::from_str::<Value>(&x).ok())
+            .and_then(|x| x.get("version")?.as_str().map(str::to_string));
+
+        Self { version, commit: read_head(&repo.join(".git")) }
+    }
+}
+
+fn read_head(git_dir: &Path) -> Option<String> {
+    let head = fs::read_to_string(git_dir.join("HEAD")).ok()?;
+    let head = head.trim();
+    let Some(reference) = head.strip_prefix("ref: ") else {
+        return Some(head.to_string());
+    };
+
+    if let Ok(commit) = fs::read_to_string(git_dir.join(reference)) {
+        return Some(commit.trim().to_string());
+    }
+
+    // Refs may have been packed by `git gc`: `<hash> <ref>` per line
+    fs::read_to_string(git_dir.join("packed-refs")).ok()?.lines().find_map(|line| {
+        let (commit, name) = line.split_once(' ')?;
+        (name == reference).then(|| commit.to_string())
+    })
+}
+
+impl std::fmt::Display for RepoInfo {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "TypeScript {}", self.version.as_deref().unwrap_or("(unknown version)"))?;
+        if let Some(commit) = &self.commit {
+            write!(f, " ({})", &commit[..commit.len().min(10)])?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::SELF_TEST_CORPUS;
+
+    #[test]
+    fn validate() {
+        let options = RunOptions::parse(["--self-test".to_string()]).unwrap();
+        let info = RepoInfo::validate(&options).unwrap();
+        assert_eq!(info.version.as_deref(), Some("5.9.0-dev"));
+
+        let options = RunOptions::parse([format!("{SELF_TEST_CORPUS}/tests")]).unwrap();
+        assert_eq!(
+            RepoInfo::validate(&options),
+            Err(RepoError::Missing(
+                options.repo.clone(),
+                vec![
+                    options.repo.join("package.json"),
+                    options.baseline_root(),
+                    options.repo.join("tests/cases")
+                ]
+            ))
+        );
+
+        let options = RunOptions::parse([format!("{SELF_TEST_CORPUS}/does-not-exist")]).unwrap();
+        assert!(matches!(RepoInfo::validate(&options), Err(RepoError::NotFound(_))));
+
+        let options =
+            RunOptions::parse(["--self-test", "--area", "es6", "--area", "jsx"].map(String::from))
+                .unwrap();
+        assert_eq!(
+            RepoInfo::validate(&options),
+            Err(RepoError::UnknownArea(
+                "jsx".to_string(),
+                ["classes", "compiler", "es6", "expressions", "types"].map(String::from).to_vec()
+            ))
+        );
+
+        let args = ["--self-test", "--compare-repo", SELF_TEST_CORPUS].map(String::from);
+        let options = RunOptions::parse(args).unwrap();
+        assert!(RepoInfo::validate(&options).is_ok());
+
+        let compare = format!("{SELF_TEST_CORPUS}/tests");
+        let options =
+            RunOptions::parse(["--self-test", "--compare-repo", &compare].map(String::from))
+                .unwrap();
+        let compare = PathBuf::from(compare);
+        assert_eq!(
+            RepoInfo::validate(&options),
+            Err(RepoError::Missing(
+                compare.clone(),
+                vec![compare.join("tests/cases"), options.compare_baseline_root().unwrap()]
+            ))
+        );
+
+        let list =
+            std::env::temp_dir().join(format!("type-runner-list-{}.json", std::process::id()));
+        fs::write(&list, r#"["compiler/anyVariables.ts", 1]"#).unwrap();
+        let options = RunOptions::parse([
+            "--self-test".to_string(),
+            "--tests-json".into(),
+            list.display().to_string(),
+        ])
+        .unwrap();
+        let result = RepoInfo::validate(&options);
+        fs::remove_file(&list).unwrap();
+        assert_eq!(
+            result,
+            Err(RepoError::TestList(list, "Expected a path at index 1".to_string()))
+        );
+    }
+}
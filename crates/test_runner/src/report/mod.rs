@@ -0,0 +1,54 @@
+use std::path::Path;
+
+use crate::{
+    baseline::BaselineSource,
+    compare::{ErrorComparison, MatchOptions, TypeComparison},
+    line_index::LineIndex,
+};
+
+mod side_by_side;
+mod verbose;
+
+pub use side_by_side::{render_type_comparison, render_types_side_by_side};
+pub use verbose::VerboseSingleTestReporter;
+
+/// Comparison results of one module: its types and the diagnostics reported in it.
+pub struct ModuleReport<'a> {
+    pub name: &'a str,
+    /// Lines of the module's source text, to line errors up with the statements they are on.
+    pub lines: LineIndex,
+    pub types: TypeComparison<'a>,
+    pub errors: ErrorComparison,
+}
+
+/// Comparison results of a single test variant.
+pub struct TestReport<'a> {
+    /// Test case path relative to the repo.
+    pub path: &'a Path,
+    pub variant: &'a str,
+    pub modules: Vec<ModuleReport<'a>>,
+    /// Diagnostics outside of the compared modules: config errors and errors in files that are
+    /// not modules of the program.
+    pub errors: ErrorComparison,
+    pub options: MatchOptions,
+    /// Baseline files the results were compared against.
+    pub baseline: &'a BaselineSource,
+}
+
+impl TestReport<'_> {
+    pub fn is_match(&self) -> bool {
+        self.error_comparisons().all(ErrorComparison::is_match)
+            && self.modules.iter().all(|x| {
+                x.types.is_match() && (!self.options.check_order || x.types.out_of_order() == 0)
+            })
+    }
+
+    /// Errors comparison of every module, then of the diagnostics outside of them.
+    pub fn error_comparisons(&self) -> impl Iterator<Item = &ErrorComparison> {
+        self.modules.iter().map(|x| &x.errors).chain([&self.errors])
+    }
+
+    pub fn out_of_order(&self) -> usize {
+        self.modules.iter().map(|x| x.types.out_of_order()).sum()
+    }
+}
@@ -0,0 +1,125 @@
+use crate::{
+    baseline::types_baseline::TypeBaselineFile,
+    compare::{ActualType, Scratch, TypeComparison, compare_types},
+};
+
+/// Renders every baseline statement of a file with the expected `expr : type` entries on the
+/// left and the types we computed for the same expressions on the right, pass or fail.
+///
+/// Rows are marked `✓` (same type), `≠` (different type), `✗` (expression not visited) or `+`
+/// (visited node the baseline has no entry for).
+pub fn render_types_side_by_side<'a>(
+    baseline: &TypeBaselineFile<'a>,
+    source_text: &'a str,
+    actual: Vec<ActualType<'a>>,
+) -> String {
+    render_type_comparison(&compare_types(baseline, source_text, actual, &mut Scratch::default()))
+}
+
+/// Same as [`render_types_side_by_side`], for a file that has already been compared.
+pub fn render_type_comparison(types: &TypeComparison<'_>) -> String {
+    let width = types
+        .statements
+        .iter()
+        .flat_map(|x| &x.assertions)
+        .map(|x| entry(x.expr, x.expected_type).chars().count())
+        .max()
+        .unwrap_or_default()
+        .max(8);
+
+    let mut out = String::new();
+    row(&mut out, width, " ", "baseline", "actual");
+    for statement in &types.statements {
+        for line in statement.statement.lines() {
+            out.push_str(line.trim_end_matches('\r'));
+            out.push('\n');
+        }
+
+        for assertion in &statement.assertions {
+            let expected = entry(assertion.expr, assertion.expected_type);
+            match &assertion.actual {
+                Some(actual) => {
+                    let mark = if assertion.is_match() { "✓" } else { "≠" };
+                    row(&mut out, width, mark, &expected, &entry(actual.text, &actual.type_name));
+                }
+                None => row(&mut out, width, "✗", &expected, ""),
+            }
+        }
+
+        for node in &statement.unexpected {
+            row(&mut out, width, "+", "", &entry(node.text, &node.type_name));
+        }
+    }
+
+    if !types.unplaced.is_empty() {
+        out.push_str("(outside of baseline statements)\n");
+        for node in &types.unplaced {
+            row(&mut out, width, "+", "", &entry(node.text, &node.type_name));
+        }
+    }
+
+    out
+}
+
+fn entry(expr: &str, type_name: &str) -> String {
+    format!("{} : {type_name}", expr.escape_debug())
+}
+
+fn row(out: &mut String, width: usize, mark: &str, expected: &str, actual: &str) {
+    let line = format!("  {mark} {expected:<width$} | {actual}");
+    out.push_str(line.trim_end());
+    out.push('\n');
+}
+
+#[cfg(test)]
+mod tests {
+    use oxc::span::Span;
+    use oxc_index::index_vec;
+
+    use super::*;
+    use crate::baseline::types_baseline::Assertion;
+
+    #[test]
+    fn render() {
+        let source_text = "const a = 5;\nfoo(a);\n";
+        let baseline = TypeBaselineFile {
+            statements: index_vec!["const a = 5;", "foo(a);"],
+            assertions: index_vec![
+                vec![
+                    Assertion { expr: "a", expected_type: "5" },
+                    Assertion { expr: "5", expected_type: "5" },
+                ],
+                vec![
+                    Assertion { expr: "foo(a)", expected_type: "void" },
+                    Assertion { expr: "a", expected_type: "5" },
+                ],
+            ],
+        };
+        let node = |start: u32, text: &'static str, type_name: &'static str| ActualType {
+            span: Span::sized(start, u32::try_from(text.len()).unwrap()),
+            kind: "Node".into(),
+            text,
+            type_name: type_name.into(),
+            placeholder: false,
+        };
+        let actual = vec![
+            node(6, "a", "5"),
+            node(10, "5", "number"),
+            node(13, "foo", "any"),
+            node(17, "a", "5"),
+        ];
+
+        assert_eq!(
+            render_types_side_by_side(&baseline, source_text, actual),
+            "    baseline      | actual
+const a = 5;
+  ✓ a : 5         | a : 5
+  ≠ 5 : 5         | 5 : number
+foo(a);
+  ✗ foo(a) : void |
+  ✓ a : 5         | a : 5
+  +               | foo : any
+"
+        );
+    }
+}
@@ -0,0 +1,257 @@
+use std::io::{self, Write};
+
+use type_info::Diagnostic;
+
+use super::{ModuleReport, TestReport};
+use crate::compare::ErrorComparison;
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+enum ErrorStatus {
+    Matched,
+    Missing,
+    Unexpected,
+}
+
+impl ErrorStatus {
+    fn mark(self) -> &'static str {
+        match self {
+            ErrorStatus::Matched => "✓",
+            ErrorStatus::Missing => "✗",
+            ErrorStatus::Unexpected => "+",
+        }
+    }
+}
+
+/// Prints a per-file checklist of a single test: every baseline statement followed by its
+/// assertions with our actual types and the errors on its lines with their match status.
+pub struct VerboseSingleTestReporter<W: Write> {
+    out: W,
+}
+
+impl<W: Write> VerboseSingleTestReporter<W> {
+    pub fn new(out: W) -> Self {
+        Self { out }
+    }
+
+    /// # Errors
+    ///
+    /// Will return `Err` if writing to the output fails
+    pub fn report(&mut self, report: &TestReport<'_>) -> io::Result<()> {
+        let mark = if report.is_match() { "✓" } else { "✗" };
+        writeln!(self.out, "{mark} {}{}", report.path.display(), report.variant)?;
+        writeln!(self.out, "  baseline: {}", report.baseline)?;
+
+        for module in &report.modules {
+            writeln!(self.out, "  ---------------- {} ----------------", module.name)?;
+            self.module(module, report.options.check_order)?;
+        }
+
+        let other = with_status(&report.errors);
+        if !other.is_empty() {
+            writeln!(self.out, "  ---------------- (other) ----------------")?;
+            self.errors(&other)?;
+        }
+
+        let hint_depth_differences: usize =
+            report.error_comparisons().map(|x| x.hint_depth_differences).sum();
+        if hint_depth_differences > 0 {
+            writeln!(
+                self.out,
+                "  hint depth differs for {hint_depth_differences} matched errors (advisory)"
+            )?;
+        }
+
+        Ok(())
+    }
+
+    /// Prints the statements of a module, each followed by the errors up to its last line. Errors
+    /// after the last located statement are listed at the end.
+    fn module(&mut self, module: &ModuleReport<'_>, check_order: bool) -> io::Result<()> {
+        let mut errors = with_status(&module.errors).into_iter().peekable();
+        for statement in &module.types.statements {
+            for line in statement.statement.lines() {
+                writeln!(self.out, "  {line}")?;
+            }
+
+            for assertion in &statement.assertions {
+                let mark = if assertion.is_match() { "✓" } else { "✗" };
+                write!(self.out, "    {mark} {} : {}", assertion.expr, assertion.expected_type)?;
+                match &assertion.actual {
+                    Some(actual) if !assertion.is_match() => {
+                        writeln!(self.out, "  (actual: {})", actual.type_name)?;
+                    }
+                    Some(_) => writeln!(self.out)?,
+                    None => writeln!(self.out, "  (not visited)")?,
+                }
+            }
+
+            for node in &statement.unexpected {
+                writeln!(
+                    self.out,
+                    "    + {} : {}  (unexpected {})",
+                    node.text.escape_debug(),
+                    node.type_name,
+                    node.kind
+                )?;
+            }
+
+            if let Some((expected, visited)) = statement.order_divergence().filter(|_| check_order)
+            {
+                self.order(&expected, &visited)?;
+            }
+
+            if let Some(span) = statement.span {
+                let last_line = module.lines.line(span.end.saturating_sub(1));
+                while let Some(error) = errors.next_if(|(_, x)| x.line <= last_line) {
+                    self.error(error)?;
+                }
+            }
+        }
+
+        for node in &module.types.unplaced {
+            writeln!(
+                self.out,
+                "  + {} : {}  (outside of baseline statements, {})",
+                node.text.escape_debug(),
+                node.type_name,
+                node.kind
+            )?;
+        }
+
+        self.errors(&errors.collect::<Vec<_>>())
+    }
+
+    /// Prints baseline and visit order of a statement side by side.
+    fn order(&mut self, expected: &[&str], visited: &[&str]) -> io::Result<()> {
+        let width = expected.iter().map(|x| x.chars().count()).max().unwrap_or_default().max(8);
+        writeln!(self.out, "    ✗ order:")?;
+        writeln!(self.out, "      {:<width$}   visited", "baseline")?;
+        for (expected, visited) in expected.iter().zip(visited) {
+            let mark = if expected == visited { " " } else { "≠" };
+            writeln!(
+                self.out,
+                "      {:<width$} {mark} {}",
+                expected.escape_debug().to_string(),
+                visited.escape_debug()
+            )?;
+        }
+
+        Ok(())
+    }
+
+    fn errors(&mut self, errors: &[(ErrorStatus, &Diagnostic)]) -> io::Result<()> {
+        if errors.is_empty() {
+            return Ok(());
+        }
+
+        writeln!(self.out, "  errors:")?;
+        for &error in errors {
+            self.error(error)?;
+        }
+
+        Ok(())
+    }
+
+    fn error(&mut self, (status, error): (ErrorStatus, &Diagnostic)) -> io::Result<()> {
+        let message = error.message.lines().next().unwrap_or_default();
+        if error.has_location() {
+            writeln!(
+                self.out,
+                "    {} ({},{}) {} TS{}: {message}",
+                status.mark(),
+                error.line,
+                error.column,
+                error.severity,
+                error.code
+            )
+        } else {
+            writeln!(
+                self.out,
+                "    {} {} TS{}: {message}",
+                status.mark(),
+                error.severity,
+                error.code
+            )
+        }
+    }
+}
+
+/// Every diagnostic of the comparison with its match status, sorted by location.
+fn with_status(errors: &ErrorComparison) -> Vec<(ErrorStatus, &Diagnostic)> {
+    let mut result: Vec<_> = [
+        (ErrorStatus::Matched, &errors.matched),
+        (ErrorStatus::Missing, &errors.missing),
+        (ErrorStatus::Unexpected, &errors.unexpected),
+    ]
+    .into_iter()
+    .flat_map(|(status, list)| list.iter().map(move |x| (status, x)))
+    .collect();
+    result.sort_by_key(|(_, x)| (x.line, x.column));
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use oxc_index::index_vec;
+
+    use super::*;
+    use crate::{
+        baseline::{BaselineSource, types_baseline::TypeBaselineFile},
+        compare::{MatchOptions, ModuleBaseline, Scratch, compare_module},
+    };
+
+    #[test]
+    fn errors_follow_statements() {
+        let source_text = "let a = 1;\nlet b = c;\nlet d = e;\n";
+        let types = TypeBaselineFile {
+            statements: index_vec!["let a = 1;", "let b = c;"],
+            assertions: index_vec![vec![], vec![]],
+        };
+        let missing = |line, column, code| Diagnostic {
+            code,
+            file: "a.ts".to_string(),
+            line,
+            column,
+            message: "Cannot find name.".to_string(),
+            ..Diagnostic::default()
+        };
+        let expected = [missing(3, 9, 2304), missing(2, 9, 2304)];
+        let baseline = ModuleBaseline { types: &types, errors: &expected };
+        let options = MatchOptions::default();
+        let module = compare_module(
+            "a.ts",
+            source_text,
+            baseline,
+            vec![],
+            &[],
+            options,
+            &mut Scratch::default(),
+        );
+        let source = BaselineSource { types: "a.types".into(), ..BaselineSource::default() };
+        let report = TestReport {
+            path: Path::new("a.ts"),
+            variant: "",
+            modules: vec![module],
+            errors: ErrorComparison::default(),
+            options,
+            baseline: &source,
+        };
+
+        let mut out = Vec::new();
+        VerboseSingleTestReporter::new(&mut out).report(&report).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "✗ a.ts
+  baseline: a.types
+  ---------------- a.ts ----------------
+  let a = 1;
+  let b = c;
+    ✗ (2,9) error TS2304: Cannot find name.
+  errors:
+    ✗ (3,9) error TS2304: Cannot find name.
+"
+        );
+    }
+}
@@ -1,9 +1,26 @@
-use std::path::Path;
+use std::{fmt::Write, fs, io::IsTerminal, path::Path};
 
-use type_info::TypeCheck;
+use type_info::{
+    Diagnostic, LibStore, ProgramCache, References, TSProgramError, TsConfig, TypeCheck,
+    TypeCheckBuilder,
+};
 
 use crate::{
-    Baseline, TestUnit, TestVariant, file_system::TestFileSystem, type_visitor::TypeVisitor,
+    Baseline, RunOptions, TestUnit, TestVariant,
+    baseline::{ErrorsBaseline, ModuleTypes, write_errors_baseline, write_types_baseline},
+    compare::{
+        DiagnosticsByFile, ModuleBaseline, Scratch, compare_diagnostics, compare_errors,
+        compare_module, compare_modules, strip_base_url, suppress_diagnostics,
+    },
+    config_check::config_errors,
+    discover::baseline_candidates,
+    file_system::TestFileSystem,
+    outcome::{
+        AssertionStats, EntryStrategy, ErrorStats, ExpectedChange, OutcomeKind, ParserPanic,
+        ProgramTiming, RepoComparison, TestOutcome,
+    },
+    report::{TestReport, VerboseSingleTestReporter, render_type_comparison},
+    type_visitor::{DepthLimitExceeded, TypeVisitor},
 };
 
 /// # Panics
@@ -11,50 +28,146 @@ pub fn run_test(
     unit: &TestUnit<'_>,
     variant: &TestVariant<'_>,
     baseline: &Baseline<'_>,
-    root_dir: &Path,
-) {
-    let compile = if let Some(compile) = unit.file_names.iter().find_map(|&name| {
-        if name == "tsconfig.json" {
-            // Not sure about this. In theory we should read the list from compilerOptions.
-            Some(Vec::new())
-        } else {
-            None
-        }
-    }) {
-        compile
-    } else if unit.settings.no_implicit_references {
-        vec![unit.file_names.last_idx()]
-    } else {
-        assert!(
-            !unit.file_names.is_empty(),
-            "Test with no files: {}",
-            relative_path(unit.path, root_dir).display()
+    options: &RunOptions,
+    cache: &ProgramCache<'_>,
+    libs: &LibStore,
+    scratch: &mut Scratch,
+) -> TestOutcome {
+    if options.only_files.is_empty() {
+        return run_variant(unit, variant, baseline, options, cache, libs, scratch);
+    }
+
+    let mut outcome = run_variant(unit, variant, baseline, options, cache, libs, scratch);
+    outcome.only_files.clone_from(&options.only_files);
+    let _ = writeln!(outcome.log, "    only: {}", outcome.only_files.join(", "));
+    outcome
+}
+
+fn run_variant(
+    unit: &TestUnit<'_>,
+    variant: &TestVariant<'_>,
+    baseline: &Baseline<'_>,
+    options: &RunOptions,
+    cache: &ProgramCache<'_>,
+    libs: &LibStore,
+    scratch: &mut Scratch,
+) -> TestOutcome {
+    let root_dir = options.repo.as_path();
+    let mut log = String::new();
+    if unit.file_names.is_empty() {
+        // Every file was left out because its content is not UTF-8
+        let _ = writeln!(
+            log,
+            "⚠  {}{}  no file with UTF-8 content\n    baseline: {}",
+            relative_path(unit.path, root_dir).display(),
+            variant.name,
+            baseline.source
         );
+        return TestOutcome {
+            path: relative_path(unit.path, root_dir).to_path_buf(),
+            variant: variant.name.clone(),
+            kind: OutcomeKind::ProgramError,
+            assertions: AssertionStats::default(),
+            errors: ErrorStats::default(),
+            baseline: baseline.source.clone(),
+            compare: compare_repo(baseline, options, |_| OutcomeKind::ProgramError),
+            parser_panic: None,
+            baseline_error: None,
+            entry_strategy: None,
+            only_files: Vec::new(),
+            timing: None,
+            log,
+        };
+    }
 
-        let last_idx = unit.file_names.last_idx();
-        let last_content = unit.file_contents
-            [if unit.file_names[last_idx] == "tsconfig.json" { last_idx - 1 } else { last_idx }];
-        if last_content.contains("require(") || last_content.contains("reference path") {
-            vec![unit.file_names.last_idx()]
-        } else {
-            unit.file_names.indices().collect()
+    // Options of the `tsconfig.json` apply where the variant does not set them
+    let (config, config_diagnostics) = match unit_config(unit) {
+        Some((name, Ok(config))) => (Some((name, config)), Vec::new()),
+        Some((_, Err(diagnostic))) => (None, vec![diagnostic]),
+        None => (None, Vec::new()),
+    };
+    let config = config.as_ref().map(|(name, config)| (*name, config));
+    let effective;
+    let variant = match config {
+        Some((_, config)) => {
+            effective = variant.with_compiler_options(&config.compiler_options);
+            &effective
         }
+        None => variant,
     };
-
-    let fs = TestFileSystem { unit };
-    let type_check = TypeCheck::new(&fs);
-    let alloc = oxc::allocator::Allocator::default();
-    let root_files: Vec<_> = compile
-        .iter()
-        .map(|&x| unit.file_names[x])
-        .filter(|&x| {
-            !std::path::Path::new(x).extension().is_some_and(|ext| {
-                ext.eq_ignore_ascii_case("json") || ext.eq_ignore_ascii_case("map")
-            })
-        })
-        .collect();
-    let program = match type_check.create_program(&root_files, &alloc) {
+    let mut config_diagnostics = config_diagnostics;
+    config_diagnostics.extend(config_errors(unit, variant, config));
+    let (entry_strategy, root_files) = root_files(unit, variant, config, root_dir);
+    let (fs, lib_files) = unit_file_system(unit, root_dir);
+    let lib_files: Vec<_> = lib_files.iter().map(String::as_str).collect();
+    let type_check =
+        variant_type_check(&fs, unit, variant).stats(options.timings).libs(libs).build();
+    let allows_js = variant.allows_js();
+    let no_errors = ErrorsBaseline::default();
+    let expected_errors = baseline.errors.as_ref().unwrap_or(&no_errors);
+    let match_options = options.match_options();
+    // The whole program is built, but only the `--only-file` files are compared.
+    let compares_file = |name: &str| {
+        options.compares_file(name) && (allows_js || !has_extension(name, JS_EXTENSIONS))
+    };
+    let compare_errors =
+        |expected: &ErrorsBaseline<'_>, actual: &[Diagnostic], scratch: &mut Scratch| {
+            let mut errors = compare_errors(expected, actual, match_options, scratch);
+            errors.retain_files(compares_file);
+            errors
+        };
+    let program = match type_check.create_program_cached(&root_files, &lib_files, cache) {
         Ok(program) => program,
+        Err(err) if options.compare_program_errors => {
+            let (failed, parser_panic) = program_error_kind(&err);
+            if let Some(dir) = &options.emit_actual {
+                emit_actual(dir, unit, variant, None, &err.diagnostics(), root_dir);
+            }
+
+            // Nothing to compare types against, but the diagnostics produced while failing
+            // (mostly parser errors) can still be checked against the errors baseline.
+            let mut diagnostics = config_diagnostics;
+            diagnostics.extend(err.diagnostics());
+            suppress_diagnostics(&mut diagnostics, variant);
+            let report = TestReport {
+                path: relative_path(unit.path, root_dir),
+                variant: &variant.name,
+                modules: Vec::new(),
+                errors: compare_errors(expected_errors, &diagnostics, scratch),
+                options: match_options,
+                baseline: &baseline.source,
+            };
+
+            let mut outcome = TestOutcome::from_report(&report);
+            outcome.compare = compare_repo(baseline, options, |other| {
+                let errors = other.errors.as_ref().unwrap_or(&no_errors);
+                if compare_errors(errors, &diagnostics, scratch).is_match() {
+                    OutcomeKind::Passed
+                } else {
+                    failed
+                }
+            });
+            outcome.parser_panic = parser_panic;
+            outcome.entry_strategy = Some(entry_strategy);
+            if outcome.kind == OutcomeKind::Passed {
+                write_report(&mut log, &report, variant, &outcome, options);
+            } else {
+                outcome.kind = failed;
+                let _ = writeln!(
+                    log,
+                    "⚠  {}{}  errors: {}/{}\n{}\n    baseline: {}\n    entry: {entry_strategy}",
+                    report.path.display(),
+                    report.variant,
+                    outcome.errors.matched,
+                    outcome.errors.matched + outcome.errors.missing,
+                    render_program_error(&err),
+                    baseline.source
+                );
+                write_tsc_args(&mut log, variant);
+            }
+            outcome.log = log;
+            return outcome;
+        }
         Err(err) => {
             // panic!(
             //   "❌ Failed to create program: \n  path: {}\n  variant: {}\n  error: {}",
@@ -63,29 +176,461 @@ pub fn run_test(
             //   err
             // );
 
-            println!(
-                "⚠  {}{}\n{}",
+            if let Some(dir) = &options.emit_actual {
+                emit_actual(dir, unit, variant, None, &err.diagnostics(), root_dir);
+            }
+
+            let (kind, parser_panic) = program_error_kind(&err);
+            let _ = writeln!(
+                log,
+                "⚠  {}{}\n{}\n    baseline: {}\n    entry: {entry_strategy}",
                 relative_path(unit.path, root_dir).display(),
                 variant.name,
-                err
+                render_program_error(&err),
+                baseline.source
             );
-            return;
+            write_tsc_args(&mut log, variant);
+            return TestOutcome {
+                path: relative_path(unit.path, root_dir).to_path_buf(),
+                variant: variant.name.clone(),
+                kind,
+                assertions: AssertionStats::default(),
+                errors: ErrorStats::default(),
+                baseline: baseline.source.clone(),
+                compare: compare_repo(baseline, options, |_| kind),
+                parser_panic,
+                baseline_error: None,
+                entry_strategy: Some(entry_strategy),
+                only_files: Vec::new(),
+                timing: None,
+                log,
+            };
         }
     };
 
-    println!("⏷ {}{}", relative_path(unit.path, root_dir).display(), variant.name);
-    for (&name, semantic) in program.modules.iter().zip(&program.semantic) {
-        println!("  ---------------- {name} ----------------");
-        let baseline = &baseline.types.files
-            [baseline.types.names.position(|&x| x == name).expect("type baseline to exist")];
-        let visitor = TypeVisitor { semantic, baseline };
-        visitor.run();
+    let timing = options.timings.then(|| ProgramTiming::of(&program));
+    // Lib files are neither in the types nor in the errors baselines
+    let diagnostics: Vec<Diagnostic> = config_diagnostics
+        .into_iter()
+        .chain(program.root_modules().flat_map(|id| program.diagnostics[id].iter().cloned()))
+        .collect();
+    // Emitted as produced, but compared without the ones the variant's options switch off.
+    let mut compared = diagnostics.clone();
+    suppress_diagnostics(&mut compared, variant);
+    let expected = DiagnosticsByFile::new(expected_errors.to_diagnostics());
+    let produced = DiagnosticsByFile::new(compared);
+    // Module names in the program may include `@baseUrl`, the baselines' do not
+    let base_url = unit.settings.base_url.as_deref();
+    let files: Vec<_> = program
+        .root_modules()
+        .map(|id| program.modules[id])
+        .flat_map(|name| std::iter::once(name).chain(strip_base_url(name, base_url)))
+        .collect();
+    // Diagnostics outside of the program's modules (config errors) have no module section.
+    let compare_other_errors = |expected: &DiagnosticsByFile, scratch: &mut Scratch| {
+        let (expected, actual) = (expected.except(&files), produced.except(&files));
+        let mut errors = compare_diagnostics(&expected, &actual, match_options, scratch);
+        errors.retain_files(compares_file);
+        errors
+    };
+    // Visited nodes are compared as they come, but kept for the other checkout and for emitting
+    let keep_types = options.compare_repo.is_some() || options.emit_actual.is_some();
+
+    let mut modules = Vec::new();
+    let mut visited = Vec::new();
+    for id in program.root_modules() {
+        // Only lib files are shared from the store
+        let Some(semantic) = program.semantic[id].parsed() else { continue };
+        let name = program.modules[id];
+        let types = match (TypeVisitor { semantic, max_depth: options.max_depth() }).run() {
+            Ok(types) => types,
+            Err(DepthLimitExceeded(depth)) => {
+                let _ = writeln!(
+                    log,
+                    "⚠  {}{}  {name}: nested deeper than {depth} levels\n    baseline: {}",
+                    relative_path(unit.path, root_dir).display(),
+                    variant.name,
+                    baseline.source
+                );
+                return TestOutcome {
+                    path: relative_path(unit.path, root_dir).to_path_buf(),
+                    variant: variant.name.clone(),
+                    kind: OutcomeKind::ResourceLimit,
+                    assertions: AssertionStats::default(),
+                    errors: ErrorStats::default(),
+                    baseline: baseline.source.clone(),
+                    compare: compare_repo(baseline, options, |_| OutcomeKind::ResourceLimit),
+                    parser_panic: None,
+                    baseline_error: None,
+                    entry_strategy: Some(entry_strategy),
+                    only_files: Vec::new(),
+                    timing: None,
+                    log,
+                };
+            }
+        };
+
+        let source_text = semantic.source_text();
+        if keep_types {
+            visited.push((name, source_text, types.clone()));
+        }
+        if compares_file(name) {
+            let module_baseline =
+                ModuleBaseline::resolve(&baseline.types, &expected, name, base_url);
+            // TS only writes types of the JSON modules it visits
+            if module_baseline.is_none() && has_extension(name, &["json"]) {
+                continue;
+            }
+            let module_baseline = module_baseline.expect("type baseline to exist");
+            let diagnostics = produced.file(name);
+            modules.push(compare_module(
+                name,
+                source_text,
+                module_baseline,
+                types,
+                diagnostics,
+                match_options,
+                scratch,
+            ));
+        }
     }
 
-    // println!("✅ {}{}", relative_path(unit.path, root_dir).display(), variant.name);
+    if let Some(dir) = &options.emit_actual {
+        let modules: Vec<_> = visited
+            .iter()
+            .map(|(name, source_text, types)| ModuleTypes { name, source_text, types })
+            .collect();
+        emit_actual(dir, unit, variant, Some(&modules), &diagnostics, root_dir);
+    }
+    let visited: Vec<_> = visited.into_iter().filter(|(name, ..)| compares_file(name)).collect();
+
+    let compare = compare_repo(baseline, options, |other| {
+        let other_expected =
+            DiagnosticsByFile::new(other.errors.as_ref().unwrap_or(&no_errors).to_diagnostics());
+        let Some(modules) = compare_modules(
+            &other.types,
+            &other_expected,
+            visited,
+            &produced,
+            base_url,
+            match_options,
+            scratch,
+        ) else {
+            return OutcomeKind::Failed;
+        };
+        let report = TestReport {
+            path: relative_path(unit.path, root_dir),
+            variant: &variant.name,
+            modules,
+            errors: compare_other_errors(&other_expected, scratch),
+            options: match_options,
+            baseline: &other.source,
+        };
+        TestOutcome::from_report(&report).kind
+    });
+
+    let report = TestReport {
+        path: relative_path(unit.path, root_dir),
+        variant: &variant.name,
+        modules,
+        errors: compare_other_errors(&expected, scratch),
+        options: match_options,
+        baseline: &baseline.source,
+    };
+
+    let mut outcome = TestOutcome::from_report(&report);
+    outcome.compare = compare;
+    outcome.entry_strategy = Some(entry_strategy);
+    outcome.timing = timing;
+    write_report(&mut log, &report, variant, &outcome, options);
+    outcome.log = log;
+    outcome
+}
+
+/// Extensions of the files `@allowJs` lets into the program.
+const JS_EXTENSIONS: &[&str] = &["js", "jsx", "mjs", "cjs"];
+
+fn has_extension(name: &str, extensions: &[&str]) -> bool {
+    Path::new(name)
+        .extension()
+        .is_some_and(|ext| extensions.iter().any(|x| ext.eq_ignore_ascii_case(x)))
+}
+
+/// Files of the unit the program of `variant` is created from, and how they were picked.
+///
+/// `config` is the unit's `tsconfig.json` with its name, see [`unit_config`]. An invalid one
+/// gives no root files.
+pub fn root_files<'u>(
+    unit: &TestUnit<'u>,
+    variant: &TestVariant<'_>,
+    config: Option<(&str, &TsConfig)>,
+    root_dir: &Path,
+) -> (EntryStrategy, Vec<&'u str>) {
+    let entry_strategy = entry_strategy(unit, root_dir);
+    // Without `@allowJs`, TS leaves `.js` files out of the program and out of its baselines.
+    let allows_js = variant.allows_js();
+    let compile = match (entry_strategy, config) {
+        (EntryStrategy::TsconfigDriven, Some((name, config))) => {
+            config.root_files(name, unit.file_names.iter().copied(), allows_js)
+        }
+        (EntryStrategy::TsconfigDriven, None) => Vec::new(),
+        (EntryStrategy::LastFileOnly | EntryStrategy::ReferenceClosure, _) => {
+            vec![unit.file_names[unit.file_names.last_idx()]]
+        }
+        (EntryStrategy::AllFiles, _) => unit.file_names.iter().copied().collect(),
+    };
+
+    // Like the TS harness, JSON files are only root files a config lists in `files`
+    let json_roots =
+        entry_strategy == EntryStrategy::TsconfigDriven && variant.resolves_json_module();
+    let root_files = compile
+        .into_iter()
+        .filter(|&x| {
+            !has_extension(x, &["map"])
+                && ((json_roots && !is_tsconfig(x)) || !has_extension(x, &["json"]))
+                && (allows_js || !has_extension(x, JS_EXTENSIONS))
+        })
+        .collect();
+    (entry_strategy, root_files)
 }
 
+/// Directory of the TS repo with the files `@libFiles` names.
+const LIB_DIR: &str = "tests/lib";
+/// Where the TS harness puts lib files in the program.
+const LIB_ROOT: &str = "/.lib";
+/// Lib of units without `@libFiles`, if the repo has it.
+const DEFAULT_LIB: &str = "lib.d.ts";
+
+/// File system of the unit with the lib files of the repo under `/.lib`, and the paths there of
+/// the unit's lib files. They are only read when a program's [`LibStore`] has not parsed them
+/// yet.
+///
 /// # Panics
-fn relative_path<'a>(path: &'a Path, root_dir: &Path) -> &'a Path {
-    path.strip_prefix(root_dir).unwrap()
+///
+/// If a file named by `@libFiles` does not exist
+pub fn unit_file_system<'u>(
+    unit: &'u TestUnit<'u>,
+    root_dir: &Path,
+) -> (TestFileSystem<'u>, Vec<String>) {
+    let names = match &unit.settings.lib_files {
+        Some(names) => names.iter().map(|x| (x.as_str(), true)).collect(),
+        None => vec![(DEFAULT_LIB, false)],
+    };
+
+    let lib_dir = root_dir.join(LIB_DIR);
+    let mut lib_files = Vec::with_capacity(names.len());
+    for (name, required) in names {
+        let path = lib_dir.join(name);
+        match fs::metadata(&path) {
+            Ok(_) => lib_files.push(format!("{LIB_ROOT}/{name}")),
+            Err(_) if !required => {}
+            Err(err) => panic!(
+                "Failed to read lib file:\n  path: {}\n  test: {}\n  error: {err}",
+                relative_path(&path, root_dir).display(),
+                relative_path(unit.path, root_dir).display()
+            ),
+        }
+    }
+    (TestFileSystem::builder(unit).with_dir(LIB_ROOT, lib_dir).build(), lib_files)
+}
+
+/// Whether the file is a `tsconfig.json`, in any directory like the TS harness looks for it.
+fn is_tsconfig(name: &str) -> bool {
+    name.rsplit('/').next() == Some("tsconfig.json")
+}
+
+/// The unit's `tsconfig.json` parsed, with its name. Invalid JSON gives a diagnostic located in
+/// the config instead, compared like the other config errors.
+pub fn unit_config<'u>(unit: &TestUnit<'u>) -> Option<(&'u str, Result<TsConfig, Diagnostic>)> {
+    let id = unit.file_names.position(|&name| is_tsconfig(name))?;
+    let name = unit.file_names[id];
+    Some((name, TsConfig::parse(name, unit.file_contents[id])))
+}
+
+/// How the root files of the unit are picked, see [`EntryStrategy`].
+fn entry_strategy(unit: &TestUnit<'_>, root_dir: &Path) -> EntryStrategy {
+    if unit.file_names.iter().any(|&name| is_tsconfig(name)) {
+        return EntryStrategy::TsconfigDriven;
+    }
+    if unit.settings.no_implicit_references {
+        return EntryStrategy::LastFileOnly;
+    }
+
+    assert!(
+        !unit.file_names.is_empty(),
+        "Test with no files: {}",
+        relative_path(unit.path, root_dir).display()
+    );
+
+    let last_idx = unit.file_names.last_idx();
+    let last_content = unit.file_contents
+        [if unit.file_names[last_idx] == "tsconfig.json" { last_idx - 1 } else { last_idx }];
+    if last_content.contains("require(") || !References::parse(last_content).paths.is_empty() {
+        EntryStrategy::ReferenceClosure
+    } else {
+        EntryStrategy::AllFiles
+    }
+}
+
+/// The type check of `variant`, with the parsing options its compiler options set.
+pub fn variant_type_check<'fs>(
+    fs: &'fs TestFileSystem<'_>,
+    unit: &TestUnit<'_>,
+    variant: &TestVariant<'_>,
+) -> TypeCheckBuilder<'fs, TestFileSystem<'fs>> {
+    TypeCheck::builder(fs)
+        .resolve_json_module(variant.resolves_json_module())
+        .no_implicit_references(unit.settings.no_implicit_references)
+}
+
+/// `err` as printed in the `⚠` lines: in color on a terminal, plain when the output is captured.
+pub fn render_program_error(err: &TSProgramError<'_>) -> String {
+    if std::io::stdout().is_terminal() { err.to_string() } else { err.render_plain() }
+}
+
+/// Outcome of a variant whose program could not be created, and the file the parser gave up on
+/// if that is why.
+pub fn program_error_kind(err: &TSProgramError<'_>) -> (OutcomeKind, Option<ParserPanic>) {
+    match err.parser_panic() {
+        Some((file, diagnostics)) => {
+            (OutcomeKind::ParserPanicked, Some(ParserPanic { file: file.to_string(), diagnostics }))
+        }
+        None => (OutcomeKind::ProgramError, None),
+    }
+}
+
+/// Classifies the `--compare-repo` baselines of the variant and computes the outcome against
+/// them with `run`. Returns `None` when not comparing against a second checkout.
+fn compare_repo<'a>(
+    baseline: &Baseline<'a>,
+    options: &RunOptions,
+    run: impl FnOnce(&Baseline<'a>) -> OutcomeKind,
+) -> Option<RepoComparison> {
+    options.compare_repo.as_ref()?;
+    let Some(other) = baseline.compare.as_deref() else {
+        return Some(RepoComparison { expected: ExpectedChange::Missing, other: None });
+    };
+
+    let expected = if baseline.same_expectations(other) {
+        ExpectedChange::Same
+    } else {
+        ExpectedChange::Different
+    };
+    Some(RepoComparison { expected, other: Some(run(other)) })
+}
+
+fn write_report(
+    log: &mut String,
+    report: &TestReport<'_>,
+    variant: &TestVariant<'_>,
+    outcome: &TestOutcome,
+    options: &RunOptions,
+) {
+    if options.dump_types {
+        for module in &report.modules {
+            let _ = writeln!(log, "=== {}{} ===", module.name, report.variant);
+            log.push_str(&render_type_comparison(&module.types));
+        }
+    }
+
+    if options.is_verbose_single_test() {
+        let mut out = Vec::new();
+        VerboseSingleTestReporter::new(&mut out).report(report).expect("failed to write report");
+        log.push_str(&String::from_utf8_lossy(&out));
+    } else {
+        let _ = write!(
+            log,
+            "{} {}{}  types: {}/{}  errors: {}/{}",
+            if outcome.kind == OutcomeKind::Passed { "✓" } else { "✗" },
+            report.path.display(),
+            report.variant,
+            outcome.assertions.matched,
+            outcome.assertions.checked,
+            outcome.errors.matched,
+            outcome.errors.matched + outcome.errors.missing,
+        );
+        if report.options.check_order && report.out_of_order() > 0 {
+            let _ = write!(log, "  out of order: {}", report.out_of_order());
+        }
+        log.push('\n');
+        if outcome.kind != OutcomeKind::Passed {
+            let _ = writeln!(log, "    baseline: {}", outcome.baseline);
+            if let Some(entry_strategy) = outcome.entry_strategy {
+                let _ = writeln!(log, "    entry: {entry_strategy}");
+            }
+        }
+    }
+    if outcome.kind != OutcomeKind::Passed {
+        write_tsc_args(log, variant);
+    }
+
+    if let Some(compare) = outcome.compare {
+        if compare.expected != ExpectedChange::Same || compare.outcome_changed(outcome.kind) {
+            let other = compare.other.map_or("-", OutcomeKind::as_str);
+            let _ = writeln!(
+                log,
+                "    compare: {} expected, {other} against the other checkout",
+                compare.expected
+            );
+        }
+    }
+}
+
+/// Writes the `tsc` flags re-running the variant with the official compiler, if it sets any.
+fn write_tsc_args(log: &mut String, variant: &TestVariant<'_>) {
+    let args = variant.to_tsc_args();
+    if !args.is_empty() {
+        let _ = writeln!(log, "    tsc: {}", args.join(" "));
+    }
+}
+
+/// Writes the baselines we produced for the variant into `dir`, named like the reference ones.
+///
+/// Stale baselines from a previous run are removed when we have nothing to write in their place.
+fn emit_actual(
+    dir: &Path,
+    unit: &TestUnit<'_>,
+    variant: &TestVariant<'_>,
+    types: Option<&[ModuleTypes<'_>]>,
+    diagnostics: &[Diagnostic],
+    root_dir: &Path,
+) {
+    let name = unit.path.file_stem().expect("path to be a file");
+    let test_path = relative_path(unit.path, root_dir).to_string_lossy().replace('\\', "/");
+    let files: Vec<_> = unit
+        .file_names
+        .iter()
+        .zip(&unit.file_contents)
+        .map(|(&name, &text)| (name, text))
+        .collect();
+
+    let candidates = baseline_candidates(dir, name, &variant.name);
+    let result = fs::create_dir_all(dir)
+        .and_then(|()| {
+            write_or_remove(&candidates.types, types.map(|x| write_types_baseline(&test_path, x)))
+        })
+        .and_then(|()| {
+            write_or_remove(&candidates.errors, write_errors_baseline(diagnostics, &files))
+        });
+    if let Err(err) = result {
+        panic!(
+            "Failed to emit actual baselines:\n  case: {test_path}\n  dir: {}\n  error: {err}",
+            dir.display()
+        );
+    }
+}
+
+fn write_or_remove(path: &Path, data: Option<String>) -> std::io::Result<()> {
+    match data {
+        Some(data) => fs::write(path, data),
+        None => match fs::remove_file(path) {
+            Err(err) if err.kind() != std::io::ErrorKind::NotFound => Err(err),
+            _ => Ok(()),
+        },
+    }
+}
+
+/// Paths outside of the repo (custom `--cases-dir` or `--baseline-dir`) are returned as is.
+pub fn relative_path<'a>(path: &'a Path, root_dir: &Path) -> &'a Path {
+    path.strip_prefix(root_dir).unwrap_or(path)
 }
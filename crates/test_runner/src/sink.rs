@@ -0,0 +1,188 @@
+use std::{
+    fs::File,
+    io::{BufWriter, Write},
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+};
+
+use crate::{OptionsError, RepoInfo, RunOptions, RunSummary, TestOutcome};
+
+/// Receives the outcome of every variant as soon as it is run, and the summary once the run is
+/// over.
+///
+/// Outcomes arrive from worker threads in no particular order.
+pub trait OutcomeSink: Send {
+    fn record(&mut self, outcome: &TestOutcome);
+    fn finish(&mut self, summary: &RunSummary);
+}
+
+/// Writes every outcome as a line of JSON.
+pub struct JsonLinesSink {
+    path: PathBuf,
+    out: BufWriter<File>,
+}
+
+impl JsonLinesSink {
+    /// # Errors
+    ///
+    /// Will return `Err` if the file cannot be created
+    pub fn create(path: &Path) -> std::io::Result<Self> {
+        Ok(Self { path: path.to_path_buf(), out: BufWriter::new(File::create(path)?) })
+    }
+}
+
+impl OutcomeSink for JsonLinesSink {
+    fn record(&mut self, outcome: &TestOutcome) {
+        if let Err(err) = writeln!(self.out, "{}", outcome.to_json()) {
+            panic!("Failed to write outcome:\n  path: {}\n  error: {err}", self.path.display());
+        }
+    }
+
+    fn finish(&mut self, _summary: &RunSummary) {
+        if let Err(err) = self.out.flush() {
+            panic!("Failed to write outcomes:\n  path: {}\n  error: {err}", self.path.display());
+        }
+    }
+}
+
+/// Writes the end-of-run summary as JSON, see [`RunSummary::to_json`].
+pub struct SummaryJsonSink {
+    path: PathBuf,
+    repo: RepoInfo,
+}
+
+impl SummaryJsonSink {
+    pub fn new(path: &Path, repo: RepoInfo) -> Self {
+        Self { path: path.to_path_buf(), repo }
+    }
+}
+
+impl OutcomeSink for SummaryJsonSink {
+    fn record(&mut self, _outcome: &TestOutcome) {}
+
+    fn finish(&mut self, summary: &RunSummary) {
+        if let Err(err) = summary.write_json(&self.path, &self.repo) {
+            panic!("Failed to write summary:\n  path: {}\n  error: {err}", self.path.display());
+        }
+    }
+}
+
+/// A sink supplied by a library consumer, who keeps a handle to read it after the run.
+#[derive(Clone)]
+pub struct SharedSink(pub Arc<Mutex<dyn OutcomeSink>>);
+
+impl SharedSink {
+    pub fn new(sink: impl OutcomeSink + 'static) -> Self {
+        Self(Arc::new(Mutex::new(sink)))
+    }
+}
+
+impl std::fmt::Debug for SharedSink {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("SharedSink(..)")
+    }
+}
+
+impl PartialEq for SharedSink {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+impl Eq for SharedSink {}
+
+/// Where the outcomes of a run go, see [`RunOptions::sinks`].
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum SinkSpec {
+    /// `--sink jsonl=<path>`: [`JsonLinesSink`].
+    JsonLines(PathBuf),
+    /// `--sink summary=<path>` or `--summary-json <path>`: [`SummaryJsonSink`].
+    SummaryJson(PathBuf),
+    Custom(SharedSink),
+}
+
+impl SinkSpec {
+    /// Parses the `kind=path` value of `--sink`.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` on unknown kinds or a missing path
+    pub fn parse(value: &str) -> Result<Self, OptionsError> {
+        match value.split_once('=') {
+            Some(("jsonl", path)) if !path.is_empty() => Ok(Self::JsonLines(PathBuf::from(path))),
+            Some(("summary", path)) if !path.is_empty() => {
+                Ok(Self::SummaryJson(PathBuf::from(path)))
+            }
+            _ => Err(OptionsError::InvalidSink(value.to_string())),
+        }
+    }
+
+    fn open(&self, options: &RunOptions) -> Arc<Mutex<dyn OutcomeSink>> {
+        match self {
+            SinkSpec::JsonLines(path) => match JsonLinesSink::create(path) {
+                Ok(sink) => Arc::new(Mutex::new(sink)),
+                Err(err) => {
+                    panic!(
+                        "Failed to create outcomes file:\n  path: {}\n  error: {err}",
+                        path.display()
+                    )
+                }
+            },
+            SinkSpec::SummaryJson(path) => {
+                Arc::new(Mutex::new(SummaryJsonSink::new(path, RepoInfo::read(&options.repo))))
+            }
+            SinkSpec::Custom(sink) => Arc::clone(&sink.0),
+        }
+    }
+}
+
+/// The sinks of a run, opened from [`RunOptions::sinks`].
+#[derive(Default)]
+pub struct Sinks(Vec<Arc<Mutex<dyn OutcomeSink>>>);
+
+impl Sinks {
+    /// # Panics
+    ///
+    /// When a sink's file cannot be created
+    pub fn open(options: &RunOptions) -> Self {
+        Self(options.sinks.iter().map(|x| x.open(options)).collect())
+    }
+
+    /// # Panics
+    ///
+    /// When a sink failed to write
+    pub fn record(&self, outcome: &TestOutcome) {
+        for sink in &self.0 {
+            sink.lock().expect("no sink to panic while recording").record(outcome);
+        }
+    }
+
+    /// # Panics
+    ///
+    /// When a sink failed to write
+    pub fn finish(&self, summary: &RunSummary) {
+        for sink in &self.0 {
+            sink.lock().expect("no sink to panic while recording").finish(summary);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse() {
+        assert_eq!(
+            SinkSpec::parse("jsonl=out/outcomes.jsonl"),
+            Ok(SinkSpec::JsonLines(PathBuf::from("out/outcomes.jsonl")))
+        );
+        assert_eq!(
+            SinkSpec::parse("summary=summary.json"),
+            Ok(SinkSpec::SummaryJson(PathBuf::from("summary.json")))
+        );
+        for value in ["jsonl", "jsonl=", "csv=a.csv"] {
+            assert_eq!(SinkSpec::parse(value), Err(OptionsError::InvalidSink(value.to_string())));
+        }
+    }
+}
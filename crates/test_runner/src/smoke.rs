@@ -0,0 +1,328 @@
+use std::{
+    panic::AssertUnwindSafe,
+    path::{Path, PathBuf},
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+use type_info::{LibStore, ProgramCache};
+
+use crate::{
+    OutcomeKind, RunOptions, RunStatus, TestUnit,
+    config_check::config_errors,
+    discover::{read_test_file, test_files},
+    human::HumanCount,
+    pipeline::{join_all, spawn_workers},
+    runner::{
+        program_error_kind, relative_path, render_program_error, root_files, unit_config,
+        unit_file_system, variant_type_check,
+    },
+};
+
+/// A variant whose program could not be built in `--smoke` mode.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct SmokeFailure {
+    /// Test case path relative to the repo.
+    pub path: PathBuf,
+    pub variant: String,
+    /// [`OutcomeKind::ProgramError`] or [`OutcomeKind::ParserPanicked`], `None` if building the
+    /// program panicked.
+    pub kind: Option<OutcomeKind>,
+    pub message: String,
+}
+
+impl std::fmt::Display for SmokeFailure {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let kind = self.kind.map_or("panicked", OutcomeKind::as_str);
+        write!(f, "{}{}  {kind}", self.path.display(), self.variant)?;
+        for line in self.message.lines() {
+            write!(f, "\n    {line}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Counts of a `--smoke` run, which builds the program of every variant without reading any
+/// baseline.
+#[derive(Debug, Default, PartialEq, Eq, Clone)]
+pub struct SmokeReport {
+    pub units: usize,
+    pub variants: usize,
+    /// Variants whose program was built.
+    pub built: usize,
+    /// Diagnostics produced while building the programs, including the failed ones.
+    pub diagnostics: usize,
+    /// Sorted by path and variant.
+    pub failures: Vec<SmokeFailure>,
+}
+
+impl SmokeReport {
+    fn merge(&mut self, other: Self) {
+        self.units += other.units;
+        self.variants += other.variants;
+        self.built += other.built;
+        self.diagnostics += other.diagnostics;
+        self.failures.extend(other.failures);
+    }
+
+    /// Builds the program of every variant of `unit` that `options` selects.
+    fn add_unit(&mut self, unit: &TestUnit<'_>, options: &RunOptions, libs: &LibStore) {
+        self.units += 1;
+        let (fs, lib_files) = unit_file_system(unit, &options.repo);
+        let lib_files: Vec<_> = lib_files.iter().map(String::as_str).collect();
+        let path = relative_path(unit.path, &options.repo);
+        let (config, config_diagnostics) = match unit_config(unit) {
+            Some((name, Ok(config))) => (Some((name, config)), 0),
+            Some((_, Err(_))) => (None, 1),
+            None => (None, 0),
+        };
+        let config = config.as_ref().map(|(name, config)| (*name, config));
+        // Variants share the modules they parse
+        let alloc = oxc::allocator::Allocator::default();
+        let cache = ProgramCache::new(&alloc);
+        for variant in unit.variations.iter().filter(|x| options.matches_variant(x)) {
+            self.variants += 1;
+            self.diagnostics += config_diagnostics;
+            let variant = match config {
+                Some((_, config)) => variant.with_compiler_options(&config.compiler_options),
+                None => variant,
+            };
+            self.diagnostics += config_errors(unit, &variant, config).len();
+            let (_, root_files) = root_files(unit, &variant, config, &options.repo);
+            let type_check = variant_type_check(&fs, unit, &variant).libs(libs).build();
+            let result = std::panic::catch_unwind(AssertUnwindSafe(|| {
+                match type_check.create_program_cached(&root_files, &lib_files, &cache) {
+                    Ok(program) => {
+                        (program.root_modules().map(|id| program.diagnostics[id].len()).sum(), None)
+                    }
+                    Err(err) => (
+                        err.diagnostics().len(),
+                        Some((program_error_kind(&err).0, render_program_error(&err))),
+                    ),
+                }
+            }));
+            let (kind, message) = match result {
+                Ok((diagnostics, None)) => {
+                    self.diagnostics += diagnostics;
+                    self.built += 1;
+                    continue;
+                }
+                Ok((diagnostics, Some((kind, message)))) => {
+                    self.diagnostics += diagnostics;
+                    (Some(kind), message)
+                }
+                Err(payload) => {
+                    let message = payload
+                        .downcast_ref::<String>()
+                        .map(String::as_str)
+                        .or_else(|| payload.downcast_ref::<&str>().copied())
+                        .unwrap_or("panicked");
+                    (None, message.to_string())
+                }
+            };
+            let variant = variant.name;
+            self.failures.push(SmokeFailure { path: path.to_path_buf(), variant, kind, message });
+        }
+    }
+
+    /// Number of failures of `kind`, `None` for panics.
+    pub fn count(&self, kind: Option<OutcomeKind>) -> usize {
+        self.failures.iter().filter(|x| x.kind == kind).count()
+    }
+
+    /// Programs that cannot be built are expected for now, panics are regressions.
+    pub fn status(&self) -> RunStatus {
+        if self.count(None) > 0 { RunStatus::Regressed } else { RunStatus::Passed }
+    }
+}
+
+impl std::fmt::Display for SmokeReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for failure in &self.failures {
+            writeln!(f, "⚠  {failure}")?;
+        }
+        write!(
+            f,
+            "{} variants of {} test cases: {} built, {} {}, {} {}, {} panicked; {} diagnostics",
+            HumanCount(self.variants),
+            HumanCount(self.units),
+            HumanCount(self.built),
+            HumanCount(self.count(Some(OutcomeKind::ProgramError))),
+            OutcomeKind::ProgramError,
+            HumanCount(self.count(Some(OutcomeKind::ParserPanicked))),
+            OutcomeKind::ParserPanicked,
+            HumanCount(self.count(None)),
+            HumanCount(self.diagnostics)
+        )
+    }
+}
+
+/// Parses every test case (or only `options.test`), expands its variants and builds their
+/// programs, without reading baselines or comparing anything.
+///
+/// Test cases are handed out to the workers of [`run_pipeline`](crate::run_pipeline), each with
+/// its own [`LibStore`].
+///
+/// # Panics
+///
+/// Panics if a test case cannot be read. A panic building a program is reported as a failure
+/// instead.
+pub fn smoke(options: &RunOptions) -> SmokeReport {
+    let files = match &options.test {
+        Some(test) => vec![options.repo.join(test)],
+        None => test_files(options).0,
+    };
+    let config = options.pipeline_config();
+    let next_file = AtomicUsize::new(0);
+    let read = |test_file: &Path| {
+        let Ok(data) = read_test_file(test_file) else {
+            panic!(
+                "Failed to read test file: {}",
+                relative_path(test_file, &options.repo).display()
+            );
+        };
+        data
+    };
+
+    let partial: Vec<SmokeReport> = std::thread::scope(|s| {
+        join_all(spawn_workers(s, config, || {
+            || {
+                let mut report = SmokeReport::default();
+                let libs = LibStore::new();
+                while let Some(test_file) = files.get(next_file.fetch_add(1, Ordering::Relaxed)) {
+                    let data = read(test_file);
+                    let limit = options.max_variation_values();
+                    report.add_unit(
+                        &TestUnit::parse_with_limit(test_file, &data, limit),
+                        options,
+                        &libs,
+                    );
+                }
+                report
+            }
+        }))
+    });
+
+    let mut result = SmokeReport::default();
+    for x in partial {
+        result.merge(x);
+    }
+    result.failures.sort_by(|a, b| (&a.path, &a.variant).cmp(&(&b.path, &b.variant)));
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn add_unit() {
+        let options = RunOptions { repo: PathBuf::from("/ts"), ..RunOptions::default() };
+        let mut report = SmokeReport::default();
+        let libs = LibStore::new();
+        let data = b"// @strict: true,false\n// @filename: a.ts\nlet a = 1;\n";
+        report.add_unit(&TestUnit::parse(Path::new("/ts/a.ts"), data), &options, &libs);
+        let data = b"// @filename: b.ts\nlet b = (;\n";
+        report.add_unit(&TestUnit::parse(Path::new("/ts/b.ts"), data), &options, &libs);
+
+        assert_eq!((report.units, report.variants, report.built), (2, 3, 2));
+        assert_eq!(report.failures.len(), report.variants - report.built);
+        assert_eq!(report.failures[0].path, PathBuf::from("b.ts"));
+        assert_eq!(report.count(None), 0);
+        assert_eq!(report.status(), RunStatus::Passed);
+        assert!(report.to_string().ends_with(
+            "3 variants of 2 test cases: 2 built, 0 program_error, 1 parser_panicked, 0 panicked; \
+             1 diagnostics"
+        ));
+    }
+
+    #[test]
+    fn lib_files() {
+        let repo = std::env::temp_dir().join(format!("type-runner-libs-{}", std::process::id()));
+        let lib_dir = repo.join("tests/lib");
+        std::fs::create_dir_all(&lib_dir).unwrap();
+        // The default lib's diagnostics are not counted, a lib the parser gives up on fails
+        std::fs::write(lib_dir.join("lib.d.ts"), "interface Array<T> {}\nconst a;\n").unwrap();
+        std::fs::write(lib_dir.join("broken.d.ts"), "var = ;\n").unwrap();
+
+        let options = RunOptions { repo: repo.clone(), ..RunOptions::default() };
+        let mut report = SmokeReport::default();
+        let libs = LibStore::new();
+        let data = b"// @filename: a.ts\nlet a: Array<number> = [];\n";
+        report.add_unit(&TestUnit::parse(&repo.join("a.ts"), data), &options, &libs);
+        let data = b"// @libFiles: broken.d.ts\n// @filename: b.ts\nlet b = 1;\n";
+        report.add_unit(&TestUnit::parse(&repo.join("b.ts"), data), &options, &libs);
+        std::fs::remove_dir_all(&repo).unwrap();
+
+        assert_eq!((report.variants, report.built, report.diagnostics), (2, 1, 1));
+        assert_eq!(report.failures[0].path, PathBuf::from("b.ts"));
+        assert_eq!(report.failures[0].kind, Some(OutcomeKind::ParserPanicked));
+        assert!(report.failures[0].message.contains("/.lib/broken.d.ts"));
+    }
+
+    #[test]
+    fn tsconfig() {
+        let options = RunOptions { repo: PathBuf::from("/ts"), ..RunOptions::default() };
+        let mut report = SmokeReport::default();
+        let libs = LibStore::new();
+        // Only `a.ts` is a root file, `b.ts` would fail to parse
+        let data = b"// @filename: /tsconfig.json\n{ \"files\": [\"a.ts\"], }\n\
+                     // @filename: /a.ts\nlet a = 1;\n// @filename: /b.ts\nlet b = (;\n";
+        report.add_unit(&TestUnit::parse(Path::new("/ts/a.ts"), data), &options, &libs);
+        assert_eq!((report.built, report.diagnostics), (1, 0));
+
+        let data = b"// @filename: /tsconfig.json\n{ \"files\": }\n// @filename: /a.ts\nlet a;\n";
+        report.add_unit(&TestUnit::parse(Path::new("/ts/b.ts"), data), &options, &libs);
+        assert_eq!((report.built, report.diagnostics), (2, 1));
+    }
+
+    #[test]
+    fn references() {
+        let options = RunOptions { repo: PathBuf::from("/ts"), ..RunOptions::default() };
+        let mut report = SmokeReport::default();
+        let libs = LibStore::new();
+        // Only `c.ts` is a root file, which pulls in the others, one of them missing a file
+        let data = b"// @filename: a.ts\n/// <reference path=\"missing.ts\" />\nlet a;\n\
+                     // @filename: b.ts\n/// <reference path=\"a.ts\" />\nlet b;\n\
+                     // @filename: c.ts\n/// <reference path=\"b.ts\" />\nlet c;\n";
+        report.add_unit(&TestUnit::parse(Path::new("/ts/a.ts"), data), &options, &libs);
+        assert_eq!((report.built, report.diagnostics), (1, 1));
+
+        // A file the parser gives up on fails the program, even when not a root file
+        let data = b"// @filename: a.ts\nlet a = (;\n\
+                     // @filename: b.ts\n/// <reference path=\"a.ts\" />\nlet b;\n";
+        report.add_unit(&TestUnit::parse(Path::new("/ts/b.ts"), data), &options, &libs);
+        assert_eq!(report.failures[0].kind, Some(OutcomeKind::ParserPanicked));
+    }
+
+    #[test]
+    fn jsx() {
+        let options = RunOptions { repo: PathBuf::from("/ts"), ..RunOptions::default() };
+        let mut report = SmokeReport::default();
+        let libs = LibStore::new();
+        // `<any>` is a type assertion in a `.ts` file, even with `@jsx`
+        let data = b"// @jsx: preserve\n// @filename: a.ts\nlet a = <any>1;\n";
+        report.add_unit(&TestUnit::parse(Path::new("/ts/a.ts"), data), &options, &libs);
+        assert_eq!(report.built, 1);
+
+        // and an unclosed element in a `.tsx` file
+        let data = b"// @jsx: preserve\n// @filename: b.tsx\nlet b = <any>1;\n";
+        report.add_unit(&TestUnit::parse(Path::new("/ts/b.ts"), data), &options, &libs);
+        assert_eq!(report.built, 1);
+        assert_eq!(report.failures[0].kind, Some(OutcomeKind::ParserPanicked));
+    }
+
+    #[test]
+    fn json_modules() {
+        let options = RunOptions { repo: PathBuf::from("/ts"), ..RunOptions::default() };
+        let mut report = SmokeReport::default();
+        let libs = LibStore::new();
+        // `b.json` would fail to parse if it were a root file
+        let data = b"// @resolveJsonModule: true,false\n// @filename: /tsconfig.json\n\
+                     { \"files\": [\"a.ts\", \"b.json\"] }\n\
+                     // @filename: /a.ts\nlet a = 1;\n// @filename: /b.json\n{ \"b\": }\n";
+        report.add_unit(&TestUnit::parse(Path::new("/ts/a.ts"), data), &options, &libs);
+        assert_eq!((report.variants, report.built), (2, 1));
+        assert_eq!(report.failures[0].variant, "(resolvejsonmodule=true)");
+        assert!(report.failures[0].message.contains("/b.json"), "{}", report.failures[0]);
+    }
+}
@@ -0,0 +1,764 @@
+use std::{
+    fmt::Write,
+    fs,
+    path::{Path, PathBuf},
+    time::{Duration, Instant},
+};
+
+use rustc_hash::FxHashMap;
+use serde_json::{Value, json};
+
+use crate::{
+    baseline::BaselineTiming,
+    human::{HumanCount, HumanDuration, HumanSize},
+    ledger::{LedgerMismatch, VariantLedger},
+    outcome::{
+        AssertionStats, EntryStrategy, ErrorStats, ExpectedChange, OutcomeKind, ProgramTiming,
+        TestOutcome,
+    },
+    parse_warning::ParseWarningStats,
+    repo::RepoInfo,
+};
+
+/// Version of the `--summary-json` structure. Bump it whenever a field is renamed, removed or
+/// changes meaning; adding fields is backwards compatible.
+pub const SUMMARY_VERSION: u32 = 1;
+
+/// Headline numbers of a run.
+#[derive(Debug, Clone)]
+pub struct RunSummary {
+    /// Test case files discovered (including the ones without variants to run).
+    pub units: usize,
+    /// Test variants run.
+    pub variants: usize,
+    /// Test variants skipped by `--variant-filter`.
+    pub filtered: usize,
+    /// Test variants compared on the `--only-file` files only.
+    pub restricted: usize,
+    /// Test variants skipped because TypeScript only has emit baselines (`.js`) for them.
+    pub emit_only: usize,
+    /// Test variants skipped because their baselines are git-lfs pointers that were not fetched.
+    pub lfs_pointers: usize,
+    /// Test cases skipped because they apply `@edit` passes, see [`TestSettings::multi_pass`].
+    ///
+    /// [`TestSettings::multi_pass`]: crate::TestSettings::multi_pass
+    pub multi_pass: usize,
+    /// Test variants skipped because they were already scheduled, see
+    /// [`ParseWarningKind::DuplicateScheduled`].
+    ///
+    /// [`ParseWarningKind::DuplicateScheduled`]: crate::ParseWarningKind::DuplicateScheduled
+    pub duplicates: usize,
+    /// Test cases of the `--tests-json` list not found in the repo, relative to it.
+    pub missing_tests: Vec<PathBuf>,
+    /// Number of outcomes per kind, indexed like [`OutcomeKind::ALL`].
+    pub outcomes: [usize; OutcomeKind::ALL.len()],
+    pub assertions: AssertionStats,
+    pub errors: ErrorStats,
+    pub elapsed: Duration,
+    /// Time reader threads spent blocked on a full work queue, summed over threads. High values
+    /// mean workers are the bottleneck.
+    pub reader_idle: Duration,
+    /// Time worker threads spent waiting on an empty work queue, summed over threads. High values
+    /// mean readers are the bottleneck.
+    pub worker_idle: Duration,
+    /// Format gaps in test cases and baselines that parsers worked around.
+    pub parse_warnings: ParseWarningStats,
+    /// Variants per [`ExpectedChange`] with `--compare-repo`, indexed like [`ExpectedChange::ALL`].
+    pub expected_changes: [usize; ExpectedChange::ALL.len()],
+    /// Variants passing against the baselines of one checkout but not the other.
+    pub outcome_changes: usize,
+    /// Variants passing against the `--compare-repo` baselines but not the primary ones.
+    pub regressions: usize,
+    /// Variants on which the oxc parser panicked, for `--report-parser-panics`.
+    pub parser_panics: Vec<TestOutcome>,
+    /// Outcome counts per [`EntryStrategy`], indexed like [`EntryStrategy::ALL`] and then
+    /// [`OutcomeKind::ALL`].
+    pub entry_strategies: [[usize; OutcomeKind::ALL.len()]; EntryStrategy::ALL.len()],
+    /// Slowest baseline files to parse with `--slow-baselines`, in no particular order. Every
+    /// partial summary keeps at most the requested number.
+    pub slow_baselines: Vec<BaselineTiming>,
+    /// Cost of building the program of every unit with `--timings`, that of its slowest variant.
+    /// Variants share the modules they parse, so their costs are not added up.
+    pub unit_timings: FxHashMap<PathBuf, ProgramTiming>,
+    /// What happened to the variants of every unit.
+    pub ledger: VariantLedger,
+    /// Units whose variants were not all skipped or run, found by [`Self::finish`]. Empty
+    /// unless variants got lost on the way to the workers.
+    pub ledger_mismatches: Vec<LedgerMismatch>,
+    start: Instant,
+}
+
+impl Default for RunSummary {
+    fn default() -> Self {
+        Self {
+            units: 0,
+            variants: 0,
+            filtered: 0,
+            restricted: 0,
+            emit_only: 0,
+            lfs_pointers: 0,
+            multi_pass: 0,
+            duplicates: 0,
+            missing_tests: Vec::new(),
+            outcomes: [0; OutcomeKind::ALL.len()],
+            assertions: AssertionStats::default(),
+            errors: ErrorStats::default(),
+            elapsed: Duration::ZERO,
+            reader_idle: Duration::ZERO,
+            worker_idle: Duration::ZERO,
+            parse_warnings: ParseWarningStats::default(),
+            expected_changes: [0; ExpectedChange::ALL.len()],
+            outcome_changes: 0,
+            regressions: 0,
+            parser_panics: Vec::new(),
+            entry_strategies: [[0; OutcomeKind::ALL.len()]; EntryStrategy::ALL.len()],
+            slow_baselines: Vec::new(),
+            unit_timings: FxHashMap::default(),
+            ledger: VariantLedger::default(),
+            ledger_mismatches: Vec::new(),
+            start: Instant::now(),
+        }
+    }
+}
+
+impl RunSummary {
+    pub fn add(&mut self, outcome: &TestOutcome) {
+        self.variants += 1;
+        self.restricted += usize::from(!outcome.only_files.is_empty());
+        self.outcomes[outcome.kind as usize] += 1;
+        self.assertions += outcome.assertions;
+        self.errors += outcome.errors;
+        if let Some(compare) = outcome.compare {
+            self.expected_changes[compare.expected as usize] += 1;
+            self.outcome_changes += usize::from(compare.outcome_changed(outcome.kind));
+            self.regressions += usize::from(compare.regressed(outcome.kind));
+        }
+        if outcome.parser_panic.is_some() {
+            self.parser_panics.push(outcome.clone());
+        }
+        if let Some(entry_strategy) = outcome.entry_strategy {
+            self.entry_strategies[entry_strategy as usize][outcome.kind as usize] += 1;
+        }
+        if let Some(timing) = outcome.timing {
+            self.add_unit_timing(&outcome.path, timing);
+        }
+    }
+
+    fn add_unit_timing(&mut self, path: &Path, timing: ProgramTiming) {
+        match self.unit_timings.get_mut(path) {
+            Some(slowest) if slowest.total() < timing.total() => *slowest = timing,
+            Some(_) => {}
+            None => {
+                self.unit_timings.insert(path.to_path_buf(), timing);
+            }
+        }
+    }
+
+    /// Records the parse time of a baseline file, keeping only the `limit` slowest.
+    pub fn add_baseline_timing(&mut self, timing: BaselineTiming, limit: usize) {
+        if self.slow_baselines.len() < limit {
+            self.slow_baselines.push(timing);
+            return;
+        }
+
+        let fastest = self.slow_baselines.iter_mut().min_by_key(|x| x.elapsed);
+        if let Some(fastest) = fastest.filter(|x| x.elapsed < timing.elapsed) {
+            *fastest = timing;
+        }
+    }
+
+    /// Adds the counters of a partial summary collected on another thread.
+    pub fn merge(&mut self, other: &Self) {
+        self.units += other.units;
+        self.variants += other.variants;
+        self.filtered += other.filtered;
+        self.restricted += other.restricted;
+        self.emit_only += other.emit_only;
+        self.lfs_pointers += other.lfs_pointers;
+        self.multi_pass += other.multi_pass;
+        self.duplicates += other.duplicates;
+        self.missing_tests.extend_from_slice(&other.missing_tests);
+        for (count, other) in self.outcomes.iter_mut().zip(other.outcomes) {
+            *count += other;
+        }
+        self.assertions += other.assertions;
+        self.errors += other.errors;
+        self.reader_idle += other.reader_idle;
+        self.worker_idle += other.worker_idle;
+        self.parse_warnings.merge(&other.parse_warnings);
+        for (count, other) in self.expected_changes.iter_mut().zip(other.expected_changes) {
+            *count += other;
+        }
+        self.outcome_changes += other.outcome_changes;
+        self.regressions += other.regressions;
+        self.parser_panics.extend_from_slice(&other.parser_panics);
+        for (counts, other) in self.entry_strategies.iter_mut().zip(other.entry_strategies) {
+            for (count, other) in counts.iter_mut().zip(other) {
+                *count += other;
+            }
+        }
+        self.slow_baselines.extend_from_slice(&other.slow_baselines);
+        for (path, &timing) in &other.unit_timings {
+            self.add_unit_timing(path, timing);
+        }
+        self.ledger.merge(&other.ledger);
+    }
+
+    pub fn count(&self, kind: OutcomeKind) -> usize {
+        self.outcomes[kind as usize]
+    }
+
+    /// How the run ended. Unparsable baselines are gaps of the baseline parsers, not of the
+    /// checker, and do not fail it.
+    pub fn status(&self) -> RunStatus {
+        let failed = OutcomeKind::ALL
+            .iter()
+            .filter(|&&x| !matches!(x, OutcomeKind::Passed | OutcomeKind::BaselineUnparsable))
+            .any(|&x| self.count(x) > 0);
+        if self.regressions > 0 {
+            RunStatus::Regressed
+        } else if failed {
+            RunStatus::Failed
+        } else {
+            RunStatus::Passed
+        }
+    }
+
+    /// Stops the clock.
+    pub fn finish(&mut self) {
+        self.elapsed = self.start.elapsed();
+        self.ledger_mismatches = self.ledger.mismatches();
+    }
+
+    pub fn to_json(&self, repo: &RepoInfo) -> Value {
+        let outcomes = |counts: &[usize]| -> serde_json::Map<_, _> {
+            OutcomeKind::ALL
+                .iter()
+                .map(|&x| (x.as_str().to_string(), counts[x as usize].into()))
+                .collect()
+        };
+        let entry_strategies: serde_json::Map<_, _> = EntryStrategy::ALL
+            .iter()
+            .map(|&x| (x.as_str().to_string(), outcomes(&self.entry_strategies[x as usize]).into()))
+            .collect();
+
+        json!({
+            "version": SUMMARY_VERSION,
+            "typescript": {
+                "version": repo.version,
+                "commit": repo.commit,
+            },
+            "units": self.units,
+            "variants": self.variants,
+            "filtered_variants": self.filtered,
+            "restricted_variants": self.restricted,
+            "emit_only_variants": self.emit_only,
+            "lfs_pointer_variants": self.lfs_pointers,
+            "multi_pass_units": self.multi_pass,
+            "duplicate_variants": self.duplicates,
+            "missing_tests": self.missing_tests,
+            "ledger_mismatches": self.ledger_mismatches.iter().map(ToString::to_string).collect::<Vec<_>>(),
+            "outcomes": outcomes(&self.outcomes),
+            "assertions": {
+                "checked": self.assertions.checked,
+                "matched": self.assertions.matched,
+                "mismatched": self.assertions.mismatched(),
+                "normalized": self.assertions.normalized,
+            },
+            "errors": {
+                "matched": self.errors.matched,
+                "missing": self.errors.missing,
+                "unexpected": self.errors.unexpected,
+            },
+            "elapsed_ms": millis(self.elapsed),
+            "idle_ms": {
+                "readers": millis(self.reader_idle),
+                "workers": millis(self.worker_idle),
+            },
+            "parse_warnings": self.parse_warnings.to_json(),
+            "compare": {
+                "same": self.expected_changes[ExpectedChange::Same as usize],
+                "different": self.expected_changes[ExpectedChange::Different as usize],
+                "missing": self.expected_changes[ExpectedChange::Missing as usize],
+                "outcome_changed": self.outcome_changes,
+                "regressed": self.regressions,
+            },
+            "entry_strategies": entry_strategies,
+        })
+    }
+
+    /// # Errors
+    ///
+    /// Will return `Err` if the file cannot be written
+    pub fn write_json(&self, path: &Path, repo: &RepoInfo) -> std::io::Result<()> {
+        let mut data = serde_json::to_string_pretty(&self.to_json(repo))?;
+        data.push('\n');
+        fs::write(path, data)
+    }
+
+    /// Outcome counts of the entry strategies that were used, one per line.
+    pub fn entry_strategy_breakdown(&self) -> String {
+        let mut out = String::from("by entry strategy:\n");
+        for &entry_strategy in EntryStrategy::ALL {
+            let counts = &self.entry_strategies[entry_strategy as usize];
+            let variants: usize = counts.iter().sum();
+            if variants == 0 {
+                continue;
+            }
+
+            let _ = write!(out, "  {entry_strategy}: {} variants:", HumanCount(variants));
+            for (idx, &kind) in OutcomeKind::ALL.iter().enumerate() {
+                let separator = if idx == 0 { "" } else { "," };
+                let _ = write!(out, "{separator} {} {kind}", HumanCount(counts[kind as usize]));
+            }
+            out.push('\n');
+        }
+        out
+    }
+
+    /// The `limit` slowest baseline files to parse with their sizes, slowest first.
+    pub fn slow_baselines_report(&self, limit: usize) -> String {
+        let mut timings: Vec<_> = self.slow_baselines.iter().collect();
+        timings.sort_by(|a, b| b.elapsed.cmp(&a.elapsed).then_with(|| a.path.cmp(&b.path)));
+        timings.truncate(limit);
+
+        let mut out = format!("{} slowest baselines:\n", timings.len());
+        for timing in timings {
+            let _ = writeln!(
+                out,
+                "  {:>10}  {:>10}  {}",
+                HumanDuration(timing.elapsed),
+                HumanSize(timing.size),
+                timing.path.display()
+            );
+        }
+        out
+    }
+
+    /// The `limit` units slowest to parse and build the semantic model of, slowest first, with
+    /// the size of their sources and of what they allocated.
+    pub fn slow_units_report(&self, limit: usize) -> String {
+        let mut timings: Vec<_> = self.unit_timings.iter().collect();
+        timings.sort_by(|a, b| b.1.total().cmp(&a.1.total()).then_with(|| a.0.cmp(b.0)));
+        timings.truncate(limit);
+
+        let mut out = format!("{} slowest test cases to build:\n", timings.len());
+        for (path, timing) in timings {
+            let _ = writeln!(
+                out,
+                "  {:>10} = {:>10} parse + {:>10} semantic  {:>10} source  {:>10} allocated  {}",
+                HumanDuration(timing.total()),
+                HumanDuration(timing.parse),
+                HumanDuration(timing.semantic),
+                HumanSize(timing.source_len),
+                HumanSize(timing.allocated),
+                path.display()
+            );
+        }
+        out
+    }
+
+    /// Lists the variants the oxc parser panicked on with the file and its diagnostics, sorted
+    /// by test case.
+    pub fn parser_panics_report(&self) -> String {
+        let mut panics: Vec<_> = self.parser_panics.iter().collect();
+        panics.sort_by(|a, b| (&a.path, &a.variant).cmp(&(&b.path, &b.variant)));
+
+        let mut out = format!("{} parser panics\n", panics.len());
+        for outcome in panics {
+            let Some(panic) = &outcome.parser_panic else { continue };
+            let _ = writeln!(
+                out,
+                "\n{}{}\n  file: {}",
+                outcome.path.display(),
+                outcome.variant,
+                panic.file
+            );
+            for diagnostic in &panic.diagnostics {
+                let _ = write!(out, "  ");
+                if diagnostic.has_location() {
+                    let _ = write!(
+                        out,
+                        "{}({},{}): ",
+                        diagnostic.file, diagnostic.line, diagnostic.column
+                    );
+                }
+                let _ = writeln!(
+                    out,
+                    "{} TS{}: {}",
+                    diagnostic.severity, diagnostic.code, diagnostic.message
+                );
+            }
+        }
+        out
+    }
+
+    /// # Errors
+    ///
+    /// Will return `Err` if the file cannot be written
+    pub fn write_parser_panics(&self, path: &Path) -> std::io::Result<()> {
+        fs::write(path, self.parser_panics_report())
+    }
+}
+
+/// How a run ended, the exit code of `test-runner`.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum RunStatus {
+    /// Every variant passed or has an unparsable baseline.
+    Passed,
+    /// Some variants failed or could not be checked.
+    Failed,
+    /// The options or the repo are invalid, nothing was run.
+    Usage,
+    /// Some variants pass against the `--compare-repo` baselines but not the primary ones, or
+    /// building a `--smoke` program panicked.
+    Regressed,
+}
+
+impl RunStatus {
+    pub const fn code(self) -> u8 {
+        match self {
+            RunStatus::Passed => 0,
+            RunStatus::Failed => 1,
+            RunStatus::Usage => 2,
+            RunStatus::Regressed => 3,
+        }
+    }
+}
+
+impl From<RunStatus> for std::process::ExitCode {
+    fn from(status: RunStatus) -> Self {
+        Self::from(status.code())
+    }
+}
+
+fn millis(duration: Duration) -> u64 {
+    u64::try_from(duration.as_millis()).unwrap_or(u64::MAX)
+}
+
+impl std::fmt::Display for RunSummary {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} variants of {} test cases:",
+            HumanCount(self.variants),
+            HumanCount(self.units)
+        )?;
+        for (idx, &kind) in OutcomeKind::ALL.iter().enumerate() {
+            let separator = if idx == 0 { "" } else { "," };
+            write!(f, "{separator} {} {kind}", HumanCount(self.count(kind)))?;
+        }
+        if self.filtered > 0 {
+            write!(f, " ({} filtered out)", HumanCount(self.filtered))?;
+        }
+        if self.restricted > 0 {
+            write!(f, " ({} restricted to --only-file)", HumanCount(self.restricted))?;
+        }
+        if self.emit_only > 0 {
+            write!(f, " ({} emit-only skipped)", HumanCount(self.emit_only))?;
+        }
+        if self.lfs_pointers > 0 {
+            write!(f, " ({} git-lfs pointers skipped)", HumanCount(self.lfs_pointers))?;
+        }
+        if self.multi_pass > 0 {
+            write!(f, " ({} multi-pass skipped)", HumanCount(self.multi_pass))?;
+        }
+        if self.duplicates > 0 {
+            write!(f, " ({} duplicates skipped)", HumanCount(self.duplicates))?;
+        }
+        if self.parse_warnings.total() > 0 {
+            write!(f, " ({} parse warnings)", HumanCount(self.parse_warnings.total()))?;
+        }
+        write!(
+            f,
+            "  types: {}/{}  errors: {}/{}  ({}, idle: readers {}, workers {})",
+            HumanCount(self.assertions.matched),
+            HumanCount(self.assertions.checked),
+            HumanCount(self.errors.matched),
+            HumanCount(self.errors.matched + self.errors.missing),
+            HumanDuration(self.elapsed),
+            HumanDuration(self.reader_idle),
+            HumanDuration(self.worker_idle)
+        )?;
+        if self.assertions.normalized > 0 {
+            write!(f, "  normalized: {}", HumanCount(self.assertions.normalized))?;
+        }
+        if self.expected_changes.iter().any(|&x| x > 0) {
+            write!(f, "  compare:")?;
+            for &change in ExpectedChange::ALL {
+                write!(f, " {} {change},", HumanCount(self.expected_changes[change as usize]))?;
+            }
+            write!(
+                f,
+                " {} outcome changed, {} regressed",
+                HumanCount(self.outcome_changes),
+                HumanCount(self.regressions)
+            )?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use type_info::Diagnostic;
+
+    use super::*;
+    use crate::{
+        baseline::BaselineSource,
+        outcome::{ParserPanic, RepoComparison},
+    };
+
+    #[test]
+    fn json_structure() {
+        let mut summary = RunSummary {
+            units: 2,
+            emit_only: 1,
+            lfs_pointers: 1,
+            multi_pass: 1,
+            duplicates: 1,
+            missing_tests: vec![PathBuf::from("tests/cases/compiler/c.ts")],
+            ..Default::default()
+        };
+        summary.add(&TestOutcome {
+            path: PathBuf::from("tests/cases/compiler/a.ts"),
+            variant: String::new(),
+            kind: OutcomeKind::Failed,
+            assertions: AssertionStats { checked: 3, matched: 2, normalized: 1 },
+            errors: ErrorStats { matched: 1, missing: 1, unexpected: 0 },
+            baseline: BaselineSource::default(),
+            compare: None,
+            parser_panic: None,
+            baseline_error: None,
+            entry_strategy: Some(EntryStrategy::AllFiles),
+            only_files: Vec::new(),
+            timing: None,
+            log: String::new(),
+        });
+        summary.add(&TestOutcome {
+            path: PathBuf::from("tests/cases/compiler/b.ts"),
+            variant: "(strict=true)".to_string(),
+            kind: OutcomeKind::ProgramError,
+            assertions: AssertionStats::default(),
+            errors: ErrorStats::default(),
+            baseline: BaselineSource::default(),
+            compare: None,
+            parser_panic: None,
+            baseline_error: None,
+            entry_strategy: Some(EntryStrategy::TsconfigDriven),
+            only_files: Vec::new(),
+            timing: None,
+            log: String::new(),
+        });
+        summary.ledger.produced(Path::new("tests/cases/compiler/b.ts"), 2);
+        summary.ledger.executed(Path::new("tests/cases/compiler/b.ts"));
+        summary.finish();
+
+        let repo = RepoInfo { version: Some("5.9.0-dev".to_string()), commit: None };
+        let json = summary.to_json(&repo);
+        assert_eq!(json["version"], SUMMARY_VERSION);
+        assert_eq!(json["typescript"], json!({ "version": "5.9.0-dev", "commit": null }));
+        assert_eq!(json["units"], 2);
+        assert_eq!(json["variants"], 2);
+        assert_eq!(json["emit_only_variants"], 1);
+        assert_eq!(json["lfs_pointer_variants"], 1);
+        assert_eq!(json["multi_pass_units"], 1);
+        assert_eq!(json["duplicate_variants"], 1);
+        assert_eq!(json["missing_tests"], json!(["tests/cases/compiler/c.ts"]));
+        assert_eq!(
+            json["ledger_mismatches"],
+            json!(["tests/cases/compiler/b.ts: produced 2, skipped 0, executed 1"])
+        );
+        assert_eq!(
+            json["outcomes"],
+            json!({
+                "passed": 0,
+                "failed": 1,
+                "program_error": 1,
+                "resource_limit": 0,
+                "parser_panicked": 0,
+                "baseline_unparsable": 0,
+            })
+        );
+        assert_eq!(
+            json["assertions"],
+            json!({ "checked": 3, "matched": 2, "mismatched": 1, "normalized": 1 })
+        );
+        assert_eq!(json["errors"], json!({ "matched": 1, "missing": 1, "unexpected": 0 }));
+        assert_eq!(json["parse_warnings"]["invalid_boolean"], 0);
+        assert_eq!(json["entry_strategies"]["all_files"]["failed"], 1);
+        assert_eq!(json["entry_strategies"]["tsconfig_driven"]["program_error"], 1);
+        assert_eq!(
+            summary.entry_strategy_breakdown(),
+            "by entry strategy:
+  all_files: 1 variants: 0 passed, 1 failed, 0 program_error, 0 resource_limit, 0 parser_panicked, 0 baseline_unparsable
+  tsconfig_driven: 1 variants: 0 passed, 0 failed, 1 program_error, 0 resource_limit, 0 parser_panicked, 0 baseline_unparsable
+"
+        );
+
+        let mut merged = RunSummary::default();
+        merged.merge(&summary);
+        merged.merge(&summary);
+        assert_eq!(merged.units, 4);
+        merged.finish();
+        assert_eq!(merged.ledger_mismatches[0].account.produced, 4);
+        assert_eq!(merged.count(OutcomeKind::Failed), 2);
+        assert_eq!(merged.assertions, AssertionStats { checked: 6, matched: 4, normalized: 2 });
+    }
+
+    #[test]
+    fn parser_panics_report() {
+        let outcome = |path: &str, file: &str| TestOutcome {
+            path: PathBuf::from(path),
+            variant: String::new(),
+            kind: OutcomeKind::ParserPanicked,
+            assertions: AssertionStats::default(),
+            errors: ErrorStats::default(),
+            baseline: BaselineSource::default(),
+            compare: None,
+            parser_panic: Some(ParserPanic {
+                file: file.to_string(),
+                diagnostics: vec![Diagnostic {
+                    file: file.to_string(),
+                    line: 1,
+                    column: 5,
+                    message: "Unexpected token".to_string(),
+                    ..Default::default()
+                }],
+            }),
+            baseline_error: None,
+            entry_strategy: None,
+            only_files: Vec::new(),
+            timing: None,
+            log: String::new(),
+        };
+
+        let mut first = RunSummary::default();
+        first.add(&outcome("tests/cases/compiler/b.ts", "b.ts"));
+        let mut summary = RunSummary::default();
+        summary.add(&outcome("tests/cases/compiler/a.ts", "a.ts"));
+        summary.merge(&first);
+        assert_eq!(summary.count(OutcomeKind::ParserPanicked), 2);
+        assert_eq!(
+            summary.parser_panics_report(),
+            "2 parser panics
+
+tests/cases/compiler/a.ts
+  file: a.ts
+  a.ts(1,5): error TS0: Unexpected token
+
+tests/cases/compiler/b.ts
+  file: b.ts
+  b.ts(1,5): error TS0: Unexpected token
+"
+        );
+    }
+
+    #[test]
+    fn slow_baselines() {
+        let timing = |path: &str, millis| BaselineTiming {
+            path: PathBuf::from(path),
+            size: path.len(),
+            elapsed: Duration::from_millis(millis),
+        };
+
+        let mut first = RunSummary::default();
+        first.add_baseline_timing(timing("a.types", 5), 2);
+        first.add_baseline_timing(timing("b.types", 1), 2);
+        first.add_baseline_timing(timing("c.errors.txt", 3), 2);
+        first.add_baseline_timing(timing("d.types", 2), 2);
+        let mut summary = RunSummary::default();
+        summary.add_baseline_timing(timing("e.types", 4), 2);
+        summary.merge(&first);
+        assert_eq!(summary.slow_baselines.len(), 3);
+        assert_eq!(
+            summary.slow_baselines_report(2),
+            "2 slowest baselines:
+       5.0ms         7 B  a.types
+       4.0ms         7 B  e.types
+"
+        );
+    }
+
+    #[test]
+    fn slow_units() {
+        let outcome = |path: &str, millis| TestOutcome {
+            path: PathBuf::from(path),
+            variant: String::new(),
+            kind: OutcomeKind::Passed,
+            assertions: AssertionStats::default(),
+            errors: ErrorStats::default(),
+            baseline: BaselineSource::default(),
+            compare: None,
+            parser_panic: None,
+            baseline_error: None,
+            entry_strategy: None,
+            only_files: Vec::new(),
+            timing: Some(ProgramTiming {
+                parse: Duration::from_millis(millis),
+                semantic: Duration::from_millis(1),
+                source_len: 10,
+                allocated: 2048,
+            }),
+            log: String::new(),
+        };
+
+        let mut first = RunSummary::default();
+        first.add(&outcome("a.ts", 5));
+        first.add(&outcome("a.ts", 2));
+        first.add(&outcome("b.ts", 1));
+        let mut summary = RunSummary::default();
+        summary.add(&outcome("a.ts", 3));
+        summary.add(&outcome("c.ts", 4));
+        summary.merge(&first);
+        assert_eq!(summary.unit_timings.len(), 3);
+        assert_eq!(
+            summary.slow_units_report(2),
+            "2 slowest test cases to build:
+       6.0ms =      5.0ms parse +      1.0ms semantic        10 B source     2.0 KiB allocated  a.ts
+       5.0ms =      4.0ms parse +      1.0ms semantic        10 B source     2.0 KiB allocated  c.ts
+"
+        );
+    }
+
+    #[test]
+    fn status() {
+        let outcome = |kind, other| TestOutcome {
+            path: PathBuf::from("a.ts"),
+            variant: String::new(),
+            kind,
+            assertions: AssertionStats::default(),
+            errors: ErrorStats::default(),
+            baseline: BaselineSource::default(),
+            compare: Some(RepoComparison { expected: ExpectedChange::Same, other }),
+            parser_panic: None,
+            baseline_error: None,
+            entry_strategy: None,
+            only_files: Vec::new(),
+            timing: None,
+            log: String::new(),
+        };
+
+        let mut summary = RunSummary::default();
+        assert_eq!(summary.status(), RunStatus::Passed);
+        summary.add(&outcome(OutcomeKind::Passed, Some(OutcomeKind::Failed)));
+        summary.add(&outcome(OutcomeKind::BaselineUnparsable, None));
+        assert_eq!(summary.status(), RunStatus::Passed);
+        summary.add(&outcome(OutcomeKind::ResourceLimit, None));
+        assert_eq!(summary.status(), RunStatus::Failed);
+        summary.add(&outcome(OutcomeKind::Failed, Some(OutcomeKind::Failed)));
+        assert_eq!((summary.status(), summary.regressions), (RunStatus::Failed, 0));
+
+        let mut regressed = RunSummary::default();
+        regressed.add(&outcome(OutcomeKind::ProgramError, Some(OutcomeKind::Passed)));
+        summary.merge(&regressed);
+        assert_eq!((summary.status(), summary.regressions), (RunStatus::Regressed, 1));
+        assert_eq!(summary.outcome_changes, 2);
+
+        let codes = [RunStatus::Passed, RunStatus::Failed, RunStatus::Usage, RunStatus::Regressed]
+            .map(RunStatus::code);
+        assert_eq!(codes, [0, 1, 2, 3]);
+    }
+}
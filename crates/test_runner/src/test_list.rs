@@ -0,0 +1,142 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use rustc_hash::FxHashSet;
+use serde_json::Value;
+
+/// Directory the paths of a test list are relative to, like in the TS harness.
+const CASES_DIR: &str = "tests/cases";
+
+#[derive(Debug)]
+pub enum TestListError {
+    IO(std::io::Error),
+    Json(serde_json::Error),
+    /// The list is not a JSON array of strings; the value at the index is not a string, or
+    /// `None` when the list is not an array.
+    NotAPathList(Option<usize>),
+}
+
+impl std::fmt::Display for TestListError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TestListError::IO(err) => err.fmt(f),
+            TestListError::Json(err) => err.fmt(f),
+            TestListError::NotAPathList(None) => f.write_str("Expected an array of paths"),
+            TestListError::NotAPathList(Some(idx)) => {
+                write!(f, "Expected a path at index {idx}")
+            }
+        }
+    }
+}
+
+impl std::error::Error for TestListError {}
+
+impl From<std::io::Error> for TestListError {
+    fn from(value: std::io::Error) -> Self {
+        Self::IO(value)
+    }
+}
+
+impl From<serde_json::Error> for TestListError {
+    fn from(value: serde_json::Error) -> Self {
+        Self::Json(value)
+    }
+}
+
+/// Test cases listed in a `--tests-json` file, the way TypeScript's CI shards its tests.
+#[derive(Debug, Default, PartialEq, Eq, Clone)]
+pub struct TestList {
+    /// Listed test cases found in the repo, in the order of the list.
+    pub files: Vec<PathBuf>,
+    /// Listed test cases not found in the repo, relative to it.
+    pub missing: Vec<PathBuf>,
+}
+
+impl TestList {
+    /// Reads the list at `path` and resolves its entries in `repo`.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if the file cannot be read or is not a JSON array of strings
+    pub fn read(path: &Path, repo: &Path) -> Result<Self, TestListError> {
+        Self::parse(&fs::read_to_string(path)?, repo)
+    }
+
+    /// Resolves the entries of a JSON array of test case paths relative to `tests/cases`. The
+    /// paths may use `\` and may already start with `tests/cases/`. Entries listed twice are run
+    /// once.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if `data` is not a JSON array of strings
+    pub fn parse(data: &str, repo: &Path) -> Result<Self, TestListError> {
+        let Value::Array(entries) = serde_json::from_str(data)? else {
+            return Err(TestListError::NotAPathList(None));
+        };
+
+        let mut result = Self::default();
+        let mut seen = FxHashSet::default();
+        for (idx, entry) in entries.iter().enumerate() {
+            let Some(entry) = entry.as_str() else {
+                return Err(TestListError::NotAPathList(Some(idx)));
+            };
+            let relative = normalize(entry);
+            if !seen.insert(relative.clone()) {
+                continue;
+            }
+            let file = repo.join(&relative);
+            if file.is_file() {
+                result.files.push(file);
+            } else {
+                result.missing.push(relative);
+            }
+        }
+        Ok(result)
+    }
+}
+
+/// Path of a listed test case relative to the repo.
+fn normalize(entry: &str) -> PathBuf {
+    let entry = entry.replace('\\', "/");
+    let entry = entry.trim_start_matches("./");
+    let entry = entry.strip_prefix(CASES_DIR).and_then(|x| x.strip_prefix('/')).unwrap_or(entry);
+    Path::new(CASES_DIR).join(entry)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::SELF_TEST_CORPUS;
+
+    #[test]
+    fn parse() {
+        let repo = Path::new(SELF_TEST_CORPUS);
+        let data = r#"[
+            "compiler/strictVariants.ts",
+            "tests\\cases\\compiler\\anyVariables.ts",
+            "compiler/notThere.ts",
+            "./tests/cases/compiler/strictVariants.ts"
+        ]"#;
+        let list = TestList::parse(data, repo).unwrap();
+        assert_eq!(
+            list.files,
+            vec![
+                repo.join("tests/cases/compiler/strictVariants.ts"),
+                repo.join("tests/cases/compiler/anyVariables.ts")
+            ]
+        );
+        assert_eq!(list.missing, vec![PathBuf::from("tests/cases/compiler/notThere.ts")]);
+
+        assert!(matches!(
+            TestList::parse(r#"{"a": 1}"#, repo),
+            Err(TestListError::NotAPathList(None))
+        ));
+        assert!(matches!(
+            TestList::parse(r#"["a.ts", 1]"#, repo),
+            Err(TestListError::NotAPathList(Some(1)))
+        ));
+        assert!(matches!(TestList::parse("[", repo), Err(TestListError::Json(_))));
+    }
+}
@@ -1,12 +1,23 @@
 use core::str;
-use std::{iter, path::Path};
+use std::{
+    fs, io, iter,
+    path::{Component, Path, PathBuf},
+};
 
 use compact_str::CompactString;
 use memchr::{memchr, memchr_iter};
 use oxc_index::IndexVec;
 use rustc_hash::FxHashMap;
 
-use crate::byte_utils::{trim_space, trim_space_end, trim_space_start};
+use crate::{
+    byte_utils::{trim_space, trim_space_end, trim_space_start},
+    config_check::REMOVED_OPTIONS,
+    parse_warning::{ParseWarning, ParseWarningKind},
+};
+
+/// Values a single directive may vary over before the rest are dropped, see
+/// [`TestUnit::parse_with_limit`].
+pub const DEFAULT_MAX_VARIATION_VALUES: usize = 16;
 
 #[derive(Debug, Default, PartialEq, Eq, Clone)]
 pub struct TestSettings {
@@ -15,10 +26,16 @@ pub struct TestSettings {
     pub no_implicit_references: bool,
     pub include_built_file: Option<CompactString>,
     pub lib_files: Option<Vec<CompactString>>,
+    /// Options TypeScript removed that a directive sets, see `REMOVED_OPTIONS` in
+    /// `config_check`. Set to `false` they are left out.
+    pub removed_options: Vec<CompactString>,
+    /// The unit applies `@edit` passes to its files, which the TS harness checks one after the
+    /// other. Not supported, such units are skipped.
+    pub multi_pass: bool,
 }
 
-#[derive(Debug, PartialEq, Clone, Copy)]
-enum TestVariationProp {
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum TestVariationProp {
     AllowArbitraryExtensions,
     AllowImportingTsExtensions,
     AllowJS,
@@ -100,6 +117,40 @@ impl TestVariationProp {
             _ => panic!("Wildcard not defined for: {self:?}"),
         }
     }
+
+    /// Option name as `tsc` spells it on the command line, without the leading `--`.
+    pub fn flag_name(self) -> &'static str {
+        match self {
+            TestVariationProp::AllowArbitraryExtensions => "allowArbitraryExtensions",
+            TestVariationProp::AllowImportingTsExtensions => "allowImportingTsExtensions",
+            TestVariationProp::AllowJS => "allowJs",
+            TestVariationProp::ESModuleInterop => "esModuleInterop",
+            TestVariationProp::ExactOptionalPropertyTypes => "exactOptionalPropertyTypes",
+            TestVariationProp::IsolatedModules => "isolatedModules",
+            TestVariationProp::Jsx => "jsx",
+            TestVariationProp::Module => "module",
+            TestVariationProp::ModuleDetection => "moduleDetection",
+            TestVariationProp::ModuleResolution => "moduleResolution",
+            TestVariationProp::NoEmit => "noEmit",
+            TestVariationProp::NoImplicitAny => "noImplicitAny",
+            TestVariationProp::NoImplicitOverride => "noImplicitOverride",
+            TestVariationProp::NoPropertyAccessFromIndexSignature => {
+                "noPropertyAccessFromIndexSignature"
+            }
+            TestVariationProp::NoUncheckedIndexedAccess => "noUncheckedIndexedAccess",
+            TestVariationProp::NoUncheckedSideEffectImports => "noUncheckedSideEffectImports",
+            TestVariationProp::PreserveConstEnums => "preserveConstEnums",
+            TestVariationProp::ResolveJsonModule => "resolveJsonModule",
+            TestVariationProp::ResolvePackageJsonExports => "resolvePackageJsonExports",
+            TestVariationProp::Strict => "strict",
+            TestVariationProp::StrictBuiltinIteratorReturn => "strictBuiltinIteratorReturn",
+            TestVariationProp::StrictNullChecks => "strictNullChecks",
+            TestVariationProp::Target => "target",
+            TestVariationProp::UseDefineForClassFields => "useDefineForClassFields",
+            TestVariationProp::UseUnknownInCatchVariables => "useUnknownInCatchVariables",
+            TestVariationProp::VerbatimModuleSyntax => "verbatimModuleSyntax",
+        }
+    }
 }
 
 impl From<TestVariationProp> for &str {
@@ -241,7 +292,13 @@ impl TestVariations {
         }
     }
 
+    /// Values are kept in declaration order (it determines baseline names), duplicates are
+    /// dropped case-insensitively.
     fn push(&mut self, prop: TestVariationProp, value: CompactString) {
+        if self.get(prop).iter().any(|x| x.eq_ignore_ascii_case(&value)) {
+            return;
+        }
+
         match prop {
             TestVariationProp::AllowArbitraryExtensions => {
                 self.allow_arbitrary_extensions.push(value);
@@ -292,6 +349,14 @@ impl TestVariations {
         }
     }
 
+    fn truncate(&mut self, prop: TestVariationProp, len: usize) {
+        let kept = self.get(prop)[..len].to_vec();
+        self.clear(prop);
+        for value in kept {
+            self.push(prop, value);
+        }
+    }
+
     fn clear(&mut self, prop: TestVariationProp) {
         match prop {
             TestVariationProp::AllowArbitraryExtensions => self.allow_arbitrary_extensions.clear(),
@@ -342,7 +407,7 @@ impl TestVariations {
     }
 }
 
-#[derive(Debug, Default, PartialEq, Eq, Clone)]
+#[derive(Debug, Default, PartialEq, Eq, Hash, Clone)]
 pub struct TestVariant<'a> {
     pub name: String,
     pub allow_arbitrary_extensions: Option<&'a str>,
@@ -419,7 +484,7 @@ impl<'a> TestVariant<'a> {
         }
     }
 
-    fn get(&self, prop: TestVariationProp) -> Option<&'_ str> {
+    pub fn get(&self, prop: TestVariationProp) -> Option<&'_ str> {
         match prop {
             TestVariationProp::AllowArbitraryExtensions => self.allow_arbitrary_extensions,
             TestVariationProp::AllowImportingTsExtensions => self.allow_importing_ts_extensions,
@@ -454,6 +519,113 @@ impl<'a> TestVariant<'a> {
         }
     }
 
+    /// The variant with the `compilerOptions` of a `tsconfig.json` it does not set itself. The
+    /// test's directives win over the config, like in the TS harness. Options the variants do
+    /// not model are left out.
+    pub fn with_compiler_options<'b>(&self, options: &'b [(String, String)]) -> TestVariant<'b>
+    where
+        'a: 'b,
+    {
+        let mut result: TestVariant<'b> = self.clone();
+        for &prop in TEST_VARIATION_PROPS {
+            if result.get(prop).is_some() {
+                continue;
+            }
+            let value =
+                options.iter().find(|(name, _)| name.eq_ignore_ascii_case(prop.flag_name()));
+            if let Some((_, value)) = value {
+                result.set(prop, Some(value));
+            }
+        }
+        result
+    }
+
+    /// Whether `.js` files take part in the program, `@allowJs: true`.
+    pub fn allows_js(&self) -> bool {
+        self.allow_js.is_some_and(|x| x.eq_ignore_ascii_case("true"))
+    }
+
+    /// Whether `.json` files are modules, `@resolveJsonModule: true`.
+    pub fn resolves_json_module(&self) -> bool {
+        self.resolve_json_module.is_some_and(|x| x.eq_ignore_ascii_case("true"))
+    }
+
+    /// The options set in the variant as `tsc` command line flags, like
+    /// `["--module", "amd", "--strict", "false"]`. Values are lowercased like `tsc` prints them.
+    pub fn to_tsc_args(&self) -> Vec<String> {
+        let mut result = vec![];
+        for &prop in TEST_VARIATION_PROPS {
+            if let Some(value) = self.get(prop) {
+                result.push(format!("--{}", prop.flag_name()));
+                result.push(value.to_ascii_lowercase());
+            }
+        }
+        result
+    }
+
+    /// Turns a variant name like `(module=amd,target=es5)` back into the variant
+    /// the [`TestUnit::variations`] yield for it.
+    ///
+    /// Keys and values are case-insensitive and may come in any order. Every option the unit
+    /// varies over must be given; options with a single value may be repeated but must agree.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if the name is malformed or does not select exactly one variant of
+    /// `variations`
+    pub fn parse_name(
+        name: &str,
+        variations: &'a TestVariations,
+    ) -> Result<Self, VariantParseError> {
+        let mut result = Self::default();
+        let mut name_props = vec![];
+        for &prop in TEST_VARIATION_PROPS {
+            match variations.get(prop).as_slice() {
+                [] => {}
+                [value] => result.set(prop, Some(value.as_str())),
+                _ => name_props.push(prop),
+            }
+        }
+
+        let components = if name.is_empty() {
+            ""
+        } else {
+            name.strip_prefix('(')
+                .and_then(|x| x.strip_suffix(')'))
+                .ok_or_else(|| VariantParseError::Malformed(name.to_string()))?
+        };
+
+        let mut seen = vec![];
+        for component in components.split(',').filter(|x| !x.is_empty()) {
+            let Some((key, value)) = component.split_once('=') else {
+                return Err(VariantParseError::Malformed(name.to_string()));
+            };
+
+            let key = key.trim().to_ascii_lowercase();
+            let value = value.trim();
+            let prop = TestVariationProp::try_from(key.as_bytes())
+                .map_err(|()| VariantParseError::UnknownKey(key.clone()))?;
+            if seen.contains(&prop) {
+                return Err(VariantParseError::DuplicateKey(key));
+            }
+            seen.push(prop);
+
+            let Some(declared) =
+                variations.get(prop).iter().find(|x| x.eq_ignore_ascii_case(value))
+            else {
+                return Err(VariantParseError::UnknownValue(key, value.to_string()));
+            };
+            result.set(prop, Some(declared.as_str()));
+        }
+
+        if let Some(&missing) = name_props.iter().find(|x| !seen.contains(x)) {
+            return Err(VariantParseError::MissingKey(<&str>::from(missing).to_string()));
+        }
+
+        result.update_name(&name_props);
+        Ok(result)
+    }
+
     fn update_name(&mut self, name_props: &[TestVariationProp]) {
         let components: Vec<_> = name_props
             .iter()
@@ -467,10 +639,95 @@ impl<'a> TestVariant<'a> {
     }
 }
 
+#[derive(Debug, PartialEq, Eq)]
+pub enum VariantParseError {
+    /// Not empty and not a parenthesized list of `key=value` pairs.
+    Malformed(String),
+    UnknownKey(String),
+    DuplicateKey(String),
+    /// The unit does not declare this value for the option.
+    UnknownValue(String, String),
+    /// The unit varies over this option, but the name does not set it.
+    MissingKey(String),
+}
+
+impl std::fmt::Display for VariantParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            VariantParseError::Malformed(name) => {
+                write!(f, "Expected variant name like (key=value,...), got: {name}")
+            }
+            VariantParseError::UnknownKey(key) => write!(f, "Unknown variant option: {key}"),
+            VariantParseError::DuplicateKey(key) => write!(f, "Variant option set twice: {key}"),
+            VariantParseError::UnknownValue(key, value) => {
+                write!(f, "Test does not declare {key}={value}")
+            }
+            VariantParseError::MissingKey(key) => write!(f, "Missing variant option: {key}"),
+        }
+    }
+}
+
+impl std::error::Error for VariantParseError {}
+
+/// Constraint on a variant option, written like in variant names: `module=esnext`.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct VariantConstraint {
+    prop: TestVariationProp,
+    value: CompactString,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum VariantConstraintError {
+    MissingValue(String),
+    UnknownKey(String),
+}
+
+impl std::fmt::Display for VariantConstraintError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            VariantConstraintError::MissingValue(text) => {
+                write!(f, "Expected key=value variant filter, got: {text}")
+            }
+            VariantConstraintError::UnknownKey(key) => {
+                write!(f, "Unknown variant filter key: {key}\n  valid keys:")?;
+                for &prop in TEST_VARIATION_PROPS {
+                    write!(f, " {}", <&str>::from(prop))?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl std::error::Error for VariantConstraintError {}
+
+impl VariantConstraint {
+    /// Keys and values are case-insensitive, like the test settings they refer to.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if there is no `=` or the key is not a variant option
+    pub fn parse(text: &str) -> Result<Self, VariantConstraintError> {
+        let Some((key, value)) = text.split_once('=') else {
+            return Err(VariantConstraintError::MissingValue(text.to_string()));
+        };
+
+        let key = key.trim().to_ascii_lowercase();
+        let prop = TestVariationProp::try_from(key.as_bytes())
+            .map_err(|()| VariantConstraintError::UnknownKey(key.clone()))?;
+        Ok(Self { prop, value: value.trim().to_ascii_lowercase().into() })
+    }
+
+    /// Variants that do not set the option at all never match.
+    pub fn matches(&self, variant: &TestVariant<'_>) -> bool {
+        variant.get(self.prop).is_some_and(|x| x.eq_ignore_ascii_case(&self.value))
+    }
+}
+
 #[derive(Debug)]
 struct RestartableIterator<'a> {
     arr: &'a Vec<CompactString>,
-    idx: u8,
+    idx: usize,
 }
 
 impl<'a> RestartableIterator<'a> {
@@ -483,7 +740,7 @@ impl<'a> Iterator for RestartableIterator<'a> {
     type Item = Option<&'a str>;
 
     fn next(&mut self) -> Option<Self::Item> {
-        let result = self.arr.get(self.idx as usize).map(|x| Some(x.as_str()));
+        let result = self.arr.get(self.idx).map(|x| Some(x.as_str()));
         if result.is_none() {
             self.idx = 0;
         } else {
@@ -499,7 +756,10 @@ pub struct VariationIter<'a> {
     name_props: Vec<TestVariationProp>,
     template: TestVariant<'a>,
     iter: Vec<RestartableIterator<'a>>,
-    done: bool,
+    started: bool,
+    /// Number of variants left to yield. Bounds the odometer below, so the iterator is fused even
+    /// though the per-prop iterators restart after returning `None`.
+    remaining: usize,
 }
 
 impl<'a> VariationIter<'a> {
@@ -508,7 +768,8 @@ impl<'a> VariationIter<'a> {
             name_props: vec![],
             template: TestVariant::default(),
             iter: vec![],
-            done: false,
+            started: false,
+            remaining: 1,
         };
 
         for &prop in TEST_VARIATION_PROPS {
@@ -518,8 +779,9 @@ impl<'a> VariationIter<'a> {
                 1 => {
                     result.template.set(prop, Some(arr[0].as_str()));
                 }
-                _ => {
+                len => {
                     result.name_props.push(prop);
+                    result.remaining = result.remaining.saturating_mul(len);
                     let mut iter = RestartableIterator::new(arr);
                     result.template.set(prop, iter.next().unwrap());
                     result.iter.push(iter);
@@ -537,42 +799,45 @@ impl<'a> Iterator for VariationIter<'a> {
     type Item = TestVariant<'a>;
 
     fn next(&mut self) -> Option<Self::Item> {
-        if self.iter.is_empty() {
-            if self.done {
-                None
-            } else {
-                self.done = true;
-                Some(self.template.clone())
-            }
-        } else if self.done {
-            for i in (0..self.name_props.len()).rev() {
-                let Some(value) = self.iter[i].next() else {
-                    // println!("{:?} -> None", self.name_props[i]);
-                    continue;
-                };
+        if self.remaining == 0 {
+            return None;
+        }
+        self.remaining -= 1;
 
-                // println!("{:?} -> {:?}", self.name_props[i], value);
+        if !self.started {
+            self.started = true;
+            return Some(self.template.clone());
+        }
 
-                self.template.set(self.name_props[i], value);
-                for i in i + 1..self.name_props.len() {
-                    let value = self.iter[i].next();
-                    // println!("[RESTART] {:?} -> {:?}", self.name_props[i], value);
-                    self.template.set(self.name_props[i], value.unwrap());
-                }
+        // Odometer: advance the last prop, restarting every prop after the one that advanced
+        for i in (0..self.name_props.len()).rev() {
+            let Some(value) = self.iter[i].next() else {
+                continue;
+            };
 
-                let mut result = self.template.clone();
-                result.update_name(&self.name_props);
-                return Some(result);
+            self.template.set(self.name_props[i], value);
+            for i in i + 1..self.name_props.len() {
+                let value = self.iter[i].next();
+                self.template.set(self.name_props[i], value.unwrap());
             }
 
-            None
-        } else {
-            self.done = true;
-            Some(self.template.clone())
+            let mut result = self.template.clone();
+            result.update_name(&self.name_props);
+            return Some(result);
         }
+
+        unreachable!("variation iterator exhausted before the expected number of variants");
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
     }
 }
 
+impl ExactSizeIterator for VariationIter<'_> {}
+
+impl std::iter::FusedIterator for VariationIter<'_> {}
+
 oxc_index::define_index_type! {
   pub struct FileId = u8;
 }
@@ -585,11 +850,20 @@ pub struct TestUnit<'a> {
     pub file_names: IndexVec<FileId, &'a str>,
     pub file_contents: IndexVec<FileId, &'a str>,
     pub symlinks: FxHashMap<&'a str, &'a str>,
+    /// Directives that were ignored because their value could not be understood.
+    pub warnings: Vec<ParseWarning>,
 }
 
 impl<'a> TestUnit<'a> {
     /// # Panics
     pub fn parse(path: &'a Path, data: &'a [u8]) -> Self {
+        Self::parse_with_limit(path, data, DEFAULT_MAX_VARIATION_VALUES)
+    }
+
+    /// Like [`TestUnit::parse`], keeping at most `max_values` values per varied option.
+    ///
+    /// # Panics
+    pub fn parse_with_limit(path: &'a Path, data: &'a [u8], max_values: usize) -> Self {
         let mut result = Self {
             path,
             settings: TestSettings::default(),
@@ -597,6 +871,11 @@ impl<'a> TestUnit<'a> {
             file_names: IndexVec::default(),
             file_contents: IndexVec::default(),
             symlinks: FxHashMap::default(),
+            warnings: Vec::new(),
+        };
+        let warn = |kind, line_start: usize, line: &[u8]| {
+            let line_number = memchr_iter(b'\n', &data[..line_start]).count() + 1;
+            ParseWarning::new(kind, path, line_number, line.strip_suffix(b"\n").unwrap_or(line))
         };
 
         let mut iter = memchr_iter(b'\n', data);
@@ -619,11 +898,14 @@ impl<'a> TestUnit<'a> {
                     if let Some(name_end) = memchr(b':', &rest[2..]) {
                         if let Some(content_start) = file_start {
                             // println!("file complete: {file_name}");
-                            result.file_names.push(file_name);
-                            result.file_contents.push(
-                                str::from_utf8(&data[content_start..line_start])
-                                    .expect("file content to be UTF8"),
-                            );
+                            let content = &data[content_start..line_start];
+                            if !result.push_file(file_name, content) {
+                                result.warnings.push(warn(
+                                    ParseWarningKind::InvalidUtf8,
+                                    content_start,
+                                    file_name.as_bytes(),
+                                ));
+                            }
                             file_start = None;
                         }
 
@@ -651,31 +933,34 @@ impl<'a> TestUnit<'a> {
                                 file_start = Some(eol + 1);
                             }
                             b"link" => {
-                                let separator = memchr(b' ', value)
-                                    .expect("symlink arguments should be separated by space");
-                                let from = str::from_utf8(&value[..separator])
-                                    .expect("symlink argument to be UTF8");
-                                let to = str::from_utf8(&value[separator + 1..])
-                                    .expect("symlink argument to be UTF8");
-                                result.symlinks.insert(from, to);
+                                if let Some(separator) = memchr(b' ', value) {
+                                    let from = str::from_utf8(&value[..separator])
+                                        .expect("symlink argument to be UTF8");
+                                    let to = str::from_utf8(&value[separator + 1..])
+                                        .expect("symlink argument to be UTF8");
+                                    result.symlinks.insert(from, to);
+                                } else {
+                                    result.warnings.push(warn(
+                                        ParseWarningKind::MalformedLink,
+                                        line_start,
+                                        line,
+                                    ));
+                                }
                             }
                             b"baseurl" => {
                                 result.settings.base_url = Some(
                                     CompactString::from_utf8(value).expect("baseUrl to be UTF8"),
                                 );
                             }
-                            b"noimplicitreferences" => {
-                                result.settings.no_implicit_references = match &value
-                                    .to_ascii_lowercase()[..]
-                                {
-                                    b"true" => true,
-                                    b"false" => false,
-                                    _ => panic!(
-                                        "Unknown value for noImplicitReferences: {}",
-                                        str::from_utf8(value).unwrap_or_default().escape_debug()
-                                    ),
-                                };
-                            }
+                            b"noimplicitreferences" => match &value.to_ascii_lowercase()[..] {
+                                b"true" => result.settings.no_implicit_references = true,
+                                b"false" => result.settings.no_implicit_references = false,
+                                _ => result.warnings.push(warn(
+                                    ParseWarningKind::InvalidBoolean,
+                                    line_start,
+                                    line,
+                                )),
+                            },
                             b"includebuiltfile" => {
                                 result.settings.include_built_file = Some(
                                     CompactString::from_utf8(value)
@@ -700,17 +985,17 @@ impl<'a> TestUnit<'a> {
 
                                 result.settings.lib_files = Some(lib_files);
                             }
-                            b"notypesandsymbols" => {
-                                result.settings.no_types_and_symbols = match &value
-                                    .to_ascii_lowercase()[..]
-                                {
-                                    b"true" => true,
-                                    b"false" => false,
-                                    _ => panic!(
-                                        "Unknown value for noTypeAndSymbols: {}",
-                                        str::from_utf8(value).unwrap_or_default().escape_debug()
-                                    ),
-                                };
+                            b"notypesandsymbols" => match &value.to_ascii_lowercase()[..] {
+                                b"true" => result.settings.no_types_and_symbols = true,
+                                b"false" => result.settings.no_types_and_symbols = false,
+                                _ => result.warnings.push(warn(
+                                    ParseWarningKind::InvalidBoolean,
+                                    line_start,
+                                    line,
+                                )),
+                            },
+                            name if is_multi_pass_directive(name) => {
+                                result.settings.multi_pass = true;
                             }
                             prop => {
                                 if let Ok(prop) = TestVariationProp::try_from(prop) {
@@ -734,6 +1019,27 @@ impl<'a> TestUnit<'a> {
                                             start = separator + 1;
                                         }
                                     }
+
+                                    let count = result.variations.get(prop).len();
+                                    if count > max_values {
+                                        result.variations.truncate(prop, max_values);
+                                        result.warnings.push(warn(
+                                            ParseWarningKind::TooManyVariationValues,
+                                            line_start,
+                                            format!(
+                                                "@{}: {count} values, kept {max_values}",
+                                                str::from_utf8(name).unwrap_or("?")
+                                            )
+                                            .as_bytes(),
+                                        ));
+                                    }
+                                } else if let Some(&option) = REMOVED_OPTIONS
+                                    .iter()
+                                    .find(|x| x.as_bytes().eq_ignore_ascii_case(prop))
+                                {
+                                    if !value.is_empty() && !value.eq_ignore_ascii_case(b"false") {
+                                        result.settings.removed_options.push(option.into());
+                                    }
                                 }
                                 // println!("unknown option: {}", str::from_utf8(name).unwrap().escape_debug());
                             }
@@ -756,23 +1062,127 @@ impl<'a> TestUnit<'a> {
 
         if let Some(file_start) = file_start {
             // println!("file complete: {file_name}");
-            let Ok(content) = str::from_utf8(&data[file_start..]) else {
-                panic!(
-                    "Expected file content to be UTF8:\n  path: {}\n  file_name: {}",
-                    path.display(),
-                    file_name
-                );
-            };
-
-            result.file_names.push(file_name);
-            result.file_contents.push(content);
-        } else if result.file_names.is_empty() {
+            if !result.push_file(file_name, &data[file_start..]) {
+                result.warnings.push(warn(
+                    ParseWarningKind::InvalidUtf8,
+                    file_start,
+                    file_name.as_bytes(),
+                ));
+            }
+        } else if result.file_names.is_empty()
+            && result.warnings.iter().all(|x| x.kind != ParseWarningKind::InvalidUtf8)
+        {
             result.file_names.push(file_name);
             result.file_contents.push("");
         }
 
         result
     }
+
+    /// Adds a file to the unit. Returns `false` and leaves the file out if its content is not
+    /// UTF-8.
+    fn push_file(&mut self, name: &'a str, content: &'a [u8]) -> bool {
+        let Ok(content) = str::from_utf8(content) else {
+            return false;
+        };
+
+        self.file_names.push(name);
+        self.file_contents.push(content);
+        true
+    }
+
+    /// Writes the files of the unit under `root`, creating directories as needed, and returns the
+    /// written paths. Absolute file names are placed inside `root`.
+    ///
+    /// Every `@link` is created as a symlink, or as a copy of its target where the platform does
+    /// not allow symlinks.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if a file name escapes `root` with `..`, or if writing fails
+    pub fn materialize(&self, root: &Path) -> io::Result<Vec<PathBuf>> {
+        let mut written = Vec::with_capacity(self.file_names.len() + self.symlinks.len());
+        for (name, content) in self.file_names.iter().zip(self.file_contents.iter()) {
+            let path = virtual_path(root, name)?;
+            if let Some(parent) = path.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            fs::write(&path, content)?;
+            written.push(path);
+        }
+
+        let mut symlinks: Vec<_> = self.symlinks.iter().collect();
+        symlinks.sort_unstable();
+        for (from, to) in symlinks {
+            let target = virtual_path(root, from)?;
+            let link = virtual_path(root, to)?;
+            if let Some(parent) = link.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            symlink_or_copy(&target, &link)?;
+            written.push(link);
+        }
+
+        Ok(written)
+    }
+}
+
+/// Directives of the incremental-edit family, `@edit` and numbered passes like `@edit2`, given
+/// lowercased.
+fn is_multi_pass_directive(name: &[u8]) -> bool {
+    name.strip_prefix(b"edit").is_some_and(|x| x.iter().all(u8::is_ascii_digit))
+}
+
+/// Maps a virtual file name onto `root`, refusing names that climb out of it.
+fn virtual_path(root: &Path, name: &str) -> io::Result<PathBuf> {
+    let mut result = PathBuf::new();
+    for component in Path::new(name).components() {
+        match component {
+            Component::Prefix(_) | Component::RootDir | Component::CurDir => {}
+            Component::ParentDir => {
+                if !result.pop() {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidInput,
+                        format!("file name escapes the unit root: {name}"),
+                    ));
+                }
+            }
+            Component::Normal(part) => result.push(part),
+        }
+    }
+    Ok(root.join(result))
+}
+
+fn symlink_or_copy(target: &Path, link: &Path) -> io::Result<()> {
+    #[cfg(unix)]
+    if std::os::unix::fs::symlink(target, link).is_ok() {
+        return Ok(());
+    }
+    #[cfg(windows)]
+    if if target.is_dir() {
+        std::os::windows::fs::symlink_dir(target, link)
+    } else {
+        std::os::windows::fs::symlink_file(target, link)
+    }
+    .is_ok()
+    {
+        return Ok(());
+    }
+
+    copy_tree(target, link)
+}
+
+fn copy_tree(from: &Path, to: &Path) -> io::Result<()> {
+    if !from.is_dir() {
+        return fs::copy(from, to).map(|_| ());
+    }
+
+    fs::create_dir_all(to)?;
+    for entry in fs::read_dir(from)? {
+        let entry = entry?;
+        copy_tree(&entry.path(), &to.join(entry.file_name()))?;
+    }
+    Ok(())
 }
 
 #[cfg(test)]
@@ -818,11 +1228,28 @@ mod tests {
                     variations: TestVariations::default(),
                     file_names: index_vec!["unit1.ts"],
                     file_contents: index_vec!["export const foo = 5;"],
+                    warnings: vec![],
                     symlinks: FxHashMap::default(),
                 }
             );
         }
 
+        #[test]
+        fn duplicate_variation_values() {
+            let path = PathBuf::from_str("tests/cases/unit1.ts").unwrap();
+            let data = br"// @strict: true, true
+// @target: esnext, ES5, es2015, es5, ESNext
+export const foo = 5;";
+
+            let test_unit = TestUnit::parse(&path, data);
+            assert_eq!(test_unit.variations.strict, vec!["true"]);
+            assert_eq!(test_unit.variations.target, vec!["esnext", "ES5", "es2015"]);
+            assert_eq!(
+                test_unit.variations.iter().map(|x| x.name).collect::<Vec<_>>(),
+                vec!["(target=esnext)", "(target=ES5)", "(target=es2015)"]
+            );
+        }
+
         #[test]
         fn single_file_with_options() {
             let path = PathBuf::from_str("tests/cases/unit1.ts").unwrap();
@@ -843,11 +1270,14 @@ export const foo = 5;";
                         base_url: Some(".".into()),
                         no_implicit_references: true,
                         include_built_file: Some("lib.d.ts".into()),
-                        lib_files: Some(vec!["lib.d.ts".into(), "react.d.ts".into()])
+                        lib_files: Some(vec!["lib.d.ts".into(), "react.d.ts".into()]),
+                        removed_options: vec![],
+                        multi_pass: false,
                     },
                     variations: TestVariations::default(),
                     file_names: index_vec!["unit1.ts"],
                     file_contents: index_vec!["export const foo = 5;"],
+                    warnings: vec![],
                     symlinks: FxHashMap::default(),
                 }
             );
@@ -871,6 +1301,7 @@ export const foo = 5;";
                     },
                     file_names: index_vec!["unit1.ts"],
                     file_contents: index_vec!["export const foo = 5;"],
+                    warnings: vec![],
                     symlinks: FxHashMap::default(),
                 }
             );
@@ -891,6 +1322,7 @@ export const foo = 5;";
                     variations: TestVariations::default(),
                     file_names: index_vec!["/a.js"],
                     file_contents: index_vec!["export const foo = 5;"],
+                    warnings: vec![],
                     symlinks: FxHashMap::default(),
                 }
             );
@@ -923,6 +1355,7 @@ export function bar() {}";
 ",
                         r"export function bar() {}"
                     ],
+                    warnings: vec![],
                     symlinks: FxHashMap::default(),
                 }
             );
@@ -962,6 +1395,7 @@ export function bar() {}
                         r"export function bar() {}
 "
                     ],
+                    warnings: vec![],
                     symlinks: vec![("foo", "bar"), ("ab1", "ab2"), ("a123", "b123"), ("q1", "q2")]
                         .into_iter()
                         .collect(),
@@ -969,6 +1403,34 @@ export function bar() {}
             );
         }
 
+        #[test]
+        fn invalid_utf8() {
+            let path = PathBuf::from_str("tests/cases/unit1.ts").unwrap();
+            let data = b"// @filename: a.ts
+export const a = 1;
+// @filename: b.ts
+const b = '\xff\xfe';
+// @filename: c.ts
+export const c = 2;
+";
+
+            let test_unit = TestUnit::parse(&path, data);
+            assert_eq!(test_unit.file_names, index_vec!["a.ts", "c.ts"]);
+            assert_eq!(test_unit.file_contents[FileId::from_raw(1)], "export const c = 2;\n");
+            assert_eq!(
+                test_unit
+                    .warnings
+                    .iter()
+                    .map(|x| (x.kind, x.line, x.snippet.as_str()))
+                    .collect::<Vec<_>>(),
+                vec![(ParseWarningKind::InvalidUtf8, 4, "b.ts")]
+            );
+
+            let test_unit = TestUnit::parse(&path, b"// @target: es5\nconst a = '\xc3';\n");
+            assert!(test_unit.file_names.is_empty());
+            assert_eq!(test_unit.warnings[0].kind, ParseWarningKind::InvalidUtf8);
+        }
+
         #[test]
         fn empty_files() {
             let path = PathBuf::from_str("tests/cases/unit1.ts").unwrap();
@@ -992,6 +1454,7 @@ export function bar() {}
 ", r"
 ", r"/// foo"
                     ],
+                    warnings: vec![],
                     symlinks: FxHashMap::default(),
                 }
             );
@@ -1019,6 +1482,7 @@ export const foo = 5;";
                     },
                     file_names: index_vec!["unit1.ts"],
                     file_contents: index_vec!["export const foo = 5;"],
+                    warnings: vec![],
                     symlinks: FxHashMap::default(),
                 }
             );
@@ -1046,13 +1510,71 @@ export const foo = 5;";
                     },
                     file_names: index_vec!["unit1.ts"],
                     file_contents: index_vec!["export const foo = 5;"],
+                    warnings: vec![],
                     symlinks: FxHashMap::default(),
                 }
             );
         }
+
+        #[test]
+        fn too_many_variation_values() {
+            let path = PathBuf::from_str("tests/cases/unit1.ts").unwrap();
+            let data =
+                b"// @target: es5, es2015, es2016, es2017\n// @module: *\nexport const foo = 5;";
+
+            let test_unit = TestUnit::parse_with_limit(&path, data, 3);
+            assert_eq!(test_unit.variations.target, vec!["es5", "es2015", "es2016"]);
+            assert_eq!(test_unit.variations.module, vec!["amd", "es6", "umd"]);
+            assert_eq!(test_unit.variations.iter().len(), 9);
+            assert_eq!(
+                test_unit
+                    .warnings
+                    .iter()
+                    .map(|x| (x.kind, x.line, x.snippet.as_str()))
+                    .collect::<Vec<_>>(),
+                vec![
+                    (ParseWarningKind::TooManyVariationValues, 1, "@target: 4 values, kept 3"),
+                    (ParseWarningKind::TooManyVariationValues, 2, "@module: 13 values, kept 3"),
+                ]
+            );
+            assert!(TestUnit::parse(&path, data).warnings.is_empty());
+        }
+
+        #[test]
+        fn multi_pass() {
+            let path = PathBuf::from_str("tests/cases/unit1.ts").unwrap();
+            let data = b"// @filename: a.ts\nlet a = 1;\n// @edit2: a.ts\nlet a = 2;\n";
+            let test_unit = TestUnit::parse(&path, data);
+            assert!(test_unit.settings.multi_pass);
+            assert!(!TestUnit::parse(&path, b"// @editor: vim\nlet a;").settings.multi_pass);
+        }
+
+        #[test]
+        fn invalid_directives() {
+            let path = PathBuf::from_str("tests/cases/unit1.ts").unwrap();
+            let data = b"// @noImplicitReferences: yes\r\n// @link: a.ts\r\n// @noTypesAndSymbols: true\r\nexport const foo = 5;";
+
+            let test_unit = TestUnit::parse(&path, data);
+            assert!(!test_unit.settings.no_implicit_references);
+            assert!(test_unit.settings.no_types_and_symbols);
+            assert!(test_unit.symlinks.is_empty());
+            assert_eq!(
+                test_unit
+                    .warnings
+                    .iter()
+                    .map(|x| (x.kind, x.line, x.snippet.as_str()))
+                    .collect::<Vec<_>>(),
+                vec![
+                    (ParseWarningKind::InvalidBoolean, 1, "// @noImplicitReferences: yes"),
+                    (ParseWarningKind::MalformedLink, 2, "// @link: a.ts"),
+                ]
+            );
+        }
     }
 
     mod variant_iter {
+        use std::collections::HashSet;
+
         use compact_str::ToCompactString;
 
         use super::*;
@@ -1146,5 +1668,336 @@ export const foo = 5;";
                 ]
             );
         }
+
+        #[test]
+        fn fused_after_exhaustion() {
+            let variations = TestVariations {
+                module: vec!["commonjs".to_compact_string(), "umd".to_compact_string()],
+                target: vec!["es5".to_compact_string(), "es6".to_compact_string()],
+                ..Default::default()
+            };
+            let mut iter = variations.iter();
+            assert_eq!(iter.by_ref().count(), 4);
+            assert_eq!(iter.next(), None);
+            assert_eq!(iter.next(), None);
+            assert_eq!(iter.len(), 0);
+        }
+
+        #[test]
+        fn single_valued_props() {
+            let variations = TestVariations {
+                module: vec!["commonjs".to_compact_string(), "umd".to_compact_string()],
+                strict: vec!["true".to_compact_string()],
+                target: vec!["es5".to_compact_string(), "es6".to_compact_string()],
+                ..Default::default()
+            };
+            let result: Vec<_> = variations.iter().collect();
+            assert_eq!(result.len(), 4);
+            assert!(result.iter().all(|x| x.strict == Some("true")));
+            assert_eq!(result[3].name, "(module=umd,target=es6)");
+
+            let variations = TestVariations::default();
+            let result: Vec<_> = variations.iter().collect();
+            assert_eq!(result, vec![TestVariant::default()]);
+        }
+
+        /// Xorshift, good enough to shuffle inputs without pulling in a dependency.
+        struct Rng(u64);
+
+        impl Rng {
+            fn next(&mut self, bound: usize) -> usize {
+                self.0 ^= self.0 << 13;
+                self.0 ^= self.0 >> 7;
+                self.0 ^= self.0 << 17;
+                usize::try_from(self.0 % bound as u64).unwrap()
+            }
+        }
+
+        fn cartesian_product(variations: &TestVariations) -> Vec<TestVariant<'_>> {
+            let mut result = vec![TestVariant::default()];
+            let mut name_props = vec![];
+            for &prop in TEST_VARIATION_PROPS {
+                let values = variations.get(prop);
+                if values.len() > 1 {
+                    name_props.push(prop);
+                }
+                if values.is_empty() {
+                    continue;
+                }
+
+                result = result
+                    .into_iter()
+                    .flat_map(|variant| {
+                        values.iter().map(move |value| {
+                            let mut variant = variant.clone();
+                            variant.set(prop, Some(value.as_str()));
+                            variant
+                        })
+                    })
+                    .collect();
+            }
+
+            for variant in &mut result {
+                variant.update_name(&name_props);
+            }
+
+            result
+        }
+
+        #[test]
+        fn matches_cartesian_product() {
+            let mut rng = Rng(0x2545_f491_4f6c_dd1d);
+            for _ in 0..500 {
+                let mut variations = TestVariations::default();
+                for _ in 0..rng.next(5) {
+                    let prop = TEST_VARIATION_PROPS[rng.next(TEST_VARIATION_PROPS.len())];
+                    variations.clear(prop);
+                    for value in 0..rng.next(5) {
+                        variations.push(prop, format!("v{value}").to_compact_string());
+                    }
+                }
+
+                let expected = cartesian_product(&variations);
+                let mut iter = variations.iter();
+                let mut actual = vec![];
+                assert_eq!(iter.len(), expected.len());
+                while let Some(variant) = iter.next() {
+                    actual.push(variant);
+                    assert_eq!(iter.len(), expected.len() - actual.len());
+                }
+                assert_eq!(iter.next(), None);
+
+                let unique: HashSet<_> = actual.iter().map(|x| x.name.as_str()).collect();
+                assert_eq!(unique.len(), actual.len(), "{variations:?}");
+                assert_eq!(
+                    actual.iter().collect::<HashSet<_>>(),
+                    expected.iter().collect::<HashSet<_>>(),
+                    "{variations:?}"
+                );
+            }
+        }
+    }
+
+    mod variant_constraint {
+        use compact_str::ToCompactString;
+
+        use super::*;
+
+        #[test]
+        fn filter() {
+            let variations = TestVariations {
+                module: vec!["commonjs".to_compact_string(), "esnext".to_compact_string()],
+                strict: vec!["true".to_compact_string(), "false".to_compact_string()],
+                ..Default::default()
+            };
+            let module = VariantConstraint::parse("Module=ESNext").unwrap();
+            let strict = VariantConstraint::parse("strict=true").unwrap();
+            let result: Vec<_> = variations
+                .iter()
+                .filter(|x| module.matches(x) && strict.matches(x))
+                .map(|x| x.name)
+                .collect();
+            assert_eq!(result, vec!["(module=esnext,strict=true)"]);
+
+            let target = VariantConstraint::parse("target=es5").unwrap();
+            assert!(variations.iter().all(|x| !target.matches(&x)));
+        }
+
+        #[test]
+        fn invalid() {
+            assert_eq!(
+                VariantConstraint::parse("module"),
+                Err(VariantConstraintError::MissingValue("module".to_string()))
+            );
+            let err = VariantConstraint::parse("modul=esnext").unwrap_err();
+            assert_eq!(err, VariantConstraintError::UnknownKey("modul".to_string()));
+            assert!(err.to_string().contains(" module "));
+        }
+    }
+
+    mod variant_name {
+        use super::*;
+
+        const CORPUS_UNITS: &[(&str, &[u8])] = &[
+            (
+                "strictVariants.ts",
+                include_bytes!("../tests/corpus/tests/cases/compiler/strictVariants.ts"),
+            ),
+            (
+                "moduleVariants.ts",
+                include_bytes!("../tests/corpus/tests/cases/compiler/moduleVariants.ts"),
+            ),
+            (
+                "targetModuleMatrix.ts",
+                include_bytes!("../tests/corpus/tests/cases/compiler/targetModuleMatrix.ts"),
+            ),
+            (
+                "wildcard.ts",
+                b"// @module: *\n// @strict: true, false\n// @target: es2015\nexport {};\n",
+            ),
+        ];
+
+        #[test]
+        fn round_trip() {
+            for &(name, data) in CORPUS_UNITS {
+                let unit = TestUnit::parse(Path::new(name), data);
+                let variants: Vec<_> = unit.variations.iter().collect();
+                assert!(variants.len() > 1, "{name}");
+                for variant in variants {
+                    assert_eq!(
+                        TestVariant::parse_name(&variant.name, &unit.variations).as_ref(),
+                        Ok(&variant),
+                        "{name}{}",
+                        variant.name
+                    );
+                }
+            }
+        }
+
+        #[test]
+        fn normalized() {
+            let unit = TestUnit::parse(Path::new("wildcard.ts"), CORPUS_UNITS[3].1);
+            let variant = TestVariant::parse_name(
+                "(Strict=FALSE,module=AMD,target=es2015)",
+                &unit.variations,
+            )
+            .unwrap();
+            assert_eq!(variant.name, "(module=amd,strict=false)");
+            assert_eq!(variant.target, Some("es2015"));
+
+            let unit = TestUnit::parse(Path::new("a.ts"), b"const a = 1;\n");
+            assert_eq!(TestVariant::parse_name("", &unit.variations), Ok(TestVariant::default()));
+        }
+
+        #[test]
+        fn invalid() {
+            let unit = TestUnit::parse(Path::new("wildcard.ts"), CORPUS_UNITS[3].1);
+            let parse = |name| TestVariant::parse_name(name, &unit.variations);
+            assert_eq!(parse("module=amd"), Err(VariantParseError::Malformed("module=amd".into())));
+            assert_eq!(
+                parse("(module=amd,strict)"),
+                Err(VariantParseError::Malformed("(module=amd,strict)".into()))
+            );
+            assert_eq!(
+                parse("(modul=amd,strict=true)"),
+                Err(VariantParseError::UnknownKey("modul".into()))
+            );
+            assert_eq!(
+                parse("(module=amd,module=esnext)"),
+                Err(VariantParseError::DuplicateKey("module".into()))
+            );
+            assert_eq!(
+                parse("(module=es3,strict=true)"),
+                Err(VariantParseError::UnknownValue("module".into(), "es3".into()))
+            );
+            assert_eq!(
+                parse("(module=amd,strict=true,target=es5)"),
+                Err(VariantParseError::UnknownValue("target".into(), "es5".into()))
+            );
+            assert_eq!(parse("(module=amd)"), Err(VariantParseError::MissingKey("strict".into())));
+            assert_eq!(parse(""), Err(VariantParseError::MissingKey("module".into())));
+        }
+
+        #[test]
+        fn tsc_args() {
+            let unit = TestUnit::parse(Path::new("wildcard.ts"), CORPUS_UNITS[3].1);
+            let variant =
+                TestVariant::parse_name("(module=amd,strict=false)", &unit.variations).unwrap();
+            assert_eq!(
+                variant.to_tsc_args().join(" "),
+                "--module amd --strict false --target es2015"
+            );
+
+            let unit = TestUnit::parse(
+                Path::new("a.ts"),
+                b"// @allowJs: TRUE\n// @esModuleInterop: true\n// @target: ES5\nlet a;\n",
+            );
+            let variant = unit.variations.iter().next().unwrap();
+            assert_eq!(
+                variant.to_tsc_args().join(" "),
+                "--allowJs true --esModuleInterop true --target es5"
+            );
+
+            let unit = TestUnit::parse(Path::new("a.ts"), b"let a;\n");
+            assert!(unit.variations.iter().next().unwrap().to_tsc_args().is_empty());
+        }
+
+        #[test]
+        fn with_compiler_options() {
+            let unit = TestUnit::parse(Path::new("a.ts"), b"// @strict: false\nlet a;\n");
+            let variant = unit.variations.iter().next().unwrap();
+            let options = [
+                ("STRICT".to_string(), "true".to_string()),
+                ("allowJs".to_string(), "true".to_string()),
+                ("outDir".to_string(), "dist".to_string()),
+            ];
+            let variant = variant.with_compiler_options(&options);
+            assert_eq!(variant.to_tsc_args().join(" "), "--allowJs true --strict false");
+            assert!(variant.allows_js());
+        }
+    }
+
+    mod materialize {
+        use std::{fs, path::PathBuf};
+
+        use super::*;
+
+        fn temp_root(name: &str) -> PathBuf {
+            let root = std::env::temp_dir()
+                .join(format!("type-runner-materialize-{}-{name}", std::process::id()));
+            let _ = fs::remove_dir_all(&root);
+            root
+        }
+
+        #[test]
+        fn nested_and_absolute() {
+            let root = temp_root("nested");
+            let path = PathBuf::from("tests/cases/unit1.ts");
+            let data = b"// @filename: /src/lib/a.ts
+export const a = 1;
+// @filename: src/./b.ts
+export const b = 2;
+// @filename: c/../c.ts
+export const c = 3;
+";
+
+            let unit = TestUnit::parse(&path, data);
+            let written = unit.materialize(&root).unwrap();
+            assert_eq!(
+                written,
+                vec![root.join("src/lib/a.ts"), root.join("src/b.ts"), root.join("c.ts")]
+            );
+            assert_eq!(fs::read_to_string(&written[0]).unwrap(), "export const a = 1;\n");
+            assert_eq!(fs::read_to_string(&written[2]).unwrap(), "export const c = 3;\n");
+
+            let unit = TestUnit::parse(&path, b"// @filename: ../a.ts\nexport {};\n");
+            let err = unit.materialize(&root).unwrap_err();
+            assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+            assert!(!root.parent().unwrap().join("a.ts").exists());
+            fs::remove_dir_all(&root).unwrap();
+        }
+
+        #[test]
+        fn symlink_pair() {
+            let root = temp_root("symlink");
+            let path = PathBuf::from("tests/cases/unit1.ts");
+            let data = b"// @filename: /packages/a/index.ts
+export const a = 1;
+// @filename: /app.ts
+import { a } from 'a';
+// @link: /packages/a /node_modules/a
+";
+
+            let unit = TestUnit::parse(&path, data);
+            let written = unit.materialize(&root).unwrap();
+            assert_eq!(written.last(), Some(&root.join("node_modules/a")));
+            assert_eq!(
+                fs::read_to_string(root.join("node_modules/a/index.ts")).unwrap(),
+                "export const a = 1;\n"
+            );
+            #[cfg(unix)]
+            assert!(root.join("node_modules/a").is_symlink());
+            fs::remove_dir_all(&root).unwrap();
+        }
     }
 }
@@ -1,3 +1,5 @@
+use std::borrow::Cow;
+
 use oxc::{
     ast::AstKind,
     semantic::Semantic,
@@ -5,100 +7,105 @@ use oxc::{
 };
 use oxc_ast_visit::Visit;
 
-use crate::baseline::types_baseline::{Assertion, TypeBaselineFile};
+use crate::compare::ActualType;
+
+/// Default nesting limit of statements and expressions the visitor recurses into.
+pub const DEFAULT_MAX_DEPTH: usize = 1000;
 
-pub struct TypeVisitor<'a> {
-    pub semantic: &'a Semantic<'a>,
-    pub baseline: &'a TypeBaselineFile<'a>,
+pub struct TypeVisitor<'s, 'a> {
+    pub semantic: &'s Semantic<'a>,
+    /// Nesting of statements and expressions past which the module is given up on.
+    pub max_depth: usize,
 }
 
-impl TypeVisitor<'_> {
-    pub fn run(&self) {
-        let source_text = self.semantic.source_text();
-        println!(
-            "{}",
-            self.baseline
-                .assertions
-                .iter()
-                .flat_map(|x| x.iter().map(|x| format!("{} : {}\n", x.expr, x.expected_type)))
-                .collect::<String>()
-        );
-        let assertions = self.baseline.assertions.iter().flat_map(|x| x.iter());
-        let mut visitor = TypeVisitorImpl { source_text, assertions, depth: 2 };
+/// The module nests statements or expressions deeper than [`TypeVisitor::max_depth`].
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct DepthLimitExceeded(pub usize);
+
+impl<'a> TypeVisitor<'_, 'a> {
+    /// Visits the module in the same order as TS `TypeWriterWalker` and collects every node that
+    /// is expected to have a type assertion in the types baseline.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if the module nests deeper than `max_depth`
+    pub fn run(&self) -> Result<Vec<ActualType<'a>>, DepthLimitExceeded> {
+        let mut visitor = TypeVisitorImpl {
+            source_text: self.semantic.source_text(),
+            nodes: vec![],
+            depth: 0,
+            max_depth: self.max_depth,
+            exceeded: false,
+        };
         let AstKind::Program(program) =
             self.semantic.nodes().root_node().expect("root node to exist").kind()
         else {
             panic!("Expected root AST node to be Program");
         };
         visitor.visit_program(program);
+        if visitor.exceeded {
+            return Err(DepthLimitExceeded(self.max_depth));
+        }
+        Ok(visitor.nodes)
     }
 }
 
-struct TypeVisitorImpl<'a, T: Iterator<Item = &'a Assertion<'a>>> {
+struct TypeVisitorImpl<'a> {
     source_text: &'a str,
-    assertions: T,
+    nodes: Vec<ActualType<'a>>,
     depth: usize,
+    max_depth: usize,
+    exceeded: bool,
 }
 
-impl<'a, T: Iterator<Item = &'a Assertion<'a>>> TypeVisitorImpl<'a, T> {
-    fn assert(&mut self, span: Span, node_type: &str, kind: &str) {
-        let text = span.source_text(self.source_text);
+impl TypeVisitorImpl<'_> {
+    /// Enters a nested statement or expression. Returns `false` (and stops the walk from
+    /// descending) once the depth limit is reached; the caller must not call [`Self::ascend`].
+    fn descend(&mut self) -> bool {
+        if self.exceeded || self.depth >= self.max_depth {
+            self.exceeded = true;
+            return false;
+        }
 
-        let Some(assertion) = self.assertions.next() else {
-            panic!("Expected assertion for:\n  source: {}", text.escape_debug());
-        };
+        self.depth += 1;
+        true
+    }
+
+    fn ascend(&mut self) {
+        self.depth -= 1;
+    }
 
-        assert!(
-            (assertion.expr == text),
-            "Expected assertion expression to match:\n  kind: {}\n  context: {}\n  expected: {}\n    actual: {}",
-            kind,
-            Span::new(
-                span.start.saturating_sub(10),
-                (span.end + 10).min(u32::try_from(self.source_text.len()).unwrap())
-            )
-            .source_text(self.source_text)
-            .escape_debug(),
-            assertion.expr.escape_debug(),
-            text.escape_debug(),
-        );
-
-        println!(
-            "{}[91m>[0m {:<width$} {} : {}",
-            " ".repeat(self.depth),
-            kind,
-            text.escape_debug(),
-            node_type,
-            width = 50 - self.depth,
-        );
+    fn record(&mut self, span: Span, kind: Cow<'_, str>) {
+        // TODO: compute the type once the checker exists
+        self.nodes.push(ActualType {
+            span,
+            kind: Cow::Owned(kind.into_owned()),
+            text: span.source_text(self.source_text),
+            type_name: Cow::Borrowed("any"),
+            placeholder: true,
+        });
     }
 }
 
-impl<'a, T: Iterator<Item = &'a Assertion<'a>>> Visit<'a> for TypeVisitorImpl<'a, T> {
+impl<'a> Visit<'a> for TypeVisitorImpl<'a> {
     fn visit_expression(&mut self, it: &oxc::ast::ast::Expression<'a>) {
         let span = GetSpan::span(it);
-        println!("{}[96mvisit_expression([90m{:?}[96m)[0m", " ".repeat(self.depth), span);
-        let node_type = "any";
-
-        self.assert(span, node_type, &AstKind::from_expression(it).debug_name());
+        self.record(span, AstKind::from_expression(it).debug_name());
 
         match it {
             oxc::ast::ast::Expression::Identifier(_) => {}
             _ => {
-                oxc_ast_visit::walk::walk_expression(self, it);
+                if self.descend() {
+                    oxc_ast_visit::walk::walk_expression(self, it);
+                    self.ascend();
+                }
             }
         }
     }
 
     fn visit_identifier_reference(&mut self, it: &oxc::ast::ast::IdentifierReference<'a>) {
         let span = GetSpan::span(it);
-        println!(
-            "{}[96mvisit_identifier_reference([90m{:?}[96m)[0m",
-            " ".repeat(self.depth),
-            span
-        );
-        let node_type = "any";
-
-        self.assert(span, node_type, &AstKind::IdentifierReference(it).debug_name());
+        self.record(span, AstKind::IdentifierReference(it).debug_name());
 
         oxc_ast_visit::walk::walk_identifier_reference(self, it);
     }
@@ -109,10 +116,7 @@ impl<'a, T: Iterator<Item = &'a Assertion<'a>>> Visit<'a> for TypeVisitorImpl<'a
         }
 
         let span = GetSpan::span(it);
-        println!("{}[96mvisit_identifier_name([90m{:?}[96m)[0m", " ".repeat(self.depth), span);
-        let node_type = "any";
-
-        self.assert(span, node_type, &AstKind::IdentifierName(it).debug_name());
+        self.record(span, AstKind::IdentifierName(it).debug_name());
 
         oxc_ast_visit::walk::walk_identifier_name(self, it);
     }
@@ -125,37 +129,20 @@ impl<'a, T: Iterator<Item = &'a Assertion<'a>>> Visit<'a> for TypeVisitorImpl<'a
             if span.size() <= len { span } else { Span::new(span.start, span.start + len) }
         };
 
-        println!(
-            "{}[96mvisit_binding_identifier([90m{:?}[96m)[0m",
-            " ".repeat(self.depth),
-            span
-        );
-        let node_type = "any";
-
-        self.assert(span, node_type, &AstKind::BindingIdentifier(it).debug_name());
+        self.record(span, AstKind::BindingIdentifier(it).debug_name());
         oxc_ast_visit::walk::walk_binding_identifier(self, it);
     }
 
     fn visit_private_identifier(&mut self, it: &oxc::ast::ast::PrivateIdentifier<'a>) {
         let span = GetSpan::span(it);
-        println!(
-            "{}[96mvisit_private_identifier([90m{:?}[96m)[0m",
-            " ".repeat(self.depth),
-            span
-        );
-        let node_type = "any";
-
-        self.assert(span, node_type, &AstKind::PrivateIdentifier(it).debug_name());
+        self.record(span, AstKind::PrivateIdentifier(it).debug_name());
 
         oxc_ast_visit::walk::walk_private_identifier(self, it);
     }
 
     fn visit_jsx_identifier(&mut self, it: &oxc::ast::ast::JSXIdentifier<'a>) {
         let span = GetSpan::span(it);
-        println!("{}[96mvisit_jsx_identifier([90m{:?}[96m)[0m", " ".repeat(self.depth), span);
-        let node_type = "any";
-
-        self.assert(span, node_type, &AstKind::JSXIdentifier(it).debug_name());
+        self.record(span, AstKind::JSXIdentifier(it).debug_name());
 
         oxc_ast_visit::walk::walk_jsx_identifier(self, it);
     }
@@ -164,15 +151,6 @@ impl<'a, T: Iterator<Item = &'a Assertion<'a>>> Visit<'a> for TypeVisitorImpl<'a
         // oxc_ast_visit::walk::walk_ts_type_name(self, it);
     }
 
-    fn enter_node(&mut self, kind: AstKind<'a>) {
-        println!("{}[90m{}[0m", " ".repeat(self.depth), kind.debug_name());
-        self.depth += 1;
-    }
-
-    fn leave_node(&mut self, _kind: AstKind<'a>) {
-        self.depth -= 1;
-    }
-
     fn enter_scope(
         &mut self,
         _flags: oxc::semantic::ScopeFlags,
@@ -388,7 +366,10 @@ impl<'a, T: Iterator<Item = &'a Assertion<'a>>> Visit<'a> for TypeVisitorImpl<'a
     }
 
     fn visit_statement(&mut self, it: &oxc::ast::ast::Statement<'a>) {
-        oxc_ast_visit::walk::walk_statement(self, it);
+        if self.descend() {
+            oxc_ast_visit::walk::walk_statement(self, it);
+            self.ascend();
+        }
     }
 
     fn visit_directive(&mut self, it: &oxc::ast::ast::Directive<'a>) {
@@ -0,0 +1,17 @@
+//// [tests/cases/compiler/emitOnly.ts] ////
+
+//// [emitOnly.ts]
+export class Point {
+    constructor(public x: number, public y: number) {}
+}
+
+
+//// [emitOnly.js]
+export class Point {
+    x;
+    y;
+    constructor(x, y) {
+        this.x = x;
+        this.y = y;
+    }
+}
@@ -0,0 +1,6 @@
+// @allowJs: true,false
+// @filename: a.ts
+var a;
+
+// @filename: b.js
+var b;
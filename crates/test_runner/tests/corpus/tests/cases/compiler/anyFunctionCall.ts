@@ -0,0 +1,3 @@
+declare var f;
+f();
+f(f);
@@ -0,0 +1,3 @@
+var x;
+var y;
+x = y;
@@ -0,0 +1,2 @@
+// @target: es3
+var es3;
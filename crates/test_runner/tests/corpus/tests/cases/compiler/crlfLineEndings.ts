@@ -0,0 +1,2 @@
+var first;
+var second;
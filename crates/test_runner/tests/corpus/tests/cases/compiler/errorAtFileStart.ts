@@ -0,0 +1,6 @@
+// @isolatedModules: true
+// @filename: a.ts
+var a;
+
+// @filename: b.ts
+var b;
@@ -0,0 +1,7 @@
+interface Foo {
+    f(): string;
+}
+
+interface Bar extends Foo {
+    f(key: string): string;
+}
@@ -0,0 +1,2 @@
+// @module: commonjs, esnext
+export var m;
@@ -0,0 +1,2 @@
+var longName =
+    longName;
@@ -0,0 +1,5 @@
+// @filename: a.ts
+let a = 1;
+
+// @edit: a.ts
+let a = "a";
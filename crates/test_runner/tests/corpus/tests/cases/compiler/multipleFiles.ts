@@ -0,0 +1,5 @@
+// @filename: a.ts
+export const a = 1;
+
+// @filename: b.ts
+export const b = "b";
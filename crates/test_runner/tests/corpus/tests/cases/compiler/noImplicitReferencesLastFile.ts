@@ -0,0 +1,6 @@
+// @noImplicitReferences: true
+// @filename: lib.ts
+export var value;
+
+// @filename: main.ts
+var main;
@@ -0,0 +1,2 @@
+// @noTypesAndSymbols: true
+const skipped = 1;
@@ -0,0 +1,2 @@
+const a = 5;
+let b = a + 1;
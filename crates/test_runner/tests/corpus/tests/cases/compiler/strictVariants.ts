@@ -0,0 +1,3 @@
+// @strict: true, false
+let x;
+x;
@@ -0,0 +1,3 @@
+// @target: es5, es2015
+// @module: commonjs, amd
+var t;
@@ -0,0 +1,5 @@
+// @filename: /tsconfig.json
+{ "compilerOptions": { "strict": true } }
+
+// @filename: /index.ts
+export {};
@@ -0,0 +1,2 @@
+var ünïcödé;
+var 变量;
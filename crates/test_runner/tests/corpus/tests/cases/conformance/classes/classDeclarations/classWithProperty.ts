@@ -0,0 +1,4 @@
+class C {
+    x;
+}
+var c = new C();
@@ -0,0 +1,3 @@
+// @target: es5, es2015
+var name = "world";
+var s = `hello ${name}`;
@@ -0,0 +1,2 @@
+var a;
+var r = a + a;
@@ -0,0 +1,2 @@
+declare var obj;
+obj.prop;
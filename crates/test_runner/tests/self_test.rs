@@ -0,0 +1,250 @@
+use std::{
+    fs,
+    path::PathBuf,
+    process::Command,
+    sync::{Arc, Mutex},
+};
+
+use test_runner::{
+    BaselineAudit, CompareFilesError, CompareFilesOptions, ExpectedChange, OutcomeKind,
+    OutcomeSink, ParseWarningKind, RunOptions, RunStatus, RunSummary, SELF_TEST_CORPUS, SharedSink,
+    SinkSpec, TestOutcome, audit_baselines, compare_files, discover, run_test, smoke,
+};
+
+/// Expected outcomes for the bundled corpus. Update these together with the corpus or when a
+/// runner change legitimately moves a test case between outcomes.
+#[test]
+fn bundled_corpus() {
+    let options = RunOptions::parse(["--self-test".to_string()]).unwrap();
+    let summary = discover(&options, run_test);
+
+    assert_eq!(summary.units, 30);
+    assert_eq!(summary.variants, 33);
+    assert_eq!(summary.emit_only, 1);
+    assert_eq!(summary.lfs_pointers, 1);
+    assert_eq!(summary.multi_pass, 1);
+    assert_eq!(summary.count(OutcomeKind::Passed), 19);
+    assert_eq!(summary.count(OutcomeKind::Failed), 11);
+    assert_eq!(summary.count(OutcomeKind::ProgramError), 0);
+    assert_eq!(summary.count(OutcomeKind::ResourceLimit), 1);
+    assert_eq!(summary.count(OutcomeKind::ParserPanicked), 1);
+    assert_eq!(summary.parser_panics.len(), 1);
+    assert_eq!(summary.count(OutcomeKind::BaselineUnparsable), 1);
+    assert_eq!(summary.assertions.checked, 78);
+    assert_eq!(summary.errors.missing, 8);
+    assert_eq!(summary.ledger_mismatches, vec![]);
+}
+
+/// The corpus compared against itself expects the same everywhere.
+#[test]
+fn compare_repo_with_itself() {
+    let args = ["--self-test", "--compare-repo", SELF_TEST_CORPUS].map(String::from);
+    let options = RunOptions::parse(args).unwrap();
+    let summary = discover(&options, run_test);
+
+    assert_eq!(summary.expected_changes[ExpectedChange::Same as usize], summary.variants);
+    assert_eq!(summary.outcome_changes, 0);
+}
+
+/// Every corpus baseline belongs to a test case and every variant has its baselines.
+#[test]
+fn audit_baselines_clean() {
+    let options = RunOptions::parse(["--self-test".to_string()]).unwrap();
+    assert_eq!(audit_baselines(&options), BaselineAudit::default());
+}
+
+/// Smoke runs build every variant, including the ones a full run skips for their baselines.
+#[test]
+fn smoke_corpus() {
+    let options = RunOptions::parse(["--self-test", "--smoke"].map(String::from)).unwrap();
+    let report = smoke(&options);
+
+    assert_eq!(report.units, 30);
+    assert_eq!(report.variants, 37);
+    assert_eq!(report.built, 36);
+    assert_eq!(report.count(Some(OutcomeKind::ParserPanicked)), 1);
+    assert_eq!(report.count(None), 0);
+}
+
+/// What a custom sink was handed.
+#[derive(Default)]
+struct Collected {
+    outcomes: Vec<OutcomeKind>,
+    paths: Vec<PathBuf>,
+    finished: Option<usize>,
+}
+
+struct Collect(Arc<Mutex<Collected>>);
+
+impl OutcomeSink for Collect {
+    fn record(&mut self, outcome: &TestOutcome) {
+        let mut collected = self.0.lock().unwrap();
+        collected.outcomes.push(outcome.kind);
+        collected.paths.push(outcome.path.clone());
+    }
+
+    fn finish(&mut self, summary: &RunSummary) {
+        self.0.lock().unwrap().finished = Some(summary.variants);
+    }
+}
+
+/// Library consumers get every outcome and the summary through their own sink.
+#[test]
+fn custom_sink() {
+    let collected = Arc::new(Mutex::new(Collected::default()));
+    let mut options = RunOptions::parse(["--self-test".to_string()]).unwrap();
+    options.sinks.push(SinkSpec::Custom(SharedSink::new(Collect(Arc::clone(&collected)))));
+    let summary = discover(&options, run_test);
+
+    let collected = collected.lock().unwrap();
+    assert_eq!(collected.outcomes.len(), summary.variants);
+    assert_eq!(
+        collected.outcomes.iter().filter(|&&x| x == OutcomeKind::Passed).count(),
+        summary.count(OutcomeKind::Passed)
+    );
+    assert_eq!(collected.finished, Some(summary.variants));
+}
+
+/// Output of the runner binary on the bundled corpus with 4 workers, without the summary line
+/// and its timings. The corpus has failing variants, so the runner exits with
+/// [`RunStatus::Failed`].
+fn corpus_log(args: &[&str]) -> Vec<String> {
+    let output = Command::new(env!("CARGO_BIN_EXE_test-runner"))
+        .args(["--self-test", "--jobs", "4"])
+        .args(args)
+        .output()
+        .unwrap();
+    assert_eq!(output.status.code(), Some(RunStatus::Failed.code().into()));
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    stdout.lines().filter(|x| !x.contains(" variants of ")).map(String::from).collect()
+}
+
+/// Invalid options and repos are reported on stderr, not as panics.
+#[test]
+fn usage_errors() {
+    let run = |args: &[&str]| {
+        let output = Command::new(env!("CARGO_BIN_EXE_test-runner")).args(args).output().unwrap();
+        assert_eq!(output.status.code(), Some(RunStatus::Usage.code().into()));
+        String::from_utf8(output.stderr).unwrap()
+    };
+    assert_eq!(run(&["--bogus"]), "Unknown argument: --bogus\n");
+    assert_eq!(run(&["--repo", "/nonexistent"]), "TypeScript repo not found: /nonexistent\n");
+    assert_eq!(
+        run(&["--self-test", "--compare-repo", "/nonexistent"]),
+        "TypeScript repo not found: /nonexistent\n"
+    );
+    assert_eq!(run(&["compare"]), "Missing --case\n");
+    let missing = run(&["--self-test", "--test", "tests/cases/compiler/nope.ts"]);
+    assert!(missing.starts_with("Test file not found: "), "{missing}");
+    assert!(missing.ends_with("tests/cases/compiler/nope.ts\n"), "{missing}");
+    let test = "tests/cases/compiler/anyVariables.ts";
+    assert_eq!(
+        run(&["--self-test", "--test", test, "--only-file", "nope.ts"]),
+        "Unknown --only-file: nope.ts\n  files: anyVariables.ts\n"
+    );
+    assert!(
+        run(&["--self-test", "--tests-json", "/nonexistent.json"])
+            .starts_with("Failed to read test list /nonexistent.json: ")
+    );
+    let missing = run(&["compare", "--case", "/nonexistent.ts", "--types", "x.types"]);
+    assert!(missing.starts_with("Failed to compare /nonexistent.ts: /nonexistent.ts"), "{missing}");
+}
+
+/// Redirected output lists variants in discovery order, so two runs can be diffed.
+#[test]
+fn deterministic_output() {
+    let first = corpus_log(&[]);
+    assert_eq!(first, corpus_log(&[]));
+
+    let mut first = first;
+    let mut completion = corpus_log(&["--output-order", "completion"]);
+    first.sort();
+    completion.sort();
+    assert_eq!(first, completion);
+}
+
+/// A `--tests-json` list runs exactly its cases in its order and reports the missing ones.
+#[test]
+fn tests_json() {
+    let list = std::env::temp_dir().join(format!("type-runner-tests-{}.json", std::process::id()));
+    let data =
+        r#"["compiler/strictVariants.ts", "compiler/notThere.ts", "compiler/anyVariables.ts"]"#;
+    fs::write(&list, data).unwrap();
+
+    let collected = Arc::new(Mutex::new(Collected::default()));
+    let args = ["--self-test", "--jobs", "1", "--readers", "1", "--tests-json"];
+    let mut options =
+        RunOptions::parse(args.into_iter().map(String::from).chain([list.display().to_string()]))
+            .unwrap();
+    options.sinks.push(SinkSpec::Custom(SharedSink::new(Collect(Arc::clone(&collected)))));
+    let summary = discover(&options, run_test);
+    fs::remove_file(&list).unwrap();
+
+    assert_eq!(summary.units, 2);
+    assert_eq!(summary.missing_tests, vec![PathBuf::from("tests/cases/compiler/notThere.ts")]);
+    let strict = PathBuf::from("tests/cases/compiler/strictVariants.ts");
+    let any = PathBuf::from("tests/cases/compiler/anyVariables.ts");
+    assert_eq!(collected.lock().unwrap().paths, vec![strict.clone(), strict, any]);
+}
+
+/// A case root reached twice through `..` runs its variants once.
+#[test]
+fn duplicate_scheduled() {
+    let args = ["--self-test", "--cases-dir", "tests/cases/compiler"];
+    let once = discover(&RunOptions::parse(args.map(String::from)).unwrap(), run_test);
+
+    let args = [
+        "--self-test",
+        "--cases-dir",
+        "tests/cases/compiler",
+        "--cases-dir",
+        "tests/baselines/../cases/compiler",
+    ];
+    let twice = discover(&RunOptions::parse(args.map(String::from)).unwrap(), run_test);
+    assert_eq!(twice.units, once.units);
+    assert_eq!(twice.variants, once.variants);
+    assert_eq!(twice.count(OutcomeKind::Passed), once.count(OutcomeKind::Passed));
+    assert_eq!(twice.multi_pass, once.multi_pass);
+    assert_eq!(twice.duplicates, once.variants + once.emit_only + once.lfs_pointers);
+    let warnings = twice.parse_warnings.total() - twice.duplicates;
+    assert_eq!(warnings, once.parse_warnings.total());
+    assert_eq!(twice.parse_warnings.count(ParseWarningKind::DuplicateScheduled), twice.duplicates);
+    assert_eq!(twice.ledger_mismatches, vec![]);
+}
+
+/// Baselines given as files are compared without discovering the repo they come from.
+#[test]
+fn compare_files_outside_repo() {
+    let corpus = PathBuf::from(SELF_TEST_CORPUS);
+    let case = corpus.join("tests/cases/compiler/strictVariants.ts");
+    let baseline = |name: &str| corpus.join("tests/baselines/reference").join(name);
+    let mut options = CompareFilesOptions {
+        case,
+        types: baseline("strictVariants(strict=true).types"),
+        errors: Some(baseline("strictVariants(strict=true).errors.txt")),
+        variant: None,
+    };
+    assert!(
+        matches!(compare_files(&options), Err(CompareFilesError::AmbiguousVariant(x)) if x.len() == 2)
+    );
+
+    options.variant = Some("(strict=true)".to_string());
+    let outcome = compare_files(&options).unwrap();
+    assert_eq!(outcome.path, PathBuf::from("strictVariants.ts"));
+    assert_eq!(outcome.variant, "(strict=true)");
+    assert_eq!(outcome.errors.missing, 2);
+    assert!(outcome.log.contains("---------------- strictVariants.ts ----------------"));
+
+    options.variant = Some("(strict=false)".to_string());
+    options.types = baseline("strictVariants(strict=false).types");
+    options.errors = None;
+    assert_eq!(compare_files(&options).unwrap().kind, OutcomeKind::Passed);
+
+    // The baseline of another case
+    options.types = baseline("anyVariables.types");
+    let err = compare_files(&options).unwrap_err();
+    assert!(
+        matches!(&err, CompareFilesError::UnknownFile { section, .. } if section == "anyVariables.ts"),
+        "{err}"
+    );
+}
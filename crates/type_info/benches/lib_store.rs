@@ -0,0 +1,51 @@
+//! Parsing a lib file for every test variant vs sharing it through `LibStore`.
+//!
+//! Run with `cargo bench -p type_info --bench lib_store`.
+
+use std::{fmt::Write, hint::black_box, time::Instant};
+
+use oxc::{allocator::Allocator, parser::Parser, semantic::SemanticBuilder, span::SourceType};
+use type_info::LibStore;
+
+const VARIANTS: usize = 100;
+
+/// Roughly the size of `lib.es2020.d.ts` and everything it references.
+fn lib_source() -> String {
+    let mut result = String::new();
+    for idx in 0..4000 {
+        writeln!(
+            result,
+            "interface Iface{idx}<T> {{\n    readonly length: number;\n    item(index: number): T | undefined;\n    map<U>(fn: (value: T, index: number) => U): Iface{idx}<U>;\n}}\ndeclare var Iface{idx}: {{ new <T>(): Iface{idx}<T>; }};"
+        )
+        .unwrap();
+    }
+    result
+}
+
+fn main() {
+    let source = lib_source();
+    // Like the store parses lib files
+    let source_type = SourceType::ts().with_script(true);
+
+    let start = Instant::now();
+    for _ in 0..VARIANTS {
+        let alloc = Allocator::default();
+        let parsed = Parser::new(&alloc, &source, source_type).parse();
+        let program = alloc.alloc(parsed.program);
+        black_box(SemanticBuilder::new().build(program).semantic.nodes().len());
+    }
+    let per_variant = start.elapsed();
+
+    let store = LibStore::new();
+    let start = Instant::now();
+    for _ in 0..VARIANTS {
+        let lib = store.get_or_load("lib.es2020.d.ts", || Ok(source.clone())).unwrap();
+        black_box(lib.semantic.nodes().len());
+    }
+    let shared = start.elapsed();
+
+    println!("lib size:           {} KiB", source.len() / 1024);
+    println!("parse per variant:  {per_variant:>10.2?} for {VARIANTS} variants");
+    println!("shared lib store:   {shared:>10.2?} for {VARIANTS} variants");
+    println!("speedup:            {:>9.1}x", per_variant.as_secs_f64() / shared.as_secs_f64());
+}
@@ -0,0 +1,99 @@
+//! Bytes allocated by programs that fail to parse, which keep the text of the failed module for
+//! rendering their errors.
+//!
+//! Run with `cargo bench -p type_info --bench parse_error`.
+
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    fmt::Write,
+    hint::black_box,
+    io,
+    path::{Path, PathBuf},
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+use oxc::allocator::Allocator;
+use oxc_resolver::{FileMetadata, FileSystem};
+use type_info::TypeCheck;
+
+const PROGRAMS: usize = 100;
+
+static ALLOCATED: AtomicUsize = AtomicUsize::new(0);
+
+struct CountingAllocator;
+
+#[expect(unsafe_code)]
+// SAFETY: forwards to the system allocator
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATED.fetch_add(layout.size(), Ordering::Relaxed);
+        // SAFETY: same contract as `GlobalAlloc::alloc`
+        unsafe { System.alloc(layout) }
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        // SAFETY: same contract as `GlobalAlloc::dealloc`
+        unsafe { System.dealloc(ptr, layout) }
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAllocator = CountingAllocator;
+
+/// Bytes allocated by `f`.
+fn allocated<T>(f: impl FnOnce() -> T) -> usize {
+    let before = ALLOCATED.load(Ordering::Relaxed);
+    black_box(f());
+    ALLOCATED.load(Ordering::Relaxed) - before
+}
+
+/// A single large module the parser gives up on at its very end.
+struct OneFile(String);
+
+impl FileSystem for &OneFile {
+    fn read_to_string(&self, _path: &Path) -> io::Result<String> {
+        Ok(self.0.clone())
+    }
+
+    fn metadata(&self, _path: &Path) -> io::Result<FileMetadata> {
+        Err(io::ErrorKind::Unsupported.into())
+    }
+
+    fn symlink_metadata(&self, _path: &Path) -> io::Result<FileMetadata> {
+        Err(io::ErrorKind::Unsupported.into())
+    }
+
+    fn read_link(&self, _path: &Path) -> io::Result<PathBuf> {
+        Err(io::ErrorKind::Unsupported.into())
+    }
+}
+
+fn main() {
+    let mut source = String::new();
+    for idx in 0..20_000 {
+        let _ = writeln!(source, "export const value{idx}: number = {idx};");
+    }
+    source.push_str("var = ;\n");
+    let fs = OneFile(source);
+    let type_check = TypeCheck::new(&fs);
+
+    // Warm up the allocator, as a worker reuses it for every unit
+    let mut alloc = Allocator::default();
+    assert!(type_check.create_program(&["a.ts"], &alloc).is_err());
+
+    let failed = allocated(|| {
+        for _ in 0..PROGRAMS {
+            alloc.reset();
+            black_box(type_check.create_program(&["a.ts"], &alloc).is_err());
+        }
+    });
+    alloc.reset();
+    let Err(err) = type_check.create_program(&["a.ts"], &alloc) else {
+        panic!("the parser to give up on the module");
+    };
+    let rendered = allocated(|| err.render_plain());
+
+    println!("module size:       {} KiB", fs.0.len() / 1024);
+    println!("failed programs:   {} KiB allocated per program", failed / PROGRAMS / 1024);
+    println!("rendered error:    {} KiB allocated", rendered / 1024);
+}
@@ -0,0 +1,141 @@
+use oxc::diagnostics::OxcDiagnostic;
+
+/// Diagnostic category, mirroring TypeScript's `DiagnosticCategory`.
+#[derive(Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Copy)]
+pub enum Severity {
+    #[default]
+    Error,
+    Warning,
+    Suggestion,
+    Message,
+}
+
+impl Severity {
+    pub fn is_error(self) -> bool {
+        self == Severity::Error
+    }
+}
+
+impl From<oxc::diagnostics::Severity> for Severity {
+    fn from(value: oxc::diagnostics::Severity) -> Self {
+        match value {
+            oxc::diagnostics::Severity::Error => Severity::Error,
+            oxc::diagnostics::Severity::Warning => Severity::Warning,
+            oxc::diagnostics::Severity::Advice => Severity::Suggestion,
+        }
+    }
+}
+
+impl std::fmt::Display for Severity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+            Severity::Suggestion => "suggestion",
+            Severity::Message => "message",
+        })
+    }
+}
+
+/// A diagnostic in the shape TypeScript prints into `.errors.txt` baselines.
+///
+/// `line` and `column` are 1-based. Diagnostics without a location (e.g. config errors) use `0`
+/// for both and an empty `file`.
+#[derive(Debug, Default, PartialEq, Eq, Clone)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    pub code: u32,
+    pub file: String,
+    pub line: u32,
+    pub column: u32,
+    pub length: Option<u32>,
+    pub message: String,
+    pub related: Vec<Diagnostic>,
+}
+
+impl Diagnostic {
+    pub fn has_location(&self) -> bool {
+        self.line != 0
+    }
+
+    /// Converts a diagnostic oxc reported for `file`, locating its primary label in
+    /// `source_text`.
+    ///
+    /// Only `TS`-scoped codes are kept, other diagnostics get code `0`.
+    pub fn from_oxc(diagnostic: &OxcDiagnostic, file: &str, source_text: &str) -> Self {
+        let code = match (&diagnostic.code.scope, &diagnostic.code.number) {
+            (Some(scope), Some(number)) if scope == "TS" => number.parse().unwrap_or_default(),
+            _ => 0,
+        };
+
+        let mut result = Self {
+            severity: diagnostic.severity.into(),
+            code,
+            file: file.to_string(),
+            message: diagnostic.message.to_string(),
+            ..Default::default()
+        };
+
+        let labels = diagnostic.labels.as_deref().unwrap_or_default();
+        if let Some(label) = labels.iter().find(|x| x.primary()).or_else(|| labels.first()) {
+            let start = label.offset().min(source_text.len());
+            let end = (start + label.len()).min(source_text.len());
+            let line_start = source_text[..start].rfind('\n').map_or(0, |x| x + 1);
+            result.line =
+                u32::try_from(source_text[..start].matches('\n').count() + 1).unwrap_or(u32::MAX);
+            result.column =
+                u32::try_from(utf16_len(&source_text[line_start..start]) + 1).unwrap_or(u32::MAX);
+            result.length = u32::try_from(utf16_len(&source_text[start..end])).ok();
+        }
+
+        result
+    }
+}
+
+pub fn utf16_len(text: &str) -> usize {
+    text.chars().map(char::len_utf16).sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use oxc::{diagnostics::LabeledSpan, span::Span};
+
+    use super::*;
+
+    #[test]
+    fn from_oxc() {
+        let source_text = "let ü = 1;\nlet x = ;\n";
+        let diagnostic = OxcDiagnostic::error("Expression expected.")
+            .with_error_code("TS", "1109")
+            .with_label(Span::new(20, 21));
+        assert_eq!(
+            Diagnostic::from_oxc(&diagnostic, "a.ts", source_text),
+            Diagnostic {
+                severity: Severity::Error,
+                code: 1109,
+                file: "a.ts".to_string(),
+                line: 2,
+                column: 9,
+                length: Some(1),
+                message: "Expression expected.".to_string(),
+                related: vec![],
+            }
+        );
+
+        let diagnostic = OxcDiagnostic::warn("Unused label").with_labels([
+            LabeledSpan::new(None, 4, 2),
+            LabeledSpan::new_primary_with_span(None, Span::new(7, 8)),
+        ]);
+        let result = Diagnostic::from_oxc(&diagnostic, "a.ts", source_text);
+        assert_eq!((result.severity, result.code), (Severity::Warning, 0));
+        assert_eq!((result.line, result.column, result.length), (1, 7, Some(1)));
+
+        // U+1D4EA takes 2 UTF-16 columns, before and inside the label
+        let source_text = "const 𝓪 = 1; 𝓪 = 2;\n";
+        let start = u32::try_from(source_text.rfind('𝓪').unwrap()).unwrap();
+        let diagnostic = OxcDiagnostic::error("Cannot assign to '𝓪' because it is a constant.")
+            .with_label(Span::sized(start, 4));
+        let result = Diagnostic::from_oxc(&diagnostic, "a.ts", source_text);
+        assert_eq!((result.line, result.column, result.length), (1, 15, Some(2)));
+    }
+}
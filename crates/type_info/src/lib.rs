@@ -1,14 +1,107 @@
-use std::{io, path::Path};
+use std::{
+    borrow::Cow,
+    collections::hash_map::Entry,
+    io,
+    path::Path,
+    rc::Rc,
+    sync::Arc,
+    time::{Duration, Instant},
+};
 
-use oxc::{allocator::Allocator, diagnostics::NamedSource, semantic::Semantic};
+use oxc::{
+    allocator::Allocator,
+    diagnostics::{GraphicalReportHandler, GraphicalTheme, NamedSource},
+    semantic::Semantic,
+};
 use oxc_index::IndexVec;
 use oxc_resolver::FileSystem;
+use rustc_hash::FxHashMap;
+
+mod diagnostic;
+mod lib_store;
+mod module_resolution;
+mod program_cache;
+mod references;
+mod semantic_facade;
+mod source_kind;
+mod tsconfig;
+
+pub use diagnostic::{Diagnostic, Severity};
+pub use lib_store::{LibFile, LibStore};
+use module_resolution::declared_ambient_modules;
+pub use module_resolution::{AmbientModules, ResolvedModule};
+pub use program_cache::{CachedModule, ProgramCache};
+pub use references::{Reference, References};
+pub use semantic_facade::SemanticFacade;
+pub use source_kind::SourceKind;
+pub use tsconfig::TsConfig;
 
 pub struct TypeCheck<'fs, Fs>
 where
     &'fs Fs: FileSystem,
 {
     fs: &'fs Fs,
+    /// Whether `.json` files are modules, see [`TypeCheckBuilder::resolve_json_module`].
+    resolve_json_module: bool,
+    /// Whether programs record [`TSProgram::stats`].
+    stats: bool,
+    /// Whether `types` and `lib` references are left out, see
+    /// [`TypeCheckBuilder::no_implicit_references`].
+    no_implicit_references: bool,
+    /// Where lib files are parsed, see [`TypeCheckBuilder::libs`].
+    libs: Option<&'fs LibStore>,
+}
+
+/// Options of a [`TypeCheck`], see [`TypeCheck::builder`].
+///
+/// `jsx`, `target` and `module` are not options. Like TypeScript, a file has JSX by its
+/// extension alone, and oxc parses the syntax of every ECMAScript version and module kind alike.
+pub struct TypeCheckBuilder<'fs, Fs>
+where
+    &'fs Fs: FileSystem,
+{
+    type_check: TypeCheck<'fs, Fs>,
+}
+
+impl<'fs, Fs> TypeCheckBuilder<'fs, Fs>
+where
+    &'fs Fs: FileSystem,
+{
+    /// Loads `.json` files as modules exporting their value, like `resolveJsonModule` does.
+    /// Without it they fail with [`ParseError::JsonModule`].
+    #[must_use]
+    pub const fn resolve_json_module(mut self, yes: bool) -> Self {
+        self.type_check.resolve_json_module = yes;
+        self
+    }
+
+    /// Records the [`ModuleStats`] of every module in [`TSProgram::stats`]. Off by default, when
+    /// nothing is timed.
+    #[must_use]
+    pub const fn stats(mut self, yes: bool) -> Self {
+        self.type_check.stats = yes;
+        self
+    }
+
+    /// Leaves the `types` and `lib` references out of [`TSProgram::references`], like
+    /// `@noImplicitReferences` does. `path` references are still followed.
+    #[must_use]
+    pub const fn no_implicit_references(mut self, yes: bool) -> Self {
+        self.type_check.no_implicit_references = yes;
+        self
+    }
+
+    /// Parses lib files once in `store` for every program including them, rather than in the
+    /// allocator of each program. They are read from the file system on first use.
+    #[must_use]
+    pub const fn libs(mut self, store: &'fs LibStore) -> Self {
+        self.type_check.libs = Some(store);
+        self
+    }
+
+    pub fn build(self) -> TypeCheck<'fs, Fs> {
+        self.type_check
+    }
 }
 
 oxc_index::define_index_type! {
@@ -16,25 +109,141 @@ oxc_index::define_index_type! {
 }
 
 pub struct TSProgram<'a> {
+    /// Lib files first, then the root files.
     pub modules: IndexVec<ModuleId, &'a str>,
-    pub semantic: IndexVec<ModuleId, Semantic<'a>>,
+    /// Whether the module is a lib file, see [`TypeCheck::create_program_with_libs`].
+    pub is_lib: IndexVec<ModuleId, bool>,
+    pub semantic: IndexVec<ModuleId, ModuleSemantic<'a>>,
+    /// Errors the parser recovered from and semantic errors, by module.
+    pub diagnostics: IndexVec<ModuleId, Vec<Diagnostic>>,
+    /// Cost of building every module with [`TypeCheckBuilder::stats`], empty without. Modules
+    /// reused from a [`ProgramCache`] have the stats of when they were parsed, lib files from a
+    /// [`LibStore`] none.
+    pub stats: IndexVec<ModuleId, ModuleStats>,
+    /// Modules declared with `declare module "name"`, see [`TSProgram::resolve_module`].
+    pub ambient_modules: AmbientModules<'a>,
+    /// Triple-slash reference directives, by module. The files of `path` references are in
+    /// the program, after the root files.
+    pub references: IndexVec<ModuleId, Rc<References<'a>>>,
+    /// Modules by normalized path, see [`TSProgram::module_id`]. The first of modules with the
+    /// same path.
+    module_ids: FxHashMap<Cow<'a, str>, ModuleId>,
+}
+
+/// The semantic model of a module of a [`TSProgram`].
+#[derive(Clone)]
+pub enum ModuleSemantic<'a> {
+    /// Shared with the other programs built from the same [`ProgramCache`].
+    Parsed(Rc<Semantic<'a>>),
+    /// Shared with every program of the thread through a [`LibStore`]. Its model lives in the
+    /// store's allocator: `Semantic` is invariant in its lifetime, so it cannot be one of the
+    /// program's.
+    Lib(&'a LibFile),
 }
 
+impl<'a> ModuleSemantic<'a> {
+    pub fn source_text(&self) -> &'a str {
+        match self {
+            ModuleSemantic::Parsed(semantic) => semantic.source_text(),
+            ModuleSemantic::Lib(lib) => lib.semantic.source_text(),
+        }
+    }
+
+    /// The model of a module parsed for the program, `None` for a lib file from a [`LibStore`].
+    pub fn parsed(&self) -> Option<&Semantic<'a>> {
+        match self {
+            ModuleSemantic::Parsed(semantic) => Some(semantic),
+            ModuleSemantic::Lib(_) => None,
+        }
+    }
+}
+
+/// A module parsed despite syntax errors, see [`ParsedModule::diagnostics`].
+pub struct ParsedModule<'a> {
+    pub semantic: Semantic<'a>,
+    /// Errors the parser recovered from, followed by the semantic errors, like redeclared
+    /// block-scoped variables. A parser that gives up fails with [`ParseError::ParserPanicked`]
+    /// instead.
+    pub diagnostics: Vec<Diagnostic>,
+    /// Names of the ambient modules the module declares, see [`AmbientModules`].
+    pub ambient_modules: Vec<&'a str>,
+    pub references: References<'a>,
+}
+
+/// Cost of building a module, see [`TypeCheckBuilder::stats`].
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy)]
+pub struct ModuleStats {
+    pub parse: Duration,
+    pub semantic: Duration,
+    /// Length of the source text in bytes.
+    pub source_len: usize,
+    /// Bytes of the allocator used by the source text, the AST and the semantic model.
+    pub allocated: usize,
+}
+
+/// Why a module could not be added to a program. Parser and semantic errors borrow the text of
+/// the module from the allocator it was parsed into.
 #[derive(Debug)]
-pub enum ParseError {
+pub enum ParseError<'a> {
     IO(io::Error),
     UnknownExtension(oxc::span::UnknownExtension),
-    Parser(Vec<oxc::diagnostics::OxcDiagnostic>, NamedSource<String>),
-    Semantic(Vec<oxc::diagnostics::OxcDiagnostic>, NamedSource<String>),
+    Parser(Vec<oxc::diagnostics::OxcDiagnostic>, ErrorSource<'a>),
+    /// The parser gave up on the file instead of recovering from its errors.
+    ParserPanicked(Vec<oxc::diagnostics::OxcDiagnostic>, ErrorSource<'a>),
+    Semantic(Vec<oxc::diagnostics::OxcDiagnostic>, ErrorSource<'a>),
+    /// A `.json` file in a program without `resolveJsonModule`.
+    JsonModule(String),
+    /// The file at `path` is not UTF-8, from the byte at `offset` on.
+    Encoding {
+        path: String,
+        offset: usize,
+    },
+    /// An import of `importer` did not resolve to a file, unlike [`ParseError::IO`] for a
+    /// resolved file that cannot be read.
+    Resolve {
+        specifier: String,
+        importer: String,
+        reason: Box<oxc_resolver::ResolveError>,
+    },
 }
 
-impl std::fmt::Display for ParseError {
+impl std::fmt::Display for ParseError<'_> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.render(f, GraphicalTheme::default())
+    }
+}
+
+impl std::error::Error for ParseError<'_> {}
+
+impl ParseError<'_> {
+    /// Like [`Display`](std::fmt::Display), without ANSI colors whatever the terminal.
+    pub fn render_plain(&self) -> String {
+        let mut result = String::new();
+        let _ = self.render(&mut result, GraphicalTheme::unicode_nocolor());
+        result
+    }
+
+    fn render(&self, f: &mut impl std::fmt::Write, theme: GraphicalTheme) -> std::fmt::Result {
         match self {
-            ParseError::IO(err) => err.fmt(f),
-            ParseError::UnknownExtension(err) => err.fmt(f),
-            ParseError::Semantic(vec, source) | ParseError::Parser(vec, source) => {
-                let reporter = oxc::diagnostics::GraphicalReportHandler::new();
+            ParseError::IO(err) => write!(f, "{err}"),
+            ParseError::UnknownExtension(err) => write!(f, "{err}"),
+            ParseError::JsonModule(path) => {
+                write!(f, "Module '{path}' is a JSON file, which needs '--resolveJsonModule'")
+            }
+            ParseError::Encoding { path, offset } => {
+                write!(
+                    f,
+                    "File '{path}' is not valid UTF-8: invalid byte sequence at offset {offset}"
+                )
+            }
+            ParseError::Resolve { specifier, importer, reason } => {
+                write!(f, "Cannot resolve '{specifier}' from '{importer}': {reason}")
+            }
+            ParseError::Semantic(vec, source)
+            | ParseError::Parser(vec, source)
+            | ParseError::ParserPanicked(vec, source) => {
+                let reporter = GraphicalReportHandler::new_themed(theme);
+                let source = source.named_source();
                 for err in vec {
                     reporter
                         .render_report(f, err.clone().with_source_code(source.clone()).as_ref())?;
@@ -43,17 +252,59 @@ impl std::fmt::Display for ParseError {
             }
         }
     }
+
+    /// What kind of error it is, in the `type:` line of [`TSProgramError`].
+    pub fn label(&self) -> &'static str {
+        match self {
+            ParseError::IO(_) => "IO",
+            ParseError::UnknownExtension(_) => "Unknown Extension",
+            ParseError::JsonModule(_) => "JSON module",
+            ParseError::Encoding { .. } => "encoding",
+            ParseError::Parser(_, _) => "parser",
+            ParseError::ParserPanicked(_, _) => "parser panic",
+            ParseError::Semantic(_, _) => "semantic",
+            ParseError::Resolve { .. } => "resolve",
+        }
+    }
+
+    /// Parser and semantic errors in the shape of baseline diagnostics. Other errors have none.
+    pub fn diagnostics(&self) -> Vec<Diagnostic> {
+        match self {
+            ParseError::IO(_)
+            | ParseError::UnknownExtension(_)
+            | ParseError::JsonModule(_)
+            | ParseError::Encoding { .. }
+            | ParseError::Resolve { .. } => Vec::new(),
+            ParseError::Parser(vec, source)
+            | ParseError::ParserPanicked(vec, source)
+            | ParseError::Semantic(vec, source) => {
+                vec.iter().map(|x| Diagnostic::from_oxc(x, &source.path, source.text)).collect()
+            }
+        }
+    }
 }
 
-impl std::error::Error for ParseError {}
+/// Path and text of the module a [`ParseError`] is about. The text is only copied when the error
+/// is displayed, into a [`NamedSource`] all its diagnostics share.
+#[derive(Debug, Clone)]
+pub struct ErrorSource<'a> {
+    pub path: String,
+    pub text: &'a str,
+}
 
-impl From<oxc::span::UnknownExtension> for ParseError {
+impl ErrorSource<'_> {
+    pub fn named_source(&self) -> NamedSource<Arc<str>> {
+        NamedSource::new(&self.path, Arc::from(self.text))
+    }
+}
+
+impl From<oxc::span::UnknownExtension> for ParseError<'_> {
     fn from(value: oxc::span::UnknownExtension) -> Self {
         Self::UnknownExtension(value)
     }
 }
 
-impl From<io::Error> for ParseError {
+impl From<io::Error> for ParseError<'_> {
     fn from(value: io::Error) -> Self {
         Self::IO(value)
     }
@@ -61,65 +312,215 @@ impl From<io::Error> for ParseError {
 
 #[derive(Debug)]
 pub enum TSProgramError<'a> {
-    ParseError(Vec<(&'a str, ParseError)>),
+    ParseError(Vec<(&'a str, ParseError<'a>)>),
 }
 
 impl std::fmt::Display for TSProgramError<'_> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.render(f, &GraphicalTheme::default())
+    }
+}
+
+impl std::error::Error for TSProgramError<'_> {}
+
+impl<'a> TSProgram<'a> {
+    /// The module at `path`. `./a.ts`, `a.ts` and `/a.ts` are the same module, and so are
+    /// `dir\a.ts` and `dir/a.ts`, like the names of baselines and of the virtual file system.
+    pub fn module_id(&self, path: &str) -> Option<ModuleId> {
+        self.module_ids.get(&normalize_path(path)).copied()
+    }
+
+    pub fn path(&self, id: ModuleId) -> &'a str {
+        self.modules[id]
+    }
+
+    pub fn semantic(&self, id: ModuleId) -> &ModuleSemantic<'a> {
+        &self.semantic[id]
+    }
+
+    /// The module `specifier` imported from `importer` resolves to, looked up like TypeScript
+    /// does: an ambient module declared with that exact name, then a file of the program, then
+    /// an ambient module whose pattern matches, like `*.css`. Relative names are never ambient.
+    ///
+    /// Only files already in the program are found: paths are resolved against `importer`, with
+    /// the extensions and `index` files TypeScript tries, and packages in the `node_modules`
+    /// directories above it.
+    pub fn resolve_module(&self, specifier: &str, importer: &str) -> Option<ResolvedModule> {
+        if !module_resolution::is_path(specifier) {
+            if let Some(id) = self.ambient_modules.get(specifier) {
+                return Some(ResolvedModule::Ambient(id));
+            }
+        }
+        let file = module_resolution::file_candidates(specifier, importer)
+            .iter()
+            .find_map(|path| self.module_id(path));
+        file.map(ResolvedModule::File)
+            .or_else(|| self.ambient_modules.matching(specifier).map(ResolvedModule::Ambient))
+    }
+
+    /// Modules that are not lib files, with their ids.
+    pub fn root_modules(&self) -> impl Iterator<Item = ModuleId> + '_ {
+        self.is_lib.iter_enumerated().filter(|&(_, &is_lib)| !is_lib).map(|(id, _)| id)
+    }
+}
+
+impl<'a> TSProgramError<'a> {
+    /// Like [`Display`](std::fmt::Display), without ANSI colors whatever the terminal, for logs.
+    pub fn render_plain(&self) -> String {
+        let mut result = String::new();
+        let _ = self.render(&mut result, &GraphicalTheme::unicode_nocolor());
+        result
+    }
+
+    /// A banner per failed file with its errors indented under it, then the number of files
+    /// per kind of error.
+    fn render(&self, f: &mut impl std::fmt::Write, theme: &GraphicalTheme) -> std::fmt::Result {
         match self {
             TSProgramError::ParseError(err) => {
-                f.write_fmt(format_args!("Failed to parse {} files\n\n", err.len()))?;
+                writeln!(f, "Failed to parse {} files", err.len())?;
 
+                let mut counts: Vec<(&str, usize)> = Vec::new();
                 for (path, err) in err {
-                    let err_type = match err {
-                        ParseError::IO(_) => "IO",
-                        ParseError::UnknownExtension(_) => "Unknown Extension",
-                        ParseError::Parser(_, _) => "parser",
-                        ParseError::Semantic(_, _) => "semantic",
-                    };
-                    f.write_fmt(format_args!(
-                        "---------------- {path} ----------------\n  type: {err_type}{err}\n"
-                    ))?;
+                    let label = err.label();
+                    match counts.iter_mut().find(|(x, _)| *x == label) {
+                        Some((_, count)) => *count += 1,
+                        None => counts.push((label, 1)),
+                    }
+
+                    writeln!(f, "\n---------------- {path} ----------------\n  type: {label}")?;
+                    let mut rendered = String::new();
+                    err.render(&mut rendered, theme.clone())?;
+                    for line in rendered.trim_end().lines() {
+                        if line.trim().is_empty() {
+                            writeln!(f)?;
+                        } else {
+                            writeln!(f, "    {line}")?;
+                        }
+                    }
                 }
 
-                Ok(())
+                let counts: Vec<_> =
+                    counts.iter().map(|(label, count)| format!("{count} {label}")).collect();
+                write!(f, "\nFailed files: {}", counts.join(", "))
             }
         }
     }
-}
 
-impl std::error::Error for TSProgramError<'_> {}
+    /// Diagnostics produced before program creation failed.
+    pub fn diagnostics(&self) -> Vec<Diagnostic> {
+        match self {
+            TSProgramError::ParseError(err) => {
+                err.iter().flat_map(|(_, err)| err.diagnostics()).collect()
+            }
+        }
+    }
+
+    /// The first file the parser gave up on, with its diagnostics.
+    pub fn parser_panic(&self) -> Option<(&'a str, Vec<Diagnostic>)> {
+        match self {
+            TSProgramError::ParseError(err) => err
+                .iter()
+                .find(|(_, err)| matches!(err, ParseError::ParserPanicked(..)))
+                .map(|&(path, ref err)| (path, err.diagnostics())),
+        }
+    }
+}
 
 impl<'fs, Fs> TypeCheck<'fs, Fs>
 where
     &'fs Fs: FileSystem,
 {
+    /// Type checks with the default options, see [`TypeCheck::builder`].
     pub fn new(fs: &'fs Fs) -> Self {
-        Self { fs }
+        Self::builder(fs).build()
+    }
+
+    pub fn builder(fs: &'fs Fs) -> TypeCheckBuilder<'fs, Fs> {
+        let type_check = Self {
+            fs,
+            resolve_json_module: false,
+            stats: false,
+            no_implicit_references: false,
+            libs: None,
+        };
+        TypeCheckBuilder { type_check }
     }
 
     /// # Errors
     ///
     /// Will return `Err` if parsing any of the root files fails
     pub fn create_program<'a>(
-        &'a self,
-        root_files: &'a [&'a str],
+        &self,
+        root_files: &[&str],
+        alloc: &'a Allocator,
+    ) -> Result<TSProgram<'a>, TSProgramError<'a>> {
+        self.create_program_with_libs(root_files, &[], alloc)
+    }
+
+    /// Like [`TypeCheck::create_program`], with `lib_files` parsed before the root files.
+    ///
+    /// Lib files are parsed as scripts rather than declaration files, which oxc builds no
+    /// semantic model for: their globals need symbols for other modules to resolve against.
+    /// Their declarations are ambient all the same, being `declare`d or types. They are marked
+    /// in [`TSProgram::is_lib`] for callers that only look at the root files, and shared with
+    /// other programs with [`TypeCheckBuilder::libs`].
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if parsing any of the lib or root files fails
+    pub fn create_program_with_libs<'a>(
+        &self,
+        root_files: &[&str],
+        lib_files: &[&str],
         alloc: &'a Allocator,
     ) -> Result<TSProgram<'a>, TSProgramError<'a>> {
+        self.create_program_cached(root_files, lib_files, &ProgramCache::new(alloc))
+    }
+
+    /// Like [`TypeCheck::create_program_with_libs`], reusing the modules `cache` parsed for
+    /// earlier programs when their source text is the same.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if parsing any of the lib or root files fails
+    pub fn create_program_cached<'a>(
+        &self,
+        root_files: &[&str],
+        lib_files: &[&str],
+        cache: &ProgramCache<'a>,
+    ) -> Result<TSProgram<'a>, TSProgramError<'a>> {
+        let alloc = cache.allocator();
+        let len = lib_files.len() + root_files.len();
         let mut result = TSProgram {
-            modules: IndexVec::with_capacity(root_files.len()),
-            semantic: IndexVec::with_capacity(root_files.len()),
+            modules: IndexVec::with_capacity(len),
+            is_lib: IndexVec::with_capacity(len),
+            semantic: IndexVec::with_capacity(len),
+            diagnostics: IndexVec::with_capacity(len),
+            stats: IndexVec::new(),
+            ambient_modules: AmbientModules::default(),
+            references: IndexVec::with_capacity(len),
+            module_ids: FxHashMap::default(),
         };
 
-        // Parse root files
+        // Source texts by path, so a module in the program more than once is read once
+        let mut sources = FxHashMap::default();
+
+        // Parse lib files, then root files
         {
+            let files = lib_files.iter().map(|&x| (x, true));
             let mut parse_err = Vec::new();
-            for &path in root_files {
-                match self.parse_file(path, alloc) {
-                    Ok(semantic) => {
-                        result.modules.push(path);
-                        result.semantic.push(semantic);
+            for (path, is_lib) in files.chain(root_files.iter().map(|&x| (x, false))) {
+                // Paths outlive the caller's list, like the modules of the cache
+                let path: &str = alloc.alloc_str(path);
+                if let (true, Some(store)) = (is_lib, self.libs) {
+                    match store.get_or_load(path, || self.read_source(path)) {
+                        Ok(lib) => self.push_lib(&mut result, lib),
+                        Err(err) => parse_err.push((path, err)),
                     }
+                    continue;
+                }
+                match self.parse_file(path, is_lib, &mut sources, cache) {
+                    Ok(module) => self.push_module(&mut result, path, is_lib, module),
                     Err(err) => parse_err.push((path, err)),
                 }
             }
@@ -129,50 +530,788 @@ where
             }
         };
 
+        // Add the files of `path` references, and the files they reference
+        {
+            let mut parse_err = Vec::new();
+            let mut next = ModuleId::new(lib_files.len());
+            while next < result.modules.next_idx() {
+                let (importer, references) =
+                    (result.modules[next], Rc::clone(&result.references[next]));
+                for reference in &references.paths {
+                    let candidates =
+                        module_resolution::reference_candidates(reference.value, importer);
+                    let mut found = false;
+                    for path in &candidates {
+                        if result.module_id(path).is_some() {
+                            found = true;
+                            break;
+                        }
+                        let path: &str = alloc.alloc_str(path);
+                        match self.parse_file(path, false, &mut sources, cache) {
+                            Ok(module) => self.push_module(&mut result, path, false, module),
+                            Err(ParseError::IO(err)) if err.kind() == io::ErrorKind::NotFound => {
+                                continue;
+                            }
+                            Err(err) => parse_err.push((path, err)),
+                        }
+                        found = true;
+                        break;
+                    }
+                    if !found {
+                        let message = format!("File '{}' not found.", candidates[0]);
+                        let diagnostic = oxc::diagnostics::OxcDiagnostic::error(message)
+                            .with_error_code("TS", "6053")
+                            .with_label(reference.span);
+                        let source_text = result.semantic[next].source_text();
+                        result.diagnostics[next].push(Diagnostic::from_oxc(
+                            &diagnostic,
+                            importer,
+                            source_text,
+                        ));
+                    }
+                }
+                next += 1;
+            }
+
+            if !parse_err.is_empty() {
+                return Err(TSProgramError::ParseError(parse_err));
+            }
+        }
+
         // Resolve imports and add parsed resolved modules to the result
         // TODO
 
         Ok(result)
     }
 
+    fn push_module<'a>(
+        &self,
+        program: &mut TSProgram<'a>,
+        path: &'a str,
+        is_lib: bool,
+        module: CachedModule<'a>,
+    ) {
+        let id = program.modules.push(path);
+        program.module_ids.entry(normalize_path(path)).or_insert(id);
+        program.is_lib.push(is_lib);
+        program.ambient_modules.add(id, &module.ambient_modules);
+        program.semantic.push(ModuleSemantic::Parsed(module.semantic));
+        program.diagnostics.push(module.diagnostics);
+        if let Some(stats) = module.stats {
+            program.stats.push(stats);
+        }
+        program.references.push(self.references(module.references));
+    }
+
+    fn push_lib<'a>(&self, program: &mut TSProgram<'a>, lib: &'a LibFile) {
+        let id = program.modules.push(&lib.name);
+        program.module_ids.entry(normalize_path(&lib.name)).or_insert(id);
+        program.is_lib.push(true);
+        program.ambient_modules.add(id, &lib.ambient_modules);
+        program.semantic.push(ModuleSemantic::Lib(lib));
+        program.diagnostics.push(lib.diagnostics.clone());
+        if self.stats {
+            program.stats.push(ModuleStats::default());
+        }
+        program.references.push(self.references(Rc::clone(&lib.references)));
+    }
+
+    /// The `references` of a module, without the implicit ones if they are left out.
+    fn references<'a>(&self, references: Rc<References<'a>>) -> Rc<References<'a>> {
+        if self.no_implicit_references {
+            Rc::new(References { paths: references.paths.clone(), ..References::default() })
+        } else {
+            references
+        }
+    }
+
     fn parse_file<'a>(
         &'_ self,
-        path: &'_ str,
-        alloc: &'a Allocator,
-    ) -> Result<Semantic<'a>, ParseError> {
-        let source_text = self.fs.read_to_string(Path::new(path))?;
-        let source_text = alloc.alloc_str(&source_text);
-        let source_type = oxc::span::SourceType::from_path(path)?;
-        let parser = oxc::parser::Parser::new(alloc, source_text, source_type);
-
-        let parse_result = parser.parse();
-        if parse_result.panicked {
-            return Err(ParseError::Parser(
-                parse_result.errors,
-                NamedSource::new(path, source_text.to_owned()),
-            ));
-        }
-
-        // TODO: parse_result.errors should be available
-        // if !parse_result.errors.is_empty() {
-        //   return Err(ParseError::Parser(
-        //     parse_result.errors,
-        //     NamedSource::new(path, source_text.to_owned()),
-        //   ));
-        // }
-
-        let program = alloc.alloc(parse_result.program);
-        let builder = oxc::semantic::SemanticBuilder::new().with_check_syntax_error(true);
-        let semantic_result = builder.build(program);
-
-        // TODO: semantic_result.errors should be available
-        // if !semantic_result.errors.is_empty() {
-        //   return Err(ParseError::Semantic(
-        //     semantic_result.errors,
-        //     NamedSource::new(path, source_text.to_owned()),
-        //   ));
-        // }
-
-        Ok(semantic_result.semantic)
+        path: &'a str,
+        is_lib: bool,
+        sources: &mut FxHashMap<&'a str, String>,
+        cache: &ProgramCache<'a>,
+    ) -> Result<CachedModule<'a>, ParseError<'a>> {
+        let kind = match SourceKind::from_path(path) {
+            _ if is_lib => SourceKind::Source(oxc::span::SourceType::ts().with_script(true)),
+            kind => kind?,
+        };
+        if kind == SourceKind::Json && !self.resolve_json_module {
+            return Err(ParseError::JsonModule(path.to_string()));
+        }
+        let source_text = match sources.entry(path) {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => entry.insert(self.read_source(path)?),
+        };
+        cache.get_or_parse(path, source_text, kind, self.stats)
+    }
+
+    /// Reads the file at `path` without its BOM, which the file system may leave in place.
+    fn read_source(&self, path: &str) -> Result<String, ParseError<'static>> {
+        let mut source_text = self.fs.read_to_string(Path::new(path)).map_err(|err| {
+            match invalid_utf8_offset(&err) {
+                Some(offset) => ParseError::Encoding { path: path.to_string(), offset },
+                None => ParseError::IO(err),
+            }
+        })?;
+        if source_text.starts_with('\u{feff}') {
+            source_text.drain(..'\u{feff}'.len_utf8());
+        }
+        Ok(source_text)
+    }
+}
+
+/// Where a file stops being UTF-8, if that is why it could not be read. File systems report it by
+/// wrapping the `FromUtf8Error` or `Utf8Error` of the bytes they read; those that only say the
+/// data is invalid give a [`ParseError::IO`] instead.
+fn invalid_utf8_offset(err: &io::Error) -> Option<usize> {
+    if err.kind() != io::ErrorKind::InvalidData {
+        return None;
+    }
+    let inner = err.get_ref()?;
+    let utf8_error = inner
+        .downcast_ref::<std::string::FromUtf8Error>()
+        .map(std::string::FromUtf8Error::utf8_error)
+        .or_else(|| inner.downcast_ref::<std::str::Utf8Error>().copied());
+    utf8_error.map(|x| x.valid_up_to())
+}
+
+/// `path` with `/` separators and without a leading `./` or `/`.
+fn normalize_path(path: &str) -> Cow<'_, str> {
+    let path = path.trim_start_matches("./").trim_start_matches('/');
+    if path.contains('\\') { Cow::Owned(path.replace('\\', "/")) } else { Cow::Borrowed(path) }
+}
+
+/// Parses a module and builds its semantic model in `alloc`.
+fn parse_source<'a>(
+    path: &str,
+    source_text: &'a str,
+    source_type: oxc::span::SourceType,
+    alloc: &'a Allocator,
+) -> Result<ParsedModule<'a>, ParseError<'a>> {
+    parse_source_timed(path, source_text, source_type, alloc, None)
+}
+
+/// Like [`parse_source`], recording the time spent parsing and building the semantic model in
+/// `stats`.
+fn parse_source_timed<'a>(
+    path: &str,
+    source_text: &'a str,
+    source_type: oxc::span::SourceType,
+    alloc: &'a Allocator,
+    stats: Option<&mut ModuleStats>,
+) -> Result<ParsedModule<'a>, ParseError<'a>> {
+    let start = stats.is_some().then(Instant::now);
+    let parser = oxc::parser::Parser::new(alloc, source_text, source_type);
+
+    let parse_result = parser.parse();
+    if parse_result.panicked {
+        let source = ErrorSource { path: path.to_string(), text: source_text };
+        return Err(ParseError::ParserPanicked(parse_result.errors, source));
+    }
+
+    let mut diagnostics: Vec<_> =
+        parse_result.errors.iter().map(|x| Diagnostic::from_oxc(x, path, source_text)).collect();
+
+    let program = alloc.alloc(parse_result.program);
+    let ambient_modules = declared_ambient_modules(program);
+    let references = References::parse(source_text);
+    let semantic_start = start.map(|_| Instant::now());
+    let builder = oxc::semantic::SemanticBuilder::new().with_check_syntax_error(true);
+    let semantic_result = builder.build(program);
+    record_times(stats, start, semantic_start);
+
+    diagnostics
+        .extend(semantic_result.errors.iter().map(|x| Diagnostic::from_oxc(x, path, source_text)));
+
+    Ok(ParsedModule {
+        semantic: semantic_result.semantic,
+        diagnostics,
+        ambient_modules,
+        references,
+    })
+}
+
+/// Parses a `.json` file as a module whose only statement is `export = <value>`, spanning the
+/// value, so positions stay those of the file.
+fn parse_json<'a>(
+    path: &str,
+    source_text: &'a str,
+    alloc: &'a Allocator,
+    stats: Option<&mut ModuleStats>,
+) -> Result<ParsedModule<'a>, ParseError<'a>> {
+    let start = stats.is_some().then(Instant::now);
+    let source_type = oxc::span::SourceType::ts();
+    let parser = oxc::parser::Parser::new(alloc, source_text, source_type);
+    let value = parser.parse_expression().map_err(|errors| {
+        let source = ErrorSource { path: path.to_string(), text: source_text };
+        ParseError::Parser(errors, source)
+    })?;
+
+    let ast = oxc::ast::AstBuilder::new(alloc);
+    let span = oxc::span::GetSpan::span(&value);
+    let statement = ast.module_declaration_ts_export_assignment(span, value).into();
+    let program = ast.program(
+        oxc::span::Span::sized(0, u32::try_from(source_text.len()).unwrap_or(u32::MAX)),
+        source_type,
+        source_text,
+        ast.vec(),
+        None,
+        ast.vec(),
+        ast.vec1(statement),
+    );
+    let semantic_start = start.map(|_| Instant::now());
+    let builder = oxc::semantic::SemanticBuilder::new();
+    let semantic_result = builder.build(alloc.alloc(program));
+    record_times(stats, start, semantic_start);
+    let diagnostics = semantic_result.errors.iter();
+    let diagnostics = diagnostics.map(|x| Diagnostic::from_oxc(x, path, source_text)).collect();
+
+    Ok(ParsedModule {
+        semantic: semantic_result.semantic,
+        diagnostics,
+        ambient_modules: Vec::new(),
+        references: References::default(),
+    })
+}
+
+fn record_times(
+    stats: Option<&mut ModuleStats>,
+    start: Option<Instant>,
+    semantic_start: Option<Instant>,
+) {
+    if let (Some(stats), Some(start), Some(semantic_start)) = (stats, start, semantic_start) {
+        stats.parse = semantic_start - start;
+        stats.semantic = semantic_start.elapsed();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use oxc::span::SourceType;
+
+    use super::*;
+
+    #[test]
+    fn parser_errors() {
+        let alloc = Allocator::default();
+        let module = parse_source("a.ts", "const a;\nlet b = 1;\n", SourceType::ts(), &alloc)
+            .unwrap_or_else(|err| panic!("{err}"));
+        let locations: Vec<_> = module.diagnostics.iter().map(|x| (x.line, x.column)).collect();
+        assert_eq!(locations, vec![(1, 7)]);
+        assert!(module.diagnostics[0].message.contains("initializ"), "{:?}", module.diagnostics);
+        assert_eq!(module.semantic.source_text(), "const a;\nlet b = 1;\n");
+
+        let module = parse_source("a.ts", "let b = 1;\n", SourceType::ts(), &alloc).unwrap();
+        assert!(module.diagnostics.is_empty());
+
+        let Err(err) = parse_source("a.ts", "var = ;\n", SourceType::ts(), &alloc) else {
+            panic!("Expected the parser to give up");
+        };
+        assert!(matches!(err, ParseError::ParserPanicked(..)));
+        assert_eq!(err.diagnostics().len(), 1);
+    }
+
+    /// Files in memory, for programs built outside of a test unit. Counts the files read.
+    struct MemoryFs {
+        files: &'static [(&'static str, &'static str)],
+        reads: AtomicUsize,
+    }
+
+    impl MemoryFs {
+        fn new(files: &'static [(&'static str, &'static str)]) -> Self {
+            Self { files, reads: AtomicUsize::new(0) }
+        }
+    }
+
+    impl FileSystem for &MemoryFs {
+        fn read_to_string(&self, path: &Path) -> io::Result<String> {
+            self.reads.fetch_add(1, Ordering::Relaxed);
+            let file = self.files.iter().find(|(name, _)| Path::new(name) == path);
+            file.map(|(_, text)| (*text).to_string()).ok_or_else(|| io::ErrorKind::NotFound.into())
+        }
+
+        fn metadata(&self, _path: &Path) -> io::Result<oxc_resolver::FileMetadata> {
+            Err(io::ErrorKind::Unsupported.into())
+        }
+
+        fn symlink_metadata(&self, _path: &Path) -> io::Result<oxc_resolver::FileMetadata> {
+            Err(io::ErrorKind::Unsupported.into())
+        }
+
+        fn read_link(&self, _path: &Path) -> io::Result<std::path::PathBuf> {
+            Err(io::ErrorKind::Unsupported.into())
+        }
+    }
+
+    #[test]
+    fn lib_files() {
+        let fs = MemoryFs::new(&[
+            (
+                "/.lib/lib.d.ts",
+                "interface Array<T> { length: number; }\ndeclare var NaN: number;\n",
+            ),
+            ("a.ts", "let a: Array<number> = [];\n"),
+        ]);
+        let type_check = TypeCheck::new(&fs);
+        let alloc = Allocator::default();
+        let program = type_check
+            .create_program_with_libs(&["a.ts"], &["/.lib/lib.d.ts"], &alloc)
+            .unwrap_or_else(|err| panic!("{err}"));
+
+        assert_eq!(program.modules.raw, vec!["/.lib/lib.d.ts", "a.ts"]);
+        assert_eq!(program.is_lib.raw, vec![true, false]);
+        assert_eq!(program.root_modules().collect::<Vec<_>>(), vec![ModuleId::new(1)]);
+
+        // Globals of the lib are bound, for the root file's references to resolve against
+        let lib = program.semantic(ModuleId::new(0)).parsed().unwrap();
+        assert!(lib.scoping().get_root_binding("Array").is_some());
+        let root = program.semantic(ModuleId::new(1)).parsed().unwrap();
+        assert!(root.scoping().root_unresolved_references().contains_key("Array"));
+        let source_text = lib.source_text();
+        let start = u32::try_from(source_text.find("NaN").unwrap()).unwrap();
+        assert!(SemanticFacade::new(lib).is_ambient(oxc::span::Span::sized(start, 3)));
+
+        let Err(err) = type_check.create_program_with_libs(&["a.ts"], &["lib.es5.d.ts"], &alloc)
+        else {
+            panic!("Expected a missing lib file to fail");
+        };
+        assert!(matches!(err, TSProgramError::ParseError(ref x) if x[0].0 == "lib.es5.d.ts"));
+    }
+
+    #[test]
+    fn lib_files_shared() {
+        let fs = MemoryFs::new(&[
+            ("/.lib/lib.d.ts", "interface Array<T> { length: number; }\n"),
+            ("a.ts", "let a: Array<number> = [];\n"),
+        ]);
+        let store = LibStore::new();
+        let type_check = TypeCheck::builder(&fs).libs(&store).build();
+
+        // Like two units run one after the other, each with its own allocator
+        let first_alloc = Allocator::default();
+        let first =
+            type_check.create_program_with_libs(&["a.ts"], &["/.lib/lib.d.ts"], &first_alloc);
+        let second_alloc = Allocator::default();
+        let second =
+            type_check.create_program_with_libs(&["a.ts"], &["/.lib/lib.d.ts"], &second_alloc);
+        let (first, second) = (first.unwrap(), second.unwrap());
+        let (ModuleSemantic::Lib(first_lib), ModuleSemantic::Lib(second_lib)) =
+            (first.semantic(ModuleId::new(0)), second.semantic(ModuleId::new(0)))
+        else {
+            panic!("Expected the lib file to be shared");
+        };
+        assert!(std::ptr::eq(*first_lib, *second_lib));
+        assert!(first_lib.semantic.scoping().get_root_binding("Array").is_some());
+        assert_eq!((store.len(), fs.reads.load(Ordering::Relaxed)), (1, 3));
+        assert_eq!(first.is_lib.raw, vec![true, false]);
+        assert_eq!(second.module_id(".lib/lib.d.ts"), Some(ModuleId::new(0)));
+
+        let Err(err) =
+            type_check.create_program_with_libs(&["a.ts"], &["lib.es5.d.ts"], &first_alloc)
+        else {
+            panic!("Expected a missing lib file to fail");
+        };
+        assert!(matches!(err, TSProgramError::ParseError(ref x) if x[0].0 == "lib.es5.d.ts"));
+        assert_eq!(store.len(), 1);
+    }
+
+    #[test]
+    fn sources_read_once() {
+        let fs = MemoryFs::new(&[("a.ts", "let a = 1;\n"), ("b.ts", "var = ;\n")]);
+        let type_check = TypeCheck::new(&fs);
+        let alloc = Allocator::default();
+        let program = type_check.create_program(&["a.ts", "a.ts"], &alloc).unwrap();
+        assert_eq!(program.modules.len(), 2);
+        assert_eq!(fs.reads.load(Ordering::Relaxed), 1);
+
+        // Once per program
+        let Err(err) = type_check.create_program(&["a.ts", "b.ts", "b.ts"], &alloc) else {
+            panic!("Expected the parser to give up");
+        };
+        assert_eq!(fs.reads.load(Ordering::Relaxed), 3);
+        assert!(err.to_string().contains("var = ;"), "{err}");
+    }
+
+    #[test]
+    fn program_cache() {
+        let fs = MemoryFs::new(&[
+            ("/.lib/lib.d.ts", "interface Array<T> {}\n"),
+            ("a.ts", "let a = 1;\n"),
+            ("b.ts", "const b;\n"),
+        ]);
+        let type_check = TypeCheck::new(&fs);
+        let alloc = Allocator::default();
+        let cache = ProgramCache::new(&alloc);
+
+        // Like the 10 variants of a test, which only differ in options
+        for _ in 0..10 {
+            let program = type_check
+                .create_program_cached(&["a.ts", "b.ts"], &["/.lib/lib.d.ts"], &cache)
+                .unwrap_or_else(|err| panic!("{err}"));
+            assert_eq!(program.diagnostics[ModuleId::new(2)].len(), 1);
+        }
+        assert_eq!((cache.parses(), cache.len()), (3, 3));
+
+        cache.invalidate("./a.ts");
+        assert_eq!(cache.len(), 2);
+        let program = type_check.create_program_cached(&["a.ts"], &[], &cache).unwrap();
+        assert_eq!(program.semantic(ModuleId::new(0)).source_text(), "let a = 1;\n");
+        assert_eq!(cache.parses(), 4);
+
+        // Another source text at the same path is another module
+        let kind = SourceKind::Source(SourceType::ts());
+        let module = cache.get_or_parse("a.ts", "let a = 2;\n", kind, false).unwrap();
+        assert_eq!(module.semantic.source_text(), "let a = 2;\n");
+        assert_eq!((cache.parses(), cache.len()), (5, 4));
+        assert!(cache.get_or_parse("a.ts", "var = ;\n", kind, false).is_err());
+        assert_eq!((cache.parses(), cache.len()), (6, 4));
+    }
+
+    #[test]
+    fn json_modules() {
+        let fs = MemoryFs::new(&[
+            ("a.json", "{ \"a\": [1, true] }\n"),
+            ("b.json", "{ \"b\": }\n"),
+            ("c.png", ""),
+        ]);
+        let alloc = Allocator::default();
+        let type_check = TypeCheck::builder(&fs).resolve_json_module(true).build();
+        let program = type_check.create_program(&["a.json"], &alloc).unwrap();
+        let semantic = program.semantic(ModuleId::new(0)).parsed().unwrap();
+        assert_eq!(semantic.source_text(), "{ \"a\": [1, true] }\n");
+        let export = semantic.nodes().iter().find_map(|x| match x.kind() {
+            oxc::ast::AstKind::TSExportAssignment(x) => Some(x.span),
+            _ => None,
+        });
+        assert_eq!(export, Some(oxc::span::Span::new(0, 18)));
+
+        let Err(TSProgramError::ParseError(err)) = type_check.create_program(&["b.json"], &alloc)
+        else {
+            panic!("Expected invalid JSON to fail");
+        };
+        assert!(matches!(err[0].1, ParseError::Parser(..)));
+        let Err(TSProgramError::ParseError(err)) = type_check.create_program(&["c.png"], &alloc)
+        else {
+            panic!("Expected an unknown extension to fail");
+        };
+        assert!(matches!(err[0].1, ParseError::UnknownExtension(_)));
+
+        let type_check = TypeCheck::new(&fs);
+        let Err(err) = type_check.create_program(&["a.json"], &alloc) else {
+            panic!("Expected a JSON module to need resolveJsonModule");
+        };
+        assert!(err.to_string().contains("  type: JSON module"), "{err}");
+    }
+
+    #[test]
+    fn render_one_file() {
+        let fs = MemoryFs::new(&[("a.ts", "let a = 1;\nvar = ;\n")]);
+        let alloc = Allocator::default();
+        let Err(err) = TypeCheck::new(&fs).create_program(&["a.ts"], &alloc) else {
+            panic!("Expected the parser to give up");
+        };
+        let expected = r"Failed to parse 1 files
+
+---------------- a.ts ----------------
+  type: parser panic
+
+      × Unexpected token
+       ╭─[a.ts:2:5]
+     1 │ let a = 1;
+     2 │ var = ;
+       ·     ─
+       ╰────
+
+Failed files: 1 parser panic";
+        assert_eq!(err.render_plain(), expected);
+    }
+
+    #[test]
+    fn render_three_files() {
+        let fs = MemoryFs::new(&[("a.ts", "var = ;\n"), ("b.ts", "let = ;\n"), ("c.png", "")]);
+        let alloc = Allocator::default();
+        let Err(err) = TypeCheck::new(&fs).create_program(&["a.ts", "b.ts", "c.png"], &alloc)
+        else {
+            panic!("Expected every file to fail");
+        };
+        let expected = r"Failed to parse 3 files
+
+---------------- a.ts ----------------
+  type: parser panic
+
+      × Unexpected token
+       ╭─[a.ts:1:5]
+     1 │ var = ;
+       ·     ─
+       ╰────
+
+---------------- b.ts ----------------
+  type: parser panic
+
+      × Unexpected token
+       ╭─[b.ts:1:7]
+     1 │ let = ;
+       ·       ─
+       ╰────
+
+---------------- c.png ----------------
+  type: Unknown Extension
+    Unknown file extension: Please provide a valid file extension for c.png: .js, .mjs, .jsx or .cjs for JavaScript, or .ts, .d.ts, .mts, .cts or .tsx for TypeScript
+
+Failed files: 2 parser panic, 1 Unknown Extension";
+        assert_eq!(err.render_plain(), expected);
+    }
+
+    #[test]
+    fn stats() {
+        let fs = MemoryFs::new(&[("a.ts", "let a = 1;\n"), ("b.json", "[1, 2]")]);
+        let alloc = Allocator::default();
+        let cache = ProgramCache::new(&alloc);
+        let type_check = TypeCheck::builder(&fs).resolve_json_module(true).build();
+        let program = type_check.create_program_cached(&["a.ts"], &[], &cache).unwrap();
+        assert!(program.stats.is_empty());
+
+        // Modules parsed without stats are parsed again
+        let type_check = TypeCheck::builder(&fs).resolve_json_module(true).stats(true).build();
+        let program = type_check.create_program_cached(&["a.ts", "b.json"], &[], &cache).unwrap();
+        assert_eq!(program.stats.len(), program.modules.len());
+        assert_eq!(cache.parses(), 3);
+        for (stats, len) in program.stats.iter().zip([11, 6]) {
+            assert_eq!(stats.source_len, len);
+            assert!(stats.allocated >= len, "{stats:?}");
+            assert!(!stats.parse.is_zero() && !stats.semantic.is_zero(), "{stats:?}");
+        }
+    }
+
+    #[test]
+    fn module_id() {
+        let fs = MemoryFs::new(&[("./a.ts", ""), ("/dir/a.ts", ""), ("dir\\b.ts", "")]);
+        let type_check = TypeCheck::new(&fs);
+        let alloc = Allocator::default();
+        let program =
+            type_check.create_program(&["./a.ts", "/dir/a.ts", "dir\\b.ts"], &alloc).unwrap();
+
+        let id = program.module_id("a.ts").unwrap();
+        assert_eq!(program.path(id), "./a.ts");
+        assert_eq!(program.module_id("./a.ts"), Some(id));
+        assert_eq!(program.semantic(id).source_text(), "");
+        let id = program.module_id("dir/a.ts").unwrap();
+        assert_eq!(program.path(id), "/dir/a.ts");
+        assert_eq!(program.module_id("/dir/a.ts"), Some(id));
+        assert_eq!(program.module_id("dir/b.ts"), Some(ModuleId::new(2)));
+        assert_eq!(program.module_id("b.ts"), None);
+    }
+
+    #[test]
+    fn ambient_modules() {
+        let fs = MemoryFs::new(&[
+            (
+                "/types.d.ts",
+                "declare module \"foo\" { export const a: number; }\n\
+                 declare module \"*.css\" { const css: string; export default css; }\n\
+                 declare module \"./*\";\n",
+            ),
+            ("/node_modules/foo/index.d.ts", "export const a: string;\n"),
+            ("/node_modules/bar.d.ts", "export const b: string;\n"),
+            ("/dir/a.ts", "import { a } from \"foo\";\n"),
+            ("/dir/b.ts", "export const b = 1;\n"),
+        ]);
+        let type_check = TypeCheck::new(&fs);
+        let alloc = Allocator::default();
+        let files = [
+            "/types.d.ts",
+            "/node_modules/foo/index.d.ts",
+            "/node_modules/bar.d.ts",
+            "/dir/a.ts",
+            "/dir/b.ts",
+        ];
+        let program =
+            type_check.create_program(&files, &alloc).unwrap_or_else(|err| panic!("{err}"));
+        let types = ModuleId::new(0);
+        assert_eq!(program.ambient_modules.len(), 3);
+
+        // Exact, shadowing the package
+        assert_eq!(
+            program.resolve_module("foo", "/dir/a.ts"),
+            Some(ResolvedModule::Ambient(types))
+        );
+        assert_eq!(
+            program.resolve_module("bar", "/dir/a.ts"),
+            Some(ResolvedModule::File(ModuleId::new(2)))
+        );
+        assert_eq!(program.resolve_module("baz", "/dir/a.ts"), None);
+
+        // Wildcards, after the files
+        assert_eq!(
+            program.resolve_module("./a.css", "/dir/a.ts"),
+            Some(ResolvedModule::Ambient(types))
+        );
+        assert_eq!(
+            program.resolve_module("./b", "/dir/a.ts"),
+            Some(ResolvedModule::File(ModuleId::new(4)))
+        );
+        assert_eq!(
+            program.resolve_module("./b.js", "/dir/a.ts"),
+            Some(ResolvedModule::File(ModuleId::new(4)))
+        );
+        assert_eq!(
+            program.resolve_module("./c", "/dir/a.ts"),
+            Some(ResolvedModule::Ambient(types))
+        );
+        assert_eq!(program.resolve_module("../dir/c", "/dir/a.ts"), None);
+    }
+
+    #[test]
+    fn references() {
+        let fs = MemoryFs::new(&[
+            (
+                "/a.ts",
+                "/// <reference path=\"dir/b.ts\" />\n/// <reference types=\"node\" />\nlet a;\n",
+            ),
+            (
+                "/dir/b.ts",
+                "/// <reference path=\"../c\" />\n/// <reference path=\"../a.ts\" />\nlet b;\n",
+            ),
+            (
+                "/c.ts",
+                "/// <reference path=\"missing.ts\" />\n/// <reference lib=\"es2015\" />\nlet c;\n",
+            ),
+        ]);
+        let alloc = Allocator::default();
+        let type_check = TypeCheck::new(&fs);
+        let program =
+            type_check.create_program(&["/a.ts"], &alloc).unwrap_or_else(|err| panic!("{err}"));
+        assert_eq!(program.modules.raw, vec!["/a.ts", "/dir/b.ts", "/c.ts"]);
+        assert_eq!(program.root_modules().count(), 3);
+        let types: Vec<_> =
+            program.references[ModuleId::new(0)].types.iter().map(|x| x.value).collect();
+        assert_eq!(types, ["node"]);
+        assert_eq!(program.references[ModuleId::new(2)].libs.len(), 1);
+
+        let diagnostics = &program.diagnostics[ModuleId::new(2)];
+        let diagnostics: Vec<_> =
+            diagnostics.iter().map(|x| (x.code, x.line, x.column, x.message.as_str())).collect();
+        assert_eq!(diagnostics, [(6053, 1, 22, "File '/missing.ts' not found.")]);
+
+        // Only `path` references with `@noImplicitReferences`
+        let type_check = TypeCheck::builder(&fs).no_implicit_references(true).build();
+        let program = type_check.create_program(&["/a.ts"], &alloc).unwrap();
+        assert_eq!(program.modules.len(), 3);
+        assert!(program.references.iter().all(|x| x.types.is_empty() && x.libs.is_empty()));
+    }
+
+    #[test]
+    fn encoding() {
+        struct BytesFs(&'static [(&'static str, &'static [u8])]);
+
+        impl FileSystem for &BytesFs {
+            fn read_to_string(&self, path: &Path) -> io::Result<String> {
+                let file = self.0.iter().find(|(name, _)| Path::new(name) == path);
+                let (_, bytes) = file.ok_or(io::ErrorKind::NotFound)?;
+                // Like `FileSystemOs`, without the position of the invalid bytes
+                if path == Path::new("opaque.ts") {
+                    return Err(io::ErrorKind::InvalidData.into());
+                }
+                String::from_utf8(bytes.to_vec())
+                    .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))
+            }
+
+            fn metadata(&self, _path: &Path) -> io::Result<oxc_resolver::FileMetadata> {
+                Err(io::ErrorKind::Unsupported.into())
+            }
+
+            fn symlink_metadata(&self, _path: &Path) -> io::Result<oxc_resolver::FileMetadata> {
+                Err(io::ErrorKind::Unsupported.into())
+            }
+
+            fn read_link(&self, _path: &Path) -> io::Result<std::path::PathBuf> {
+                Err(io::ErrorKind::Unsupported.into())
+            }
+        }
+
+        let fs = BytesFs(&[
+            ("bom.ts", b"\xef\xbb\xbfconst a;\n"),
+            // `é` in Latin-1
+            ("latin1.ts", b"let caf\xe9 = 1;\n"),
+            ("opaque.ts", b"\xe9"),
+        ]);
+        let alloc = Allocator::default();
+        let type_check = TypeCheck::new(&fs);
+        let program = type_check.create_program(&["bom.ts"], &alloc).unwrap();
+        assert_eq!(program.semantic(ModuleId::new(0)).source_text(), "const a;\n");
+        let locations: Vec<_> =
+            program.diagnostics[ModuleId::new(0)].iter().map(|x| (x.line, x.column)).collect();
+        assert_eq!(locations, vec![(1, 7)]);
+
+        let Err(TSProgramError::ParseError(err)) =
+            type_check.create_program(&["latin1.ts"], &alloc)
+        else {
+            panic!("Expected Latin-1 content to fail");
+        };
+        assert!(matches!(err[0].1, ParseError::Encoding { offset: 7, .. }), "{:?}", err[0].1);
+        assert_eq!(
+            err[0].1.to_string(),
+            "File 'latin1.ts' is not valid UTF-8: invalid byte sequence at offset 7"
+        );
+        assert_eq!(err[0].1.label(), "encoding");
+
+        let Err(TSProgramError::ParseError(err)) =
+            type_check.create_program(&["opaque.ts"], &alloc)
+        else {
+            panic!("Expected invalid content to fail");
+        };
+        assert!(matches!(&err[0].1, ParseError::IO(x) if x.kind() == io::ErrorKind::InvalidData));
+    }
+
+    #[test]
+    fn jsx() {
+        let fs = MemoryFs::new(&[("a.ts", "let a = <any>1;\n"), ("b.tsx", "let b = <any>1;\n")]);
+        let alloc = Allocator::default();
+        let type_check = TypeCheck::new(&fs);
+        let program = type_check.create_program(&["a.ts"], &alloc).unwrap();
+        assert!(program.diagnostics[ModuleId::new(0)].is_empty());
+        let assertion = program
+            .semantic(ModuleId::new(0))
+            .parsed()
+            .unwrap()
+            .nodes()
+            .iter()
+            .any(|x| matches!(x.kind(), oxc::ast::AstKind::TSTypeAssertion(_)));
+        assert!(assertion);
+
+        // An unclosed `<any>` element
+        let Err(err) = type_check.create_program(&["b.tsx"], &alloc) else {
+            panic!("Expected `<any>` to be parsed as JSX");
+        };
+        assert!(err.parser_panic().is_some(), "{err}");
+    }
+
+    #[test]
+    fn resolve_error() {
+        let err = ParseError::Resolve {
+            specifier: "./b".to_string(),
+            importer: "a.ts".to_string(),
+            reason: Box::new(oxc_resolver::ResolveError::NotFound("./b".to_string())),
+        };
+        assert_eq!(err.to_string(), "Cannot resolve './b' from 'a.ts': Cannot find module './b'");
+        assert!(err.diagnostics().is_empty());
+
+        let err = TSProgramError::ParseError(vec![("a.ts", err)]);
+        assert!(err.to_string().contains("  type: resolve"), "{err}");
+        assert!(err.parser_panic().is_none());
+    }
+
+    #[test]
+    fn semantic_errors() {
+        let alloc = Allocator::default();
+        let source_text = "const a = 1;\nconst a = 2;\n";
+        let module = parse_source("a.ts", source_text, SourceType::ts(), &alloc).unwrap();
+        let locations: Vec<_> =
+            module.diagnostics.iter().map(|x| (x.line, x.column, x.length)).collect();
+        assert_eq!(locations, vec![(1, 7, Some(1))], "{:?}", module.diagnostics);
+        assert!(module.diagnostics[0].message.contains("`a`"), "{:?}", module.diagnostics);
     }
 }
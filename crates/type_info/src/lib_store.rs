@@ -0,0 +1,122 @@
+use std::{cell::RefCell, rc::Rc};
+
+use oxc::{allocator::Allocator, semantic::Semantic, span::SourceType};
+use rustc_hash::FxHashMap;
+
+use crate::{Diagnostic, ParseError, References, parse_source};
+
+/// A parsed lib file shared by every program that includes it, see
+/// [`TypeCheck::create_program_with_libs`](crate::TypeCheck::create_program_with_libs).
+pub struct LibFile {
+    /// Path of the lib file in the programs, like `/.lib/lib.d.ts`.
+    pub name: String,
+    pub semantic: Semantic<'static>,
+    pub diagnostics: Vec<Diagnostic>,
+    /// Names of the ambient modules the lib file declares.
+    pub ambient_modules: Vec<&'static str>,
+    pub references: Rc<References<'static>>,
+}
+
+/// Parses lib files once and hands out shared references to them.
+///
+/// Entries are parsed into a dedicated allocator that lives for the rest of the process, so
+/// programs can reference them without owning them. `Semantic` is not `Sync`: keep one store
+/// per thread for the whole run instead of creating one per test.
+pub struct LibStore {
+    alloc: &'static Allocator,
+    entries: RefCell<FxHashMap<String, &'static LibFile>>,
+}
+
+impl Default for LibStore {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl LibStore {
+    pub fn new() -> Self {
+        Self { alloc: Box::leak(Box::default()), entries: RefCell::default() }
+    }
+
+    /// Returns the lib file at `name`, calling `load` to read its source on first use. A BOM
+    /// `load` leaves in place is dropped. Lib files are parsed as scripts, like
+    /// [`TypeCheck::create_program_with_libs`](crate::TypeCheck::create_program_with_libs) does.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if `load` fails or the lib file cannot be parsed. Failures are not
+    /// cached.
+    pub fn get_or_load(
+        &self,
+        name: &str,
+        load: impl FnOnce() -> Result<String, ParseError<'static>>,
+    ) -> Result<&'static LibFile, ParseError<'static>> {
+        if let Some(&lib) = self.entries.borrow().get(name) {
+            return Ok(lib);
+        }
+
+        let source_text = load()?;
+        let source_text = self.alloc.alloc_str(source_text.trim_start_matches('\u{feff}'));
+        let source_type = SourceType::ts().with_script(true);
+        let parsed = parse_source(name, source_text, source_type, self.alloc)?;
+        let lib: &'static LibFile = Box::leak(Box::new(LibFile {
+            name: name.to_string(),
+            semantic: parsed.semantic,
+            diagnostics: parsed.diagnostics,
+            ambient_modules: parsed.ambient_modules,
+            references: Rc::new(parsed.references),
+        }));
+        self.entries.borrow_mut().insert(name.to_string(), lib);
+        Ok(lib)
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.borrow().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.borrow().is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::Cell;
+
+    use super::*;
+
+    #[test]
+    fn parsed_once() {
+        let store = LibStore::new();
+        let loads = Cell::new(0);
+        let load = || {
+            loads.set(loads.get() + 1);
+            Ok("\u{feff}interface Array<T> { length: number; }".to_string())
+        };
+
+        let first = store.get_or_load("/.lib/lib.d.ts", load).unwrap();
+        let second = store.get_or_load("/.lib/lib.d.ts", load).unwrap();
+        assert!(std::ptr::eq(first, second));
+        assert_eq!(loads.get(), 1);
+        assert_eq!(first.semantic.source_text(), "interface Array<T> { length: number; }");
+
+        let other = store.get_or_load("/.lib/lib.es5.d.ts", load).unwrap();
+        assert!(!std::ptr::eq(first, other));
+        assert_eq!(loads.get(), 2);
+        assert_eq!(store.len(), 2);
+    }
+
+    #[test]
+    fn failures_not_cached() {
+        let store = LibStore::new();
+        let missing = store.get_or_load("lib.dom.d.ts", || {
+            Err(std::io::Error::from(std::io::ErrorKind::NotFound).into())
+        });
+        assert!(matches!(missing, Err(ParseError::IO(_))));
+        assert!(store.is_empty());
+
+        let lib = store.get_or_load("lib.dom.d.ts", || Ok("declare var document: any;".into()));
+        assert!(lib.is_ok());
+        assert_eq!(store.len(), 1);
+    }
+}
@@ -0,0 +1,269 @@
+use oxc::ast::ast::{Program, Statement, TSModuleDeclarationName, TSModuleReference};
+use rustc_hash::FxHashMap;
+
+use crate::{ModuleId, SourceKind};
+
+/// What an import resolved to, see [`TSProgram::resolve_module`](crate::TSProgram::resolve_module).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ResolvedModule {
+    /// A `declare module` of this module, by name or by pattern.
+    Ambient(ModuleId),
+    /// This module of the program.
+    File(ModuleId),
+}
+
+/// Modules declared with `declare module "name"` by the scripts of a program.
+#[derive(Debug, Default)]
+pub struct AmbientModules<'a> {
+    /// By name, the first module declaring it.
+    exact: FxHashMap<&'a str, ModuleId>,
+    /// Names with a `*` wildcard, like `*.css`, in declaration order.
+    patterns: Vec<(&'a str, ModuleId)>,
+}
+
+impl<'a> AmbientModules<'a> {
+    /// Adds the ambient modules declared by `names`, see `declared_ambient_modules`.
+    pub fn add(&mut self, id: ModuleId, names: &[&'a str]) {
+        for &name in names {
+            if name.contains('*') {
+                self.patterns.push((name, id));
+            } else {
+                self.exact.entry(name).or_insert(id);
+            }
+        }
+    }
+
+    /// The module declaring `specifier` by name.
+    pub fn get(&self, specifier: &str) -> Option<ModuleId> {
+        self.exact.get(specifier).copied()
+    }
+
+    /// The module declaring a pattern `specifier` matches. Like TypeScript, the pattern with
+    /// the longest prefix before its `*` wins, and patterns with more than one `*` match nothing.
+    pub fn matching(&self, specifier: &str) -> Option<ModuleId> {
+        let mut result: Option<(usize, ModuleId)> = None;
+        for &(pattern, id) in &self.patterns {
+            let Some((prefix, suffix)) = pattern.split_once('*') else {
+                continue;
+            };
+            let matches = !suffix.contains('*')
+                && specifier.len() >= prefix.len() + suffix.len()
+                && specifier.starts_with(prefix)
+                && specifier.ends_with(suffix);
+            if matches && result.is_none_or(|(len, _)| len < prefix.len()) {
+                result = Some((prefix.len(), id));
+            }
+        }
+        result.map(|(_, id)| id)
+    }
+
+    /// Number of names and patterns declared.
+    pub fn len(&self) -> usize {
+        self.exact.len() + self.patterns.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.exact.is_empty() && self.patterns.is_empty()
+    }
+}
+
+/// Names of the ambient modules `program` declares at the top level. Files with imports or
+/// exports declare none: their `declare module "name"` augments the module `name` instead.
+///
+/// Read from the AST rather than the semantic model, which oxc does not build for declaration
+/// files.
+pub fn declared_ambient_modules<'a>(program: &Program<'a>) -> Vec<&'a str> {
+    let is_module = program.body.iter().any(|x| match x {
+        Statement::TSImportEqualsDeclaration(decl) => {
+            matches!(decl.module_reference, TSModuleReference::ExternalModuleReference(_))
+        }
+        _ => x.is_module_declaration(),
+    });
+    if is_module {
+        return Vec::new();
+    }
+
+    let names = program.body.iter().filter_map(|x| match x {
+        Statement::TSModuleDeclaration(decl) => match &decl.id {
+            TSModuleDeclarationName::StringLiteral(name) => Some(name.value.as_str()),
+            TSModuleDeclarationName::Identifier(_) => None,
+        },
+        _ => None,
+    });
+    names.collect()
+}
+
+/// Whether `specifier` names a path rather than a package, like `./a` or `/a`.
+pub fn is_path(specifier: &str) -> bool {
+    specifier.starts_with("./")
+        || specifier.starts_with("../")
+        || matches!(specifier, "." | "..")
+        || specifier.starts_with('/')
+}
+
+/// Paths the file `specifier` imported from `importer` may have, in the order TypeScript tries
+/// them: the TypeScript sources of a JavaScript path first, then the path as written, with an
+/// extension, and its `index` file. Packages are looked up in the `node_modules` of the
+/// directories of `importer`, without reading their `package.json`.
+pub fn file_candidates(specifier: &str, importer: &str) -> Vec<String> {
+    let importer_dir = dir_of(importer);
+    let bases = if is_path(specifier) {
+        let dir = if specifier.starts_with('/') { "" } else { importer_dir };
+        vec![join(dir, specifier)]
+    } else {
+        let mut result = Vec::new();
+        let mut dir = importer_dir;
+        loop {
+            result.push(join(dir, &format!("node_modules/{specifier}")));
+            result.push(join(dir, &format!("node_modules/@types/{specifier}")));
+            dir = match dir.rsplit_once(['/', '\\']) {
+                Some(("", _)) if dir.len() > 1 => "/",
+                Some((parent, _)) if dir != "/" => parent,
+                None if !dir.is_empty() => "",
+                _ => break,
+            };
+        }
+        result
+    };
+
+    let mut result = Vec::new();
+    for base in bases {
+        for (js, ts) in JS_TO_TS {
+            if let Some(stem) = base.strip_suffix(js) {
+                result.extend(ts.iter().map(|x| format!("{stem}{x}")));
+            }
+        }
+        result.extend(EXTENSIONS.iter().map(|x| format!("{base}{x}")));
+        result.extend(EXTENSIONS.iter().map(|x| format!("{base}/index{x}")));
+        result.push(base);
+    }
+    result
+}
+
+/// Paths the file of `/// <reference path="..." />` in `importer` may have: `value` relative
+/// to the directory of `importer`, with the TypeScript extensions tried when it has none.
+pub fn reference_candidates(value: &str, importer: &str) -> Vec<String> {
+    let importer_dir = dir_of(importer);
+    let base = join(importer_dir, value);
+    if SourceKind::from_path(&base).is_ok() {
+        return vec![base];
+    }
+    [".ts", ".tsx", ".d.ts"].iter().map(|x| format!("{base}{x}")).collect()
+}
+
+/// Extensions tried after a path without one.
+const EXTENSIONS: &[&str] = &[".ts", ".tsx", ".d.ts", ".js", ".jsx"];
+
+/// TypeScript sources of JavaScript files, which imports name by their output.
+const JS_TO_TS: &[(&str, &[&str])] = &[
+    (".js", &[".ts", ".tsx", ".d.ts"]),
+    (".jsx", &[".tsx", ".ts", ".d.ts"]),
+    (".mjs", &[".mts", ".d.mts"]),
+    (".cjs", &[".cts", ".d.cts"]),
+];
+
+/// Directory of the file at `path`, `/` for the files at the root.
+fn dir_of(path: &str) -> &str {
+    match path.rsplit_once(['/', '\\']) {
+        Some(("", _)) => "/",
+        Some((dir, _)) => dir,
+        None => "",
+    }
+}
+
+/// `path` relative to `dir`, with `.` and `..` segments applied.
+fn join(dir: &str, path: &str) -> String {
+    let mut segments: Vec<&str> = Vec::new();
+    for segment in dir.split(['/', '\\']).chain(path.split(['/', '\\'])) {
+        match segment {
+            "" | "." => {}
+            ".." => {
+                segments.pop();
+            }
+            _ => segments.push(segment),
+        }
+    }
+    let absolute = dir.starts_with('/') || path.starts_with('/');
+    let path = segments.join("/");
+    if absolute { format!("/{path}") } else { path }
+}
+
+#[cfg(test)]
+mod tests {
+    use oxc::{allocator::Allocator, span::SourceType};
+
+    use super::*;
+
+    fn ambient_modules<'a>(alloc: &'a Allocator, sources: &[&'a str]) -> AmbientModules<'a> {
+        let mut result = AmbientModules::default();
+        for (idx, &source_text) in sources.iter().enumerate() {
+            let module = crate::parse_source("a.d.ts", source_text, SourceType::d_ts(), alloc)
+                .unwrap_or_else(|err| panic!("{err}"));
+            result.add(ModuleId::new(idx), &module.ambient_modules);
+        }
+        result
+    }
+
+    #[test]
+    fn declared_names() {
+        let alloc = Allocator::default();
+        let modules = ambient_modules(
+            &alloc,
+            &[
+                "declare module \"foo\" {}\ndeclare module \"*.css\";\ndeclare namespace bar {}\n",
+                "declare module \"foo\" {}\ndeclare module \"baz\" {}\n",
+                // Augmentations
+                "export {};\ndeclare module \"qux\" {}\n",
+                "import x = require(\"foo\");\ndeclare module \"quux\" {}\n",
+            ],
+        );
+        assert_eq!(modules.len(), 3);
+        assert_eq!(modules.get("foo"), Some(ModuleId::new(0)));
+        assert_eq!(modules.get("baz"), Some(ModuleId::new(1)));
+        assert_eq!(modules.get("bar"), None);
+        assert_eq!(modules.get("qux"), None);
+        assert_eq!(modules.get("quux"), None);
+        assert_eq!(modules.get("a.css"), None);
+    }
+
+    #[test]
+    fn patterns() {
+        let alloc = Allocator::default();
+        let modules = ambient_modules(
+            &alloc,
+            &[
+                "declare module \"*.css\";\ndeclare module \"*!text\";\n",
+                "declare module \"styles/*.css\";\ndeclare module \"*a*\";\n",
+            ],
+        );
+        assert_eq!(modules.matching("./a.css"), Some(ModuleId::new(0)));
+        assert_eq!(modules.matching("styles/a.css"), Some(ModuleId::new(1)));
+        assert_eq!(modules.matching("./a.txt!text"), Some(ModuleId::new(0)));
+        assert_eq!(modules.matching(".css"), Some(ModuleId::new(0)));
+        assert_eq!(modules.matching("a.ts"), None);
+    }
+
+    #[test]
+    fn candidates() {
+        assert_eq!(join("/dir/sub", "../a"), "/dir/a");
+        assert_eq!(join("dir", "./a/./b"), "dir/a/b");
+        assert_eq!(join("", "../../a"), "a");
+
+        let candidates = file_candidates("./b.js", "/dir/a.ts");
+        assert_eq!(&candidates[..4], ["/dir/b.ts", "/dir/b.tsx", "/dir/b.d.ts", "/dir/b.js.ts"]);
+        assert_eq!(candidates.last().unwrap(), "/dir/b.js");
+        let candidates = file_candidates("/b", "/dir/a.ts");
+        assert_eq!(candidates[0], "/b.ts");
+        assert!(candidates.contains(&"/b/index.d.ts".to_string()));
+
+        let candidates = file_candidates("foo", "/dir/a.ts");
+        assert_eq!(candidates[0], "/dir/node_modules/foo.ts");
+        assert!(candidates.contains(&"/dir/node_modules/@types/foo/index.d.ts".to_string()));
+        assert!(candidates.contains(&"/node_modules/foo/index.d.ts".to_string()));
+        assert_eq!(reference_candidates("../b.d.ts", "/dir/a.ts"), ["/b.d.ts"]);
+        assert_eq!(reference_candidates("b", "a.ts"), ["b.ts", "b.tsx", "b.d.ts"]);
+
+        let candidates = file_candidates("foo", "a.ts");
+        assert_eq!(candidates[0], "node_modules/foo.ts");
+    }
+}
@@ -0,0 +1,125 @@
+use std::{
+    cell::{Cell, RefCell},
+    hash::{Hash, Hasher},
+    rc::Rc,
+};
+
+use oxc::{allocator::Allocator, semantic::Semantic};
+use rustc_hash::{FxHashMap, FxHasher};
+
+use crate::{
+    Diagnostic, ModuleStats, ParseError, References, SourceKind, normalize_path, parse_json,
+    parse_source_timed,
+};
+
+/// A module parsed by a [`ProgramCache`], shared by every program built from it.
+#[derive(Clone)]
+pub struct CachedModule<'a> {
+    pub kind: SourceKind,
+    pub semantic: Rc<Semantic<'a>>,
+    pub diagnostics: Vec<Diagnostic>,
+    /// Set when parsed with stats.
+    pub stats: Option<ModuleStats>,
+    /// Names of the ambient modules the module declares.
+    pub ambient_modules: Rc<[&'a str]>,
+    pub references: Rc<References<'a>>,
+}
+
+/// Parses modules once for every program built in the same allocator, see
+/// [`TypeCheck::create_program_cached`](crate::TypeCheck::create_program_cached).
+///
+/// Modules are keyed by their path and a hash of their source text, so the variants
+/// of a test, which only differ in options, share them, and a file whose content changed is
+/// parsed again. [`ProgramCache::invalidate`] drops a file's modules explicitly. Like the
+/// [`LibStore`](crate::LibStore), a cache is not `Sync`: keep one per thread.
+pub struct ProgramCache<'a> {
+    alloc: &'a Allocator,
+    modules: RefCell<FxHashMap<(&'a str, u64), CachedModule<'a>>>,
+    parses: Cell<usize>,
+}
+
+impl<'a> ProgramCache<'a> {
+    pub fn new(alloc: &'a Allocator) -> Self {
+        Self { alloc, modules: RefCell::default(), parses: Cell::new(0) }
+    }
+
+    pub fn allocator(&self) -> &'a Allocator {
+        self.alloc
+    }
+
+    /// Number of modules parsed so far, including the ones that failed to parse.
+    pub fn parses(&self) -> usize {
+        self.parses.get()
+    }
+
+    /// Returns the module at `path` with `source_text`, parsing it as `kind` unless it already
+    /// was, or was without the [`ModuleStats`] `with_stats` asks for.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if the module cannot be parsed. Failures are not cached.
+    pub fn get_or_parse(
+        &self,
+        path: &str,
+        source_text: &str,
+        kind: SourceKind,
+        with_stats: bool,
+    ) -> Result<CachedModule<'a>, ParseError<'a>> {
+        let mut hasher = FxHasher::default();
+        source_text.hash(&mut hasher);
+        kind.hash(&mut hasher);
+        let hash = hasher.finish();
+        if let Some(module) = self.modules.borrow().get(&(path, hash)) {
+            if module.semantic.source_text() == source_text
+                && module.kind == kind
+                && (module.stats.is_some() || !with_stats)
+            {
+                return Ok(module.clone());
+            }
+        }
+
+        self.parses.set(self.parses.get() + 1);
+        let mut stats = with_stats.then(|| ModuleStats {
+            source_len: source_text.len(),
+            allocated: self.alloc.used_bytes(),
+            ..ModuleStats::default()
+        });
+        let source_text = self.alloc.alloc_str(source_text);
+        let parsed = match kind {
+            SourceKind::Source(source_type) => {
+                parse_source_timed(path, source_text, source_type, self.alloc, stats.as_mut())?
+            }
+            SourceKind::Json => parse_json(path, source_text, self.alloc, stats.as_mut())?,
+        };
+        if let Some(stats) = &mut stats {
+            stats.allocated = self.alloc.used_bytes() - stats.allocated;
+        }
+        let semantic = Rc::new(parsed.semantic);
+        let module = CachedModule {
+            kind,
+            semantic,
+            diagnostics: parsed.diagnostics,
+            stats,
+            ambient_modules: parsed.ambient_modules.into(),
+            references: Rc::new(parsed.references),
+        };
+        let key = (self.alloc.alloc_str(path), hash);
+        self.modules.borrow_mut().insert(key, module.clone());
+        Ok(module)
+    }
+
+    /// Drops the modules parsed for `path`, so the next program parses it again. Paths are
+    /// compared like [`TSProgram::module_id`](crate::TSProgram::module_id) does.
+    pub fn invalidate(&self, path: &str) {
+        let path = normalize_path(path);
+        self.modules.borrow_mut().retain(|&(x, _), _| normalize_path(x) != path);
+    }
+
+    pub fn len(&self) -> usize {
+        self.modules.borrow().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.modules.borrow().is_empty()
+    }
+}
@@ -0,0 +1,135 @@
+use oxc::span::Span;
+
+/// A value of a triple-slash reference directive, with its span in the file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Reference<'a> {
+    pub value: &'a str,
+    /// Span of the value, without its quotes.
+    pub span: Span,
+}
+
+/// Triple-slash reference directives of a file, like `/// <reference path="a.ts" />`.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct References<'a> {
+    /// Files, relative to the referencing file, which the program includes like imports.
+    pub paths: Vec<Reference<'a>>,
+    /// `types` packages, recorded but not resolved.
+    pub types: Vec<Reference<'a>>,
+    /// `lib` files, like `es2015`, recorded but not resolved.
+    pub libs: Vec<Reference<'a>>,
+}
+
+impl<'a> References<'a> {
+    /// Parses the directives of the comments leading `source_text`. Like TypeScript, the ones
+    /// after the first statement are plain comments.
+    pub fn parse(source_text: &'a str) -> Self {
+        let mut result = Self::default();
+        let mut rest = source_text.strip_prefix('\u{feff}').unwrap_or(source_text);
+        if rest.starts_with("#!") {
+            rest = rest.find('\n').map_or("", |x| &rest[x..]);
+        }
+        loop {
+            rest = rest.trim_start();
+            if let Some(comment) = rest.strip_prefix("//") {
+                let end = comment.find('\n').unwrap_or(comment.len());
+                if let Some(directive) = comment[..end].strip_prefix('/') {
+                    let offset = source_text.len() - directive.len() - (comment.len() - end);
+                    result.add(directive, offset);
+                }
+                rest = &comment[end..];
+            } else if let Some(comment) = rest.strip_prefix("/*") {
+                rest = comment.find("*/").map_or("", |x| &comment[x + 2..]);
+            } else {
+                return result;
+            }
+        }
+    }
+
+    /// Whether there are no directives.
+    pub fn is_empty(&self) -> bool {
+        self.paths.is_empty() && self.types.is_empty() && self.libs.is_empty()
+    }
+
+    /// Adds the directive of `line`, the text after `///` at `offset` in the file.
+    fn add(&mut self, line: &'a str, offset: usize) {
+        let Some(attributes) = line.trim_start().strip_prefix("<reference") else {
+            return;
+        };
+        let offset = offset + (line.len() - attributes.len());
+        for (name, list) in
+            [("path", &mut self.paths), ("types", &mut self.types), ("lib", &mut self.libs)]
+        {
+            if let Some(reference) = attribute(attributes, name, offset) {
+                list.push(reference);
+                return;
+            }
+        }
+    }
+}
+
+/// Value of the attribute `name` of a directive, `attributes` being at `offset` in the file.
+fn attribute<'a>(attributes: &'a str, name: &str, offset: usize) -> Option<Reference<'a>> {
+    let mut rest = attributes;
+    loop {
+        let start = rest.find(name)?;
+        let preceded = rest[..start].ends_with(|x: char| x.is_ascii_whitespace());
+        rest = &rest[start + name.len()..];
+        let Some(value) = rest.trim_start().strip_prefix('=') else {
+            continue;
+        };
+        let value = value.trim_start();
+        let Some(quote) = value.chars().next().filter(|x| matches!(x, '"' | '\'')) else {
+            continue;
+        };
+        let Some(len) = value[1..].find(quote) else {
+            continue;
+        };
+        if !preceded {
+            continue;
+        }
+        let start = offset + (attributes.len() - value.len()) + 1;
+        let span = Span::sized(u32::try_from(start).ok()?, u32::try_from(len).ok()?);
+        return Some(Reference { value: &value[1..=len], span });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn values(references: &[Reference<'_>]) -> Vec<String> {
+        references.iter().map(|x| x.value.to_string()).collect()
+    }
+
+    #[test]
+    fn directives() {
+        let source_text = "#!/usr/bin/env node\n/* header */\n\
+                           /// <reference path=\"a.ts\" />\n\
+                           ///<reference types='node'/>\n\
+                           // plain comment\n\
+                           /// <reference lib=\"es2015\" />\n\
+                           /// <reference no-default-lib=\"true\"/>\n\
+                           let a = 1;\n\
+                           /// <reference path=\"b.ts\" />\n";
+        let references = References::parse(source_text);
+        assert_eq!(values(&references.paths), ["a.ts"]);
+        assert_eq!(values(&references.types), ["node"]);
+        assert_eq!(values(&references.libs), ["es2015"]);
+
+        let span = references.paths[0].span;
+        assert_eq!(&source_text[span.start as usize..span.end as usize], "a.ts");
+        let span = references.types[0].span;
+        assert_eq!(&source_text[span.start as usize..span.end as usize], "node");
+    }
+
+    #[test]
+    fn not_directives() {
+        assert!(References::parse("let a;\n/// <reference path=\"a.ts\" />\n").is_empty());
+        assert!(References::parse("// <reference path=\"a.ts\" />\n").is_empty());
+        assert!(References::parse("/// <reference xpath=\"a.ts\" />\n").is_empty());
+        assert!(References::parse("/// <reference path=a.ts />\n").is_empty());
+        assert!(References::parse("/* /// <reference path=\"a.ts\" /> */").is_empty());
+        let references = References::parse("\u{feff}/// <reference path=\"a.ts\" />");
+        assert_eq!(values(&references.paths), ["a.ts"]);
+    }
+}
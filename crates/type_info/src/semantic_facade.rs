@@ -0,0 +1,177 @@
+use oxc::{
+    ast::{
+        AstKind,
+        ast::{BindingPattern, BindingPatternKind, TSType},
+    },
+    semantic::{ReferenceId, Semantic, SymbolId},
+    span::{GetSpan, Span},
+};
+use rustc_hash::FxHashMap;
+
+/// The symbol and scope lookups type computation needs, answered from a module's [`Semantic`].
+///
+/// Both tables are built once, when the facade is created, so every query is cheap.
+pub struct SemanticFacade<'s, 'a> {
+    semantic: &'s Semantic<'a>,
+    /// References by the span of their `IdentifierReference`.
+    references: FxHashMap<Span, ReferenceId>,
+    /// Spans of `declare` declarations, outermost first.
+    ambient: Vec<Span>,
+}
+
+impl<'s, 'a> SemanticFacade<'s, 'a> {
+    pub fn new(semantic: &'s Semantic<'a>) -> Self {
+        let mut references = FxHashMap::default();
+        let mut ambient = Vec::new();
+        for node in semantic.nodes().iter() {
+            let declare = match node.kind() {
+                AstKind::IdentifierReference(ident) => {
+                    if let Some(reference_id) = ident.reference_id.get() {
+                        references.insert(ident.span, reference_id);
+                    }
+                    false
+                }
+                AstKind::VariableDeclaration(decl) => decl.declare,
+                AstKind::Function(func) => func.declare,
+                AstKind::Class(class) => class.declare,
+                AstKind::TSModuleDeclaration(decl) => decl.declare,
+                AstKind::TSEnumDeclaration(decl) => decl.declare,
+                _ => false,
+            };
+            if declare && !ambient.last().is_some_and(|x: &Span| x.contains_inclusive(node.span()))
+            {
+                ambient.push(node.span());
+            }
+        }
+        Self { semantic, references, ambient }
+    }
+
+    pub fn semantic(&self) -> &'s Semantic<'a> {
+        self.semantic
+    }
+
+    /// Symbol the identifier reference at `span` resolves to. `None` if there is no reference
+    /// at `span` or it is unresolved, like a global from a lib file.
+    pub fn resolve_reference(&self, span: Span) -> Option<SymbolId> {
+        let reference_id = *self.references.get(&span)?;
+        self.semantic.scoping().get_reference(reference_id).symbol_id()
+    }
+
+    /// Type annotation declared on `symbol`, for variables and parameters bound directly to an
+    /// identifier. Bindings inside a destructuring pattern have none of their own.
+    pub fn annotation_of(&self, symbol: SymbolId) -> Option<&'a TSType<'a>> {
+        let pattern = match self.semantic.symbol_declaration(symbol).kind() {
+            AstKind::VariableDeclarator(decl) => &decl.id,
+            AstKind::FormalParameter(param) => &param.pattern,
+            AstKind::CatchParameter(param) => &param.pattern,
+            _ => return None,
+        };
+        annotation(pattern, symbol)
+    }
+
+    /// Whether `span` is inside an ambient context: a declaration file or a `declare`
+    /// declaration.
+    pub fn is_ambient(&self, span: Span) -> bool {
+        if self.semantic.source_type().is_typescript_definition() {
+            return true;
+        }
+        let idx = self.ambient.partition_point(|x| x.start <= span.start);
+        idx > 0 && self.ambient[idx - 1].contains_inclusive(span)
+    }
+}
+
+fn annotation<'a>(pattern: &'a BindingPattern<'a>, symbol: SymbolId) -> Option<&'a TSType<'a>> {
+    match &pattern.kind {
+        BindingPatternKind::BindingIdentifier(ident) if ident.symbol_id.get() == Some(symbol) => {
+            pattern.type_annotation.as_ref().map(|x| &x.type_annotation)
+        }
+        // `x: number = 5` in a parameter list
+        BindingPatternKind::AssignmentPattern(assign) => annotation(&assign.left, symbol),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use oxc::{allocator::Allocator, span::SourceType};
+
+    use super::*;
+    use crate::parse_source;
+
+    /// Span of the identifier `context` starts with, at the first occurrence of `context`.
+    fn span_of(source_text: &str, context: &str) -> Span {
+        let start = source_text.find(context).unwrap();
+        let len = context.find(|x: char| !x.is_alphanumeric()).unwrap_or(context.len());
+        Span::sized(u32::try_from(start).unwrap(), u32::try_from(len).unwrap())
+    }
+
+    fn annotation_text<'a>(facade: &SemanticFacade<'_, 'a>, reference: Span) -> Option<&'a str> {
+        let symbol = facade.resolve_reference(reference)?;
+        let annotation = facade.annotation_of(symbol)?;
+        Some(annotation.span().source_text(facade.semantic().source_text()))
+    }
+
+    #[test]
+    fn resolve_reference() {
+        let alloc = Allocator::default();
+        let source_text = "let a = 1;\nfunction f() { let a = 2; return a; }\na;\nb;\n";
+        let semantic =
+            parse_source("a.ts", source_text, SourceType::ts(), &alloc).unwrap().semantic;
+        let facade = SemanticFacade::new(&semantic);
+
+        let outer = facade.resolve_reference(span_of(source_text, "a;\nb")).unwrap();
+        let inner = facade.resolve_reference(span_of(source_text, "a; }")).unwrap();
+        assert_eq!(semantic.scoping().symbol_name(outer), "a");
+        assert_ne!(outer, inner);
+        assert_eq!(facade.resolve_reference(span_of(source_text, "b;")), None);
+        // A binding is not a reference
+        assert_eq!(facade.resolve_reference(span_of(source_text, "a = 1")), None);
+    }
+
+    #[test]
+    fn annotation_of() {
+        let alloc = Allocator::default();
+        let source_text = "\
+const a: string = '';
+const { b }: { b: number } = { b: 1 };
+let c = 1;
+function f(d: boolean, e: number = 1) { try {} catch (g: unknown) { d; e; g; } }
+a; b; c;
+";
+        let semantic =
+            parse_source("a.ts", source_text, SourceType::ts(), &alloc).unwrap().semantic;
+        let facade = SemanticFacade::new(&semantic);
+
+        assert_eq!(annotation_text(&facade, span_of(source_text, "a;")), Some("string"));
+        assert_eq!(annotation_text(&facade, span_of(source_text, "b;")), None);
+        assert_eq!(annotation_text(&facade, span_of(source_text, "c;")), None);
+        assert_eq!(annotation_text(&facade, span_of(source_text, "d;")), Some("boolean"));
+        assert_eq!(annotation_text(&facade, span_of(source_text, "e;")), Some("number"));
+        assert_eq!(annotation_text(&facade, span_of(source_text, "g;")), Some("unknown"));
+    }
+
+    #[test]
+    fn is_ambient() {
+        let alloc = Allocator::default();
+        let source_text = "\
+declare namespace N { const a: number; }
+declare function f(): void;
+const b = 1;
+namespace M { declare const c: number; const d = 1; }
+";
+        let semantic =
+            parse_source("a.ts", source_text, SourceType::ts(), &alloc).unwrap().semantic;
+        let facade = SemanticFacade::new(&semantic);
+
+        assert!(facade.is_ambient(span_of(source_text, "a: number")));
+        assert!(facade.is_ambient(span_of(source_text, "f()")));
+        assert!(!facade.is_ambient(span_of(source_text, "b = 1")));
+        assert!(facade.is_ambient(span_of(source_text, "c: number")));
+        assert!(!facade.is_ambient(span_of(source_text, "d = 1")));
+
+        let source_text = "const a: number;\n";
+        let semantic =
+            parse_source("a.d.ts", source_text, SourceType::d_ts(), &alloc).unwrap().semantic;
+        assert!(SemanticFacade::new(&semantic).is_ambient(span_of(source_text, "a")));
+    }
+}
@@ -0,0 +1,110 @@
+use oxc::span::{SourceType, UnknownExtension};
+
+/// How a file is parsed, by its extension.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SourceKind {
+    /// A TypeScript or JavaScript file.
+    Source(SourceType),
+    /// A `.json` file, a module exporting its value with `resolveJsonModule`.
+    Json,
+}
+
+impl SourceKind {
+    /// The kind of the file at `path`, like TypeScript tells them apart:
+    ///
+    /// - `.d.ts`, `.d.mts` and `.d.cts` are declaration files, and so is `.d.<ext>.ts` for
+    ///   `allowArbitraryExtensions`.
+    /// - `.mts` and `.mjs` are modules, `.cts` and `.cjs` are CommonJS scripts.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` if the extension is neither TypeScript, JavaScript nor JSON
+    pub fn from_path(path: &str) -> Result<Self, UnknownExtension> {
+        let name = path.rsplit(['/', '\\']).next().unwrap_or(path);
+        let Some((stem, extension)) = name.rsplit_once('.') else {
+            return SourceType::from_path(path).map(Self::Source);
+        };
+        // `a.d` of `a.d.ts`, or `a.d.css` of `a.d.css.ts`, which declares `a.css`
+        let is_declaration = |stem: &str, arbitrary: bool| {
+            let Some((_, extensions)) = stem.split_once('.') else {
+                return false;
+            };
+            let mut parts = extensions.rsplit('.');
+            parts.next() == Some("d") || arbitrary && parts.next() == Some("d")
+        };
+        let source_type = match extension {
+            "json" => return Ok(Self::Json),
+            "ts" if is_declaration(stem, true) => SourceType::d_ts(),
+            "mts" if is_declaration(stem, false) => SourceType::d_ts(),
+            "cts" if is_declaration(stem, false) => SourceType::d_ts().with_script(true),
+            "ts" | "mts" => SourceType::ts(),
+            "cts" => SourceType::ts().with_script(true),
+            "tsx" => SourceType::tsx(),
+            "js" | "mjs" => SourceType::mjs(),
+            "cjs" => SourceType::cjs(),
+            "jsx" => SourceType::jsx(),
+            _ => return SourceType::from_path(path).map(Self::Source),
+        };
+        Ok(Self::Source(source_type))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn source_type(path: &str) -> SourceType {
+        match SourceKind::from_path(path) {
+            Ok(SourceKind::Source(source_type)) => source_type,
+            other => panic!("Expected a source type for {path}, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn declarations() {
+        for path in ["a.d.ts", "/dir/a.d.mts", "a.d.css.ts", "a.b.d.ts"] {
+            let d_ts = source_type(path);
+            assert!(d_ts.is_typescript_definition(), "{path}");
+            assert!(d_ts.is_module(), "{path}");
+        }
+        let d_cts = source_type("a.d.cts");
+        assert!(d_cts.is_typescript_definition());
+        assert!(d_cts.is_script());
+
+        // Not declarations, only named like them
+        assert!(!source_type("d.ts").is_typescript_definition());
+        assert!(!source_type("a.css.ts").is_typescript_definition());
+    }
+
+    #[test]
+    fn typescript() {
+        let ts = source_type("a.ts");
+        assert!(ts.is_typescript() && !ts.is_typescript_definition() && ts.is_module());
+        let mts = source_type("a.mts");
+        assert!(mts.is_typescript() && mts.is_module());
+        let cts = source_type("a.cts");
+        assert!(cts.is_typescript() && cts.is_script());
+        let tsx = source_type("a.tsx");
+        assert!(tsx.is_typescript() && tsx.is_jsx() && tsx.is_module());
+    }
+
+    #[test]
+    fn javascript() {
+        for path in ["a.js", "a.mjs"] {
+            let js = source_type(path);
+            assert!(js.is_javascript() && js.is_module(), "{path}");
+        }
+        let cjs = source_type("a.cjs");
+        assert!(cjs.is_javascript() && cjs.is_script());
+        let jsx = source_type("a.jsx");
+        assert!(jsx.is_javascript() && jsx.is_jsx());
+    }
+
+    #[test]
+    fn json_and_unknown() {
+        assert_eq!(SourceKind::from_path("/a/b.json").unwrap(), SourceKind::Json);
+        assert!(SourceKind::from_path("a.png").is_err());
+        assert!(SourceKind::from_path("a.js.map").is_err());
+        assert!(SourceKind::from_path("a").is_err());
+    }
+}
@@ -0,0 +1,369 @@
+use serde_json::Value;
+
+use crate::{Diagnostic, Severity, diagnostic::utf16_len};
+
+/// Extensions of the files `include` picks, the TypeScript ones first.
+const EXTENSIONS: &[&str] = &[".ts", ".tsx", ".mts", ".cts", ".js", ".jsx", ".mjs", ".cjs"];
+const TS_EXTENSIONS: usize = 4;
+
+/// Directories left out of `include` when the config has no `exclude`, like TypeScript does.
+const DEFAULT_EXCLUDE: &[&str] = &["node_modules", "bower_components", "jspm_packages"];
+
+/// Code and message of the TypeScript error for a `serde_json` error starting with the text.
+const SYNTAX_ERRORS: &[(&str, u32, &str)] = &[
+    ("expected `:`", 1005, "':' expected."),
+    ("expected `,`", 1005, "',' expected."),
+    ("EOF while parsing an object", 1005, "'}' expected."),
+    ("EOF while parsing a list", 1005, "']' expected."),
+    ("EOF while parsing a string", 1002, "Unterminated string literal."),
+    ("EOF while parsing a value", 1109, "Expression expected."),
+    ("expected value", 1109, "Expression expected."),
+    ("expected ident", 1109, "Expression expected."),
+    ("key must be a string", 1327, "String literal with double quotes expected."),
+];
+
+/// The parts of a `tsconfig.json` that decide the program: its root files and its options.
+#[derive(Debug, Default, PartialEq, Eq, Clone)]
+pub struct TsConfig {
+    /// `compilerOptions` by name. Strings are kept as they are, other values as JSON, like
+    /// `true`.
+    pub compiler_options: Vec<(String, String)>,
+    pub files: Option<Vec<String>>,
+    pub include: Option<Vec<String>>,
+    pub exclude: Option<Vec<String>>,
+}
+
+impl TsConfig {
+    /// Parses the `tsconfig.json` at `path`, which may have comments and trailing commas like
+    /// TypeScript allows.
+    ///
+    /// # Errors
+    ///
+    /// Will return `Err` with a diagnostic located in `path` if the text is not a JSON object.
+    /// Syntax errors have the code and message `tsc` reports for them.
+    pub fn parse(path: &str, source_text: &str) -> Result<Self, Diagnostic> {
+        let json: Value = serde_json::from_str(&strip_jsonc(source_text)).map_err(|err| {
+            let (code, message) = syntax_error(&err);
+            let mut result = Diagnostic {
+                severity: Severity::Error,
+                code,
+                file: path.to_string(),
+                message: message.to_string(),
+                ..Diagnostic::default()
+            };
+            // Comments and commas are blanked out byte for byte, so the location still holds
+            let line = source_text.split('\n').nth(err.line().saturating_sub(1));
+            if let Some(line) = line {
+                let prefix = line.get(..err.column().saturating_sub(1)).unwrap_or(line);
+                result.line = u32::try_from(err.line()).unwrap_or(u32::MAX);
+                result.column = u32::try_from(utf16_len(prefix) + 1).unwrap_or(u32::MAX);
+            }
+            result
+        })?;
+        let Value::Object(json) = json else {
+            return Err(Diagnostic {
+                severity: Severity::Error,
+                code: 5092,
+                file: path.to_string(),
+                line: 1,
+                column: 1,
+                message: "The root value of a 'tsconfig.json' file must be an object.".to_string(),
+                ..Diagnostic::default()
+            });
+        };
+
+        let strings = |name: &str| {
+            let Some(Value::Array(values)) = json.get(name) else {
+                return None;
+            };
+            Some(values.iter().filter_map(Value::as_str).map(str::to_string).collect())
+        };
+        let compiler_options = match json.get("compilerOptions") {
+            Some(Value::Object(options)) => options
+                .iter()
+                .map(|(name, value)| {
+                    let value = value.as_str().map_or_else(|| value.to_string(), str::to_string);
+                    (name.clone(), value)
+                })
+                .collect(),
+            _ => Vec::new(),
+        };
+        Ok(Self {
+            compiler_options,
+            files: strings("files"),
+            include: strings("include"),
+            exclude: strings("exclude"),
+        })
+    }
+
+    /// Value of the compiler option `name`, matched regardless of case like `tsc` does.
+    pub fn compiler_option(&self, name: &str) -> Option<&str> {
+        self.compiler_options
+            .iter()
+            .find(|(x, _)| x.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// The root files among `files` for the config at `path`: the ones listed in `files`, in
+    /// their order, then the ones `include` matches and `exclude` does not.
+    ///
+    /// Without `files` and `include` every file under the config's directory is included.
+    /// `include` only picks TypeScript files, and JavaScript ones with `allows_js`.
+    pub fn root_files<'f>(
+        &self,
+        path: &str,
+        files: impl IntoIterator<Item = &'f str>,
+        allows_js: bool,
+    ) -> Vec<&'f str> {
+        let dir = path.rfind('/').map_or("", |x| &path[..=x]);
+        let files: Vec<_> = files.into_iter().collect();
+
+        let mut result = Vec::new();
+        for name in self.files.iter().flatten() {
+            let name = join(dir, name);
+            if let Some(&file) = files.iter().find(|&&x| normalize(x) == name) {
+                if !result.contains(&file) {
+                    result.push(file);
+                }
+            }
+        }
+
+        let default_include = [String::from("**/*")];
+        let include = match (&self.include, &self.files) {
+            (Some(include), _) => include.as_slice(),
+            (None, Some(_)) => &[],
+            (None, None) => &default_include,
+        };
+        let include: Vec<_> = include.iter().map(|x| join(dir, x)).collect();
+        let exclude: Vec<_> = match &self.exclude {
+            Some(exclude) => exclude.iter().map(|x| join(dir, x)).collect(),
+            None => DEFAULT_EXCLUDE.iter().map(|x| join(dir, &format!("**/{x}"))).collect(),
+        };
+        let extensions = if allows_js { EXTENSIONS } else { &EXTENSIONS[..TS_EXTENSIONS] };
+        for &file in &files {
+            let name = normalize(file);
+            if !extensions.iter().any(|x| name.ends_with(x))
+                || result.contains(&file)
+                || !include.iter().any(|x| matches_pattern(x, &name))
+                || exclude.iter().any(|x| matches_pattern(x, &name))
+            {
+                continue;
+            }
+            result.push(file);
+        }
+        result
+    }
+}
+
+/// Blanks out comments and trailing commas, keeping every other byte where it is.
+fn strip_jsonc(text: &str) -> String {
+    let bytes = text.as_bytes();
+    let mut result = bytes.to_vec();
+    // A comma with only whitespace and comments after it so far
+    let mut comma = None;
+    let mut idx = 0;
+    while idx < bytes.len() {
+        match (bytes[idx], bytes.get(idx + 1)) {
+            (b'"', _) => {
+                comma = None;
+                idx += 1;
+                while idx < bytes.len() && bytes[idx] != b'"' {
+                    idx += if bytes[idx] == b'\\' { 2 } else { 1 };
+                }
+                idx += 1;
+            }
+            (b'/', Some(b'/')) => {
+                while idx < bytes.len() && bytes[idx] != b'\n' {
+                    result[idx] = b' ';
+                    idx += 1;
+                }
+            }
+            (b'/', Some(b'*')) => {
+                let end = text[idx + 2..].find("*/").map_or(bytes.len(), |x| idx + 2 + x + 2);
+                for byte in &mut result[idx..end] {
+                    if *byte != b'\n' {
+                        *byte = b' ';
+                    }
+                }
+                idx = end;
+            }
+            (b',', _) => {
+                comma = Some(idx);
+                idx += 1;
+            }
+            (b'}' | b']', _) => {
+                if let Some(comma) = comma.take() {
+                    result[comma] = b' ';
+                }
+                idx += 1;
+            }
+            (byte, _) => {
+                if !byte.is_ascii_whitespace() {
+                    comma = None;
+                }
+                idx += 1;
+            }
+        }
+    }
+    // Only whole comments are replaced, so the text stays UTF-8
+    String::from_utf8(result).expect("stripped config to be UTF8")
+}
+
+/// `path` without a leading `/` or `./`.
+fn normalize(path: &str) -> String {
+    join("", path)
+}
+
+/// `name` relative to the directory `dir`, with `.` and `..` segments applied and without a
+/// leading `/`.
+fn join(dir: &str, name: &str) -> String {
+    let mut segments: Vec<&str> = Vec::new();
+    let name = if name.starts_with('/') { name } else { &format!("{dir}{name}") };
+    for segment in name.split('/') {
+        match segment {
+            "" | "." => {}
+            ".." => {
+                segments.pop();
+            }
+            _ => segments.push(segment),
+        }
+    }
+    segments.join("/")
+}
+
+/// Whether `pattern` of `include` or `exclude` matches `path`. A pattern without wildcards in
+/// its last segment also matches everything under it, like a directory.
+fn matches_pattern(pattern: &str, path: &str) -> bool {
+    let pattern: Vec<_> = pattern.split('/').collect();
+    let path: Vec<_> = path.split('/').collect();
+    if matches_segments(&pattern, &path) {
+        return true;
+    }
+    let is_dir = pattern.last().is_some_and(|x| !x.contains(['*', '?']));
+    is_dir && matches_segments(&[pattern.as_slice(), &["**"]].concat(), &path)
+}
+
+fn matches_segments(pattern: &[&str], path: &[&str]) -> bool {
+    match (pattern.split_first(), path.split_first()) {
+        (None, None) => true,
+        (Some((&"**", rest)), _) => {
+            // `src/**` is `src/**/*`
+            (0..=path.len()).any(|skip| {
+                matches_segments(rest, &path[skip..]) || (rest.is_empty() && skip < path.len())
+            })
+        }
+        (Some((first, rest)), Some((name, path))) => {
+            matches_segment(first.as_bytes(), name.as_bytes()) && matches_segments(rest, path)
+        }
+        _ => false,
+    }
+}
+
+fn matches_segment(pattern: &[u8], name: &[u8]) -> bool {
+    match (pattern.split_first(), name.split_first()) {
+        (None, None) => true,
+        (Some((b'*', rest)), _) => {
+            (0..=name.len()).any(|skip| matches_segment(rest, &name[skip..]))
+        }
+        (Some((b'?', rest)), Some((_, name))) => matches_segment(rest, name),
+        (Some((first, rest)), Some((byte, name))) => {
+            first.eq_ignore_ascii_case(byte) && matches_segment(rest, name)
+        }
+        _ => false,
+    }
+}
+
+/// Code and message of the error `tsc` reports for the JSON syntax error `err`, or the
+/// `Unexpected token.` it falls back to.
+fn syntax_error(err: &serde_json::Error) -> (u32, &'static str) {
+    let message = err.to_string();
+    SYNTAX_ERRORS
+        .iter()
+        .find(|(prefix, _, _)| message.starts_with(prefix))
+        .map_or((1012, "Unexpected token."), |&(_, code, message)| (code, message))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse() {
+        let source_text = r#"{
+  // Comments and trailing commas, like TS allows
+  "compilerOptions": { "strict": true, /* "noEmit": true, */ "target": "ES2020", },
+  "include": ["src", "/* not a comment */",],
+}
+"#;
+        let config = TsConfig::parse("/tsconfig.json", source_text).unwrap();
+        assert_eq!(config.compiler_option("STRICT"), Some("true"));
+        assert_eq!(config.compiler_option("target"), Some("ES2020"));
+        assert_eq!(config.compiler_option("noEmit"), None);
+        assert_eq!(config.include, Some(vec!["src".to_string(), "/* not a comment */".into()]));
+        assert_eq!((config.files, config.exclude), (None, None));
+
+        let err = TsConfig::parse("/tsconfig.json", "{\n  // ü\n  \"ü\": 1 \"b\": 2\n}\n");
+        let err = err.unwrap_err();
+        assert_eq!((err.file.as_str(), err.line, err.column), ("/tsconfig.json", 3, 10));
+        assert_eq!((err.code, err.message.as_str()), (1005, "',' expected."));
+
+        let err = TsConfig::parse("tsconfig.json", "{ \"strict\" true }").unwrap_err();
+        assert_eq!((err.code, err.message.as_str(), err.column), (1005, "':' expected.", 12));
+        let err = TsConfig::parse("tsconfig.json", "{ \"files\": [\"a.ts\"\n").unwrap_err();
+        assert_eq!((err.code, err.message.as_str()), (1005, "']' expected."));
+        let err = TsConfig::parse("tsconfig.json", "{ files: [] }").unwrap_err();
+        assert_eq!(err.code, 1327);
+        let err = TsConfig::parse("tsconfig.json", "{} {}").unwrap_err();
+        assert_eq!((err.code, err.message.as_str()), (1012, "Unexpected token."));
+
+        let err = TsConfig::parse("tsconfig.json", "[]").unwrap_err();
+        assert_eq!((err.code, err.line, err.column), (5092, 1, 1));
+    }
+
+    #[test]
+    fn root_files() {
+        let files = [
+            "/tsconfig.json",
+            "/src/a.ts",
+            "/src/b.d.ts",
+            "/src/c.js",
+            "/src/nested/d.tsx",
+            "/node_modules/e/index.ts",
+            "/other/f.ts",
+        ];
+        let parse = |text| TsConfig::parse("/tsconfig.json", text).unwrap();
+        let root_files = |config: &TsConfig, allows_js| {
+            config.root_files("/tsconfig.json", files.iter().copied(), allows_js)
+        };
+
+        let config = parse("{}");
+        assert_eq!(
+            root_files(&config, false),
+            vec!["/src/a.ts", "/src/b.d.ts", "/src/nested/d.tsx", "/other/f.ts"]
+        );
+        assert!(root_files(&config, true).contains(&"/src/c.js"));
+
+        let config = parse(r#"{ "files": ["./other/f.ts", "src/a.ts", "missing.ts"] }"#);
+        assert_eq!(root_files(&config, false), vec!["/other/f.ts", "/src/a.ts"]);
+
+        let config = parse(r#"{ "files": ["other/f.ts"], "include": ["src/*"] }"#);
+        assert_eq!(root_files(&config, false), vec!["/other/f.ts", "/src/a.ts", "/src/b.d.ts"]);
+
+        let config =
+            parse(r#"{ "include": ["src/**/*", "node_modules"], "exclude": ["**/*.d.ts"] }"#);
+        assert_eq!(
+            root_files(&config, false),
+            vec!["/src/a.ts", "/src/nested/d.tsx", "/node_modules/e/index.ts"]
+        );
+
+        let config = parse(r#"{ "include": ["src/**"], "exclude": ["src/nested"] }"#);
+        assert_eq!(root_files(&config, false), vec!["/src/a.ts", "/src/b.d.ts"]);
+
+        // Relative to the config's directory
+        let files = ["/src/tsconfig.json", "/src/a.ts", "/b.ts"];
+        let config = TsConfig::parse("/src/tsconfig.json", "{}").unwrap();
+        assert_eq!(config.root_files("/src/tsconfig.json", files, false), vec!["/src/a.ts"]);
+        let config = TsConfig::parse("/src/tsconfig.json", r#"{ "files": ["../b.ts"] }"#).unwrap();
+        assert_eq!(config.root_files("/src/tsconfig.json", files, false), vec!["/b.ts"]);
+    }
+}